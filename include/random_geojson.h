@@ -0,0 +1,28 @@
+/* C API of random-geojson, built with `cargo build --release --features ffi`
+ * as target/release/librandom_geojson.so (.dylib on macOS, .dll on Windows). */
+
+#ifndef RANDOM_GEOJSON_H
+#define RANDOM_GEOJSON_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Generates a random GeoJSON FeatureCollection from options given as a JSON
+ * object, e.g. {"length": 50, "geometryType": "Point", "seed": 42}, or from
+ * the defaults if config_json is NULL. Returns the collection as text to be
+ * released with random_geojson_free, or NULL on failure. */
+char *random_geojson_generate(const char *config_json);
+
+/* Releases text returned by random_geojson_generate. */
+void random_geojson_free(char *json);
+
+/* The reason the last call to random_geojson_generate on this thread failed,
+ * or NULL if it succeeded. Valid until the next call; do not release it. */
+const char *random_geojson_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,197 @@
+use rand::Rng;
+
+use crate::geometry::{Crs, RandomGeometry, random_geographic_coords};
+use crate::random;
+
+/// Approximate length of one degree of latitude in metres.
+pub(crate) const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Role of a feature within an anchored group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SiteRole {
+    Building,
+    Entrance,
+    Path,
+}
+
+impl SiteRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SiteRole::Building => "building",
+            SiteRole::Entrance => "entrance",
+            SiteRole::Path => "path",
+        }
+    }
+}
+
+/// A feature generated around a shared anchor point.
+pub struct SiteFeature {
+    pub role: SiteRole,
+    pub geometry: RandomGeometry,
+}
+
+/// Generates a "site" of `num_features` related features around a random anchor.
+///
+/// The first feature is always a building footprint; the remaining ones
+/// alternate between entrance points on the building outline and access paths
+/// leading away from the most recent entrance.
+pub fn random_site(crs: &Crs, num_features: usize) -> Vec<SiteFeature> {
+    let mut rng = random::rng();
+    let (lon, lat) = random_geographic_coords(crs);
+    let site = Site {
+        lon,
+        lat,
+        width: rng.random_range(10.0..60.0),
+        depth: rng.random_range(10.0..60.0),
+        rotation: rng.random_range(0.0..std::f64::consts::PI),
+    };
+
+    let mut features = Vec::with_capacity(num_features);
+    if num_features == 0 {
+        return features;
+    }
+
+    let corners = site.corners();
+    let mut ring: Vec<(f64, f64)> = corners.iter().map(|&(e, n)| site.offset(e, n)).collect();
+    ring.push(ring[0]);
+    features.push(SiteFeature {
+        role: SiteRole::Building,
+        geometry: RandomGeometry::Polygon(vec![project(crs, &ring)]),
+    });
+
+    // Local position and outward direction of the latest entrance
+    let mut entrance = None;
+    for i in 1..num_features {
+        if i % 2 == 1 {
+            let edge = rng.random_range(0..4);
+            let (a, b) = (corners[edge], corners[(edge + 1) % 4]);
+            let t = rng.random_range(0.1..0.9);
+            let point = (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+            // Corners are ordered counter-clockwise, so the outward normal points right
+            let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+            let len = (dx * dx + dy * dy).sqrt();
+            let normal = (dy / len, -dx / len);
+            entrance = Some((point, normal));
+            features.push(SiteFeature {
+                role: SiteRole::Entrance,
+                geometry: RandomGeometry::Point(project_one(crs, site.offset(point.0, point.1))),
+            });
+        } else if let Some((start, normal)) = entrance {
+            let mut heading = normal.1.atan2(normal.0);
+            let mut current = start;
+            let mut vertices = vec![site.offset(current.0, current.1)];
+            for _ in 0..rng.random_range(2..6) {
+                heading += rng.random_range(-0.5..0.5);
+                let step = rng.random_range(10.0..50.0);
+                current = (
+                    current.0 + step * heading.cos(),
+                    current.1 + step * heading.sin(),
+                );
+                vertices.push(site.offset(current.0, current.1));
+            }
+            features.push(SiteFeature {
+                role: SiteRole::Path,
+                geometry: RandomGeometry::LineString(project(crs, &vertices)),
+            });
+        }
+    }
+
+    features
+}
+
+/// Local frame of a site, measured in metres east/north of the anchor.
+struct Site {
+    lon: f64,
+    lat: f64,
+    width: f64,
+    depth: f64,
+    rotation: f64,
+}
+
+impl Site {
+    /// Building corners in local metres, counter-clockwise.
+    fn corners(&self) -> [(f64, f64); 4] {
+        let (hw, hd) = (self.width / 2.0, self.depth / 2.0);
+        let (sin, cos) = self.rotation.sin_cos();
+        [(-hw, -hd), (hw, -hd), (hw, hd), (-hw, hd)]
+            .map(|(x, y)| (x * cos - y * sin, x * sin + y * cos))
+    }
+
+    /// Converts a local offset in metres into geographic coordinates.
+    fn offset(&self, east: f64, north: f64) -> (f64, f64) {
+        offset(self.lon, self.lat, east, north)
+    }
+}
+
+/// Moves a geographic position by a local offset in metres east and north.
+pub fn offset(lon: f64, lat: f64, east: f64, north: f64) -> (f64, f64) {
+    let lat_scale = lat.to_radians().cos().max(0.01);
+    (
+        lon + east / (METERS_PER_DEGREE * lat_scale),
+        lat + north / METERS_PER_DEGREE,
+    )
+}
+
+/// Clamps a geographic position to the bounds of `crs` and projects it.
+pub(crate) fn project_one(crs: &Crs, (lon, lat): (f64, f64)) -> Vec<f64> {
+    let bounds = crs.bounds();
+    let lon = lon.clamp(bounds.min_x, bounds.max_x);
+    let lat = lat.clamp(bounds.min_y, bounds.max_y);
+    let (x, y) = crs.transform().transform(lon, lat);
+    vec![x, y]
+}
+
+pub(crate) fn project(crs: &Crs, coords: &[(f64, f64)]) -> Vec<Vec<f64>> {
+    coords.iter().map(|&c| project_one(crs, c)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measure::line_length;
+
+    #[test]
+    fn test_random_site_roles() {
+        let site = random_site(&Crs::WGS84, 5);
+        let roles: Vec<SiteRole> = site.iter().map(|f| f.role).collect();
+        assert_eq!(
+            roles,
+            vec![
+                SiteRole::Building,
+                SiteRole::Entrance,
+                SiteRole::Path,
+                SiteRole::Entrance,
+                SiteRole::Path
+            ]
+        );
+        assert!(random_site(&Crs::WGS84, 0).is_empty());
+    }
+
+    #[test]
+    fn test_random_site_is_compact() {
+        for _ in 0..100 {
+            let site = random_site(&Crs::WGS84, 6);
+            let anchor = match &site[0].geometry {
+                RandomGeometry::Polygon(rings) => {
+                    assert_eq!(rings[0].len(), 5);
+                    assert_eq!(rings[0].first(), rings[0].last());
+                    (rings[0][0][0], rings[0][0][1])
+                }
+                _ => panic!("Expected Polygon geometry"),
+            };
+            // Everything in the site is within a few hundred metres of the
+            // building: paths of up to 250 m from the far side of it
+            for feature in &site {
+                let coords = match &feature.geometry {
+                    RandomGeometry::Point(c) => vec![c.clone()],
+                    RandomGeometry::LineString(c) => c.clone(),
+                    RandomGeometry::Polygon(r) => r[0].clone(),
+                };
+                for c in coords {
+                    let distance = line_length(&[anchor, (c[0], c[1])]);
+                    assert!(distance < 400.0, "{} metres from anchor", distance);
+                }
+            }
+        }
+    }
+}
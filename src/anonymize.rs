@@ -0,0 +1,56 @@
+use geojson::FeatureCollection;
+
+use crate::property::random_value_like;
+
+/// Replaces every property value of `fc` with random data of the same type,
+/// keeping the geometries and property keys, so a dataset can be shared
+/// without its contents. Feature ids are also removed if `drop_ids`.
+pub fn anonymize(fc: &mut FeatureCollection, drop_ids: bool) {
+    for feature in &mut fc.features {
+        if let Some(properties) = &mut feature.properties {
+            properties
+                .values_mut()
+                .for_each(|value| *value = random_value_like(value));
+        }
+        if drop_ids {
+            feature.id = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Feature, Geometry, JsonObject};
+
+    use super::*;
+
+    #[test]
+    fn test_anonymize() {
+        let mut properties = JsonObject::new();
+        properties.insert("customer".to_string(), "ACME Ltd".into());
+        properties.insert("revenue".to_string(), 125000.into());
+        let geometry = Geometry::new(geojson::Value::Point(vec![1.0, 2.0]));
+        let mut fc = FeatureCollection {
+            features: vec![Feature {
+                geometry: Some(geometry.clone()),
+                id: Some(geojson::feature::Id::String("c-1".to_string())),
+                properties: Some(properties.clone()),
+                ..Default::default()
+            }],
+            bbox: None,
+            foreign_members: None,
+        };
+        anonymize(&mut fc, true);
+
+        let feature = &fc.features[0];
+        assert_eq!(feature.geometry, Some(geometry));
+        assert_eq!(feature.id, None);
+        let anonymized = feature.properties.as_ref().unwrap();
+        assert_eq!(
+            anonymized.keys().collect::<Vec<_>>(),
+            properties.keys().collect::<Vec<_>>()
+        );
+        assert_ne!(anonymized["customer"], properties["customer"]);
+        assert!(anonymized["revenue"].is_u64());
+    }
+}
@@ -0,0 +1,425 @@
+use std::fmt::Write;
+
+use clap::{Arg, Command};
+
+use crate::error::RandomGeojsonError;
+
+/// A shell that completion scripts can be generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+}
+
+impl Shell {
+    /// Generates a script completing the subcommands and flags of `command`,
+    /// installed as `bin`, and the values of flags that list them.
+    pub fn script(&self, command: &Command, bin: &str) -> String {
+        let mut command = command.clone();
+        // Adds the global flags to each subcommand, and --help and --version
+        command.build();
+        let commands: Vec<Completions> = std::iter::once(Completions::new(&command, None))
+            .chain(
+                command
+                    .get_subcommands()
+                    .map(|sub| Completions::new(sub, Some(sub.get_name()))),
+            )
+            .collect();
+        match self {
+            Shell::Bash => bash(&commands, bin),
+            Shell::Zsh => zsh(&commands, bin),
+            Shell::Fish => fish(&commands, bin),
+            Shell::PowerShell => powershell(&commands, bin),
+        }
+    }
+}
+
+impl std::str::FromStr for Shell {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            "powershell" | "pwsh" => Ok(Shell::PowerShell),
+            _ => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid shell: {} (expected one of: bash, zsh, fish, powershell)",
+                s
+            ))),
+        }
+    }
+}
+
+// What can be completed at the level of the top command or a subcommand.
+struct Completions<'a> {
+    name: Option<&'a str>,
+    about: String,
+    flags: Vec<Flag>,
+}
+
+struct Flag {
+    long: Option<String>,
+    short: Option<char>,
+    help: String,
+    takes_value: bool,
+    values: Vec<String>,
+}
+
+impl<'a> Completions<'a> {
+    fn new(command: &'a Command, name: Option<&'a str>) -> Self {
+        let flags = command
+            .get_arguments()
+            .filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+            .map(Flag::new)
+            .collect();
+        Completions {
+            name,
+            about: first_line(command.get_about().map(|a| a.to_string())),
+            flags,
+        }
+    }
+}
+
+impl Flag {
+    fn new(arg: &Arg) -> Self {
+        Flag {
+            long: arg.get_long().map(str::to_string),
+            short: arg.get_short(),
+            help: first_line(arg.get_help().map(|h| h.to_string())),
+            takes_value: arg.get_action().takes_values(),
+            values: arg
+                .get_possible_values()
+                .iter()
+                .filter(|v| !v.is_hide_set())
+                .map(|v| v.get_name().to_string())
+                .collect(),
+        }
+    }
+
+    // The flag as typed, e.g. "--length" or "-q" without a long name.
+    fn spellings(&self) -> Vec<String> {
+        let long = self.long.iter().map(|l| format!("--{}", l));
+        let short = self.short.iter().map(|s| format!("-{}", s));
+        long.chain(short).collect()
+    }
+}
+
+// The first sentence of a help text, without the "(optional, ...)" note on
+// its default.
+fn first_line(help: Option<String>) -> String {
+    let help = help.unwrap_or_default();
+    let line = help.lines().next().unwrap_or_default();
+    let sentence_end = line
+        .match_indices(". ")
+        .map(|(i, _)| i)
+        .find(|&i| line[i + 2..].starts_with(char::is_uppercase));
+    let end = sentence_end
+        .into_iter()
+        .chain(line.find(" (optional"))
+        .min()
+        .unwrap_or(line.len());
+    let line = line[..end].trim();
+    line.strip_suffix('.').unwrap_or(line).to_string()
+}
+
+// A shell function name for `bin`, e.g. "_random_geojson".
+fn function_name(bin: &str) -> String {
+    format!(
+        "_{}",
+        bin.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+    )
+}
+
+fn bash(commands: &[Completions], bin: &str) -> String {
+    let function = function_name(bin);
+    let subcommands: Vec<&str> = commands.iter().filter_map(|c| c.name).collect();
+    let mut script = String::new();
+    let _ = writeln!(script, "{}() {{", function);
+    let _ = writeln!(script, "    local cur prev sub i");
+    let _ = writeln!(script, "    COMPREPLY=()");
+    let _ = writeln!(script, "    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"");
+    let _ = writeln!(script, "    prev=\"${{COMP_WORDS[COMP_CWORD - 1]}}\"");
+    let _ = writeln!(script, "    sub=\"\"");
+    let _ = writeln!(script, "    for ((i = 1; i < COMP_CWORD; i++)); do");
+    let _ = writeln!(script, "        case \"${{COMP_WORDS[i]}}\" in");
+    let _ = writeln!(
+        script,
+        "            {}) sub=\"${{COMP_WORDS[i]}}\"; break ;;",
+        subcommands.join("|")
+    );
+    let _ = writeln!(script, "        esac");
+    let _ = writeln!(script, "    done");
+    let _ = writeln!(script, "    case \"$sub\" in");
+    for command in commands {
+        let _ = writeln!(script, "        \"{}\")", command.name.unwrap_or_default());
+        let _ = writeln!(script, "            case \"$prev\" in");
+        for flag in command.flags.iter().filter(|f| f.takes_value) {
+            let words = if flag.values.is_empty() {
+                "-f -- \"$cur\"".to_string()
+            } else {
+                format!("-W \"{}\" -- \"$cur\"", flag.values.join(" "))
+            };
+            let _ = writeln!(
+                script,
+                "                {}) COMPREPLY=($(compgen {})); return 0 ;;",
+                flag.spellings().join("|"),
+                words
+            );
+        }
+        let _ = writeln!(script, "            esac");
+        let mut words: Vec<String> = command.flags.iter().flat_map(Flag::spellings).collect();
+        if command.name.is_none() {
+            words.extend(subcommands.iter().map(|s| s.to_string()));
+        }
+        let _ = writeln!(
+            script,
+            "            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+            words.join(" ")
+        );
+        let _ = writeln!(script, "            if [[ \"$cur\" != -* ]]; then");
+        let _ = writeln!(
+            script,
+            "                COMPREPLY+=($(compgen -f -- \"$cur\"))"
+        );
+        let _ = writeln!(script, "            fi");
+        let _ = writeln!(script, "            ;;");
+    }
+    let _ = writeln!(script, "    esac");
+    let _ = writeln!(script, "}}");
+    let _ = writeln!(script, "complete -o filenames -F {} {}", function, bin);
+    script
+}
+
+fn zsh(commands: &[Completions], bin: &str) -> String {
+    let quote = |s: &str| {
+        s.replace('\'', "'\\''")
+            .replace('[', "\\[")
+            .replace(']', "\\]")
+            .replace(':', "\\:")
+    };
+    let specs = |command: &Completions| -> Vec<String> {
+        command
+            .flags
+            .iter()
+            .flat_map(|flag| {
+                let value = if !flag.takes_value {
+                    String::new()
+                } else if flag.values.is_empty() {
+                    ":value:_files".to_string()
+                } else {
+                    format!(":value:({})", flag.values.join(" "))
+                };
+                flag.spellings()
+                    .into_iter()
+                    .map(move |spelling| format!("'{}[{}]{}'", spelling, quote(&flag.help), value))
+            })
+            .collect()
+    };
+    let function = function_name(bin);
+    let mut script = String::new();
+    let _ = writeln!(script, "#compdef {}", bin);
+    let _ = writeln!(script);
+    let _ = writeln!(script, "{}() {{", function);
+    let _ = writeln!(script, "    local -a subcommands");
+    let _ = writeln!(script, "    subcommands=(");
+    for command in commands.iter().filter(|c| c.name.is_some()) {
+        let name = command.name.unwrap_or_default();
+        let _ = writeln!(script, "        '{}:{}'", name, quote(&command.about));
+    }
+    let _ = writeln!(script, "    )");
+    let _ = writeln!(
+        script,
+        "    if (( CURRENT == 2 )) && [[ $words[2] != -* ]]; then"
+    );
+    let _ = writeln!(script, "        _describe 'subcommand' subcommands");
+    let _ = writeln!(script, "        return");
+    let _ = writeln!(script, "    fi");
+    let _ = writeln!(script, "    case $words[2] in");
+    // The top command, matching anything, comes last
+    for command in commands.iter().rev() {
+        let pattern = command.name.unwrap_or("*");
+        let _ = writeln!(script, "        {})", pattern);
+        let _ = writeln!(script, "            _arguments \\");
+        for spec in specs(command) {
+            let _ = writeln!(script, "                {} \\", spec);
+        }
+        let _ = writeln!(script, "                '*:file:_files'");
+        let _ = writeln!(script, "            ;;");
+    }
+    let _ = writeln!(script, "    esac");
+    let _ = writeln!(script, "}}");
+    let _ = writeln!(script);
+    let _ = writeln!(script, "{} \"$@\"", function);
+    script
+}
+
+fn fish(commands: &[Completions], bin: &str) -> String {
+    let quote = |s: &str| s.replace('\\', "\\\\").replace('\'', "\\'");
+    let subcommands: Vec<&str> = commands.iter().filter_map(|c| c.name).collect();
+    let mut script = String::new();
+    for command in commands {
+        let condition = match command.name {
+            Some(name) => format!("__fish_seen_subcommand_from {}", name),
+            None => "__fish_use_subcommand".to_string(),
+        };
+        if let Some(name) = command.name {
+            let _ = writeln!(
+                script,
+                "complete -c {} -n '__fish_use_subcommand' -f -a {} -d '{}'",
+                bin,
+                name,
+                quote(&command.about)
+            );
+        }
+        for flag in &command.flags {
+            let mut line = format!("complete -c {} -n '{}'", bin, condition);
+            if let Some(long) = &flag.long {
+                let _ = write!(line, " -l {}", long);
+            }
+            if let Some(short) = flag.short {
+                let _ = write!(line, " -s {}", short);
+            }
+            if flag.takes_value {
+                line.push_str(" -r");
+            }
+            if !flag.values.is_empty() {
+                let _ = write!(line, " -f -a '{}'", flag.values.join(" "));
+            }
+            let _ = writeln!(script, "{} -d '{}'", line, quote(&flag.help));
+        }
+    }
+    let _ = writeln!(
+        script,
+        "complete -c {} -n 'not __fish_seen_subcommand_from {}' -F",
+        bin,
+        subcommands.join(" ")
+    );
+    script
+}
+
+fn powershell(commands: &[Completions], bin: &str) -> String {
+    let quote = |s: &str| s.replace('\'', "''");
+    let mut script = String::new();
+    let _ = writeln!(
+        script,
+        "Register-ArgumentCompleter -Native -CommandName '{}' -ScriptBlock {{",
+        bin
+    );
+    let _ = writeln!(
+        script,
+        "    param($wordToComplete, $commandAst, $cursorPosition)"
+    );
+    let _ = writeln!(script, "    $completions = @{{");
+    for command in commands {
+        let _ = writeln!(
+            script,
+            "        '{}' = @(",
+            command.name.unwrap_or_default()
+        );
+        if command.name.is_none() {
+            for sub in commands.iter().filter(|c| c.name.is_some()) {
+                let _ = writeln!(
+                    script,
+                    "            @('{}', '{}')",
+                    sub.name.unwrap_or_default(),
+                    quote(&sub.about)
+                );
+            }
+        }
+        for flag in &command.flags {
+            for spelling in flag.spellings() {
+                let _ = writeln!(
+                    script,
+                    "            @('{}', '{}')",
+                    spelling,
+                    quote(&flag.help)
+                );
+            }
+        }
+        let _ = writeln!(script, "        )");
+    }
+    let _ = writeln!(script, "    }}");
+    let _ = writeln!(script, "    $sub = ''");
+    let _ = writeln!(
+        script,
+        "    foreach ($element in $commandAst.CommandElements | Select-Object -Skip 1) {{"
+    );
+    let _ = writeln!(
+        script,
+        "        if ($completions.ContainsKey(\"$element\") -and \"$element\" -ne '') {{ $sub = \"$element\"; break }}"
+    );
+    let _ = writeln!(script, "    }}");
+    let _ = writeln!(
+        script,
+        "    $completions[$sub] | Where-Object {{ $_[0] -like \"$wordToComplete*\" }} | ForEach-Object {{"
+    );
+    let _ = writeln!(
+        script,
+        "        [System.Management.Automation.CompletionResult]::new($_[0], $_[0], 'ParameterValue', $_[1])"
+    );
+    let _ = writeln!(script, "    }}");
+    let _ = writeln!(script, "}}");
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::ArgAction;
+
+    use super::*;
+
+    fn command() -> Command {
+        Command::new("tool")
+            .arg(
+                Arg::new("format")
+                    .long("format")
+                    .help("Output format's name (optional, defaults to \"geojson\")")
+                    .value_parser(["geojson", "csv"]),
+            )
+            .arg(
+                Arg::new("quiet")
+                    .long("quiet")
+                    .short('q')
+                    .global(true)
+                    .action(ArgAction::SetTrue),
+            )
+            .subcommand(
+                Command::new("stats")
+                    .about("Summarize a file.")
+                    .arg(Arg::new("file")),
+            )
+    }
+
+    #[test]
+    fn test_scripts() {
+        let bash = Shell::Bash.script(&command(), "tool");
+        assert!(bash.contains("complete -o filenames -F _tool tool"));
+        assert!(bash.contains("--format) COMPREPLY=($(compgen -W \"geojson csv\""));
+        assert!(bash.contains("        \"stats\")\n"));
+
+        let zsh = Shell::Zsh.script(&command(), "tool");
+        assert!(zsh.starts_with("#compdef tool"));
+        assert!(zsh.contains("'--format[Output format'\\''s name]:value:(geojson csv)'"));
+        assert!(zsh.contains("'stats:Summarize a file'"));
+        assert!(zsh.find("        stats)") < zsh.find("        *)"));
+
+        // Global flags are completed after subcommands too
+        let fish = Shell::Fish.script(&command(), "tool");
+        assert!(fish.contains("-n '__fish_seen_subcommand_from stats' -l quiet -s q"));
+        assert!(fish.contains("-l format -r -f -a 'geojson csv' -d 'Output format\\'s name'"));
+
+        let powershell = Shell::PowerShell.script(&command(), "tool");
+        assert!(powershell.contains("@('stats', 'Summarize a file')"));
+        assert!(powershell.contains("@('--format', 'Output format''s name')"));
+    }
+
+    #[test]
+    fn test_shell_from_str() {
+        assert_eq!("pwsh".parse::<Shell>().unwrap(), Shell::PowerShell);
+        assert_eq!("Zsh".parse::<Shell>().unwrap(), Shell::Zsh);
+        assert!("tcsh".parse::<Shell>().is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use std::io::{Read, Write};
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+
+/// Compression applied to the serialized output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// File extension conventionally appended to compressed files.
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Zstd => Some("zst"),
+        }
+    }
+
+    /// Value of the HTTP Content-Encoding header for compressed bodies.
+    pub fn content_encoding(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gzip"),
+            Compression::Zstd => Some("zstd"),
+        }
+    }
+
+    /// Guesses the compression of a file from its extension.
+    pub fn from_path(path: &str) -> Compression {
+        if path.ends_with(".gz") {
+            Compression::Gzip
+        } else if path.ends_with(".zst") {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Default level used when none is given.
+    pub fn default_level(&self) -> i32 {
+        match self {
+            Compression::None => 0,
+            Compression::Gzip => 6,
+            Compression::Zstd => 3,
+        }
+    }
+
+    /// Validates that `level` is supported by the algorithm.
+    pub fn validate_level(&self, level: i32) -> RandomGeojsonResult<()> {
+        let range = match self {
+            Compression::None => return Ok(()),
+            Compression::Gzip => 0..=9,
+            Compression::Zstd => 1..=22,
+        };
+        if range.contains(&level) {
+            Ok(())
+        } else {
+            Err(RandomGeojsonError::InvalidArgument(format!(
+                "Compression level for {:?} must be between {} and {}",
+                self,
+                range.start(),
+                range.end()
+            )))
+        }
+    }
+
+    /// Appends the compression extension to `path` unless it is already there.
+    pub fn output_path(&self, path: &str) -> String {
+        match self.extension() {
+            Some(ext) if !path.ends_with(&format!(".{}", ext)) => format!("{}.{}", path, ext),
+            _ => path.to_string(),
+        }
+    }
+
+    /// Compresses `data` at the given level.
+    pub fn compress(&self, data: &[u8], level: i32) -> RandomGeojsonResult<Vec<u8>> {
+        let failed = |e: std::io::Error| {
+            RandomGeojsonError::Serialize(format!("Failed to compress output: {}", e))
+        };
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(
+                    Vec::new(),
+                    flate2::Compression::new(level as u32),
+                );
+                encoder.write_all(data).map_err(failed)?;
+                encoder.finish().map_err(failed)
+            }
+            Compression::Zstd => zstd::stream::encode_all(data, level).map_err(failed),
+        }
+    }
+
+    /// Wraps `reader` to decompress data written with this compression as it
+    /// is read.
+    pub fn decoder<'a>(&self, reader: impl Read + 'a) -> RandomGeojsonResult<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Compression::None => Box::new(reader),
+            Compression::Gzip => Box::new(flate2::read::GzDecoder::new(reader)),
+            Compression::Zstd => {
+                Box::new(zstd::stream::read::Decoder::new(reader).map_err(|e| {
+                    RandomGeojsonError::InvalidInput(format!("Failed to decompress input: {}", e))
+                })?)
+            }
+        })
+    }
+
+    /// Decompresses data previously written with this compression.
+    pub fn decompress(&self, data: &[u8]) -> RandomGeojsonResult<Vec<u8>> {
+        let failed = |e: std::io::Error| {
+            RandomGeojsonError::InvalidInput(format!("Failed to decompress input: {}", e))
+        };
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Gzip => {
+                let mut decoded = Vec::new();
+                flate2::read::GzDecoder::new(data)
+                    .read_to_end(&mut decoded)
+                    .map_err(failed)?;
+                Ok(decoded)
+            }
+            Compression::Zstd => zstd::stream::decode_all(data).map_err(failed),
+        }
+    }
+}
+
+impl std::str::FromStr for Compression {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Compression::None),
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            "zstd" | "zst" => Ok(Compression::Zstd),
+            _ => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid compression: {}",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression_round_trip() {
+        let data = b"{\"type\":\"FeatureCollection\",\"features\":[]}".repeat(10);
+
+        let gz = Compression::Gzip.compress(&data, 6).unwrap();
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(gz.as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, data);
+        assert_eq!(Compression::Gzip.decompress(&gz).unwrap(), data);
+
+        let zst = Compression::Zstd.compress(&data, 19).unwrap();
+        assert_eq!(zstd::stream::decode_all(zst.as_slice()).unwrap(), data);
+    }
+
+    #[test]
+    fn test_compression_levels_and_paths() {
+        assert!(Compression::Zstd.validate_level(22).is_ok());
+        assert!(Compression::Zstd.validate_level(0).is_err());
+        assert!(Compression::Gzip.validate_level(10).is_err());
+        assert_eq!(
+            Compression::Zstd.output_path("random.geojson"),
+            "random.geojson.zst"
+        );
+        assert_eq!(
+            Compression::Gzip.output_path("a.geojson.gz"),
+            "a.geojson.gz"
+        );
+        assert_eq!(Compression::None.output_path("a.geojson"), "a.geojson");
+    }
+}
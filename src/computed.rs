@@ -0,0 +1,481 @@
+use geojson::{FeatureCollection, JsonValue, Value};
+use rand::Rng;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::random;
+use crate::series::split_args;
+use crate::tile::from_mercator;
+
+/// Digits of a geohash, five bits each.
+const GEOHASH_DIGITS: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Longest geohash, about 4 cm across.
+const MAX_GEOHASH_PRECISION: usize = 12;
+
+/// Most octaves of noise, beyond which the finer ones are too faint to
+/// matter.
+const MAX_OCTAVES: usize = 16;
+
+/// A property derived from the geometry of each feature once generated,
+/// e.g. to bucket features without a processing step.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComputedProperty {
+    /// The geohash of the centroid, with this many characters.
+    Geohash(usize),
+    /// Coherent noise at the centroid under the given name, so that nearby
+    /// features get similar values.
+    Noise { name: String, noise: Noise },
+}
+
+impl ComputedProperty {
+    /// Name of the property.
+    pub fn name(&self) -> &str {
+        match self {
+            ComputedProperty::Geohash(_) => "geohash",
+            ComputedProperty::Noise { name, .. } => name,
+        }
+    }
+
+    /// The value of the property for a feature with its centroid at `lon`,
+    /// `lat` in degrees, with `seed` picking the surface of noise.
+    pub fn value(&self, lon: f64, lat: f64, seed: u64) -> JsonValue {
+        match self {
+            ComputedProperty::Geohash(precision) => geohash(lon, lat, *precision).into(),
+            ComputedProperty::Noise { noise, .. } => noise.value(lon, lat, seed).into(),
+        }
+    }
+}
+
+impl std::str::FromStr for ComputedProperty {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            RandomGeojsonError::InvalidArgument(format!(
+                "Invalid computed property: {} (expected geohash:<1-{}>, e.g. geohash:7, or <name>:noise(...), e.g. elevation:noise(scale=0.5,octaves=4))",
+                s, MAX_GEOHASH_PRECISION
+            ))
+        };
+        let (name, value) = s.trim().split_once(':').ok_or_else(invalid)?;
+        let (name, value) = (name.trim(), value.trim());
+        if let Some(args) = value.strip_prefix("noise") {
+            if name.is_empty() {
+                return Err(invalid());
+            }
+            let noise = args.parse().map_err(|_| invalid())?;
+            let name = name.to_string();
+            return Ok(ComputedProperty::Noise { name, noise });
+        }
+        let precision: usize = value.parse().map_err(|_| invalid())?;
+        match name.to_lowercase().as_str() {
+            "geohash" if (1..=MAX_GEOHASH_PRECISION).contains(&precision) => {
+                Ok(ComputedProperty::Geohash(precision))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Computed properties separated by commas outside parentheses, e.g.
+/// "geohash:7,elevation:noise(scale=0.5,octaves=4)".
+pub fn parse_computed_properties(s: &str) -> RandomGeojsonResult<Vec<ComputedProperty>> {
+    split_args(s).into_iter().map(str::parse).collect()
+}
+
+/// Fractal gradient (Perlin) noise over longitude and latitude, scaled from
+/// `min` to `max`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Noise {
+    /// Size of the coarsest features of the surface in degrees.
+    pub scale: f64,
+    /// Layers of ever finer noise, each of half the size and weight of the
+    /// one before.
+    pub octaves: usize,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Noise {
+            scale: 1.0,
+            octaves: 4,
+            min: 0.0,
+            max: 1.0,
+        }
+    }
+}
+
+impl Noise {
+    /// The noise at `lon`, `lat` in degrees on the surface picked by `seed`.
+    pub fn value(&self, lon: f64, lat: f64, seed: u64) -> f64 {
+        let (mut sum, mut total) = (0.0, 0.0);
+        let (mut frequency, mut amplitude) = (1.0 / self.scale, 1.0);
+        for octave in 0..self.octaves as u64 {
+            sum += amplitude * perlin(lon * frequency, lat * frequency, seed ^ octave);
+            total += amplitude;
+            frequency *= 2.0;
+            amplitude /= 2.0;
+        }
+        // Perlin noise in two dimensions stays within ±√½
+        let share = ((sum / total) * std::f64::consts::SQRT_2 + 1.0) / 2.0;
+        self.min + (self.max - self.min) * share.clamp(0.0, 1.0)
+    }
+}
+
+/// Parses the arguments of `noise(...)`, any of `scale`, `octaves`, `min`
+/// and `max`, e.g. "(scale=0.5,octaves=4)". Empty or missing
+/// parentheses keep the defaults.
+impl std::str::FromStr for Noise {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            RandomGeojsonError::InvalidArgument(format!("Invalid noise '{}': {}", s, reason))
+        };
+        let mut noise = Noise::default();
+        let args = s.trim();
+        if args.is_empty() {
+            return Ok(noise);
+        }
+        let args = args
+            .strip_prefix('(')
+            .and_then(|args| args.strip_suffix(')'))
+            .ok_or_else(|| invalid("expected arguments in parentheses"))?;
+        for arg in split_args(args).into_iter().filter(|arg| !arg.is_empty()) {
+            let (key, value) = arg
+                .split_once('=')
+                .ok_or_else(|| invalid("expected key=value arguments"))?;
+            let value = value.trim();
+            let number = || {
+                value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|n| n.is_finite())
+                    .ok_or_else(|| invalid(&format!("{} must be a number", key.trim())))
+            };
+            match key.trim() {
+                "scale" => noise.scale = number()?,
+                "min" => noise.min = number()?,
+                "max" => noise.max = number()?,
+                "octaves" => {
+                    noise.octaves = value
+                        .parse()
+                        .ok()
+                        .filter(|n| (1..=MAX_OCTAVES).contains(n))
+                        .ok_or_else(|| {
+                            invalid(&format!("octaves must be from 1 to {}", MAX_OCTAVES))
+                        })?
+                }
+                key => return Err(invalid(&format!("unknown argument {}", key))),
+            }
+        }
+        if noise.scale <= 0.0 {
+            return Err(invalid("scale must be positive"));
+        }
+        if noise.min > noise.max {
+            return Err(invalid("min must not be above max"));
+        }
+        Ok(noise)
+    }
+}
+
+// Gradient noise at `x`, `y`: a random unit gradient at each corner of the
+// unit grid, blended smoothly across the cell.
+fn perlin(x: f64, y: f64, seed: u64) -> f64 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let corner = |dx: f64, dy: f64| {
+        let (cx, cy) = ((x0 + dx) as i64, (y0 + dy) as i64);
+        let angle = hash(cx, cy, seed) as f64 / u64::MAX as f64 * std::f64::consts::TAU;
+        let (sin, cos) = angle.sin_cos();
+        cos * (fx - dx) + sin * (fy - dy)
+    };
+    let fade = |t: f64| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+    let (u, v) = (fade(fx), fade(fy));
+    lerp(
+        lerp(corner(0.0, 0.0), corner(1.0, 0.0), u),
+        lerp(corner(0.0, 1.0), corner(1.0, 1.0), u),
+        v,
+    )
+}
+
+// Mixes the grid corner and seed into well-spread bits (SplitMix64).
+fn hash(x: i64, y: i64, seed: u64) -> u64 {
+    let mut z = seed
+        .wrapping_add((x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .wrapping_add((y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Adds the computed properties to every feature of `fc` with a geometry,
+/// whose coordinates are in the EPSG code `epsg` (4326 or 3857).
+pub fn add_computed_properties(
+    fc: &mut FeatureCollection,
+    computed: &[ComputedProperty],
+    epsg: Option<u32>,
+) -> RandomGeojsonResult<()> {
+    if computed.is_empty() {
+        return Ok(());
+    }
+    let to_lon_lat = lon_lat(epsg).ok_or_else(|| {
+        RandomGeojsonError::InvalidArgument(
+            "Computed properties need WGS84 or Web Mercator coordinates".to_string(),
+        )
+    })?;
+    // One surface of noise per property for the whole collection
+    let seeds: Vec<u64> = computed.iter().map(|_| random::rng().random()).collect();
+    for feature in &mut fc.features {
+        let Some((x, y)) = feature.geometry.as_ref().and_then(|g| centroid(&g.value)) else {
+            continue;
+        };
+        let (lon, lat) = to_lon_lat(x, y);
+        let properties = feature.properties.get_or_insert_default();
+        for (property, &seed) in computed.iter().zip(&seeds) {
+            properties.insert(property.name().to_string(), property.value(lon, lat, seed));
+        }
+    }
+    Ok(())
+}
+
+/// Converts coordinates to longitude and latitude in degrees.
+pub type ToLonLat = fn(f64, f64) -> (f64, f64);
+
+/// The conversion of coordinates in the EPSG code `epsg` to longitude and
+/// latitude, for WGS84 and Web Mercator only.
+pub fn lon_lat(epsg: Option<u32>) -> Option<ToLonLat> {
+    match epsg {
+        Some(4326) => Some(|x, y| (x, y)),
+        Some(3857) => Some(from_mercator),
+        _ => None,
+    }
+}
+
+/// Encodes a longitude and latitude in degrees as a geohash of `precision`
+/// characters.
+pub fn geohash(lon: f64, lat: f64, precision: usize) -> String {
+    let (mut lon_range, mut lat_range) = ((-180.0, 180.0), (-90.0, 90.0));
+    let mut hash = String::with_capacity(precision);
+    let mut bits = 0;
+    let mut digit = 0;
+    // Bits alternate between longitude and latitude, longitude first
+    let mut is_lon = true;
+    while hash.len() < precision {
+        let (range, value): (&mut (f64, f64), f64) = if is_lon {
+            (&mut lon_range, lon)
+        } else {
+            (&mut lat_range, lat)
+        };
+        let mid = (range.0 + range.1) / 2.0;
+        digit <<= 1;
+        if value >= mid {
+            digit |= 1;
+            range.0 = mid;
+        } else {
+            range.1 = mid;
+        }
+        is_lon = !is_lon;
+        bits += 1;
+        if bits == 5 {
+            hash.push(GEOHASH_DIGITS[digit] as char);
+            bits = 0;
+            digit = 0;
+        }
+    }
+    hash
+}
+
+/// The centroid of a geometry in its own coordinates: of the areas of its
+/// polygons if they have any, else of the lengths of its lines, else the
+/// mean of its positions. `None` for empty geometries.
+pub fn centroid(value: &Value) -> Option<(f64, f64)> {
+    let mut sums = [Weighted::default(); 3];
+    accumulate(value, &mut sums);
+    sums.iter().rev().find_map(Weighted::mean)
+}
+
+// A running weighted sum of positions.
+#[derive(Debug, Clone, Copy, Default)]
+struct Weighted {
+    x: f64,
+    y: f64,
+    weight: f64,
+}
+
+impl Weighted {
+    fn add(&mut self, x: f64, y: f64, weight: f64) {
+        self.x += x * weight;
+        self.y += y * weight;
+        self.weight += weight;
+    }
+
+    fn mean(&self) -> Option<(f64, f64)> {
+        (self.weight.abs() > 0.0).then(|| (self.x / self.weight, self.y / self.weight))
+    }
+}
+
+// Adds the positions of `value` to `sums`, indexed by dimension.
+fn accumulate(value: &Value, sums: &mut [Weighted; 3]) {
+    match value {
+        Value::Point(p) => sums[0].add(p[0], p[1], 1.0),
+        Value::MultiPoint(points) => {
+            for p in points {
+                sums[0].add(p[0], p[1], 1.0);
+            }
+        }
+        Value::LineString(line) => accumulate_line(line, sums),
+        Value::MultiLineString(lines) => {
+            for line in lines {
+                accumulate_line(line, sums);
+            }
+        }
+        Value::Polygon(rings) => accumulate_polygon(rings, sums),
+        Value::MultiPolygon(polygons) => {
+            for rings in polygons {
+                accumulate_polygon(rings, sums);
+            }
+        }
+        Value::GeometryCollection(geometries) => {
+            for geometry in geometries {
+                accumulate(&geometry.value, sums);
+            }
+        }
+    }
+}
+
+fn accumulate_line(line: &[Vec<f64>], sums: &mut [Weighted; 3]) {
+    for p in line {
+        sums[0].add(p[0], p[1], 1.0);
+    }
+    for segment in line.windows(2) {
+        let (a, b) = (&segment[0], &segment[1]);
+        let length = (b[0] - a[0]).hypot(b[1] - a[1]);
+        sums[1].add((a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0, length);
+    }
+}
+
+// Holes take their area away from the exterior ring, whichever way either
+// is wound.
+fn accumulate_polygon(rings: &[Vec<Vec<f64>>], sums: &mut [Weighted; 3]) {
+    for (i, ring) in rings.iter().enumerate() {
+        accumulate_line(ring, sums);
+        let (mut area, mut x, mut y) = (0.0, 0.0, 0.0);
+        for segment in ring.windows(2) {
+            let (a, b) = (&segment[0], &segment[1]);
+            let cross = a[0] * b[1] - b[0] * a[1];
+            area += cross / 2.0;
+            x += (a[0] + b[0]) * cross;
+            y += (a[1] + b[1]) * cross;
+        }
+        if area != 0.0 {
+            let sign = if i == 0 { 1.0 } else { -1.0 };
+            sums[2].add(x / (6.0 * area), y / (6.0 * area), sign * area.abs());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Feature, Geometry};
+
+    use super::*;
+
+    fn square(min: f64, max: f64) -> Vec<Vec<f64>> {
+        vec![
+            vec![min, min],
+            vec![max, min],
+            vec![max, max],
+            vec![min, max],
+            vec![min, min],
+        ]
+    }
+
+    #[test]
+    fn test_geohash() {
+        assert_eq!(geohash(-5.6, 42.6, 5), "ezs42");
+        assert_eq!(geohash(10.40744, 57.64911, 11), "u4pruydqqvj");
+        assert_eq!(geohash(0.0, 0.0, 1), "s");
+        assert_eq!(
+            "geohash:7".parse::<ComputedProperty>().unwrap(),
+            ComputedProperty::Geohash(7)
+        );
+        assert!("geohash:13".parse::<ComputedProperty>().is_err());
+        assert!("geohash".parse::<ComputedProperty>().is_err());
+    }
+
+    #[test]
+    fn test_noise() {
+        let computed =
+            parse_computed_properties("geohash:7, elevation:noise(scale=0.5,octaves=2,max=1000)")
+                .unwrap();
+        assert_eq!(computed.len(), 2);
+        let noise = Noise {
+            scale: 0.5,
+            octaves: 2,
+            min: 0.0,
+            max: 1000.0,
+        };
+        assert_eq!(
+            computed[1],
+            ComputedProperty::Noise {
+                name: "elevation".to_string(),
+                noise
+            }
+        );
+        assert_eq!(computed[1].name(), "elevation");
+        assert_eq!("(  )".parse::<Noise>().unwrap(), Noise::default());
+        assert_eq!("".parse::<Noise>().unwrap(), Noise::default());
+        for s in [
+            "temp:noise(octaves=0)",
+            "temp:noise(scale=-1)",
+            "temp:noise(min=5,max=1)",
+            "temp:noise(depth=3)",
+            ":noise",
+        ] {
+            assert!(s.parse::<ComputedProperty>().is_err(), "{}", s);
+        }
+
+        // Nearby values are close, distant ones are not tied together
+        let (mut near, mut far) = (0.0, 0.0);
+        for i in 0..500 {
+            let (lon, lat) = (i as f64 * 0.37, i as f64 * -0.21);
+            let value = noise.value(lon, lat, 7);
+            assert!((0.0..=1000.0).contains(&value));
+            assert_eq!(value, noise.value(lon, lat, 7));
+            near += (value - noise.value(lon + 0.001, lat, 7)).abs();
+            far += (value - noise.value(lon + 10.0, lat + 10.0, 7)).abs();
+        }
+        assert!(near * 20.0 < far, "{} {}", near, far);
+    }
+
+    #[test]
+    fn test_centroid() {
+        let polygon = Value::Polygon(vec![square(0.0, 4.0), square(0.0, 2.0)]);
+        let (x, y) = centroid(&polygon).unwrap();
+        assert!((x - 7.0 / 3.0).abs() < 1e-9 && (y - 7.0 / 3.0).abs() < 1e-9);
+        let line = Value::LineString(vec![vec![0.0, 0.0], vec![2.0, 0.0], vec![2.0, 0.0]]);
+        assert_eq!(centroid(&line), Some((1.0, 0.0)));
+        let point = Value::LineString(vec![vec![3.0, 1.0], vec![3.0, 1.0]]);
+        assert_eq!(centroid(&point), Some((3.0, 1.0)));
+        assert_eq!(centroid(&Value::MultiPoint(vec![])), None);
+    }
+
+    #[test]
+    fn test_add_computed_properties() {
+        let mut fc = FeatureCollection {
+            features: vec![
+                Feature::from(Geometry::new(Value::Point(vec![-5.6, 42.6]))),
+                Feature::default(),
+            ],
+            ..Default::default()
+        };
+        let computed = [ComputedProperty::Geohash(5)];
+        add_computed_properties(&mut fc, &computed, Some(4326)).unwrap();
+        assert_eq!(fc.features[0].property("geohash").unwrap(), "ezs42");
+        assert!(fc.features[1].properties.is_none());
+        assert!(add_computed_properties(&mut fc, &computed, None).is_err());
+    }
+}
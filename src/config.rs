@@ -0,0 +1,289 @@
+use std::path::Path;
+
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Command};
+use serde_json::{Map, Value as JsonValue};
+use toml::{Table, Value};
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+
+/// Reads a TOML, YAML or JSON config file, chosen by its extension, into a
+/// table of settings keyed by long flag name, in file order.
+pub fn read(path: &str) -> RandomGeojsonResult<Table> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| RandomGeojsonError::Io(format!("Failed to read {}: {}", path, e)))?;
+    let extension = Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase());
+    let parsed = match extension.as_deref() {
+        Some("toml") => toml::from_str::<Table>(&text).map_err(|e| e.to_string()),
+        // An empty YAML document holds no settings
+        Some("yaml" | "yml") if text.trim().is_empty() => Ok(Table::new()),
+        Some("yaml" | "yml") => serde_yaml::from_str::<Table>(&text).map_err(|e| e.to_string()),
+        Some("json") => serde_json::from_str::<Table>(&text).map_err(|e| e.to_string()),
+        _ => {
+            return Err(RandomGeojsonError::InvalidArgument(format!(
+                "Config file must end in .toml, .yaml, .yml or .json: {}",
+                path
+            )));
+        }
+    };
+    parsed.map_err(|e| RandomGeojsonError::InvalidInput(format!("Failed to parse {}: {}", path, e)))
+}
+
+/// Turns config settings into command-line arguments for `command`, leaving
+/// out flags already given on the command line so that those win.
+///
+/// Keys are long flag names, with `-` or `_` between words. Flags take
+/// `true` or `false`, lists become repeated (or comma-separated) values and
+/// tables become one `key:value` value per entry, so a `[property]` table of
+/// `height = "float"` means `--property height:float`.
+///
+/// When a subcommand runs, its arguments come from the top-level settings it
+/// accepts, overridden by those in a table named after it, e.g. `[serve]`.
+/// The returned arguments belong at the end of the command line.
+pub fn args(
+    command: &Command,
+    matches: &ArgMatches,
+    settings: &Table,
+) -> RandomGeojsonResult<Vec<String>> {
+    let subcommands: Vec<&str> = command.get_subcommands().map(|s| s.get_name()).collect();
+    let top_level = || {
+        settings
+            .iter()
+            .filter(|(key, _)| !subcommands.contains(&key.as_str()))
+    };
+
+    let Some((name, sub_matches)) = matches.subcommand() else {
+        return top_level()
+            .map(|(key, value)| setting_args(command, matches, key, value, true))
+            .collect::<RandomGeojsonResult<Vec<_>>>()
+            .map(|args| args.concat());
+    };
+
+    let subcommand = command
+        .find_subcommand(name)
+        .expect("matches come from this command");
+    let section = match settings.get(name) {
+        Some(Value::Table(section)) => Some(section),
+        Some(_) => {
+            return Err(RandomGeojsonError::InvalidArgument(format!(
+                "Config setting {} must be a table",
+                name
+            )));
+        }
+        None => None,
+    };
+    let mut args = Vec::new();
+    for (key, value) in top_level() {
+        // Top-level settings for the main command only apply if this
+        // subcommand has the same flag
+        let overridden = section.is_some_and(|section| section.contains_key(key));
+        if !overridden {
+            args.extend(setting_args(subcommand, sub_matches, key, value, false)?);
+        }
+    }
+    for (key, value) in section.into_iter().flatten() {
+        args.extend(setting_args(subcommand, sub_matches, key, value, true)?);
+    }
+    Ok(args)
+}
+
+// Arguments setting the flag `key` of `command` to `value`. Unknown keys are
+// an error if `strict`, and skipped otherwise.
+fn setting_args(
+    command: &Command,
+    matches: &ArgMatches,
+    key: &str,
+    value: &Value,
+    strict: bool,
+) -> RandomGeojsonResult<Vec<String>> {
+    let long = key.replace('_', "-");
+    let arg = command
+        .get_arguments()
+        .find(|arg| arg.get_long() == Some(long.as_str()) && long != "config");
+    let Some(arg) = arg else {
+        if strict {
+            return Err(RandomGeojsonError::InvalidArgument(format!(
+                "Unknown config setting: {}",
+                key
+            )));
+        }
+        return Ok(Vec::new());
+    };
+    if matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine) {
+        return Ok(Vec::new());
+    }
+
+    let invalid = |expected: &str| {
+        RandomGeojsonError::InvalidArgument(format!("Config setting {} must be {}", key, expected))
+    };
+    let flag = format!("--{}", long);
+    if !arg.get_action().takes_values() {
+        return match value {
+            Value::Boolean(true) => Ok(vec![flag]),
+            Value::Boolean(false) => Ok(Vec::new()),
+            _ => Err(invalid("true or false")),
+        };
+    }
+
+    let scalar = |value: &Value| match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Integer(n) => Ok(n.to_string()),
+        Value::Float(n) => Ok(n.to_string()),
+        Value::Boolean(b) => Ok(b.to_string()),
+        _ => Err(invalid(
+            "a string, number or boolean, or a list or table of them",
+        )),
+    };
+    let values = match value {
+        Value::Array(items) => items.iter().map(scalar).collect::<Result<Vec<_>, _>>()?,
+        Value::Table(entries) => entries
+            .iter()
+            .map(|(name, value)| Ok(format!("{}:{}", name, scalar(value)?)))
+            .collect::<Result<Vec<_>, _>>()?,
+        value => vec![scalar(value)?],
+    };
+    if values.len() > 1 && arg.get_value_delimiter().is_some() {
+        return Ok(vec![format!("{}={}", flag, values.join(","))]);
+    }
+    Ok(values
+        .into_iter()
+        .map(|value| format!("{}={}", flag, value))
+        .collect())
+}
+
+/// The effective settings of the flags of `command` in `matches`, defaults
+/// included, keyed by long flag name so that [`args`] reads them back: flags
+/// as booleans, repeated or comma-separated values as lists and other values
+/// as strings. Flags without a value and counted flags are left out.
+pub fn settings(command: &Command, matches: &ArgMatches) -> Map<String, JsonValue> {
+    let mut settings = Map::new();
+    for arg in command.get_arguments() {
+        let (Some(long), id) = (arg.get_long(), arg.get_id().as_str()) else {
+            continue;
+        };
+        let value = match arg.get_action() {
+            ArgAction::SetTrue | ArgAction::SetFalse => JsonValue::Bool(matches.get_flag(id)),
+            ArgAction::Set | ArgAction::Append => {
+                let Ok(Some(raw)) = matches.try_get_raw(id) else {
+                    continue;
+                };
+                let values: Vec<JsonValue> = raw
+                    .map(|value| JsonValue::String(value.to_string_lossy().into_owned()))
+                    .collect();
+                if matches!(arg.get_action(), ArgAction::Append)
+                    || arg.get_value_delimiter().is_some()
+                {
+                    JsonValue::Array(values)
+                } else {
+                    values.into_iter().next().unwrap_or(JsonValue::Null)
+                }
+            }
+            _ => continue,
+        };
+        settings.insert(long.to_string(), value);
+    }
+    settings
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Arg;
+
+    use super::*;
+
+    fn command() -> Command {
+        Command::new("random-geojson")
+            .args_conflicts_with_subcommands(true)
+            .arg(Arg::new("length").long("length").default_value("100"))
+            .arg(Arg::new("pretty").long("pretty").action(ArgAction::SetTrue))
+            .arg(
+                Arg::new("property")
+                    .long("property")
+                    .action(ArgAction::Append),
+            )
+            .arg(
+                Arg::new("scaling_sizes")
+                    .long("scaling-sizes")
+                    .value_delimiter(','),
+            )
+            .subcommand(
+                Command::new("serve")
+                    .arg(Arg::new("length").long("length"))
+                    .arg(Arg::new("port").long("port")),
+            )
+    }
+
+    fn parse(toml: &str) -> Table {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_args() {
+        let settings = parse(
+            r#"
+            length = 5
+            pretty = true
+            scaling_sizes = [10, 20]
+            property = { name = "string", height = "float" }
+
+            [serve]
+            port = 9000
+            "#,
+        );
+        let matches = command().get_matches_from(["random-geojson"]);
+        assert_eq!(
+            args(&command(), &matches, &settings).unwrap(),
+            [
+                "--length=5",
+                "--pretty",
+                "--scaling-sizes=10,20",
+                "--property=name:string",
+                "--property=height:float",
+            ]
+        );
+
+        // Flags on the command line win over the file
+        let matches = command().get_matches_from(["random-geojson", "--length", "7"]);
+        let args = args(&command(), &matches, &settings).unwrap();
+        assert!(!args.iter().any(|arg| arg.starts_with("--length")));
+    }
+
+    #[test]
+    fn test_settings() {
+        let matches =
+            command().get_matches_from(["random-geojson", "--pretty", "--scaling-sizes", "1,2"]);
+        let settings = settings(&command(), &matches);
+        assert_eq!(settings["length"], "100");
+        assert_eq!(settings["pretty"], true);
+        assert_eq!(settings["scaling-sizes"], serde_json::json!(["1", "2"]));
+        assert!(!settings.contains_key("property"));
+
+        // They read back as the same arguments
+        let table: Table = serde_json::from_value(JsonValue::Object(settings)).unwrap();
+        let matches = command().get_matches_from(["random-geojson"]);
+        assert_eq!(
+            args(&command(), &matches, &table).unwrap(),
+            ["--length=100", "--pretty", "--scaling-sizes=1,2"]
+        );
+    }
+
+    #[test]
+    fn test_subcommand_args() {
+        let settings = parse("length = 5\npretty = true\n[serve]\nport = 9000\n");
+        let matches = command().get_matches_from(["random-geojson", "serve"]);
+        assert_eq!(
+            args(&command(), &matches, &settings).unwrap(),
+            ["--length=5", "--port=9000"]
+        );
+
+        let typo = parse("[serve]\nprot = 9000\n");
+        assert!(args(&command(), &matches, &typo).is_err());
+        let typo = parse("lenght = 5\n");
+        let matches = command().get_matches_from(["random-geojson"]);
+        assert!(args(&command(), &matches, &typo).is_err());
+        let invalid = parse("pretty = \"yes\"\n");
+        assert!(args(&command(), &matches, &invalid).is_err());
+    }
+}
@@ -0,0 +1,614 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::anchor::project;
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::geometry::{Bounds, Crs, RandomGeometry, random_geographic_coords};
+use crate::spacing::{Distance, EARTH_RADIUS};
+
+/// Most cells a grid may have, to catch a --cell-size far too small for the
+/// area before running out of memory.
+const MAX_GRID_CELLS: usize = 10_000_000;
+
+/// A layer of polygons covering the sampled area without gaps or overlaps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coverage {
+    /// The Voronoi cells of random seed points.
+    Voronoi,
+    /// Regular flat-topped hexagons.
+    HexGrid,
+    /// Regular squares.
+    SquareGrid,
+    /// The Delaunay triangles of random seed points.
+    Delaunay,
+}
+
+impl Coverage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Coverage::Voronoi => "voronoi",
+            Coverage::HexGrid => "hexgrid",
+            Coverage::SquareGrid => "squaregrid",
+            Coverage::Delaunay => "delaunay",
+        }
+    }
+
+    /// Generates the polygons of a coverage of the bounds of `crs`.
+    ///
+    /// A Voronoi coverage has `length` cells and a Delaunay one triangulates
+    /// `length` seed points and the corners of the bounds. The cells of a grid have sides
+    /// of `cell_size`, or a size giving about `length` cells if it is `None`;
+    /// sides in metres are measured at the middle latitude of the bounds.
+    pub fn generate(
+        &self,
+        crs: &Crs,
+        length: usize,
+        cell_size: Option<Distance>,
+    ) -> RandomGeojsonResult<Vec<RandomGeometry>> {
+        let bounds = crs.bounds();
+        let cells = match self {
+            Coverage::Voronoi => {
+                let seeds: Vec<(f64, f64)> =
+                    (0..length).map(|_| random_geographic_coords(crs)).collect();
+                voronoi(&seeds, &bounds)
+            }
+            Coverage::Delaunay => {
+                let seeds: Vec<(f64, f64)> =
+                    (0..length).map(|_| random_geographic_coords(crs)).collect();
+                delaunay(&seeds, &bounds)
+            }
+            Coverage::HexGrid | Coverage::SquareGrid => {
+                let hexagons = *self == Coverage::HexGrid;
+                let side = match cell_size {
+                    Some(Distance::Degrees(degrees)) => (degrees, degrees),
+                    Some(Distance::Meters(meters)) => {
+                        let degrees = meters / (EARTH_RADIUS * std::f64::consts::PI / 180.0);
+                        let latitude = (bounds.min_y + bounds.max_y) / 2.0;
+                        (degrees / latitude.to_radians().cos().max(1e-6), degrees)
+                    }
+                    None => {
+                        // Area of a hexagon or square with sides of 1
+                        let unit_area = if hexagons { 1.5 * 3f64.sqrt() } else { 1.0 };
+                        let area = bounds.width() * bounds.height();
+                        let side = (area / (unit_area * length.max(1) as f64)).sqrt();
+                        (side, side)
+                    }
+                };
+                grid(hexagons, side, &bounds)?
+            }
+        };
+        Ok(cells
+            .into_iter()
+            .map(|mut ring| {
+                ring.push(ring[0]);
+                RandomGeometry::Polygon(vec![project(crs, &ring)])
+            })
+            .collect())
+    }
+}
+
+impl std::str::FromStr for Coverage {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "voronoi" => Ok(Coverage::Voronoi),
+            "hexgrid" => Ok(Coverage::HexGrid),
+            "squaregrid" => Ok(Coverage::SquareGrid),
+            "delaunay" => Ok(Coverage::Delaunay),
+            _ => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid coverage: {} (expected voronoi, hexgrid, squaregrid or delaunay)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Computes the Voronoi cell of every seed within `bounds`, as open
+/// counter-clockwise rings in the order of the seeds. Neighbouring cells
+/// share their vertices exactly.
+///
+/// Each cell starts as the bounds and is cut by the bisector between its
+/// seed and every other seed close enough to matter. Seeds are kept in a
+/// grid so the search can stop once the remaining ones are more than twice
+/// the cell's radius away.
+pub fn voronoi(seeds: &[(f64, f64)], bounds: &Bounds) -> Vec<Vec<(f64, f64)>> {
+    let grid = SeedGrid::new(seeds, bounds);
+    let cells = (0..seeds.len())
+        .map(|i| grid.voronoi_cell(i, bounds))
+        .collect();
+    snap(cells, bounds)
+}
+
+/// Computes the Delaunay triangulation of `points` and the corners of
+/// `bounds`, as open counter-clockwise rings covering the bounds. Points that
+/// are not strictly inside the bounds, and repeated points, are skipped.
+///
+/// Starts from the bounds split in two triangles and inserts the points one
+/// by one (Bowyer-Watson), in rows so each search starts near the last one.
+pub fn delaunay(points: &[(f64, f64)], bounds: &Bounds) -> Vec<Vec<(f64, f64)>> {
+    let mut points: Vec<(f64, f64)> = points
+        .iter()
+        .copied()
+        .filter(|&(x, y)| {
+            bounds.min_x < x && x < bounds.max_x && bounds.min_y < y && y < bounds.max_y
+        })
+        .collect();
+    let size = (bounds.width() * bounds.height() / points.len().max(1) as f64).sqrt();
+    let order = |&(x, y): &(f64, f64)| {
+        let row = ((y - bounds.min_y) / size).floor();
+        (row, if row % 2.0 == 0.0 { x } else { -x })
+    };
+    points.sort_by(|a, b| order(a).partial_cmp(&order(b)).unwrap());
+
+    let mut mesh = Triangulation {
+        vertices: vec![
+            (bounds.min_x, bounds.min_y),
+            (bounds.max_x, bounds.min_y),
+            (bounds.max_x, bounds.max_y),
+            (bounds.min_x, bounds.max_y),
+        ],
+        triangles: vec![[0, 1, 2], [0, 2, 3]],
+        neighbours: vec![[None, Some(1), None], [None, None, Some(0)]],
+        alive: vec![true, true],
+        last: 0,
+    };
+    for p in points {
+        mesh.insert(p);
+    }
+    mesh.triangles
+        .iter()
+        .zip(&mesh.alive)
+        .filter(|(_, alive)| **alive)
+        .map(|(triangle, _)| triangle.iter().map(|&v| mesh.vertices[v]).collect())
+        .collect()
+}
+
+// Triangles as counter-clockwise vertex indices. Neighbour k is the triangle
+// across the edge opposite vertex k, if any; replaced triangles are kept but
+// no longer alive.
+struct Triangulation {
+    vertices: Vec<(f64, f64)>,
+    triangles: Vec<[usize; 3]>,
+    neighbours: Vec<[Option<usize>; 3]>,
+    alive: Vec<bool>,
+    // Latest triangle, where the search for the next point starts
+    last: usize,
+}
+
+impl Triangulation {
+    fn edge(&self, t: usize, k: usize) -> (usize, usize) {
+        let triangle = self.triangles[t];
+        (triangle[(k + 1) % 3], triangle[(k + 2) % 3])
+    }
+
+    // Walks from the last triangle towards `p` until reaching the triangle
+    // that contains it.
+    fn locate(&self, p: (f64, f64)) -> usize {
+        let mut t = self.last;
+        'walk: loop {
+            for k in 0..3 {
+                let (a, b) = self.edge(t, k);
+                if orientation(self.vertices[a], self.vertices[b], p) < 0.0
+                    && let Some(next) = self.neighbours[t][k]
+                {
+                    t = next;
+                    continue 'walk;
+                }
+            }
+            return t;
+        }
+    }
+
+    fn in_circumcircle(&self, t: usize, p: (f64, f64)) -> bool {
+        let [a, b, c] =
+            self.triangles[t].map(|v| (self.vertices[v].0 - p.0, self.vertices[v].1 - p.1));
+        let det = (a.0 * a.0 + a.1 * a.1) * (b.0 * c.1 - c.0 * b.1)
+            - (b.0 * b.0 + b.1 * b.1) * (a.0 * c.1 - c.0 * a.1)
+            + (c.0 * c.0 + c.1 * c.1) * (a.0 * b.1 - b.0 * a.1);
+        det > 0.0
+    }
+
+    // Removes the triangles whose circumcircle contains `p` and connects
+    // the edges of the hole they leave to it.
+    fn insert(&mut self, p: (f64, f64)) {
+        let start = self.locate(p);
+        if self.triangles[start].iter().any(|&v| self.vertices[v] == p) {
+            return;
+        }
+        let mut cavity = HashSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(t) = stack.pop() {
+            for next in self.neighbours[t].into_iter().flatten() {
+                if !cavity.contains(&next) && self.in_circumcircle(next, p) {
+                    cavity.insert(next);
+                    stack.push(next);
+                }
+            }
+        }
+        // Edges of the hole, with the triangles on either side
+        let mut edges = Vec::new();
+        for &t in &cavity {
+            for k in 0..3 {
+                let outside = self.neighbours[t][k];
+                if outside.is_none_or(|n| !cavity.contains(&n)) {
+                    let (a, b) = self.edge(t, k);
+                    edges.push((a, b, t, outside));
+                }
+            }
+            self.alive[t] = false;
+        }
+
+        let vertex = self.vertices.len();
+        self.vertices.push(p);
+        let first = self.triangles.len();
+        let mut starting = HashMap::new();
+        for (i, &(a, b, inside, outside)) in edges.iter().enumerate() {
+            self.triangles.push([vertex, a, b]);
+            self.neighbours.push([outside, None, None]);
+            self.alive.push(true);
+            if let Some(n) = outside {
+                for slot in &mut self.neighbours[n] {
+                    if *slot == Some(inside) {
+                        *slot = Some(first + i);
+                    }
+                }
+            }
+            starting.insert(a, first + i);
+        }
+        // The new triangles surround the point, each sharing an edge with the
+        // one whose hole edge starts where its own ends
+        for (i, &(_, b, _, _)) in edges.iter().enumerate() {
+            let next = starting[&b];
+            self.neighbours[first + i][1] = Some(next);
+            self.neighbours[next][2] = Some(first + i);
+        }
+        self.last = first;
+    }
+}
+
+// Positive if `p` is left of the line from `a` to `b`.
+fn orientation(a: (f64, f64), b: (f64, f64), p: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+/// Covers `bounds` with a grid of hexagons or squares, as open
+/// counter-clockwise rings row by row from the bottom left. `side` is the
+/// length of a side along x and along y. The grid starts at the bottom left
+/// corner and cells reaching past the bounds are cut at their edges.
+pub fn grid(
+    hexagons: bool,
+    (side_x, side_y): (f64, f64),
+    bounds: &Bounds,
+) -> RandomGeojsonResult<Vec<Vec<(f64, f64)>>> {
+    if !(side_x > 0.0 && side_y > 0.0 && side_x.is_finite() && side_y.is_finite()) {
+        return Err(RandomGeojsonError::InvalidArgument(
+            "The grid cell size must be greater than 0".to_string(),
+        ));
+    }
+    // Flat-topped hexagons are spaced 1.5 sides apart across and sqrt(3)
+    // sides apart vertically, with every other column shifted half a row
+    let (step_x, step_y) = if hexagons {
+        (1.5 * side_x, 3f64.sqrt() * side_y)
+    } else {
+        (side_x, side_y)
+    };
+    let columns = (bounds.width() / step_x).ceil() as usize + 1;
+    let rows = (bounds.height() / step_y).ceil() as usize + 2;
+    if columns.saturating_mul(rows) > MAX_GRID_CELLS {
+        return Err(RandomGeojsonError::InvalidArgument(format!(
+            "The grid cell size is too small for the area, it would take more than {} cells",
+            MAX_GRID_CELLS
+        )));
+    }
+    let mut cells = Vec::new();
+    for row in 0..rows {
+        for column in 0..columns {
+            let x = bounds.min_x + column as f64 * step_x;
+            let cell = if hexagons {
+                let shift = if column % 2 == 1 { 0.5 } else { 0.0 };
+                let y = bounds.min_y + (row as f64 - 1.0 + shift) * step_y;
+                (0..6)
+                    .map(|k| {
+                        let angle = (k as f64 * 60.0).to_radians();
+                        (x + side_x * angle.cos(), y + side_y * angle.sin())
+                    })
+                    .collect()
+            } else {
+                let y = bounds.min_y + row as f64 * step_y;
+                vec![
+                    (x, y),
+                    (x + side_x, y),
+                    (x + side_x, y + side_y),
+                    (x, y + side_y),
+                ]
+            };
+            let cell = clip_to_bounds(cell, bounds);
+            if cell.len() >= 3 {
+                cells.push(cell);
+            }
+        }
+    }
+    let tolerance = snap_tolerance(bounds);
+    Ok(snap(cells, bounds)
+        .into_iter()
+        .filter(|cell| cell.len() >= 3 && ring_area(cell) > tolerance * tolerance)
+        .collect())
+}
+
+// Seeds bucketed into square cells holding one seed on average.
+struct SeedGrid<'a> {
+    seeds: &'a [(f64, f64)],
+    origin: (f64, f64),
+    size: f64,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    // Rings of cells needed to reach every seed from any cell
+    span: i64,
+}
+
+impl<'a> SeedGrid<'a> {
+    fn new(seeds: &'a [(f64, f64)], bounds: &Bounds) -> Self {
+        let size = (bounds.width() * bounds.height() / seeds.len().max(1) as f64).sqrt();
+        let mut grid = SeedGrid {
+            seeds,
+            origin: (bounds.min_x, bounds.min_y),
+            size,
+            cells: HashMap::new(),
+            span: (bounds.width().max(bounds.height()) / size).ceil() as i64 + 1,
+        };
+        for (i, &seed) in seeds.iter().enumerate() {
+            grid.cells.entry(grid.key(seed)).or_default().push(i);
+        }
+        grid
+    }
+
+    fn key(&self, (x, y): (f64, f64)) -> (i64, i64) {
+        (
+            ((x - self.origin.0) / self.size).floor() as i64,
+            ((y - self.origin.1) / self.size).floor() as i64,
+        )
+    }
+
+    fn voronoi_cell(&self, i: usize, bounds: &Bounds) -> Vec<(f64, f64)> {
+        let seed = self.seeds[i];
+        let mut cell = vec![
+            (bounds.min_x, bounds.min_y),
+            (bounds.max_x, bounds.min_y),
+            (bounds.max_x, bounds.max_y),
+            (bounds.min_x, bounds.max_y),
+        ];
+        let (cx, cy) = self.key(seed);
+        for ring in 0..=self.span {
+            // Seeds in this ring of grid cells are at least this far away
+            let nearest = (ring - 1).max(0) as f64 * self.size;
+            let radius = cell
+                .iter()
+                .map(|&(x, y)| (x - seed.0).hypot(y - seed.1))
+                .fold(0.0, f64::max);
+            if nearest > 2.0 * radius {
+                break;
+            }
+            for gx in cx - ring..=cx + ring {
+                for gy in cy - ring..=cy + ring {
+                    if (gx - cx).abs() != ring && (gy - cy).abs() != ring {
+                        continue;
+                    }
+                    for &j in self.cells.get(&(gx, gy)).into_iter().flatten() {
+                        if j != i && self.seeds[j] != seed {
+                            cell = clip_to_bisector(&cell, seed, self.seeds[j]);
+                        }
+                    }
+                }
+            }
+        }
+        cell
+    }
+}
+
+// Merges vertices of different rings that differ only by rounding errors,
+// so adjacent polygons share them exactly, and drops the repeated vertices
+// this leaves within a ring.
+fn snap(rings: Vec<Vec<(f64, f64)>>, bounds: &Bounds) -> Vec<Vec<(f64, f64)>> {
+    let tolerance = snap_tolerance(bounds);
+    let key = |(x, y): (f64, f64)| {
+        (
+            (x / tolerance).round() as i64,
+            (y / tolerance).round() as i64,
+        )
+    };
+    let mut canonical: HashMap<(i64, i64), Vec<(f64, f64)>> = HashMap::new();
+    rings
+        .into_iter()
+        .map(|ring| {
+            let mut snapped: Vec<(f64, f64)> = Vec::with_capacity(ring.len());
+            for p in ring {
+                let (kx, ky) = key(p);
+                let existing = (kx - 1..=kx + 1)
+                    .flat_map(|x| (ky - 1..=ky + 1).map(move |y| (x, y)))
+                    .filter_map(|k| canonical.get(&k))
+                    .flatten()
+                    .find(|q| (p.0 - q.0).abs() <= tolerance && (p.1 - q.1).abs() <= tolerance)
+                    .copied();
+                let p = existing.unwrap_or_else(|| {
+                    canonical.entry((kx, ky)).or_default().push(p);
+                    p
+                });
+                if snapped.last() != Some(&p) {
+                    snapped.push(p);
+                }
+            }
+            if snapped.len() > 1 && snapped.first() == snapped.last() {
+                snapped.pop();
+            }
+            snapped
+        })
+        .collect()
+}
+fn snap_tolerance(bounds: &Bounds) -> f64 {
+    bounds.width().max(bounds.height()) * 1e-9
+}
+
+// Shoelace formula, positive for counter-clockwise rings.
+fn ring_area(ring: &[(f64, f64)]) -> f64 {
+    (0..ring.len())
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+// Keeps the part of a convex ring closer to `seed` than to `other`.
+fn clip_to_bisector(ring: &[(f64, f64)], seed: (f64, f64), other: (f64, f64)) -> Vec<(f64, f64)> {
+    let (mx, my) = ((seed.0 + other.0) / 2.0, (seed.1 + other.1) / 2.0);
+    let (dx, dy) = (other.0 - seed.0, other.1 - seed.1);
+    clip(ring, |(x, y)| (x - mx) * dx + (y - my) * dy)
+}
+
+// Keeps the part of a convex ring inside the bounds.
+fn clip_to_bounds(ring: Vec<(f64, f64)>, bounds: &Bounds) -> Vec<(f64, f64)> {
+    let ring = clip(&ring, |(x, _)| bounds.min_x - x);
+    let ring = clip(&ring, |(x, _)| x - bounds.max_x);
+    let ring = clip(&ring, |(_, y)| bounds.min_y - y);
+    clip(&ring, |(_, y)| y - bounds.max_y)
+}
+
+// Keeps the part of a convex ring where `side` is not positive
+// (Sutherland-Hodgman), with the crossing points computed exactly on the
+// boundary when `side` is linear.
+pub(crate) fn clip(ring: &[(f64, f64)], side: impl Fn((f64, f64)) -> f64) -> Vec<(f64, f64)> {
+    if ring.is_empty() {
+        return Vec::new();
+    }
+    let mut clipped = Vec::with_capacity(ring.len() + 1);
+    for (k, &current) in ring.iter().enumerate() {
+        let previous = ring[(k + ring.len() - 1) % ring.len()];
+        let (a, b) = (side(previous), side(current));
+        if (a <= 0.0) != (b <= 0.0) {
+            let t = a / (a - b);
+            clipped.push((
+                previous.0 + t * (current.0 - previous.0),
+                previous.1 + t * (current.1 - previous.1),
+            ));
+        }
+        if b <= 0.0 {
+            clipped.push(current);
+        }
+    }
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::Unit;
+
+    use super::*;
+
+    // Checks that the cells add up to the bounds and that every edge inside
+    // the bounds is shared, reversed, by a neighbour.
+    fn assert_tiles(cells: &[Vec<(f64, f64)>], bounds: &Bounds) {
+        let total: f64 = cells.iter().map(|c| ring_area(c)).sum();
+        let expected = bounds.width() * bounds.height();
+        assert!((total - expected).abs() < expected * 1e-9, "{}", total);
+        let edges: std::collections::HashSet<[u64; 4]> = cells
+            .iter()
+            .flat_map(|c| (0..c.len()).map(move |k| (c[k], c[(k + 1) % c.len()])))
+            .map(|(a, b)| [a.0.to_bits(), a.1.to_bits(), b.0.to_bits(), b.1.to_bits()])
+            .collect();
+        let on_bounds = |x: u64, y: u64| {
+            let (x, y) = (f64::from_bits(x), f64::from_bits(y));
+            x == bounds.min_x || x == bounds.max_x || y == bounds.min_y || y == bounds.max_y
+        };
+        for e in &edges {
+            let along_bounds = on_bounds(e[0], e[1]) && on_bounds(e[2], e[3]);
+            assert!(along_bounds || edges.contains(&[e[2], e[3], e[0], e[1]]));
+        }
+        assert!(cells.iter().all(|c| ring_area(c) > 0.0));
+    }
+
+    #[test]
+    fn test_voronoi() {
+        let bounds = Bounds::new(0.0, 0.0, 4.0, 2.0, Unit::Degrees);
+        let cells = voronoi(&[(1.0, 1.0), (3.0, 1.0)], &bounds);
+        assert_eq!(ring_area(&cells[0]), 4.0);
+        assert!(cells[0].iter().all(|&(x, _)| x <= 2.0));
+        assert!(cells[1].iter().all(|&(x, _)| x >= 2.0));
+    }
+
+    #[test]
+    fn test_voronoi_covers_bounds() {
+        let bounds = Bounds::new(-10.0, 40.0, 10.0, 55.0, Unit::Degrees);
+        let crs = Crs::WGS84.clipped(bounds).unwrap();
+        let seeds: Vec<(f64, f64)> = (0..500).map(|_| random_geographic_coords(&crs)).collect();
+        let cells = voronoi(&seeds, &bounds);
+        assert_tiles(&cells, &bounds);
+        for (cell, seed) in cells.iter().zip(&seeds) {
+            // Every vertex is at least as close to its seed as to the others
+            for &(x, y) in cell {
+                let d = (x - seed.0).hypot(y - seed.1);
+                assert!(seeds.iter().all(|s| (x - s.0).hypot(y - s.1) >= d - 1e-9));
+            }
+        }
+    }
+
+    #[test]
+    fn test_delaunay() {
+        let bounds = Bounds::new(0.0, 0.0, 2.0, 2.0, Unit::Degrees);
+        let triangles = delaunay(&[(1.0, 1.0), (1.0, 1.0), (2.0, 1.0)], &bounds);
+        // The centre connects to the four corners; the rest is skipped
+        assert_eq!(triangles.len(), 4);
+        assert!(triangles.iter().all(|t| ring_area(t) == 1.0));
+        assert!(triangles.iter().all(|t| t.contains(&(1.0, 1.0))));
+    }
+
+    #[test]
+    fn test_delaunay_covers_bounds() {
+        let bounds = Bounds::new(-10.0, 40.0, 10.0, 55.0, Unit::Degrees);
+        let crs = Crs::WGS84.clipped(bounds).unwrap();
+        let seeds: Vec<(f64, f64)> = (0..500).map(|_| random_geographic_coords(&crs)).collect();
+        let triangles = delaunay(&seeds, &bounds);
+        // Euler's formula for 504 points, 4 of them on the hull
+        assert_eq!(triangles.len(), 2 * 504 - 2 - 4);
+        assert_tiles(&triangles, &bounds);
+        // No seed lies inside the circumcircle of a triangle
+        for t in &triangles {
+            let (a, b, c) = (t[0], t[1], t[2]);
+            let d = 2.0 * (a.0 * (b.1 - c.1) + b.0 * (c.1 - a.1) + c.0 * (a.1 - b.1));
+            let norm = |p: (f64, f64)| p.0 * p.0 + p.1 * p.1;
+            let ux = (norm(a) * (b.1 - c.1) + norm(b) * (c.1 - a.1) + norm(c) * (a.1 - b.1)) / d;
+            let uy = (norm(a) * (c.0 - b.0) + norm(b) * (a.0 - c.0) + norm(c) * (b.0 - a.0)) / d;
+            let r = (a.0 - ux).hypot(a.1 - uy);
+            assert!(seeds.iter().all(|s| (s.0 - ux).hypot(s.1 - uy) >= r - 1e-6));
+        }
+    }
+
+    #[test]
+    fn test_square_grid() {
+        let bounds = Bounds::new(0.0, 0.0, 2.5, 1.0, Unit::Degrees);
+        let cells = grid(false, (1.0, 0.5), &bounds).unwrap();
+        // Three columns, the last one cut in half, and two rows
+        assert_eq!(cells.len(), 6);
+        assert_eq!(
+            cells[0],
+            vec![(0.0, 0.0), (1.0, 0.0), (1.0, 0.5), (0.0, 0.5)]
+        );
+        assert_eq!(ring_area(&cells[2]), 0.25);
+        assert_tiles(&cells, &bounds);
+        assert!(grid(false, (0.0, 0.0), &bounds).is_err());
+        assert!(grid(false, (1e-6, 1e-6), &bounds).is_err());
+    }
+
+    #[test]
+    fn test_hex_grid() {
+        let bounds = Bounds::new(-10.0, 40.0, 10.0, 55.0, Unit::Degrees);
+        let cells = grid(true, (0.7, 0.5), &bounds).unwrap();
+        assert_tiles(&cells, &bounds);
+        // Cells away from the edges are whole hexagons
+        let whole = cells.iter().filter(|c| c.len() == 6).count();
+        assert!(whole > cells.len() / 2);
+
+        let crs = Crs::WGS84.clipped(bounds).unwrap();
+        let sized = Coverage::HexGrid.generate(&crs, 300, None).unwrap();
+        assert!((250..350).contains(&sized.len()), "{}", sized.len());
+    }
+}
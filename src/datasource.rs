@@ -0,0 +1,246 @@
+use geozero::error::{GeozeroError, Result as GeozeroResult};
+use geozero::{ColumnValue, FeatureProcessor, GeomProcessor, GeozeroDatasource, GeozeroGeometry};
+use rand::Rng;
+use serde_json::Value;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::generator::{GeometryGenerator, generate_geometry};
+use crate::geometry::{Crs, RandomGeometry};
+use crate::property::random_property_value;
+use crate::random;
+use crate::shape::{LineShape, PolygonShape};
+
+/// The kind of geometry of generated features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GeometryKind {
+    Point,
+    LineString,
+    Polygon,
+    /// Any of the three, drawn at random for each feature.
+    #[default]
+    All,
+}
+
+impl std::str::FromStr for GeometryKind {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "point" => Ok(GeometryKind::Point),
+            "linestring" => Ok(GeometryKind::LineString),
+            "polygon" => Ok(GeometryKind::Polygon),
+            "all" => Ok(GeometryKind::All),
+            _ => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid geometry type: {} (expected Point, LineString, Polygon or All)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Random features as a geozero datasource, so that any geozero sink (WKB,
+/// WKT, SVG, GeoPackage, PostGIS and so on) can consume them. Features are
+/// generated one at a time as they are processed, so they are never all in
+/// memory, and `random::with_seed` makes them repeatable.
+pub struct RandomFeatures<'a> {
+    pub crs: &'a Crs,
+    /// Number of features.
+    pub length: usize,
+    pub geometry_kind: GeometryKind,
+    /// Generator of the geometries, in place of `geometry_kind` and the
+    /// shapes if set.
+    pub generator: Option<&'a dyn GeometryGenerator>,
+    /// Shape of the polygons.
+    pub polygon: PolygonShape,
+    /// Shape of the lines.
+    pub line: LineShape,
+    /// Number of random properties of each feature, named `prop1`,
+    /// `prop2` and so on.
+    pub num_properties: usize,
+}
+
+impl<'a> RandomFeatures<'a> {
+    /// Features of any geometry kind and the default shapes, without
+    /// properties.
+    pub fn new(crs: &'a Crs, length: usize) -> Self {
+        RandomFeatures {
+            crs,
+            length,
+            geometry_kind: GeometryKind::All,
+            generator: None,
+            polygon: PolygonShape::default(),
+            line: LineShape::default(),
+            num_properties: 0,
+        }
+    }
+
+    fn random_geometry(&self) -> RandomGeojsonResult<RandomGeometry> {
+        if let Some(generator) = self.generator {
+            return Ok(generate_geometry(self.crs, generator));
+        }
+        let kind = match self.geometry_kind {
+            GeometryKind::All => match random::rng().random_range(0..3) {
+                0 => GeometryKind::Point,
+                1 => GeometryKind::LineString,
+                _ => GeometryKind::Polygon,
+            },
+            kind => kind,
+        };
+        match kind {
+            GeometryKind::LineString => self.line.random_linestring(self.crs),
+            GeometryKind::Polygon => self.polygon.random_polygon(self.crs),
+            _ => Ok(RandomGeometry::random_point(self.crs)),
+        }
+    }
+}
+
+impl GeozeroDatasource for RandomFeatures<'_> {
+    fn process<P: FeatureProcessor>(&mut self, processor: &mut P) -> GeozeroResult<()> {
+        processor.dataset_begin(Some("random"))?;
+        for idx in 0..self.length as u64 {
+            processor.feature_begin(idx)?;
+            if self.num_properties > 0 {
+                processor.properties_begin()?;
+                for i in 0..self.num_properties {
+                    let name = format!("prop{}", i + 1);
+                    let value = random_property_value();
+                    let text;
+                    let value = match &value {
+                        Value::Bool(b) => ColumnValue::Bool(*b),
+                        Value::Number(n) => match n.as_i64() {
+                            Some(n) => ColumnValue::Long(n),
+                            None => ColumnValue::Double(n.as_f64().unwrap_or(f64::NAN)),
+                        },
+                        Value::String(s) => ColumnValue::String(s),
+                        v => {
+                            text = v.to_string();
+                            ColumnValue::Json(&text)
+                        }
+                    };
+                    processor.property(i, &name, &value)?;
+                }
+                processor.properties_end()?;
+            }
+            processor.geometry_begin()?;
+            self.random_geometry()
+                .map_err(|e| GeozeroError::Geometry(e.to_string()))?
+                .process_geom(processor)?;
+            processor.geometry_end()?;
+            processor.feature_end(idx)?;
+        }
+        processor.dataset_end()
+    }
+}
+
+impl GeozeroGeometry for RandomGeometry {
+    fn process_geom<P: GeomProcessor>(&self, processor: &mut P) -> GeozeroResult<()> {
+        match self {
+            RandomGeometry::Point(p) => {
+                processor.point_begin(0)?;
+                processor.xy(p[0], p[1], 0)?;
+                processor.point_end(0)
+            }
+            RandomGeometry::LineString(line) => process_line(line, true, 0, processor),
+            RandomGeometry::Polygon(rings) => {
+                processor.polygon_begin(true, rings.len(), 0)?;
+                for (i, ring) in rings.iter().enumerate() {
+                    process_line(ring, false, i, processor)?;
+                }
+                processor.polygon_end(true, 0)
+            }
+        }
+    }
+}
+
+// Passes a line or ring to `processor`, `tagged` when it is a geometry of
+// its own rather than part of a polygon.
+fn process_line<P: GeomProcessor>(
+    line: &[Vec<f64>],
+    tagged: bool,
+    idx: usize,
+    processor: &mut P,
+) -> GeozeroResult<()> {
+    processor.linestring_begin(tagged, line.len(), idx)?;
+    for (i, p) in line.iter().enumerate() {
+        processor.xy(p[0], p[1], i)?;
+    }
+    processor.linestring_end(tagged, idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::FeatureCollection;
+    use geozero::geojson::GeoJsonWriter;
+
+    use super::*;
+    use crate::geometry::{Bounds, Unit};
+    use crate::shape::PolygonStyle;
+
+    fn write(features: &mut RandomFeatures) -> FeatureCollection {
+        let mut json = Vec::new();
+        features
+            .process(&mut GeoJsonWriter::new(&mut json))
+            .unwrap();
+        String::from_utf8(json).unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn test_process() {
+        let crs = Crs::WGS84;
+        let mut features = RandomFeatures {
+            geometry_kind: GeometryKind::Polygon,
+            polygon: PolygonShape {
+                style: PolygonStyle::Circle,
+                ..Default::default()
+            },
+            num_properties: 3,
+            ..RandomFeatures::new(&crs, 20)
+        };
+        let fc = write(&mut features);
+        assert_eq!(fc.features.len(), 20);
+        for feature in &fc.features {
+            let geojson::Value::Polygon(rings) = &feature.geometry.as_ref().unwrap().value else {
+                panic!("not a polygon");
+            };
+            assert_eq!(rings[0].len(), 65);
+            assert_eq!(feature.properties.as_ref().unwrap().len(), 3);
+        }
+    }
+
+    #[test]
+    fn test_generator() {
+        // Points at the origin, moved out of the area
+        struct Far;
+        impl GeometryGenerator for Far {
+            fn generate(
+                &self,
+                _rng: &mut dyn rand::RngCore,
+                (lon, _): (f64, f64),
+            ) -> RandomGeometry {
+                RandomGeometry::Point(vec![lon, 80.0])
+            }
+        }
+        let crs = Crs::WGS84
+            .clipped(Bounds::new(0.0, 0.0, 10.0, 10.0, Unit::Degrees))
+            .unwrap();
+        let fc = write(&mut RandomFeatures {
+            generator: Some(&Far),
+            ..RandomFeatures::new(&crs, 10)
+        });
+        for feature in &fc.features {
+            let geojson::Value::Point(p) = &feature.geometry.as_ref().unwrap().value else {
+                panic!("not a point");
+            };
+            assert!((0.0..=10.0).contains(&p[0]) && p[1] == 10.0, "{:?}", p);
+        }
+    }
+
+    #[test]
+    fn test_seeded() {
+        let crs = Crs::WebMercator;
+        let fc = || random::with_seed(5, || write(&mut RandomFeatures::new(&crs, 30)));
+        let (a, b) = (fc(), fc());
+        assert_eq!(a, b);
+        assert!(a.features.iter().all(|f| f.properties.is_none()));
+    }
+}
@@ -0,0 +1,473 @@
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::geometry::{Bounds, Unit};
+use crate::random;
+
+/// Most cells of a weight grid, to keep its cumulative weights in memory.
+const MAX_CELLS: usize = 100_000_000;
+
+/// A grid of weights over an area, in geographic coordinates, that
+/// coordinates are sampled in proportion to, e.g. a population raster.
+#[derive(Debug)]
+pub struct DensityGrid {
+    bounds: Bounds,
+    columns: usize,
+    rows: usize,
+    /// Running totals of the weights of the cells, row by row from the
+    /// north.
+    cumulative: Vec<f64>,
+}
+
+/// A weight grid in JSON: the bounding box `[west, south, east, north]` in
+/// degrees and rows of weights from the north.
+#[derive(Deserialize)]
+struct JsonGrid {
+    bbox: [f64; 4],
+    weights: Vec<Vec<f64>>,
+}
+
+impl DensityGrid {
+    /// Creates a grid over `bounds` from `rows` rows of `columns` weights
+    /// from the north. Negative and non-finite weights count as 0.
+    pub fn new(
+        bounds: Bounds,
+        columns: usize,
+        weights: impl IntoIterator<Item = f64>,
+    ) -> RandomGeojsonResult<Self> {
+        let mut total = 0.0;
+        let cumulative: Vec<f64> = weights
+            .into_iter()
+            .map(|w| {
+                if w.is_finite() && w > 0.0 {
+                    total += w;
+                }
+                total
+            })
+            .collect();
+        if columns == 0 || cumulative.is_empty() || !cumulative.len().is_multiple_of(columns) {
+            return Err(RandomGeojsonError::InvalidInput(
+                "Weight grid rows must all have the same, non-zero number of cells".to_string(),
+            ));
+        }
+        if total <= 0.0 {
+            return Err(RandomGeojsonError::InvalidInput(
+                "Weight grid has no positive weight".to_string(),
+            ));
+        }
+        if !(bounds.min_x >= -180.0
+            && bounds.max_x <= 180.0
+            && bounds.min_y >= -90.0
+            && bounds.max_y <= 90.0
+            && bounds.width() > 0.0
+            && bounds.height() > 0.0)
+        {
+            return Err(RandomGeojsonError::InvalidInput(
+                "Weight grid must cover an area in longitude and latitude (EPSG:4326)".to_string(),
+            ));
+        }
+        Ok(DensityGrid {
+            bounds,
+            columns,
+            rows: cumulative.len() / columns,
+            cumulative,
+        })
+    }
+
+    /// Reads a GeoTIFF (".tif" or ".tiff") in EPSG:4326, whose first band
+    /// holds the weights, or a JSON grid such as
+    /// `{"bbox": [-10, 35, 5, 45], "weights": [[0, 1], [3, 2]]}`.
+    pub fn read(path: &str) -> RandomGeojsonResult<Self> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| RandomGeojsonError::Io(format!("Failed to read {}: {}", path, e)))?;
+        let invalid = |e: String| {
+            RandomGeojsonError::InvalidInput(format!("Invalid weight grid {}: {}", path, e))
+        };
+        let lower = path.to_lowercase();
+        if lower.ends_with(".tif") || lower.ends_with(".tiff") {
+            let raster = read_geotiff(&bytes).map_err(invalid)?;
+            return DensityGrid::new(raster.bounds, raster.columns, raster.values)
+                .map_err(|e| invalid(e.to_string()));
+        }
+        let grid: JsonGrid = serde_json::from_slice(&bytes).map_err(|e| invalid(e.to_string()))?;
+        let [west, south, east, north] = grid.bbox;
+        let columns = grid.weights.first().map_or(0, Vec::len);
+        if grid.weights.iter().any(|row| row.len() != columns) {
+            return Err(invalid("rows have different lengths".to_string()));
+        }
+        let bounds = Bounds::new(west, south, east, north, Unit::Degrees);
+        DensityGrid::new(bounds, columns, grid.weights.into_iter().flatten())
+            .map_err(|e| invalid(e.to_string()))
+    }
+
+    /// Geographic bounds of the grid.
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    /// Draws a coordinate within the grid with a probability proportional
+    /// to the weight of its cell, or `None` if it is outside `bounds`.
+    pub fn candidate(&self, bounds: &Bounds) -> Option<(f64, f64)> {
+        let mut rng = random::rng();
+        let total = *self.cumulative.last()?;
+        let pick = rng.random_range(0.0..total);
+        let cell = self
+            .cumulative
+            .partition_point(|&c| c <= pick)
+            .min(self.cumulative.len() - 1);
+        let (row, column) = (cell / self.columns, cell % self.columns);
+        let width = self.bounds.width() / self.columns as f64;
+        let height = self.bounds.height() / self.rows as f64;
+        let x = self.bounds.min_x + (column as f64 + rng.random::<f64>()) * width;
+        let y = self.bounds.max_y - (row as f64 + rng.random::<f64>()) * height;
+        bounds.contains(x, y).then_some((x, y))
+    }
+}
+
+// The first band of a GeoTIFF and its bounds.
+struct Raster {
+    bounds: Bounds,
+    columns: usize,
+    values: Vec<f64>,
+}
+
+// Tags of a TIFF image file directory.
+const IMAGE_WIDTH: u16 = 256;
+const IMAGE_LENGTH: u16 = 257;
+const BITS_PER_SAMPLE: u16 = 258;
+const COMPRESSION: u16 = 259;
+const STRIP_OFFSETS: u16 = 273;
+const SAMPLES_PER_PIXEL: u16 = 277;
+const ROWS_PER_STRIP: u16 = 278;
+const STRIP_BYTE_COUNTS: u16 = 279;
+const PREDICTOR: u16 = 317;
+const TILE_WIDTH: u16 = 322;
+const SAMPLE_FORMAT: u16 = 339;
+const MODEL_PIXEL_SCALE: u16 = 33550;
+const MODEL_TIEPOINT: u16 = 33922;
+const GDAL_NODATA: u16 = 42113;
+
+// Reads a striped, uncompressed or deflated GeoTIFF with one value per
+// pixel or interleaved samples, of which the first is kept.
+fn read_geotiff(bytes: &[u8]) -> Result<Raster, String> {
+    let tiff = Tiff::new(bytes)?;
+    let entries = tiff.entries()?;
+    let field = |tag: u16| entries.iter().find(|e| e.tag == tag);
+    let numbers = |tag: u16| field(tag).map(|e| tiff.numbers(e)).transpose();
+    let number = |tag: u16, default: Option<f64>| {
+        numbers(tag)?
+            .and_then(|n| n.first().copied())
+            .or(default)
+            .ok_or_else(|| format!("missing TIFF tag {}", tag))
+    };
+    if field(TILE_WIDTH).is_some() {
+        return Err("tiled GeoTIFFs are not supported, write strips".to_string());
+    }
+    let columns = number(IMAGE_WIDTH, None)? as usize;
+    let rows = number(IMAGE_LENGTH, None)? as usize;
+    if columns == 0 || rows == 0 {
+        return Err("empty image".to_string());
+    }
+    if columns.saturating_mul(rows) > MAX_CELLS {
+        return Err(format!("more than {} cells", MAX_CELLS));
+    }
+    let bits = number(BITS_PER_SAMPLE, Some(1.0))? as usize;
+    let samples = number(SAMPLES_PER_PIXEL, Some(1.0))? as usize;
+    let format = number(SAMPLE_FORMAT, Some(1.0))? as u16;
+    let predictor = number(PREDICTOR, Some(1.0))? as u16;
+    let compression = number(COMPRESSION, Some(1.0))? as u16;
+    let rows_per_strip = (number(ROWS_PER_STRIP, Some(rows as f64))? as usize).clamp(1, rows);
+    if !matches!((format, bits), (1 | 2, 8 | 16 | 32) | (3, 32 | 64)) {
+        return Err(format!(
+            "unsupported samples of {} bits in format {}",
+            bits, format
+        ));
+    }
+    if predictor != 1 && !(predictor == 2 && format != 3 && samples == 1) {
+        return Err(format!(
+            "unsupported predictor {} for {} samples per pixel",
+            predictor, samples
+        ));
+    }
+
+    let offsets = numbers(STRIP_OFFSETS)?.ok_or("missing strip offsets")?;
+    let counts = numbers(STRIP_BYTE_COUNTS)?.ok_or("missing strip byte counts")?;
+    let bytes_per_sample = bits / 8;
+    let row_bytes = columns * samples * bytes_per_sample;
+    let mut data = Vec::with_capacity(rows * row_bytes);
+    for (&offset, &count) in offsets.iter().zip(&counts) {
+        let strip = bytes
+            .get(offset as usize..(offset + count) as usize)
+            .ok_or("strip past the end of the file")?;
+        match compression {
+            1 => data.extend_from_slice(strip),
+            8 | 32946 => {
+                ZlibDecoder::new(strip)
+                    .read_to_end(&mut data)
+                    .map_err(|e| format!("invalid deflated strip: {}", e))?;
+            }
+            _ => return Err(format!("unsupported compression {}", compression)),
+        }
+    }
+    if data.len() < rows * row_bytes {
+        return Err(format!(
+            "{} bytes of pixels, expected {} in strips of {} rows",
+            data.len(),
+            rows * row_bytes,
+            rows_per_strip
+        ));
+    }
+
+    let nodata = field(GDAL_NODATA)
+        .and_then(|e| tiff.ascii(e))
+        .and_then(|s| s.trim().parse::<f64>().ok());
+    let mut values = Vec::with_capacity(rows * columns);
+    for row in data.chunks_exact(row_bytes).take(rows) {
+        let mut previous = 0i64;
+        for (i, sample) in row.chunks_exact(bytes_per_sample).enumerate() {
+            let mut value = tiff.sample(sample, format);
+            if predictor == 2 {
+                // Each integer is the difference from the previous sample
+                // of the same band, wrapping at its width
+                let modulus = 1i64 << bits;
+                let sum = (previous + value as i64).rem_euclid(modulus);
+                previous = sum;
+                value = if format == 2 && sum >= modulus / 2 {
+                    (sum - modulus) as f64
+                } else {
+                    sum as f64
+                };
+            }
+            if i % samples == 0 {
+                values.push(if Some(value) == nodata { 0.0 } else { value });
+            }
+        }
+    }
+
+    let scale = numbers(MODEL_PIXEL_SCALE)?.ok_or("missing ModelPixelScale")?;
+    let tiepoint = numbers(MODEL_TIEPOINT)?.ok_or("missing ModelTiepoint")?;
+    let (&[sx, sy, ..], &[i, j, _, x, y, ..]) = (scale.as_slice(), tiepoint.as_slice()) else {
+        return Err("invalid georeferencing".to_string());
+    };
+    let west = x - i * sx;
+    let north = y + j * sy;
+    let bounds = Bounds::new(
+        west,
+        north - rows as f64 * sy,
+        west + columns as f64 * sx,
+        north,
+        Unit::Degrees,
+    );
+    Ok(Raster {
+        bounds,
+        columns,
+        values,
+    })
+}
+
+// A TIFF file and its byte order.
+struct Tiff<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: usize,
+    // The offset of the values, or of the field holding them if they fit
+    offset: usize,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(bytes: &'a [u8]) -> Result<Self, String> {
+        let little_endian = match bytes.get(..4) {
+            Some(b"II*\0") => true,
+            Some(b"MM\0*") => false,
+            Some([b'I', b'I', 43, 0]) | Some([b'M', b'M', 0, 43]) => {
+                return Err("BigTIFF is not supported".to_string());
+            }
+            _ => return Err("not a TIFF file".to_string()),
+        };
+        Ok(Tiff {
+            bytes,
+            little_endian,
+        })
+    }
+
+    fn read<const N: usize>(&self, offset: usize) -> Result<[u8; N], String> {
+        let mut array: [u8; N] = self
+            .bytes
+            .get(offset..offset + N)
+            .and_then(|b| b.try_into().ok())
+            .ok_or("truncated TIFF file")?;
+        if !self.little_endian {
+            array.reverse();
+        }
+        Ok(array)
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, String> {
+        self.read(offset).map(u16::from_le_bytes)
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, String> {
+        self.read(offset).map(u32::from_le_bytes)
+    }
+
+    // The entries of the first image file directory.
+    fn entries(&self) -> Result<Vec<Entry>, String> {
+        let ifd = self.u32(4)? as usize;
+        (0..self.u16(ifd)? as usize)
+            .map(|i| {
+                let at = ifd + 2 + 12 * i;
+                let kind = self.u16(at + 2)?;
+                let count = self.u32(at + 4)? as usize;
+                let size = count * type_size(kind);
+                let offset = if size <= 4 {
+                    at + 8
+                } else {
+                    self.u32(at + 8)? as usize
+                };
+                Ok(Entry {
+                    tag: self.u16(at)?,
+                    kind,
+                    count,
+                    offset,
+                })
+            })
+            .collect()
+    }
+
+    fn numbers(&self, entry: &Entry) -> Result<Vec<f64>, String> {
+        let size = type_size(entry.kind);
+        (0..entry.count)
+            .map(|i| {
+                let at = entry.offset + i * size;
+                Ok(match entry.kind {
+                    1 => self.bytes.get(at).copied().ok_or("truncated TIFF file")? as f64,
+                    3 => self.u16(at)? as f64,
+                    4 => self.u32(at)? as f64,
+                    5 => self.u32(at)? as f64 / self.u32(at + 4)? as f64,
+                    11 => f32::from_le_bytes(self.read(at)?) as f64,
+                    12 => f64::from_le_bytes(self.read(at)?),
+                    kind => return Err(format!("unsupported TIFF field type {}", kind)),
+                })
+            })
+            .collect()
+    }
+
+    fn ascii(&self, entry: &Entry) -> Option<String> {
+        let bytes = self.bytes.get(entry.offset..entry.offset + entry.count)?;
+        Some(
+            String::from_utf8_lossy(bytes)
+                .trim_end_matches('\0')
+                .to_string(),
+        )
+    }
+
+    // A pixel sample of an unsigned (1), signed (2) or float (3) format.
+    fn sample(&self, bytes: &[u8], format: u16) -> f64 {
+        let mut array = [0u8; 8];
+        array[..bytes.len()].copy_from_slice(bytes);
+        if !self.little_endian {
+            array[..bytes.len()].reverse();
+        }
+        let unsigned = u64::from_le_bytes(array);
+        match (format, bytes.len()) {
+            (3, 4) => f32::from_bits(unsigned as u32) as f64,
+            (3, _) => f64::from_bits(unsigned),
+            (2, n) => {
+                // Sign-extend from the width of the sample
+                let shift = 64 - 8 * n as u32;
+                ((unsigned << shift) as i64 >> shift) as f64
+            }
+            _ => unsigned as f64,
+        }
+    }
+}
+
+// Bytes of a value of a TIFF field type.
+fn type_size(kind: u16) -> usize {
+    match kind {
+        3 => 2,
+        4 | 9 | 11 => 4,
+        5 | 10 | 12 => 8,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An uncompressed little-endian GeoTIFF of 8-bit weights with pixels of
+    // 1 degree, its north-west corner at `west`, `north`.
+    fn geotiff(columns: u16, rows: u16, pixels: &[u8], west: f64, north: f64) -> Vec<u8> {
+        let mut data = b"II*\0".to_vec();
+        data.extend(8u32.to_le_bytes());
+        let entries: [(u16, u16, u32, u32); 9] = [
+            (IMAGE_WIDTH, 3, 1, columns as u32),
+            (IMAGE_LENGTH, 3, 1, rows as u32),
+            (BITS_PER_SAMPLE, 3, 1, 8),
+            (COMPRESSION, 3, 1, 1),
+            (STRIP_OFFSETS, 4, 1, 0),
+            (ROWS_PER_STRIP, 3, 1, rows as u32),
+            (STRIP_BYTE_COUNTS, 4, 1, pixels.len() as u32),
+            (MODEL_PIXEL_SCALE, 12, 3, 0),
+            (MODEL_TIEPOINT, 12, 6, 0),
+        ];
+        let values_at = 8 + 2 + 12 * entries.len() + 4;
+        let scale_at = values_at;
+        let tiepoint_at = scale_at + 24;
+        let pixels_at = tiepoint_at + 48;
+        data.extend((entries.len() as u16).to_le_bytes());
+        for (tag, kind, count, value) in entries {
+            let value = match tag {
+                STRIP_OFFSETS => pixels_at as u32,
+                MODEL_PIXEL_SCALE => scale_at as u32,
+                MODEL_TIEPOINT => tiepoint_at as u32,
+                _ => value,
+            };
+            data.extend(tag.to_le_bytes());
+            data.extend(kind.to_le_bytes());
+            data.extend(count.to_le_bytes());
+            data.extend(value.to_le_bytes());
+        }
+        data.extend(0u32.to_le_bytes());
+        for value in [1.0, 1.0, 0.0, 0.0, 0.0, 0.0, west, north, 0.0] {
+            data.extend(f64::to_le_bytes(value));
+        }
+        data.extend(pixels);
+        data
+    }
+
+    #[test]
+    fn test_candidate() {
+        let bounds = Bounds::new(0.0, 0.0, 2.0, 2.0, Unit::Degrees);
+        // Only the north-east cell has weight
+        let grid = DensityGrid::new(bounds, 2, [0.0, 1.0, 0.0, -5.0]).unwrap();
+        for _ in 0..100 {
+            let (x, y) = grid.candidate(&bounds).unwrap();
+            assert!((1.0..2.0).contains(&x) && (1.0..2.0).contains(&y));
+        }
+        assert!(DensityGrid::new(bounds, 2, [0.0; 4]).is_err());
+        assert!(DensityGrid::new(bounds, 3, [1.0; 4]).is_err());
+    }
+
+    #[test]
+    fn test_read_geotiff() {
+        let bytes = geotiff(3, 2, &[0, 0, 9, 1, 0, 0], -10.0, 45.0);
+        let raster = read_geotiff(&bytes).unwrap();
+        assert_eq!(raster.columns, 3);
+        assert_eq!(raster.values, [0.0, 0.0, 9.0, 1.0, 0.0, 0.0]);
+        assert_eq!((raster.bounds.min_x, raster.bounds.min_y), (-10.0, 43.0));
+        assert_eq!((raster.bounds.max_x, raster.bounds.max_y), (-7.0, 45.0));
+        assert!(read_geotiff(b"not a tiff").is_err());
+        assert!(read_geotiff(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
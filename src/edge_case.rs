@@ -0,0 +1,343 @@
+use std::f64::consts::TAU;
+
+use geojson::Value;
+use rand::Rng;
+
+use crate::anchor::METERS_PER_DEGREE;
+use crate::coverage::clip;
+use crate::error::RandomGeojsonError;
+use crate::random;
+use crate::shape::destination;
+
+/// A family of geometries that often break code consuming GeoJSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeCase {
+    /// Geometries on or across ±180° longitude.
+    Antimeridian,
+    /// Geometries at, around or over ±90° latitude.
+    Poles,
+}
+
+/// A feature generated for an [`EdgeCase`], in WGS84.
+pub struct EdgeCaseFeature {
+    pub geometry: Value,
+    pub edge_case: EdgeCase,
+    /// The kind of geometry, e.g. "split_linestring".
+    pub fixture: &'static str,
+}
+
+impl EdgeCase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EdgeCase::Antimeridian => "antimeridian",
+            EdgeCase::Poles => "poles",
+        }
+    }
+
+    // The kinds of geometry generated for this edge case.
+    fn fixtures(&self) -> &'static [&'static str] {
+        match self {
+            EdgeCase::Antimeridian => &[
+                "point_on_antimeridian",
+                "split_linestring",
+                "unsplit_linestring",
+                "split_polygon",
+            ],
+            EdgeCase::Poles => &[
+                "point_at_pole",
+                "point_near_pole",
+                "linestring_over_pole",
+                "polygon_around_pole",
+                "polygon_near_pole",
+            ],
+        }
+    }
+}
+
+impl std::str::FromStr for EdgeCase {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "antimeridian" => Ok(EdgeCase::Antimeridian),
+            "poles" => Ok(EdgeCase::Poles),
+            _ => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid edge case: {} (expected antimeridian or poles)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Generates `length` features of the given edge cases, going through each
+/// of their kinds of geometry in turn so that every kind appears as soon as
+/// `length` allows.
+///
+/// Lines and polygons crossing the antimeridian are cut in two at ±180° as
+/// RFC 7946 section 3.1.9 recommends, except for `unsplit_linestring`s,
+/// which jump across it the way naive data does. Polygons around a pole
+/// follow a parallel all the way around and close along the pole.
+pub fn edge_cases(cases: &[EdgeCase], length: usize) -> Vec<EdgeCaseFeature> {
+    let fixtures: Vec<(EdgeCase, &'static str)> = cases
+        .iter()
+        .flat_map(|&case| case.fixtures().iter().map(move |&fixture| (case, fixture)))
+        .collect();
+    if fixtures.is_empty() {
+        return Vec::new();
+    }
+    (0..length)
+        .map(|i| {
+            let (edge_case, fixture) = fixtures[i % fixtures.len()];
+            EdgeCaseFeature {
+                geometry: fixture_geometry(fixture),
+                edge_case,
+                fixture,
+            }
+        })
+        .collect()
+}
+
+fn fixture_geometry(fixture: &str) -> Value {
+    let mut rng = random::rng();
+    // North or south
+    let hemisphere = if rng.random_bool(0.5) { 1.0 } else { -1.0 };
+    match fixture {
+        "point_on_antimeridian" => {
+            Value::Point(vec![180.0 * hemisphere, rng.random_range(-80.0..=80.0)])
+        }
+        "split_linestring" => Value::MultiLineString(split_line(&crossing_line())),
+        "unsplit_linestring" => Value::LineString(
+            crossing_line()
+                .into_iter()
+                .map(|(x, y)| vec![wrap(x), y])
+                .collect(),
+        ),
+        "split_polygon" => Value::MultiPolygon(split_polygon(&crossing_polygon())),
+        "point_at_pole" => Value::Point(vec![rng.random_range(-180.0..=180.0), 90.0 * hemisphere]),
+        "point_near_pole" => Value::Point(vec![
+            rng.random_range(-180.0..=180.0),
+            rng.random_range(89.9..90.0) * hemisphere,
+        ]),
+        "linestring_over_pole" => {
+            let lon = rng.random_range(-180.0..0.0);
+            let lat = rng.random_range(80.0..89.0) * hemisphere;
+            Value::LineString(vec![
+                vec![lon, lat],
+                vec![lon, 90.0 * hemisphere],
+                vec![lon + 180.0, lat],
+            ])
+        }
+        "polygon_around_pole" => Value::Polygon(vec![polar_cap(hemisphere)]),
+        "polygon_near_pole" => Value::Polygon(vec![near_pole(hemisphere)]),
+        _ => unreachable!("unknown fixture {}", fixture),
+    }
+}
+
+// Wraps a longitude into [-180, 180), counting the turns it takes.
+fn turns(lon: f64) -> f64 {
+    ((lon + 180.0) / 360.0).floor()
+}
+
+fn wrap(lon: f64) -> f64 {
+    lon - 360.0 * turns(lon)
+}
+
+// A line running east or west across 180° longitude, with longitudes past
+// 180 instead of wrapped.
+fn crossing_line() -> Vec<(f64, f64)> {
+    let mut rng = random::rng();
+    let n = rng.random_range(2..=6);
+    let mut lons: Vec<f64> = (0..n).map(|_| rng.random_range(170.0..190.0)).collect();
+    lons[0] = rng.random_range(170.0..179.5);
+    lons[n - 1] = rng.random_range(180.5..190.0);
+    lons.sort_by(f64::total_cmp);
+    if rng.random_bool(0.5) {
+        lons.reverse();
+    }
+    let mut lat: f64 = rng.random_range(-60.0..60.0);
+    lons.into_iter()
+        .map(|lon| {
+            lat += rng.random_range(-5.0..5.0);
+            (lon, lat)
+        })
+        .collect()
+}
+
+// A convex polygon straddling 180° longitude, counter-clockwise, with
+// longitudes past 180 instead of wrapped.
+fn crossing_polygon() -> Vec<(f64, f64)> {
+    let mut rng = random::rng();
+    let (lon, lat) = (
+        rng.random_range(178.0..182.0),
+        rng.random_range(-60.0..60.0),
+    );
+    let radius = rng.random_range(3.0..8.0);
+    let n = rng.random_range(4..=8);
+    let start = rng.random_range(0.0..TAU);
+    (0..n)
+        .map(|i| {
+            let angle = start + TAU * i as f64 / n as f64;
+            (lon + radius * angle.cos(), lat + radius * angle.sin())
+        })
+        .collect()
+}
+
+// Cuts a line whose longitudes may run past ±180 where it crosses the
+// antimeridian, ending one part and starting the next on either side of it.
+fn split_line(line: &[(f64, f64)]) -> Vec<Vec<Vec<f64>>> {
+    let mut parts = vec![Vec::new()];
+    for (i, &(x, y)) in line.iter().enumerate() {
+        if i > 0 {
+            let (px, py) = line[i - 1];
+            let (from, to) = (turns(px), turns(x));
+            if from != to {
+                let seam = 180.0 + 360.0 * from.min(to);
+                let crossing = py + (seam - px) / (x - px) * (y - py);
+                let side = if x > px { 180.0 } else { -180.0 };
+                parts.last_mut().unwrap().push(vec![side, crossing]);
+                parts.push(vec![vec![-side, crossing]]);
+            }
+        }
+        parts.last_mut().unwrap().push(vec![wrap(x), y]);
+    }
+    parts
+}
+
+// Cuts a convex ring straddling 180° longitude into its closed western and
+// eastern parts.
+fn split_polygon(ring: &[(f64, f64)]) -> Vec<Vec<Vec<Vec<f64>>>> {
+    let close = |part: Vec<(f64, f64)>, lon: &dyn Fn(f64) -> f64| {
+        let mut part: Vec<Vec<f64>> = part.into_iter().map(|(x, y)| vec![lon(x), y]).collect();
+        part.push(part[0].clone());
+        vec![part]
+    };
+    let west = clip(ring, |(x, _)| x - 180.0);
+    let east = clip(ring, |(x, _)| 180.0 - x);
+    vec![
+        close(west, &|x| x.min(180.0)),
+        close(east, &|x| x.max(180.0) - 360.0),
+    ]
+}
+
+// A counter-clockwise ring around a pole: along a parallel from -180° to
+// 180° longitude, or back for the south pole, then along the pole itself.
+fn polar_cap(hemisphere: f64) -> Vec<Vec<f64>> {
+    let mut rng = random::rng();
+    let lat = rng.random_range(60.0..85.0) * hemisphere;
+    let mut ring: Vec<Vec<f64>> = (-4i32..=4)
+        .map(|i| {
+            let lon = 45.0 * i as f64 * hemisphere;
+            // Both ends of the parallel meet at the seam
+            let jitter = if i.abs() == 4 {
+                0.0
+            } else {
+                rng.random_range(-2.0..2.0)
+            };
+            vec![lon, lat + jitter]
+        })
+        .collect();
+    ring.push(vec![180.0 * hemisphere, 90.0 * hemisphere]);
+    ring.push(vec![-180.0 * hemisphere, 90.0 * hemisphere]);
+    ring.push(ring[0].clone());
+    ring
+}
+
+// A small counter-clockwise circle within two degrees of a pole, not
+// containing it and not crossing the antimeridian.
+fn near_pole(hemisphere: f64) -> Vec<Vec<f64>> {
+    let mut rng = random::rng();
+    let (lon, lat) = (
+        rng.random_range(-150.0..150.0),
+        rng.random_range(88.0..89.5) * hemisphere,
+    );
+    // Less than half the way to the pole
+    let to_pole = (90.0 - lat.abs()) * METERS_PER_DEGREE;
+    let radius = rng.random_range(0.1..0.5) * to_pole;
+    let mut ring: Vec<Vec<f64>> = (0..16)
+        .rev()
+        .map(|i| {
+            let (x, y) = destination(lon, lat, TAU * i as f64 / 16.0, radius);
+            vec![x, y]
+        })
+        .collect();
+    ring.push(ring[0].clone());
+    ring
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edge_cases() {
+        let features = edge_cases(&[EdgeCase::Antimeridian, EdgeCase::Poles], 90);
+        assert_eq!(features.len(), 90);
+        assert_eq!(features[0].fixture, "point_on_antimeridian");
+        assert_eq!(features[4].fixture, "point_at_pole");
+        assert_eq!(features[9].fixture, "point_on_antimeridian");
+        for feature in &features {
+            let mut positions = Vec::new();
+            crate::geometry::for_each_position(&feature.geometry, &mut |p| {
+                positions.push(p.to_vec())
+            });
+            for p in positions {
+                assert!((-180.0..=180.0).contains(&p[0]), "{:?}", p);
+                assert!((-90.0..=90.0).contains(&p[1]), "{:?}", p);
+            }
+        }
+        assert!(edge_cases(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn test_split_line() {
+        for _ in 0..100 {
+            let parts = split_line(&crossing_line());
+            assert_eq!(parts.len(), 2);
+            let (end, start) = (parts[0].last().unwrap(), &parts[1][0]);
+            assert_eq!(end[0], -start[0]);
+            assert_eq!(end[0].abs(), 180.0);
+            assert_eq!(end[1], start[1]);
+            // Neither part crosses the antimeridian
+            for part in &parts {
+                assert!(part.windows(2).all(|w| (w[0][0] - w[1][0]).abs() < 180.0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_polygon() {
+        for _ in 0..100 {
+            let parts = split_polygon(&crossing_polygon());
+            assert_eq!(parts.len(), 2);
+            let (west, east) = (&parts[0][0], &parts[1][0]);
+            assert!(west.iter().all(|p| p[0] > 0.0 && p[0] <= 180.0));
+            assert!(east.iter().all(|p| p[0] < 0.0 && p[0] >= -180.0));
+            assert!(west.iter().any(|p| p[0] == 180.0));
+            assert!(east.iter().any(|p| p[0] == -180.0));
+            assert_eq!(west.first(), west.last());
+            assert_eq!(east.first(), east.last());
+        }
+    }
+
+    #[test]
+    fn test_polar_cap() {
+        for hemisphere in [1.0, -1.0] {
+            let ring = polar_cap(hemisphere);
+            assert_eq!(ring.first(), ring.last());
+            assert_eq!(ring[0][1], ring[8][1]);
+            assert_eq!(ring[0][0], -ring[8][0]);
+            // Counter-clockwise in longitude and latitude
+            let area: f64 = ring
+                .windows(2)
+                .map(|w| w[0][0] * w[1][1] - w[1][0] * w[0][1])
+                .sum();
+            assert!(area > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_edge_case_from_str() {
+        assert_eq!("Poles".parse::<EdgeCase>().unwrap(), EdgeCase::Poles);
+        assert!("equator".parse::<EdgeCase>().is_err());
+    }
+}
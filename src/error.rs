@@ -2,8 +2,183 @@ use thiserror::Error;
 
 pub type RandomGeojsonResult<T> = Result<T, RandomGeojsonError>;
 
+/// Exit code for invalid arguments, as for command-line parsing errors.
+pub const EXIT_INVALID_ARGUMENT: i32 = 2;
+/// Exit code for files or streams that could not be read or written.
+pub const EXIT_IO: i32 = 3;
+/// Exit code for data that could not be encoded or decoded.
+pub const EXIT_SERIALIZE: i32 = 4;
+/// Exit code for failed requests and server sockets.
+pub const EXIT_NETWORK: i32 = 5;
+
 #[derive(Error, Debug)]
 pub enum RandomGeojsonError {
+    /// An option, or a combination of options, that cannot be used.
     #[error("Invalid argument: {0}")]
     InvalidArgument(String),
+    /// Bounds or masks that leave no area to generate coordinates in.
+    #[error("{0}")]
+    InvalidBounds(String),
+    /// A format that does not exist or cannot hold the generated data.
+    #[error("{0}")]
+    UnsupportedFormat(String),
+    /// A file or stream that could not be read or written.
+    #[error("{0}")]
+    Io(String),
+    /// Data that could not be encoded in the output format.
+    #[error("{0}")]
+    Serialize(String),
+    /// An input file that is not what it should be, e.g. not valid GeoJSON.
+    #[error("{0}")]
+    InvalidInput(String),
+    /// A request to a server, or a server socket, that failed.
+    #[error("{0}")]
+    Network(String),
+}
+
+impl RandomGeojsonError {
+    /// A stable name of the kind of error, e.g. "invalid_argument".
+    pub fn code(&self) -> &'static str {
+        match self {
+            RandomGeojsonError::InvalidArgument(_) => "invalid_argument",
+            RandomGeojsonError::InvalidBounds(_) => "invalid_bounds",
+            RandomGeojsonError::UnsupportedFormat(_) => "unsupported_format",
+            RandomGeojsonError::Io(_) => "io",
+            RandomGeojsonError::Serialize(_) => "serialize",
+            RandomGeojsonError::InvalidInput(_) => "invalid_input",
+            RandomGeojsonError::Network(_) => "network",
+        }
+    }
+
+    /// The process exit code for the kind of error, so that scripts can
+    /// tell failures apart without parsing messages.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RandomGeojsonError::InvalidArgument(_)
+            | RandomGeojsonError::InvalidBounds(_)
+            | RandomGeojsonError::UnsupportedFormat(_) => EXIT_INVALID_ARGUMENT,
+            RandomGeojsonError::Io(_) => EXIT_IO,
+            RandomGeojsonError::Serialize(_) | RandomGeojsonError::InvalidInput(_) => {
+                EXIT_SERIALIZE
+            }
+            RandomGeojsonError::Network(_) => EXIT_NETWORK,
+        }
+    }
+
+    /// The message of the error, without the kind.
+    pub fn message(&self) -> &str {
+        match self {
+            RandomGeojsonError::InvalidArgument(message)
+            | RandomGeojsonError::InvalidBounds(message)
+            | RandomGeojsonError::UnsupportedFormat(message)
+            | RandomGeojsonError::Io(message)
+            | RandomGeojsonError::Serialize(message)
+            | RandomGeojsonError::InvalidInput(message)
+            | RandomGeojsonError::Network(message) => message,
+        }
+    }
+}
+
+/// How errors are reported on stderr.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// A line of text, e.g. "Error: Invalid argument: ...".
+    #[default]
+    Text,
+    /// A JSON object with the `code` of the error, its `message` and the
+    /// `flag` it is about, if any.
+    Json,
+}
+
+impl ErrorFormat {
+    /// Writes `error` to stderr in this format.
+    pub fn report(&self, error: &RandomGeojsonError) {
+        match self {
+            ErrorFormat::Text => eprintln!("Error: {}", error),
+            ErrorFormat::Json => eprintln!(
+                "{}",
+                error_json(
+                    error.code(),
+                    error.message(),
+                    mentioned_flag(error.message())
+                )
+            ),
+        }
+    }
+}
+
+impl std::str::FromStr for ErrorFormat {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(ErrorFormat::Text),
+            "json" => Ok(ErrorFormat::Json),
+            _ => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid error format: {} (expected text or json)",
+                s
+            ))),
+        }
+    }
+}
+
+/// The JSON object reporting an error of kind `code`, as written by
+/// [`ErrorFormat::Json`].
+pub fn error_json(code: &str, message: &str, flag: Option<&str>) -> serde_json::Value {
+    serde_json::json!({
+        "code": code,
+        "message": message,
+        "flag": flag,
+    })
+}
+
+// The first flag the message talks about, e.g. "--format".
+fn mentioned_flag(message: &str) -> Option<&str> {
+    let start = message.find("--")?;
+    let rest = &message[start..];
+    let end = rest[2..]
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+        .map_or(rest.len(), |i| i + 2);
+    (end > 2).then(|| &rest[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_json() {
+        let error = RandomGeojsonError::UnsupportedFormat(
+            "--invalid-rate needs --format geojson".to_string(),
+        );
+        let json = error_json(
+            error.code(),
+            error.message(),
+            mentioned_flag(error.message()),
+        );
+        assert_eq!(json["code"], "unsupported_format");
+        assert_eq!(json["message"], "--invalid-rate needs --format geojson");
+        assert_eq!(json["flag"], "--invalid-rate");
+        assert_eq!(
+            mentioned_flag("No room left, use a smaller --min-distance"),
+            Some("--min-distance")
+        );
+        assert_eq!(mentioned_flag("Value must be one or more"), None);
+        assert_eq!(mentioned_flag("a -- b"), None);
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let code = |error: RandomGeojsonError| error.exit_code();
+        assert_eq!(code(RandomGeojsonError::InvalidBounds(String::new())), 2);
+        assert_eq!(code(RandomGeojsonError::Io(String::new())), 3);
+        assert_eq!(code(RandomGeojsonError::InvalidInput(String::new())), 4);
+        assert_eq!(code(RandomGeojsonError::Network(String::new())), 5);
+    }
+
+    #[test]
+    fn test_error_format_from_str() {
+        assert_eq!("JSON".parse::<ErrorFormat>().unwrap(), ErrorFormat::Json);
+        assert!("xml".parse::<ErrorFormat>().is_err());
+    }
 }
@@ -0,0 +1,449 @@
+use geojson::JsonObject;
+use serde_json::Value;
+
+use crate::error::RandomGeojsonError;
+
+/// An expression computing a property from other properties of a feature,
+/// e.g. `population / area_km2` or `population > 100000 ? "city" : "town"`.
+///
+/// Expressions have numbers, strings in double quotes, `true`, `false`,
+/// `null`, property names, `index` for the index of the feature, the
+/// operators `+ - * / %`, `== != < <= > >=`, `&& || !`, `c ? a : b` and
+/// parentheses, with the precedence they have in C. Integers stay integers
+/// except through `/`, `+` joins strings, and anything involving a missing
+/// or null property, or a division by zero, is null.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr(Node);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Literal(Value),
+    Property(String),
+    Index,
+    Not(Box<Node>),
+    Negate(Box<Node>),
+    Binary(Op, Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Conditional(Box<Node>, Box<Node>, Box<Node>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl Expr {
+    /// Evaluates the expression for the feature at `index` with
+    /// `properties`.
+    pub fn evaluate(&self, properties: &JsonObject, index: usize) -> Value {
+        evaluate(&self.0, properties, index)
+    }
+
+    /// Names of the properties the expression refers to.
+    pub fn properties(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        collect_properties(&self.0, &mut names);
+        names
+    }
+}
+
+impl std::str::FromStr for Expr {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| {
+            RandomGeojsonError::InvalidArgument(format!("Invalid expression '{}': {}", s, reason))
+        };
+        let mut parser = Parser {
+            tokens: tokenize(s).map_err(invalid)?,
+            pos: 0,
+        };
+        let node = parser.conditional().map_err(invalid)?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(invalid(format!("unexpected {:?}", token)));
+        }
+        Ok(Expr(node))
+    }
+}
+
+fn collect_properties<'a>(node: &'a Node, names: &mut Vec<&'a str>) {
+    match node {
+        Node::Literal(_) | Node::Index => {}
+        Node::Property(name) => names.push(name),
+        Node::Not(a) | Node::Negate(a) => collect_properties(a, names),
+        Node::Binary(_, a, b) | Node::And(a, b) | Node::Or(a, b) => {
+            collect_properties(a, names);
+            collect_properties(b, names);
+        }
+        Node::Conditional(c, a, b) => {
+            collect_properties(c, names);
+            collect_properties(a, names);
+            collect_properties(b, names);
+        }
+    }
+}
+
+fn evaluate(node: &Node, properties: &JsonObject, index: usize) -> Value {
+    let eval = |node: &Node| evaluate(node, properties, index);
+    match node {
+        Node::Literal(value) => value.clone(),
+        Node::Property(name) => properties.get(name).cloned().unwrap_or(Value::Null),
+        Node::Index => index.into(),
+        Node::Not(a) => match eval(a) {
+            Value::Null => Value::Null,
+            a => Value::Bool(!truthy(&a)),
+        },
+        Node::Negate(a) => match eval(a) {
+            Value::Number(n) => match n.as_i64().and_then(i64::checked_neg) {
+                Some(i) => i.into(),
+                None => n.as_f64().map_or(Value::Null, |f| number(-f)),
+            },
+            _ => Value::Null,
+        },
+        Node::Binary(op, a, b) => binary(*op, eval(a), eval(b)),
+        Node::And(a, b) => match eval(a) {
+            a if truthy(&a) => eval(b),
+            a => a,
+        },
+        Node::Or(a, b) => match eval(a) {
+            a if truthy(&a) => a,
+            _ => eval(b),
+        },
+        Node::Conditional(c, a, b) => {
+            if truthy(&eval(c)) {
+                eval(a)
+            } else {
+                eval(b)
+            }
+        }
+    }
+}
+
+// Whether a value counts as true in a condition: true, non-zero numbers and
+// non-empty strings, arrays and objects.
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|f| f != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(members) => !members.is_empty(),
+    }
+}
+
+// A float as a JSON number, or null if it is not finite.
+fn number(f: f64) -> Value {
+    serde_json::Number::from_f64(f).map_or(Value::Null, Value::Number)
+}
+
+fn binary(op: Op, a: Value, b: Value) -> Value {
+    if a.is_null() || b.is_null() {
+        return match op {
+            Op::Equal => Value::Bool(a == b),
+            Op::NotEqual => Value::Bool(a != b),
+            _ => Value::Null,
+        };
+    }
+    match (op, &a, &b) {
+        (Op::Equal, _, _) => return Value::Bool(equal(&a, &b)),
+        (Op::NotEqual, _, _) => return Value::Bool(!equal(&a, &b)),
+        (Op::Add, Value::String(a), b) => return Value::String(format!("{}{}", a, text(b))),
+        (Op::Add, a, Value::String(b)) => return Value::String(format!("{}{}", text(a), b)),
+        (Op::Less | Op::LessEqual | Op::Greater | Op::GreaterEqual, _, _) => {
+            let ordering = match (&a, &b) {
+                (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                (Value::Number(a), Value::Number(b)) => a
+                    .as_f64()
+                    .zip(b.as_f64())
+                    .and_then(|(a, b)| a.partial_cmp(&b)),
+                _ => None,
+            };
+            return ordering.map_or(Value::Null, |ordering| {
+                Value::Bool(match op {
+                    Op::Less => ordering.is_lt(),
+                    Op::LessEqual => ordering.is_le(),
+                    Op::Greater => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                })
+            });
+        }
+        _ => {}
+    }
+    let (Value::Number(a), Value::Number(b)) = (&a, &b) else {
+        return Value::Null;
+    };
+    if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+        let result = match op {
+            Op::Add => a.checked_add(b),
+            Op::Subtract => a.checked_sub(b),
+            Op::Multiply => a.checked_mul(b),
+            Op::Remainder if b == 0 => return Value::Null,
+            Op::Remainder => a.checked_rem(b),
+            _ => None,
+        };
+        if let Some(result) = result {
+            return result.into();
+        }
+    }
+    let (Some(a), Some(b)) = (a.as_f64(), b.as_f64()) else {
+        return Value::Null;
+    };
+    match op {
+        Op::Add => number(a + b),
+        Op::Subtract => number(a - b),
+        Op::Multiply => number(a * b),
+        Op::Divide if b == 0.0 => Value::Null,
+        Op::Divide => number(a / b),
+        Op::Remainder if b == 0.0 => Value::Null,
+        Op::Remainder => number(a % b),
+        _ => Value::Null,
+    }
+}
+
+// Numbers are equal by value, so that 2 == 2.0.
+fn equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => a == b,
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(Value),
+    String(String),
+    Name(String),
+    Symbol(&'static str),
+}
+
+/// Symbols, longest first so that "<=" is not read as "<".
+const SYMBOLS: [&str; 19] = [
+    "==", "!=", "<=", ">=", "&&", "||", "+", "-", "*", "/", "%", "<", ">", "!", "?", ":", "(", ")",
+    "=",
+];
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = s.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_digit() || c == '.' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.'))
+                .unwrap_or(rest.len());
+            let literal = &rest[..end];
+            let number = match literal.parse::<i64>() {
+                Ok(i) => Value::from(i),
+                Err(_) => literal
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(Value::Number)
+                    .ok_or_else(|| format!("invalid number {}", literal))?,
+            };
+            tokens.push(Token::Number(number));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if c == '"' {
+            let mut string = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, '"')) => break i + 2,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, c)) => string.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    },
+                    Some((_, c)) => string.push(c),
+                    None => return Err("unterminated string".to_string()),
+                }
+            };
+            tokens.push(Token::String(string));
+            rest = &rest[end..];
+        } else {
+            let symbol = SYMBOLS
+                .into_iter()
+                .find(|symbol| rest.starts_with(symbol))
+                .ok_or_else(|| format!("unexpected '{}'", c))?;
+            if symbol == "=" {
+                return Err("'=' is not an operator, compare with '=='".to_string());
+            }
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn eat(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.tokens.get(self.pos), Some(Token::Symbol(s)) if *s == symbol);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    // The first of the symbols that comes next, if any, taken.
+    fn eat_any(&mut self, symbols: &[&'static str]) -> Option<&'static str> {
+        symbols.iter().copied().find(|symbol| self.eat(symbol))
+    }
+
+    fn conditional(&mut self) -> Result<Node, String> {
+        let condition = self.or()?;
+        if !self.eat("?") {
+            return Ok(condition);
+        }
+        let then = self.conditional()?;
+        if !self.eat(":") {
+            return Err("missing ':' of '?'".to_string());
+        }
+        let otherwise = self.conditional()?;
+        Ok(Node::Conditional(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn or(&mut self) -> Result<Node, String> {
+        let mut node = self.and()?;
+        while self.eat("||") {
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, String> {
+        let mut node = self.binary(0)?;
+        while self.eat("&&") {
+            node = Node::And(Box::new(node), Box::new(self.binary(0)?));
+        }
+        Ok(node)
+    }
+
+    // Binary operators from the loosest binding level `level` up.
+    fn binary(&mut self, level: usize) -> Result<Node, String> {
+        const LEVELS: [&[(&str, Op)]; 4] = [
+            &[("==", Op::Equal), ("!=", Op::NotEqual)],
+            &[
+                ("<=", Op::LessEqual),
+                (">=", Op::GreaterEqual),
+                ("<", Op::Less),
+                (">", Op::Greater),
+            ],
+            &[("+", Op::Add), ("-", Op::Subtract)],
+            &[("*", Op::Multiply), ("/", Op::Divide), ("%", Op::Remainder)],
+        ];
+        let Some(operators) = LEVELS.get(level) else {
+            return self.unary();
+        };
+        let symbols: Vec<&'static str> = operators.iter().map(|&(symbol, _)| symbol).collect();
+        let mut node = self.binary(level + 1)?;
+        while let Some(symbol) = self.eat_any(&symbols) {
+            let op = operators.iter().find(|&&(s, _)| s == symbol).unwrap().1;
+            node = Node::Binary(op, Box::new(node), Box::new(self.binary(level + 1)?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.eat("!") {
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("-") {
+            return Ok(Node::Negate(Box::new(self.unary()?)));
+        }
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        Ok(match token {
+            Some(Token::Number(n)) => Node::Literal(n),
+            Some(Token::String(s)) => Node::Literal(Value::String(s)),
+            Some(Token::Name(name)) => match name.as_str() {
+                "true" => Node::Literal(Value::Bool(true)),
+                "false" => Node::Literal(Value::Bool(false)),
+                "null" => Node::Literal(Value::Null),
+                "index" => Node::Index,
+                _ => Node::Property(name),
+            },
+            Some(Token::Symbol("(")) => {
+                let node = self.conditional()?;
+                if !self.eat(")") {
+                    return Err("missing ')'".to_string());
+                }
+                node
+            }
+            Some(token) => return Err(format!("unexpected {:?}", token)),
+            None => return Err("unexpected end".to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn eval(expr: &str) -> Value {
+        let properties =
+            json!({"population": 250000, "area_km2": 100.0, "name": "Leeds", "ref": null});
+        let expr: Expr = expr.parse().unwrap();
+        expr.evaluate(properties.as_object().unwrap(), 3)
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(eval("population / area_km2"), json!(2500.0));
+        assert_eq!(
+            eval("population > 100000 ? \"city\" : \"town\""),
+            json!("city")
+        );
+        assert_eq!(eval("1 + 2 * 3 - -4 % 3"), json!(8));
+        assert_eq!(eval("(1 + 2) * 3 == 9 && !false"), json!(true));
+        assert_eq!(eval("name + \" #\" + index"), json!("Leeds #3"));
+        assert_eq!(eval("ref * 2"), Value::Null);
+        assert_eq!(eval("missing == null"), json!(true));
+        assert_eq!(eval("population / 0"), Value::Null);
+        assert_eq!(eval("ref || 7 / 2"), json!(3.5));
+        assert_eq!(eval("1 < 2 ? 2 < 1 ? 1 : 2 : 3"), json!(2));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for expr in ["", "1 +", "(1", "a = 1", "1 ? 2", "\"x", "1 2", "1..2", "#"] {
+            assert!(expr.parse::<Expr>().is_err(), "{}", expr);
+        }
+        let expr: Expr = "a > b ? c : a".parse().unwrap();
+        assert_eq!(expr.properties(), ["a", "b", "c", "a"]);
+    }
+}
@@ -0,0 +1,107 @@
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+
+use crate::error::RandomGeojsonError;
+use crate::options::generate_json;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Generates a random GeoJSON FeatureCollection from options given as a
+/// JSON object (see [`crate::options::Options`]), or from the defaults if
+/// `config_json` is null.
+///
+/// Returns the collection as text to be released with
+/// [`random_geojson_free`], or null on failure, with the reason available
+/// from [`random_geojson_last_error`].
+///
+/// # Safety
+///
+/// `config_json` must be null or a valid null-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn random_geojson_generate(config_json: *const c_char) -> *mut c_char {
+    let options = if config_json.is_null() {
+        Ok("{}")
+    } else {
+        // SAFETY: the caller passes a valid null-terminated string
+        unsafe { CStr::from_ptr(config_json) }
+            .to_str()
+            .map_err(|_| RandomGeojsonError::InvalidArgument("Options are not UTF-8".to_string()))
+    };
+    let result = options.and_then(generate_json).and_then(|json| {
+        CString::new(json)
+            .map_err(|_| RandomGeojsonError::Serialize("Output contains a null byte".to_string()))
+    });
+    match result {
+        Ok(json) => {
+            set_last_error(None);
+            json.into_raw()
+        }
+        Err(e) => {
+            set_last_error(Some(e));
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Releases text returned by [`random_geojson_generate`].
+///
+/// # Safety
+///
+/// `json` must be null or returned by [`random_geojson_generate`], and not
+/// released before.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn random_geojson_free(json: *mut c_char) {
+    if !json.is_null() {
+        // SAFETY: the text was allocated by `CString::into_raw`
+        drop(unsafe { CString::from_raw(json) });
+    }
+}
+
+/// The reason the last call to [`random_geojson_generate`] on this thread
+/// failed, or null if it succeeded. The text stays valid until the next
+/// call on the thread and must not be released.
+#[unsafe(no_mangle)]
+pub extern "C" fn random_geojson_last_error() -> *const c_char {
+    LAST_ERROR.with_borrow(|error| error.as_ref().map_or(std::ptr::null(), |e| e.as_ptr()))
+}
+
+fn set_last_error(error: Option<RandomGeojsonError>) {
+    let error = error.map(|e| CString::new(e.to_string().replace('\0', " ")).unwrap_or_default());
+    LAST_ERROR.set(error);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_and_free() {
+        let options = CString::new(r#"{"length": 3, "geometryType": "Polygon"}"#).unwrap();
+        let json = unsafe { random_geojson_generate(options.as_ptr()) };
+        assert!(!json.is_null());
+        assert!(random_geojson_last_error().is_null());
+        let fc: geojson::FeatureCollection = unsafe { CStr::from_ptr(json) }
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(fc.features.len(), 3);
+        unsafe { random_geojson_free(json) };
+
+        let json = unsafe { random_geojson_generate(std::ptr::null()) };
+        assert!(!json.is_null());
+        unsafe { random_geojson_free(json) };
+    }
+
+    #[test]
+    fn test_last_error() {
+        let options = CString::new(r#"{"length": "many"}"#).unwrap();
+        let json = unsafe { random_geojson_generate(options.as_ptr()) };
+        assert!(json.is_null());
+        let error = unsafe { CStr::from_ptr(random_geojson_last_error()) };
+        assert!(error.to_str().unwrap().contains("Invalid options"));
+        unsafe { random_geojson_free(std::ptr::null_mut()) };
+    }
+}
@@ -0,0 +1,151 @@
+use geojson::JsonValue;
+use serde::Serialize;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::format::EncodeOptions;
+
+/// Decimal places floats are rounded to in canonical output, about 1 cm in
+/// degrees.
+pub const DECIMALS: i32 = 7;
+
+/// Serializes `value` as JSON the way the encode options ask for: compact,
+/// pretty-printed or canonical. `name` is the format named in errors.
+pub(crate) fn to_json(
+    value: &impl Serialize,
+    options: &EncodeOptions,
+    name: &str,
+) -> RandomGeojsonResult<Vec<u8>> {
+    let invalid = |e: serde_json::Error| {
+        RandomGeojsonError::Serialize(format!("Failed to serialize {}: {}", name, e))
+    };
+    if options.canonical {
+        let value = serde_json::to_value(value).map_err(invalid)?;
+        return Ok(encode(&value, options.pretty));
+    }
+    if options.pretty {
+        serde_json::to_vec_pretty(value).map_err(invalid)
+    } else {
+        serde_json::to_vec(value).map_err(invalid)
+    }
+}
+
+/// Writes `value` in a canonical form, so the same data always gives the
+/// same bytes: object keys sorted, floats rounded to [`DECIMALS`] places and
+/// written without exponents, and a final newline.
+pub fn encode(value: &JsonValue, pretty: bool) -> Vec<u8> {
+    let mut out = String::new();
+    write_value(&mut out, value, pretty.then_some(0));
+    out.push('\n');
+    out.into_bytes()
+}
+
+// Writes a value at the given indentation depth, or on one line if `None`.
+fn write_value(out: &mut String, value: &JsonValue, depth: Option<usize>) {
+    match value {
+        JsonValue::Null => out.push_str("null"),
+        JsonValue::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        JsonValue::Number(n) => match (n.as_i64(), n.as_u64(), n.as_f64()) {
+            (Some(i), _, _) => out.push_str(&i.to_string()),
+            (_, Some(u), _) => out.push_str(&u.to_string()),
+            (_, _, Some(f)) => out.push_str(&format_float(f)),
+            _ => out.push_str(&n.to_string()),
+        },
+        JsonValue::String(s) => write_string(out, s),
+        JsonValue::Array(items) => {
+            write_container(out, '[', ']', items.iter(), depth, |out, item, depth| {
+                write_value(out, item, depth)
+            })
+        }
+        JsonValue::Object(members) => {
+            let mut members: Vec<_> = members.iter().collect();
+            members.sort_by_key(|&(key, _)| key);
+            let separator = if depth.is_some() { ": " } else { ":" };
+            write_container(
+                out,
+                '{',
+                '}',
+                members.into_iter(),
+                depth,
+                |out, (key, value), depth| {
+                    write_string(out, key);
+                    out.push_str(separator);
+                    write_value(out, value, depth);
+                },
+            )
+        }
+    }
+}
+
+// Writes the items of an array or object between `open` and `close`, one
+// per line when pretty-printing.
+fn write_container<T>(
+    out: &mut String,
+    open: char,
+    close: char,
+    items: impl ExactSizeIterator<Item = T>,
+    depth: Option<usize>,
+    write_item: impl Fn(&mut String, T, Option<usize>),
+) {
+    out.push(open);
+    let empty = items.len() == 0;
+    for (i, item) in items.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        if let Some(depth) = depth {
+            newline(out, depth + 1);
+        }
+        write_item(out, item, depth.map(|depth| depth + 1));
+    }
+    if let Some(depth) = depth.filter(|_| !empty) {
+        newline(out, depth);
+    }
+    out.push(close);
+}
+
+fn newline(out: &mut String, depth: usize) {
+    out.push('\n');
+    out.push_str(&"  ".repeat(depth));
+}
+
+fn write_string(out: &mut String, s: &str) {
+    // Serializing a string cannot fail
+    out.push_str(&serde_json::to_string(s).unwrap_or_default());
+}
+
+// A float rounded to DECIMALS places, in plain decimal notation and keeping
+// a ".0" on whole numbers so they still read as floats.
+fn format_float(f: f64) -> String {
+    let scale = 10f64.powi(DECIMALS);
+    let rounded = (f * scale).round() / scale;
+    // No negative zero
+    let rounded = if rounded == 0.0 { 0.0 } else { rounded };
+    if rounded.fract() == 0.0 {
+        format!("{:.1}", rounded)
+    } else {
+        format!("{}", rounded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let value = json!({
+            "b": [1.123456789, -0.00000001, 2.0, 3, 1e-7],
+            "a": {"z": "\"quoted\"", "y": null, "x": []},
+        });
+        assert_eq!(
+            String::from_utf8(encode(&value, false)).unwrap(),
+            "{\"a\":{\"x\":[],\"y\":null,\"z\":\"\\\"quoted\\\"\"},\"b\":[1.1234568,0.0,2.0,3,0.0000001]}\n"
+        );
+        assert_eq!(
+            String::from_utf8(encode(&json!({"b": [1], "a": {}}), true)).unwrap(),
+            "{\n  \"a\": {},\n  \"b\": [\n    1\n  ]\n}\n"
+        );
+    }
+}
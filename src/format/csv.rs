@@ -0,0 +1,114 @@
+use geojson::FeatureCollection;
+use serde_json::Value;
+
+use crate::format::schema::{infer_schema, value_to_text};
+use crate::format::wkt;
+
+/// Encodes a feature collection as CSV with one row per feature.
+///
+/// Columns are the feature `id` (unless a property already uses that name),
+/// one column per property, optional `lon`/`lat` columns filled in for Point
+/// geometries, and a `geometry` column holding WKT.
+pub fn encode(fc: &FeatureCollection, lon_lat: bool) -> Vec<u8> {
+    let columns = infer_schema(&fc.features);
+    let with_id =
+        fc.features.iter().any(|f| f.id.is_some()) && !columns.iter().any(|c| c.name == "id");
+
+    let mut header: Vec<&str> = Vec::new();
+    if with_id {
+        header.push("id");
+    }
+    header.extend(columns.iter().map(|c| c.name.as_str()));
+    if lon_lat {
+        header.extend(["lon", "lat"]);
+    }
+    header.push("geometry");
+
+    let mut out = String::new();
+    write_row(&mut out, header.into_iter().map(str::to_string));
+
+    for feature in &fc.features {
+        let mut row = Vec::with_capacity(columns.len() + 4);
+        if with_id {
+            row.push(match &feature.id {
+                Some(geojson::feature::Id::String(s)) => s.clone(),
+                Some(geojson::feature::Id::Number(n)) => n.to_string(),
+                None => String::new(),
+            });
+        }
+        for column in &columns {
+            row.push(match feature.property(&column.name) {
+                None | Some(Value::Null) => String::new(),
+                Some(value) => value_to_text(value),
+            });
+        }
+        let geometry = feature.geometry.as_ref().map(|g| &g.value);
+        if lon_lat {
+            match geometry {
+                Some(geojson::Value::Point(p)) if p.len() >= 2 => {
+                    row.push(p[0].to_string());
+                    row.push(p[1].to_string());
+                }
+                _ => row.extend([String::new(), String::new()]),
+            }
+        }
+        row.push(geometry.map(wkt::encode_geometry).unwrap_or_default());
+        write_row(&mut out, row.into_iter());
+    }
+
+    out.into_bytes()
+}
+
+fn write_row(out: &mut String, fields: impl Iterator<Item = String>) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&escape(&field));
+    }
+    out.push_str("\r\n");
+}
+
+// Quotes a field if it contains a delimiter, quote or line break (RFC 4180).
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Feature, Geometry};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_csv() {
+        let fc = FeatureCollection {
+            features: vec![
+                Feature {
+                    id: Some(geojson::feature::Id::String("a".to_string())),
+                    geometry: Some(Geometry::new(geojson::Value::Point(vec![1.0, 2.0]))),
+                    properties: json!({"name": "x, \"y\"", "n": 1}).as_object().cloned(),
+                    ..Default::default()
+                },
+                Feature {
+                    geometry: Some(Geometry::new(geojson::Value::LineString(vec![
+                        vec![0.0, 0.0],
+                        vec![1.0, 1.0],
+                    ]))),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let csv = String::from_utf8(encode(&fc, true)).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "id,n,name,lon,lat,geometry");
+        assert_eq!(lines[1], "a,1,\"x, \"\"y\"\"\",1,2,POINT (1 2)");
+        assert_eq!(lines[2], ",,,,,\"LINESTRING (0 0, 1 1)\"");
+    }
+}
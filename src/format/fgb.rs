@@ -0,0 +1,161 @@
+use flatgeobuf::{ColumnType, FgbCrs, FgbWriter, FgbWriterOptions, GeometryType};
+use geojson::FeatureCollection;
+use geozero::ColumnValue;
+use geozero::PropertyProcessor;
+use serde_json::Value;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::format::schema::{ColumnKind, infer_schema, value_to_text};
+
+/// Encodes a feature collection as a FlatGeobuf file with a spatial index.
+pub fn encode(fc: &FeatureCollection, epsg: Option<u32>) -> RandomGeojsonResult<Vec<u8>> {
+    let failed = |e: flatgeobuf::Error| {
+        RandomGeojsonError::Serialize(format!("Failed to write FlatGeobuf: {}", e))
+    };
+
+    let mut types = fc
+        .features
+        .iter()
+        .filter_map(|f| f.geometry.as_ref())
+        .map(|g| geometry_type(&g.value));
+    let first = types.next().unwrap_or(GeometryType::Unknown);
+    let dataset_type = if types.all(|t| t == first) {
+        first
+    } else {
+        GeometryType::Unknown
+    };
+
+    let mut fgb = FgbWriter::create_with_options(
+        "random",
+        dataset_type,
+        FgbWriterOptions {
+            write_index: true,
+            detect_type: false,
+            promote_to_multi: false,
+            crs: FgbCrs {
+                code: epsg.map_or(0, |c| c as i32),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .map_err(failed)?;
+
+    let schema = infer_schema(&fc.features);
+    for column in &schema {
+        let col_type = match column.kind {
+            ColumnKind::Bool => ColumnType::Bool,
+            ColumnKind::Int => ColumnType::Long,
+            ColumnKind::Float => ColumnType::Double,
+            ColumnKind::String => ColumnType::String,
+            ColumnKind::Json => ColumnType::Json,
+        };
+        fgb.add_column(&column.name, col_type, |_, col| col.nullable = true);
+    }
+
+    for feature in &fc.features {
+        let Some(geometry) = &feature.geometry else {
+            continue;
+        };
+        let geometry_json = serde_json::to_string(geometry).map_err(|e| {
+            RandomGeojsonError::Serialize(format!("Failed to serialize geometry: {}", e))
+        })?;
+
+        let mut result = Ok(());
+        fgb.add_feature_geom(geozero::geojson::GeoJson(&geometry_json), |writer| {
+            for (i, column) in schema.iter().enumerate() {
+                let Some(value) = feature.property(&column.name) else {
+                    continue;
+                };
+                let text;
+                let column_value = match (column.kind, value) {
+                    (_, Value::Null) => continue,
+                    (ColumnKind::Bool, Value::Bool(b)) => ColumnValue::Bool(*b),
+                    (ColumnKind::Int, Value::Number(n)) => {
+                        ColumnValue::Long(n.as_i64().unwrap_or_default())
+                    }
+                    (ColumnKind::Float, Value::Number(n)) => {
+                        ColumnValue::Double(n.as_f64().unwrap_or(f64::NAN))
+                    }
+                    (ColumnKind::String, v) => {
+                        text = value_to_text(v);
+                        ColumnValue::String(&text)
+                    }
+                    (_, v) => {
+                        text = v.to_string();
+                        ColumnValue::Json(&text)
+                    }
+                };
+                if let Err(e) = writer.property(i, &column.name, &column_value) {
+                    result = Err(e);
+                }
+            }
+        })
+        .and(result)
+        .map_err(|e| RandomGeojsonError::Serialize(format!("Failed to write FlatGeobuf: {}", e)))?;
+    }
+
+    let mut bytes = Vec::new();
+    fgb.write(&mut bytes).map_err(failed)?;
+    Ok(bytes)
+}
+
+fn geometry_type(value: &geojson::Value) -> GeometryType {
+    match value {
+        geojson::Value::Point(_) => GeometryType::Point,
+        geojson::Value::MultiPoint(_) => GeometryType::MultiPoint,
+        geojson::Value::LineString(_) => GeometryType::LineString,
+        geojson::Value::MultiLineString(_) => GeometryType::MultiLineString,
+        geojson::Value::Polygon(_) => GeometryType::Polygon,
+        geojson::Value::MultiPolygon(_) => GeometryType::MultiPolygon,
+        geojson::Value::GeometryCollection(_) => GeometryType::GeometryCollection,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flatgeobuf::{FallibleStreamingIterator, FgbReader};
+    use geojson::{Feature, Geometry};
+    use geozero::FeatureProperties;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_round_trip() {
+        let fc = FeatureCollection {
+            features: vec![
+                Feature {
+                    geometry: Some(Geometry::new(geojson::Value::Point(vec![1.0, 2.0]))),
+                    properties: json!({"name": "a", "n": 1}).as_object().cloned(),
+                    ..Default::default()
+                },
+                Feature {
+                    geometry: Some(Geometry::new(geojson::Value::LineString(vec![
+                        vec![0.0, 0.0],
+                        vec![1.0, 1.0],
+                    ]))),
+                    properties: json!({"name": "b", "n": 2}).as_object().cloned(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let bytes = encode(&fc, Some(4326)).unwrap();
+        let mut input = bytes.as_slice();
+        let reader = FgbReader::open(&mut input).unwrap();
+        let header = reader.header();
+        assert_eq!(header.features_count(), 2);
+        assert_eq!(header.geometry_type(), GeometryType::Unknown);
+        assert_eq!(header.crs().unwrap().code(), 4326);
+
+        let mut features = reader.select_all_seq().unwrap();
+        let mut names = Vec::new();
+        while let Some(feature) = features.next().unwrap() {
+            names.push(feature.property::<String>("name").unwrap());
+        }
+        names.sort();
+        assert_eq!(names, vec!["a", "b"]);
+    }
+}
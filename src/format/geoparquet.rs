@@ -0,0 +1,273 @@
+use std::sync::Arc;
+
+use geojson::FeatureCollection;
+use parquet::basic::{LogicalType, Repetition, Type as PhysicalType};
+use parquet::data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+use serde_json::{Value, json};
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::format::schema::{Column, ColumnKind, infer_schema, value_to_text};
+use crate::format::wkb;
+use crate::geometry::for_each_position;
+
+/// Encodes a feature collection as GeoParquet 1.1 with a WKB `geometry`
+/// column and one typed column per property. Feature ids are stored in an
+/// `id` column unless a property already uses that name.
+pub fn encode(fc: &FeatureCollection, epsg: Option<u32>) -> RandomGeojsonResult<Vec<u8>> {
+    let failed = |e: parquet::errors::ParquetError| {
+        RandomGeojsonError::Serialize(format!("Failed to write GeoParquet: {}", e))
+    };
+
+    let mut columns = infer_schema(&fc.features);
+    let with_id =
+        fc.features.iter().any(|f| f.id.is_some()) && !columns.iter().any(|c| c.name == "id");
+    if with_id {
+        columns.insert(
+            0,
+            Column {
+                name: "id".to_string(),
+                kind: ColumnKind::String,
+            },
+        );
+    }
+
+    let mut fields = vec![Arc::new(
+        Type::primitive_type_builder("geometry", PhysicalType::BYTE_ARRAY)
+            .with_repetition(Repetition::OPTIONAL)
+            .build()
+            .map_err(failed)?,
+    )];
+    for column in &columns {
+        let (physical, logical) = match column.kind {
+            ColumnKind::Bool => (PhysicalType::BOOLEAN, None),
+            ColumnKind::Int => (PhysicalType::INT64, None),
+            ColumnKind::Float => (PhysicalType::DOUBLE, None),
+            ColumnKind::String => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+            ColumnKind::Json => (PhysicalType::BYTE_ARRAY, Some(LogicalType::Json)),
+        };
+        fields.push(Arc::new(
+            Type::primitive_type_builder(&column.name, physical)
+                .with_repetition(Repetition::OPTIONAL)
+                .with_logical_type(logical)
+                .build()
+                .map_err(failed)?,
+        ));
+    }
+    let schema = Arc::new(
+        Type::group_type_builder("schema")
+            .with_fields(fields)
+            .build()
+            .map_err(failed)?,
+    );
+
+    let properties = Arc::new(WriterProperties::builder().build());
+    let mut bytes = Vec::new();
+    let mut writer = SerializedFileWriter::new(&mut bytes, schema, properties).map_err(failed)?;
+    writer.append_key_value_metadata(KeyValue::new("geo".to_string(), geo_metadata(fc, epsg)));
+
+    let mut row_group = writer.next_row_group().map_err(failed)?;
+
+    // Geometry column
+    let (values, levels): (Vec<ByteArray>, Vec<i16>) = {
+        let mut values = Vec::new();
+        let levels = fc
+            .features
+            .iter()
+            .map(|f| match &f.geometry {
+                Some(g) => {
+                    values.push(ByteArray::from(wkb::encode_geometry(&g.value)));
+                    1
+                }
+                None => 0,
+            })
+            .collect();
+        (values, levels)
+    };
+    if let Some(mut column) = row_group.next_column().map_err(failed)? {
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&values, Some(&levels), None)
+            .map_err(failed)?;
+        column.close().map_err(failed)?;
+    }
+
+    // Property columns
+    for (i, schema_column) in columns.iter().enumerate() {
+        let cells: Vec<Option<Value>> = fc
+            .features
+            .iter()
+            .map(|f| {
+                if with_id && i == 0 {
+                    f.id.as_ref().map(|id| match id {
+                        geojson::feature::Id::String(s) => Value::String(s.clone()),
+                        geojson::feature::Id::Number(n) => Value::String(n.to_string()),
+                    })
+                } else {
+                    f.property(&schema_column.name)
+                        .filter(|v| !v.is_null())
+                        .cloned()
+                }
+            })
+            .collect();
+        let levels: Vec<i16> = cells.iter().map(|c| c.is_some() as i16).collect();
+        let present = cells.iter().flatten();
+
+        let Some(mut column) = row_group.next_column().map_err(failed)? else {
+            break;
+        };
+        match schema_column.kind {
+            ColumnKind::Bool => {
+                let values: Vec<bool> = present.map(|v| v.as_bool().unwrap_or_default()).collect();
+                column
+                    .typed::<BoolType>()
+                    .write_batch(&values, Some(&levels), None)
+            }
+            ColumnKind::Int => {
+                let values: Vec<i64> = present.map(|v| v.as_i64().unwrap_or_default()).collect();
+                column
+                    .typed::<Int64Type>()
+                    .write_batch(&values, Some(&levels), None)
+            }
+            ColumnKind::Float => {
+                let values: Vec<f64> = present.map(|v| v.as_f64().unwrap_or(f64::NAN)).collect();
+                column
+                    .typed::<DoubleType>()
+                    .write_batch(&values, Some(&levels), None)
+            }
+            ColumnKind::String | ColumnKind::Json => {
+                let values: Vec<ByteArray> = present
+                    .map(|v| match schema_column.kind {
+                        ColumnKind::String => ByteArray::from(value_to_text(v).as_str()),
+                        _ => ByteArray::from(v.to_string().as_str()),
+                    })
+                    .collect();
+                column
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, Some(&levels), None)
+            }
+        }
+        .map_err(failed)?;
+        column.close().map_err(failed)?;
+    }
+
+    row_group.close().map_err(failed)?;
+    writer.close().map_err(failed)?;
+    Ok(bytes)
+}
+
+// Builds the "geo" file metadata required by the GeoParquet specification.
+fn geo_metadata(fc: &FeatureCollection, epsg: Option<u32>) -> String {
+    let mut types: Vec<&str> = Vec::new();
+    let mut bbox = [
+        f64::INFINITY,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        f64::NEG_INFINITY,
+    ];
+    for geometry in fc.features.iter().filter_map(|f| f.geometry.as_ref()) {
+        let name = geometry.value.type_name();
+        if !types.contains(&name) {
+            types.push(name);
+        }
+        for_each_position(&geometry.value, &mut |p| {
+            bbox[0] = bbox[0].min(p[0]);
+            bbox[1] = bbox[1].min(p[1]);
+            bbox[2] = bbox[2].max(p[0]);
+            bbox[3] = bbox[3].max(p[1]);
+        });
+    }
+
+    let mut column = json!({
+        "encoding": "WKB",
+        "geometry_types": types,
+    });
+    if bbox[0].is_finite() {
+        column["bbox"] = json!(bbox);
+    }
+    match epsg {
+        // OGC:CRS84 is the default and is left implicit
+        Some(4326) => {}
+        Some(code) => {
+            column["crs"] = json!({"id": {"authority": "EPSG", "code": code}});
+        }
+        None => column["crs"] = Value::Null,
+    }
+
+    json!({
+        "version": "1.1.0",
+        "primary_column": "geometry",
+        "columns": { "geometry": column },
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Feature, Geometry};
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::Field;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_round_trip() {
+        let fc = FeatureCollection {
+            features: vec![
+                Feature {
+                    id: Some(geojson::feature::Id::String("a".to_string())),
+                    geometry: Some(Geometry::new(geojson::Value::Point(vec![1.0, 2.0]))),
+                    properties: json!({"n": 1, "flag": true}).as_object().cloned(),
+                    ..Default::default()
+                },
+                Feature {
+                    id: Some(geojson::feature::Id::String("b".to_string())),
+                    geometry: None,
+                    properties: json!({"n": 2}).as_object().cloned(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let bytes = encode(&fc, Some(3857)).unwrap();
+        let reader = SerializedFileReader::new(bytes::Bytes::from(bytes)).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 2);
+
+        let geo = metadata
+            .key_value_metadata()
+            .unwrap()
+            .iter()
+            .find(|kv| kv.key == "geo")
+            .and_then(|kv| kv.value.clone())
+            .unwrap();
+        let geo: Value = serde_json::from_str(&geo).unwrap();
+        assert_eq!(
+            geo["columns"]["geometry"]["geometry_types"],
+            json!(["Point"])
+        );
+        assert_eq!(geo["columns"]["geometry"]["crs"]["id"]["code"], 3857);
+
+        let rows: Vec<_> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        let n_values: Vec<&Field> = rows
+            .iter()
+            .map(|r| {
+                r.get_column_iter()
+                    .find(|(name, _)| name.as_str() == "n")
+                    .unwrap()
+                    .1
+            })
+            .collect();
+        assert_eq!(n_values, vec![&Field::Long(1), &Field::Long(2)]);
+        let geometry = rows[1].get_column_iter().next().unwrap().1;
+        assert_eq!(geometry, &Field::Null);
+    }
+}
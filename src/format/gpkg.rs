@@ -0,0 +1,299 @@
+use geojson::FeatureCollection;
+use rusqlite::{Connection, params_from_iter, types::Value as SqlValue};
+use serde_json::Value;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::format::schema::{ColumnKind, infer_schema, value_to_text};
+use crate::format::wkb;
+use crate::geometry::geometry_bbox;
+use crate::random;
+
+/// Name of the feature table (and layer) written to the GeoPackage.
+const TABLE_NAME: &str = "random";
+
+/// "GPKG" in ASCII, as required for the SQLite application id.
+const APPLICATION_ID: i32 = 0x4750_4B47;
+
+const WGS84_WKT: &str = r#"GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0,AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,AUTHORITY["EPSG","9122"]],AUTHORITY["EPSG","4326"]]"#;
+
+const WEB_MERCATOR_WKT: &str = r#"PROJCS["WGS 84 / Pseudo-Mercator",GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0,AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,AUTHORITY["EPSG","9122"]],AUTHORITY["EPSG","4326"]],PROJECTION["Mercator_1SP"],PARAMETER["central_meridian",0],PARAMETER["scale_factor",1],PARAMETER["false_easting",0],PARAMETER["false_northing",0],UNIT["metre",1,AUTHORITY["EPSG","9001"]],AXIS["Easting",EAST],AXIS["Northing",NORTH],EXTENSION["PROJ4","+proj=merc +a=6378137 +b=6378137 +lat_ts=0 +lon_0=0 +x_0=0 +y_0=0 +k=1 +units=m +nadgrids=@null +wktext +no_defs"],AUTHORITY["EPSG","3857"]]"#;
+
+/// Encodes a feature collection as a GeoPackage with a single `random`
+/// feature layer, including the `gpkg_contents` and `gpkg_geometry_columns`
+/// metadata readers such as QGIS rely on.
+pub fn encode(fc: &FeatureCollection, epsg: Option<u32>) -> RandomGeojsonResult<Vec<u8>> {
+    write(fc, epsg)
+        .map_err(|e| RandomGeojsonError::Serialize(format!("Failed to write GeoPackage: {}", e)))
+}
+
+fn write(fc: &FeatureCollection, epsg: Option<u32>) -> rusqlite::Result<Vec<u8>> {
+    let conn = Connection::open_in_memory()?;
+    conn.pragma_update(None, "application_id", APPLICATION_ID)?;
+    conn.pragma_update(None, "user_version", 10400)?;
+
+    conn.execute_batch(
+        "CREATE TABLE gpkg_spatial_ref_sys (
+            srs_name TEXT NOT NULL,
+            srs_id INTEGER NOT NULL PRIMARY KEY,
+            organization TEXT NOT NULL,
+            organization_coordsys_id INTEGER NOT NULL,
+            definition TEXT NOT NULL,
+            description TEXT
+        );
+        CREATE TABLE gpkg_contents (
+            table_name TEXT NOT NULL PRIMARY KEY,
+            data_type TEXT NOT NULL,
+            identifier TEXT UNIQUE,
+            description TEXT DEFAULT '',
+            last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+            min_x DOUBLE, min_y DOUBLE, max_x DOUBLE, max_y DOUBLE,
+            srs_id INTEGER,
+            CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id)
+        );
+        CREATE TABLE gpkg_geometry_columns (
+            table_name TEXT NOT NULL,
+            column_name TEXT NOT NULL,
+            geometry_type_name TEXT NOT NULL,
+            srs_id INTEGER NOT NULL,
+            z TINYINT NOT NULL,
+            m TINYINT NOT NULL,
+            CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name),
+            CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name),
+            CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id)
+        );
+        INSERT INTO gpkg_spatial_ref_sys VALUES
+            ('Undefined cartesian SRS', -1, 'NONE', -1, 'undefined', 'undefined cartesian coordinate reference system'),
+            ('Undefined geographic SRS', 0, 'NONE', 0, 'undefined', 'undefined geographic coordinate reference system');",
+    )?;
+
+    let srs_id: i64 = match epsg {
+        Some(4326) => 4326,
+        Some(3857) => 3857,
+        _ => -1,
+    };
+    match srs_id {
+        4326 => conn.execute(
+            "INSERT INTO gpkg_spatial_ref_sys VALUES ('WGS 84 geodetic', 4326, 'EPSG', 4326, ?1, 'longitude/latitude coordinates in decimal degrees on the WGS 84 spheroid')",
+            [WGS84_WKT],
+        )?,
+        3857 => conn.execute(
+            "INSERT INTO gpkg_spatial_ref_sys VALUES ('WGS 84 / Pseudo-Mercator', 3857, 'EPSG', 3857, ?1, NULL)",
+            [WEB_MERCATOR_WKT],
+        )?,
+        _ => 0,
+    };
+
+    let schema: Vec<_> = infer_schema(&fc.features)
+        .into_iter()
+        .filter(|c| !c.name.eq_ignore_ascii_case("fid") && !c.name.eq_ignore_ascii_case("geom"))
+        .collect();
+    let column_defs: String = schema
+        .iter()
+        .map(|c| {
+            let sql_type = match c.kind {
+                ColumnKind::Bool => "BOOLEAN",
+                ColumnKind::Int => "INTEGER",
+                ColumnKind::Float => "DOUBLE",
+                ColumnKind::String | ColumnKind::Json => "TEXT",
+            };
+            format!(", {} {}", quote_identifier(&c.name), sql_type)
+        })
+        .collect();
+    let geometry_type_name = geometry_type_name(fc);
+    conn.execute_batch(&format!(
+        "CREATE TABLE {} (fid INTEGER PRIMARY KEY AUTOINCREMENT, geom {}{});",
+        TABLE_NAME, geometry_type_name, column_defs
+    ))?;
+
+    let mut extent: Option<[f64; 4]> = None;
+
+    let placeholders: String = (2..=schema.len() + 1)
+        .map(|i| format!(", ?{}", i))
+        .collect();
+    let columns: String = schema
+        .iter()
+        .map(|c| format!(", {}", quote_identifier(&c.name)))
+        .collect();
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut insert = tx.prepare(&format!(
+            "INSERT INTO {} (geom{}) VALUES (?1{})",
+            TABLE_NAME, columns, placeholders
+        ))?;
+        for feature in &fc.features {
+            let geometry = feature.geometry.as_ref().map(|g| {
+                let bbox = geometry_bbox(&g.value);
+                if let Some(b) = bbox {
+                    let e = extent.get_or_insert(b);
+                    *e = [
+                        e[0].min(b[0]),
+                        e[1].min(b[1]),
+                        e[2].max(b[2]),
+                        e[3].max(b[3]),
+                    ];
+                }
+                SqlValue::Blob(geometry_blob(&g.value, bbox, srs_id as i32))
+            });
+
+            let mut values = vec![geometry.unwrap_or(SqlValue::Null)];
+            for column in &schema {
+                let value = match (column.kind, feature.property(&column.name)) {
+                    (_, None | Some(Value::Null)) => SqlValue::Null,
+                    (ColumnKind::Bool, Some(Value::Bool(b))) => SqlValue::Integer(*b as i64),
+                    (ColumnKind::Int, Some(Value::Number(n))) => {
+                        SqlValue::Integer(n.as_i64().unwrap_or_default())
+                    }
+                    (ColumnKind::Float, Some(Value::Number(n))) => {
+                        SqlValue::Real(n.as_f64().unwrap_or(f64::NAN))
+                    }
+                    (_, Some(v)) => SqlValue::Text(value_to_text(v)),
+                };
+                values.push(value);
+            }
+            insert.execute(params_from_iter(values))?;
+        }
+    }
+    tx.commit()?;
+
+    let [min_x, min_y, max_x, max_y] = extent.map_or([None; 4], |e| e.map(Some));
+    // Seeded runs are stamped with the Unix epoch, so they are byte for byte
+    // the same every time
+    let last_change = if random::is_seeded() {
+        "1970-01-01"
+    } else {
+        "now"
+    };
+    conn.execute(
+        "INSERT INTO gpkg_contents (table_name, data_type, identifier, last_change, min_x, min_y, max_x, max_y, srs_id)
+         VALUES (?1, 'features', ?1, strftime('%Y-%m-%dT%H:%M:%fZ', ?2), ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![TABLE_NAME, last_change, min_x, min_y, max_x, max_y, srs_id],
+    )?;
+    conn.execute(
+        "INSERT INTO gpkg_geometry_columns VALUES (?1, 'geom', ?2, ?3, 0, 0)",
+        rusqlite::params![TABLE_NAME, geometry_type_name, srs_id],
+    )?;
+
+    Ok(conn.serialize("main")?.to_vec())
+}
+
+// Single geometry type shared by all features, or GEOMETRY if they are mixed.
+fn geometry_type_name(fc: &FeatureCollection) -> String {
+    let mut types = fc
+        .features
+        .iter()
+        .filter_map(|f| f.geometry.as_ref())
+        .map(|g| g.value.type_name());
+    match types.next() {
+        Some(first) if types.all(|t| t == first) => first.to_uppercase(),
+        _ => "GEOMETRY".to_string(),
+    }
+}
+
+// Encodes a geometry as a GeoPackage binary blob: the "GP" header with an
+// XY envelope, followed by standard WKB.
+fn geometry_blob(value: &geojson::Value, bbox: Option<[f64; 4]>, srs_id: i32) -> Vec<u8> {
+    let mut blob = vec![b'G', b'P', 0];
+    match bbox {
+        Some([min_x, min_y, max_x, max_y]) => {
+            // Little endian, envelope [minx, maxx, miny, maxy]
+            blob.push(0b0000_0011);
+            blob.extend_from_slice(&srs_id.to_le_bytes());
+            for v in [min_x, max_x, min_y, max_y] {
+                blob.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        None => {
+            // Little endian, no envelope, empty geometry
+            blob.push(0b0001_0001);
+            blob.extend_from_slice(&srs_id.to_le_bytes());
+        }
+    }
+    blob.extend_from_slice(&wkb::encode_geometry(value));
+    blob
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Feature, Geometry};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_metadata_and_rows() {
+        let fc = FeatureCollection {
+            features: vec![
+                Feature {
+                    geometry: Some(Geometry::new(geojson::Value::Point(vec![1.0, 2.0]))),
+                    properties: json!({"name": "a", "weird \"col\"": 1})
+                        .as_object()
+                        .cloned(),
+                    ..Default::default()
+                },
+                Feature {
+                    geometry: Some(Geometry::new(geojson::Value::Point(vec![-3.0, 4.0]))),
+                    properties: json!({"name": "b"}).as_object().cloned(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let bytes = encode(&fc, Some(4326)).unwrap();
+        assert_eq!(&bytes[..16], b"SQLite format 3\0");
+
+        let path = std::env::temp_dir().join(format!("random-geojson-{}.gpkg", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+        let conn = Connection::open(&path).unwrap();
+
+        let application_id: i32 = conn
+            .pragma_query_value(None, "application_id", |r| r.get(0))
+            .unwrap();
+        assert_eq!(application_id, APPLICATION_ID);
+
+        let (geometry_type, srs_id): (String, i64) = conn
+            .query_row(
+                "SELECT geometry_type_name, srs_id FROM gpkg_geometry_columns WHERE table_name = 'random'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(geometry_type, "POINT");
+        assert_eq!(srs_id, 4326);
+
+        let min_x: f64 = conn
+            .query_row("SELECT min_x FROM gpkg_contents", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(min_x, -3.0);
+        let last_change: String = conn
+            .query_row("SELECT last_change FROM gpkg_contents", [], |r| r.get(0))
+            .unwrap();
+        assert!(!last_change.starts_with("1970"), "{}", last_change);
+
+        // Seeded runs are reproducible
+        let seeded = || random::with_seed(7, || encode(&fc, Some(4326)).unwrap());
+        let first = seeded();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert_eq!(first, seeded());
+
+        let count: i64 = conn
+            .query_row(
+                "SELECT count(*) FROM random WHERE name IS NOT NULL",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let blob: Vec<u8> = conn
+            .query_row("SELECT geom FROM random WHERE fid = 1", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(&blob[..2], b"GP");
+
+        drop(conn);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
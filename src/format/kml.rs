@@ -0,0 +1,186 @@
+use std::io::{Cursor, Write};
+
+use geojson::FeatureCollection;
+use zip::write::{SimpleFileOptions, ZipWriter};
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::format::schema::value_to_text;
+
+/// Encodes a feature collection as a KML document with one Placemark per
+/// feature. Feature ids become Placemark names and properties are stored as
+/// ExtendedData.
+///
+/// KML coordinates are always WGS84, so other coordinate systems are rejected.
+pub fn encode(fc: &FeatureCollection, epsg: Option<u32>) -> RandomGeojsonResult<Vec<u8>> {
+    if epsg != Some(4326) {
+        return Err(RandomGeojsonError::UnsupportedFormat(
+            "KML output requires WGS84 coordinates".to_string(),
+        ));
+    }
+
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n");
+    out.push_str("<name>random</name>\n");
+    for feature in &fc.features {
+        out.push_str("<Placemark>\n");
+        if let Some(id) = &feature.id {
+            let id = match id {
+                geojson::feature::Id::String(s) => s.clone(),
+                geojson::feature::Id::Number(n) => n.to_string(),
+            };
+            out.push_str(&format!("<name>{}</name>\n", escape(&id)));
+        }
+        if let Some(properties) = feature.properties.as_ref().filter(|p| !p.is_empty()) {
+            out.push_str("<ExtendedData>\n");
+            for (key, value) in properties {
+                out.push_str(&format!(
+                    "<Data name=\"{}\"><value>{}</value></Data>\n",
+                    escape(key),
+                    escape(&value_to_text(value))
+                ));
+            }
+            out.push_str("</ExtendedData>\n");
+        }
+        if let Some(geometry) = &feature.geometry {
+            write_geometry(&mut out, &geometry.value);
+        }
+        out.push_str("</Placemark>\n");
+    }
+    out.push_str("</Document>\n</kml>\n");
+    Ok(out.into_bytes())
+}
+
+/// Encodes a feature collection as KMZ: the KML document zipped as `doc.kml`.
+pub fn encode_kmz(fc: &FeatureCollection, epsg: Option<u32>) -> RandomGeojsonResult<Vec<u8>> {
+    let kml = encode(fc, epsg)?;
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file(
+        "doc.kml",
+        SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated),
+    )
+    .and_then(|_| Ok(zip.write_all(&kml)?))
+    .and_then(|_| zip.finish())
+    .map(Cursor::into_inner)
+    .map_err(|e| RandomGeojsonError::Serialize(format!("Failed to write KMZ: {}", e)))
+}
+
+fn write_coordinates(out: &mut String, positions: &[Vec<f64>]) {
+    let coordinates: Vec<String> = positions
+        .iter()
+        .map(|p| format!("{},{}", p[0], p[1]))
+        .collect();
+    out.push_str(&format!(
+        "<coordinates>{}</coordinates>",
+        coordinates.join(" ")
+    ));
+}
+
+fn write_polygon(out: &mut String, rings: &[Vec<Vec<f64>>]) {
+    out.push_str("<Polygon>");
+    for (i, ring) in rings.iter().enumerate() {
+        let boundary = if i == 0 {
+            "outerBoundaryIs"
+        } else {
+            "innerBoundaryIs"
+        };
+        out.push_str(&format!("<{}><LinearRing>", boundary));
+        write_coordinates(out, ring);
+        out.push_str(&format!("</LinearRing></{}>", boundary));
+    }
+    out.push_str("</Polygon>");
+}
+
+fn write_geometry(out: &mut String, value: &geojson::Value) {
+    match value {
+        geojson::Value::Point(p) => {
+            out.push_str("<Point>");
+            write_coordinates(out, std::slice::from_ref(p));
+            out.push_str("</Point>");
+        }
+        geojson::Value::LineString(ps) => {
+            out.push_str("<LineString>");
+            write_coordinates(out, ps);
+            out.push_str("</LineString>");
+        }
+        geojson::Value::Polygon(rings) => write_polygon(out, rings),
+        geojson::Value::MultiPoint(ps) => {
+            out.push_str("<MultiGeometry>");
+            for p in ps {
+                write_geometry(out, &geojson::Value::Point(p.clone()));
+            }
+            out.push_str("</MultiGeometry>");
+        }
+        geojson::Value::MultiLineString(lines) => {
+            out.push_str("<MultiGeometry>");
+            for line in lines {
+                write_geometry(out, &geojson::Value::LineString(line.clone()));
+            }
+            out.push_str("</MultiGeometry>");
+        }
+        geojson::Value::MultiPolygon(polygons) => {
+            out.push_str("<MultiGeometry>");
+            polygons.iter().for_each(|p| write_polygon(out, p));
+            out.push_str("</MultiGeometry>");
+        }
+        geojson::Value::GeometryCollection(geometries) => {
+            out.push_str("<MultiGeometry>");
+            geometries
+                .iter()
+                .for_each(|g| write_geometry(out, &g.value));
+            out.push_str("</MultiGeometry>");
+        }
+    }
+    out.push('\n');
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use geojson::{Feature, Geometry};
+    use serde_json::json;
+
+    use super::*;
+
+    fn collection() -> FeatureCollection {
+        FeatureCollection {
+            features: vec![Feature {
+                id: Some(geojson::feature::Id::String("a".to_string())),
+                geometry: Some(Geometry::new(geojson::Value::Point(vec![1.0, 2.0]))),
+                properties: json!({"name": "<b> & c"}).as_object().cloned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_encode_placemark() {
+        let kml = String::from_utf8(encode(&collection(), Some(4326)).unwrap()).unwrap();
+        assert!(kml.contains("<name>a</name>"));
+        assert!(kml.contains("<Data name=\"name\"><value>&lt;b&gt; &amp; c</value></Data>"));
+        assert!(kml.contains("<Point><coordinates>1,2</coordinates></Point>"));
+        assert!(encode(&collection(), Some(3857)).is_err());
+    }
+
+    #[test]
+    fn test_encode_kmz() {
+        let kmz = encode_kmz(&collection(), Some(4326)).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(kmz)).unwrap();
+        let mut kml = String::new();
+        archive
+            .by_name("doc.kml")
+            .unwrap()
+            .read_to_string(&mut kml)
+            .unwrap();
+        assert!(kml.starts_with("<?xml"));
+    }
+}
@@ -0,0 +1,268 @@
+pub mod canonical;
+pub mod csv;
+pub mod fgb;
+pub mod geoparquet;
+pub mod gpkg;
+pub mod gpx;
+pub mod kml;
+pub mod mvt;
+pub mod pmtiles;
+pub mod schema;
+pub mod shp;
+pub mod sql;
+pub mod topojson;
+pub mod wkb;
+pub mod wkt;
+
+use geojson::FeatureCollection;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::format::sql::SqlMode;
+use crate::tile::TileId;
+
+/// File format of the generated output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    GeoJson,
+    FlatGeobuf,
+    GeoParquet,
+    GeoPackage,
+    Shapefile,
+    Csv,
+    TopoJson,
+    Kml,
+    Kmz,
+    Gpx,
+    /// A Mapbox Vector Tile of the `tile` option.
+    Mvt,
+    /// A PMTiles archive of vector tiles between `min_zoom` and `max_zoom`.
+    PmTiles,
+    /// A PostGIS SQL script.
+    Sql,
+    /// One WKT geometry per line.
+    Wkt,
+    /// One hex-encoded WKB geometry per line.
+    Wkb,
+}
+
+/// Format-specific settings shared by all encoders.
+#[derive(Debug, Clone)]
+pub struct EncodeOptions {
+    /// Pretty-print text formats that support it.
+    pub pretty: bool,
+    /// Write JSON formats in canonical form, see [`canonical::encode`].
+    pub canonical: bool,
+    /// EPSG code of the output coordinates, if known.
+    pub epsg: Option<u32>,
+    /// Add `lon`/`lat` columns for Point geometries to CSV output.
+    pub lon_lat: bool,
+    /// Table created by SQL output, optionally schema-qualified.
+    pub table: String,
+    /// Whether SQL output uses INSERT statements or COPY.
+    pub sql_mode: SqlMode,
+    /// Tile encoded by MVT output.
+    pub tile: Option<TileId>,
+    /// Zoom range of PMTiles output.
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            pretty: false,
+            canonical: false,
+            epsg: None,
+            lon_lat: false,
+            table: "random".to_string(),
+            sql_mode: SqlMode::Insert,
+            tile: None,
+            min_zoom: 0,
+            max_zoom: 6,
+        }
+    }
+}
+
+impl Format {
+    /// Conventional file extension of the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::GeoJson => "geojson",
+            Format::FlatGeobuf => "fgb",
+            Format::GeoParquet => "parquet",
+            Format::GeoPackage => "gpkg",
+            Format::Shapefile => "shp.zip",
+            Format::Csv => "csv",
+            Format::TopoJson => "topojson",
+            Format::Kml => "kml",
+            Format::Kmz => "kmz",
+            Format::Gpx => "gpx",
+            Format::Mvt => "mvt",
+            Format::PmTiles => "pmtiles",
+            Format::Sql => "sql",
+            Format::Wkt => "wkt",
+            Format::Wkb => "wkb",
+        }
+    }
+
+    /// Media type of the format, e.g. for HTTP Content-Type headers.
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            Format::GeoJson => "application/geo+json",
+            Format::FlatGeobuf => "application/flatgeobuf",
+            Format::GeoParquet => "application/vnd.apache.parquet",
+            Format::GeoPackage => "application/geopackage+sqlite3",
+            Format::Shapefile | Format::Kmz => "application/zip",
+            Format::Csv => "text/csv",
+            Format::TopoJson => "application/json",
+            Format::Kml => "application/vnd.google-earth.kml+xml",
+            Format::Gpx => "application/gpx+xml",
+            Format::Mvt => "application/vnd.mapbox-vector-tile",
+            Format::PmTiles => "application/vnd.pmtiles",
+            Format::Sql => "application/sql",
+            Format::Wkt | Format::Wkb => "text/plain",
+        }
+    }
+
+    /// Encodes a feature collection in this format.
+    pub fn encode(
+        &self,
+        fc: &FeatureCollection,
+        options: &EncodeOptions,
+    ) -> RandomGeojsonResult<Vec<u8>> {
+        match self {
+            Format::GeoJson => canonical::to_json(fc, options, "GeoJSON"),
+            Format::FlatGeobuf => fgb::encode(fc, options.epsg),
+            Format::GeoParquet => geoparquet::encode(fc, options.epsg),
+            Format::GeoPackage => gpkg::encode(fc, options.epsg),
+            Format::Shapefile => shp::encode(fc, options.epsg),
+            Format::Csv => Ok(csv::encode(fc, options.lon_lat)),
+            Format::TopoJson => topojson::encode(fc, options),
+            Format::Kml => kml::encode(fc, options.epsg),
+            Format::Kmz => kml::encode_kmz(fc, options.epsg),
+            Format::Gpx => gpx::encode(fc, options.epsg),
+            Format::Mvt => {
+                let tile = options.tile.ok_or_else(|| {
+                    RandomGeojsonError::InvalidArgument(
+                        "MVT output requires a tile, see --tile".to_string(),
+                    )
+                })?;
+                mvt::encode(fc, tile, options.epsg)
+            }
+            Format::PmTiles => {
+                pmtiles::encode(fc, options.min_zoom, options.max_zoom, options.epsg)
+            }
+            Format::Sql => sql::encode(fc, &options.table, options.sql_mode, options.epsg),
+            Format::Wkt => Ok(encode_lines(fc, wkt::encode_geometry)),
+            Format::Wkb => Ok(encode_lines(fc, |value| {
+                wkb::encode_geometry(value)
+                    .iter()
+                    .map(|b| format!("{:02X}", b))
+                    .collect()
+            })),
+        }
+    }
+}
+
+// Writes one encoded geometry per line, skipping features without a geometry.
+fn encode_lines(fc: &FeatureCollection, encode: impl Fn(&geojson::Value) -> String) -> Vec<u8> {
+    let mut out = String::new();
+    for geometry in fc.features.iter().filter_map(|f| f.geometry.as_ref()) {
+        out.push_str(&encode(&geometry.value));
+        out.push('\n');
+    }
+    out.into_bytes()
+}
+
+/// Current time in seconds since the Unix epoch.
+pub(crate) fn unix_time() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Current time in milliseconds since the Unix epoch.
+pub(crate) fn unix_time_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Converts days since 1970-01-01 into a (year, month, day) civil date, using
+/// Howard Hinnant's algorithm.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+impl std::str::FromStr for Format {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "geojson" | "json" => Ok(Format::GeoJson),
+            "fgb" | "flatgeobuf" => Ok(Format::FlatGeobuf),
+            "geoparquet" | "parquet" => Ok(Format::GeoParquet),
+            "gpkg" | "geopackage" => Ok(Format::GeoPackage),
+            "shp" | "shapefile" => Ok(Format::Shapefile),
+            "csv" => Ok(Format::Csv),
+            "topojson" => Ok(Format::TopoJson),
+            "kml" => Ok(Format::Kml),
+            "kmz" => Ok(Format::Kmz),
+            "gpx" => Ok(Format::Gpx),
+            "mvt" => Ok(Format::Mvt),
+            "pmtiles" => Ok(Format::PmTiles),
+            "sql" | "postgis" => Ok(Format::Sql),
+            "wkt" => Ok(Format::Wkt),
+            "wkb" => Ok(Format::Wkb),
+            _ => Err(RandomGeojsonError::UnsupportedFormat(format!(
+                "Invalid format: {}",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Feature, Geometry};
+
+    use super::*;
+
+    #[test]
+    fn test_encode_lines() {
+        let fc = FeatureCollection {
+            features: vec![
+                Feature {
+                    geometry: Some(Geometry::new(geojson::Value::Point(vec![1.0, 2.0]))),
+                    ..Default::default()
+                },
+                Feature::default(),
+                Feature {
+                    geometry: Some(Geometry::new(geojson::Value::Point(vec![3.0, 4.0]))),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let options = EncodeOptions::default();
+
+        let wkt = Format::Wkt.encode(&fc, &options).unwrap();
+        assert_eq!(wkt, b"POINT (1 2)\nPOINT (3 4)\n");
+
+        let wkb = String::from_utf8(Format::Wkb.encode(&fc, &options).unwrap()).unwrap();
+        let lines: Vec<&str> = wkb.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "0101000000000000000000F03F0000000000000040");
+    }
+}
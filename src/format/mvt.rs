@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+
+use geojson::{Feature, FeatureCollection};
+use serde_json::Value;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::tile::{TileId, to_mercator};
+
+/// Name of the layer holding the features.
+const LAYER_NAME: &str = "random";
+
+/// Tile-local grid size of each tile.
+pub const EXTENT: u32 = 4096;
+
+// Geometry types of the vector tile specification
+const POINT: u64 = 1;
+const LINESTRING: u64 = 2;
+const POLYGON: u64 = 3;
+
+// Geometry commands
+const MOVE_TO: u32 = 1;
+const LINE_TO: u32 = 2;
+const CLOSE_PATH: u32 = 7;
+
+/// Encodes a feature collection as a Mapbox Vector Tile (version 2) with a
+/// single `random` layer.
+///
+/// Coordinates must be WGS84 or Web Mercator. Feature ids are stored as an
+/// `id` property, since vector tile ids must be integers.
+pub fn encode(
+    fc: &FeatureCollection,
+    tile: TileId,
+    epsg: Option<u32>,
+) -> RandomGeojsonResult<Vec<u8>> {
+    encode_features(&fc.features, tile, epsg)
+}
+
+/// Encodes features into a tile, see [`encode`].
+pub fn encode_features<'a>(
+    features: impl IntoIterator<Item = &'a Feature>,
+    tile: TileId,
+    epsg: Option<u32>,
+) -> RandomGeojsonResult<Vec<u8>> {
+    if !matches!(epsg, Some(4326) | Some(3857)) {
+        return Err(RandomGeojsonError::UnsupportedFormat(
+            "Vector tiles require WGS84 or Web Mercator coordinates".to_string(),
+        ));
+    }
+
+    let bounds = tile.mercator_bounds();
+    let to_tile = |position: &[f64]| -> (i32, i32) {
+        let (x, y) = to_mercator(epsg, position).expect("EPSG code was checked");
+        let scale = f64::from(EXTENT);
+        (
+            ((x - bounds.min_x) / bounds.width() * scale).round() as i32,
+            ((bounds.max_y - y) / bounds.height() * scale).round() as i32,
+        )
+    };
+
+    let mut layer = Layer::default();
+    for feature in features {
+        let Some(geometry) = &feature.geometry else {
+            continue;
+        };
+        let Some((geometry_type, commands)) = encode_geometry(&geometry.value, &to_tile) else {
+            continue;
+        };
+
+        let mut tags = Vec::new();
+        if let Some(id) = &feature.id
+            && !feature.contains_property("id")
+        {
+            let id = match id {
+                geojson::feature::Id::String(s) => Value::String(s.clone()),
+                geojson::feature::Id::Number(n) => Value::Number(n.clone()),
+            };
+            tags.extend(layer.tag("id", &id));
+        }
+        for (key, value) in feature.properties.iter().flatten() {
+            tags.extend(layer.tag(key, value));
+        }
+
+        let mut message = Vec::new();
+        write_packed(&mut message, 2, tags.iter().flatten().copied());
+        write_varint_field(&mut message, 3, geometry_type);
+        write_packed(&mut message, 4, commands.into_iter());
+        layer.features.push(message);
+    }
+
+    let mut tile = Vec::new();
+    write_bytes_field(&mut tile, 3, &layer.encode());
+    Ok(tile)
+}
+
+/// Property keys and values of a layer, each stored once.
+#[derive(Default)]
+struct Layer {
+    keys: Vec<String>,
+    key_index: HashMap<String, u32>,
+    values: Vec<Vec<u8>>,
+    value_index: HashMap<Vec<u8>, u32>,
+    features: Vec<Vec<u8>>,
+}
+
+impl Layer {
+    /// Returns the key and value indices of a property, or nothing for nulls.
+    fn tag(&mut self, key: &str, value: &Value) -> Option<[u32; 2]> {
+        let value = encode_value(value)?;
+        let next_key = self.keys.len() as u32;
+        let key = *self.key_index.entry(key.to_string()).or_insert_with(|| {
+            self.keys.push(key.to_string());
+            next_key
+        });
+        let next_value = self.values.len() as u32;
+        let value = *self.value_index.entry(value.clone()).or_insert_with(|| {
+            self.values.push(value);
+            next_value
+        });
+        Some([key, value])
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut layer = Vec::new();
+        write_varint_field(&mut layer, 15, 2);
+        write_bytes_field(&mut layer, 1, LAYER_NAME.as_bytes());
+        for feature in &self.features {
+            write_bytes_field(&mut layer, 2, feature);
+        }
+        for key in &self.keys {
+            write_bytes_field(&mut layer, 3, key.as_bytes());
+        }
+        for value in &self.values {
+            write_bytes_field(&mut layer, 4, value);
+        }
+        write_varint_field(&mut layer, 5, u64::from(EXTENT));
+        layer
+    }
+}
+
+// Encodes a property as a vector tile Value message.
+fn encode_value(value: &Value) -> Option<Vec<u8>> {
+    let mut message = Vec::new();
+    match value {
+        Value::Null => return None,
+        Value::Bool(b) => write_varint_field(&mut message, 7, u64::from(*b)),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(u), _) => write_varint_field(&mut message, 5, u),
+            (None, Some(i)) => write_varint_field(&mut message, 6, zigzag(i)),
+            _ => {
+                write_key(&mut message, 3, 1);
+                message.extend_from_slice(&n.as_f64().unwrap_or(f64::NAN).to_le_bytes());
+            }
+        },
+        Value::String(s) => write_bytes_field(&mut message, 1, s.as_bytes()),
+        other => write_bytes_field(&mut message, 1, other.to_string().as_bytes()),
+    }
+    Some(message)
+}
+
+/// Encodes a geometry as vector tile commands in tile coordinates, or `None`
+/// if nothing is left after snapping to the tile grid.
+fn encode_geometry(
+    value: &geojson::Value,
+    to_tile: &impl Fn(&[f64]) -> (i32, i32),
+) -> Option<(u64, Vec<u32>)> {
+    let mut commands = Commands::default();
+    let points = |ps: &[Vec<f64>]| -> Vec<(i32, i32)> {
+        let mut points: Vec<(i32, i32)> = ps.iter().map(|p| to_tile(p)).collect();
+        points.dedup();
+        points
+    };
+    let geometry_type = match value {
+        geojson::Value::Point(p) => {
+            commands.points(&[to_tile(p)]);
+            POINT
+        }
+        geojson::Value::MultiPoint(ps) => {
+            commands.points(&ps.iter().map(|p| to_tile(p)).collect::<Vec<_>>());
+            POINT
+        }
+        geojson::Value::LineString(line) => {
+            commands.line(&points(line));
+            LINESTRING
+        }
+        geojson::Value::MultiLineString(lines) => {
+            lines.iter().for_each(|l| commands.line(&points(l)));
+            LINESTRING
+        }
+        geojson::Value::Polygon(rings) => {
+            commands.polygon(rings.iter().map(|r| points(r)).collect());
+            POLYGON
+        }
+        geojson::Value::MultiPolygon(polygons) => {
+            for polygon in polygons {
+                commands.polygon(polygon.iter().map(|r| points(r)).collect());
+            }
+            POLYGON
+        }
+        // Vector tiles have no geometry collections
+        geojson::Value::GeometryCollection(_) => return None,
+    };
+    (!commands.commands.is_empty()).then_some((geometry_type, commands.commands))
+}
+
+/// Builds a command stream, tracking the cursor for delta encoding.
+#[derive(Default)]
+struct Commands {
+    commands: Vec<u32>,
+    cursor: (i32, i32),
+}
+
+impl Commands {
+    fn command(&mut self, id: u32, count: usize) {
+        self.commands.push(id | ((count as u32) << 3));
+    }
+
+    fn move_cursor(&mut self, point: (i32, i32)) {
+        self.commands
+            .push(zigzag(i64::from(point.0 - self.cursor.0)) as u32);
+        self.commands
+            .push(zigzag(i64::from(point.1 - self.cursor.1)) as u32);
+        self.cursor = point;
+    }
+
+    fn points(&mut self, points: &[(i32, i32)]) {
+        self.command(MOVE_TO, points.len());
+        points.iter().for_each(|&p| self.move_cursor(p));
+    }
+
+    fn line(&mut self, points: &[(i32, i32)]) {
+        if points.len() < 2 {
+            return;
+        }
+        self.command(MOVE_TO, 1);
+        self.move_cursor(points[0]);
+        self.command(LINE_TO, points.len() - 1);
+        points[1..].iter().for_each(|&p| self.move_cursor(p));
+    }
+
+    /// Writes the rings of a polygon, with the exterior ring clockwise and
+    /// holes counter-clockwise on screen (y pointing down), as required.
+    fn polygon(&mut self, rings: Vec<Vec<(i32, i32)>>) {
+        for (i, mut ring) in rings.into_iter().enumerate() {
+            if ring.len() > 1 && ring.first() == ring.last() {
+                ring.pop();
+            }
+            let area = signed_area(&ring);
+            if ring.len() < 3 || area == 0 {
+                // A hole cannot be written without its exterior ring
+                if i == 0 {
+                    return;
+                }
+                continue;
+            }
+            if (area > 0) != (i == 0) {
+                ring.reverse();
+            }
+            self.command(MOVE_TO, 1);
+            self.move_cursor(ring[0]);
+            self.command(LINE_TO, ring.len() - 1);
+            ring[1..].iter().for_each(|&p| self.move_cursor(p));
+            self.command(CLOSE_PATH, 1);
+        }
+    }
+}
+
+// Twice the signed area of a ring, positive when clockwise on screen.
+fn signed_area(ring: &[(i32, i32)]) -> i64 {
+    (0..ring.len())
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            i64::from(a.0) * i64::from(b.1) - i64::from(b.0) * i64::from(a.1)
+        })
+        .sum()
+}
+
+fn zigzag(n: i64) -> u64 {
+    ((n << 1) ^ (n >> 63)) as u64
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_key(out: &mut Vec<u8>, field: u32, wire_type: u32) {
+    write_varint(out, u64::from((field << 3) | wire_type));
+}
+
+fn write_varint_field(out: &mut Vec<u8>, field: u32, value: u64) {
+    write_key(out, field, 0);
+    write_varint(out, value);
+}
+
+fn write_bytes_field(out: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    write_key(out, field, 2);
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn write_packed(out: &mut Vec<u8>, field: u32, values: impl Iterator<Item = u32>) {
+    let mut packed = Vec::new();
+    values.for_each(|v| write_varint(&mut packed, u64::from(v)));
+    if !packed.is_empty() {
+        write_bytes_field(out, field, &packed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::Geometry;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_point_tile() {
+        let tile = TileId::new(0, 0, 0).unwrap();
+        let fc = FeatureCollection {
+            features: vec![Feature {
+                geometry: Some(Geometry::new(geojson::Value::Point(vec![0.0, 0.0]))),
+                properties: json!({"n": 1}).as_object().cloned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let bytes = encode(&fc, tile, Some(4326)).unwrap();
+        // Tile.layers (field 3, length-delimited)
+        assert_eq!(bytes[0], 0x1A);
+        // The point lands in the middle of the tile: MoveTo(1), zigzag(2048) twice
+        let geometry = [0x22, 0x05, 0x09, 0x80, 0x20, 0x80, 0x20];
+        assert!(bytes.windows(geometry.len()).any(|w| w == geometry));
+        assert!(encode(&fc, tile, None).is_err());
+    }
+
+    #[test]
+    fn test_polygon_winding() {
+        let mut commands = Commands::default();
+        // Counter-clockwise on screen, so it gets reversed
+        commands.polygon(vec![vec![(0, 0), (0, 10), (10, 10), (10, 0), (0, 0)]]);
+        assert_eq!(commands.commands[0], MOVE_TO | (1 << 3));
+        assert_eq!(commands.commands[3], LINE_TO | (3 << 3));
+        // Starts at (10, 0) then goes down to (10, 10)
+        assert_eq!(&commands.commands[1..3], &[20, 0]);
+        assert_eq!(&commands.commands[4..6], &[0, 20]);
+        assert_eq!(*commands.commands.last().unwrap(), CLOSE_PATH | (1 << 3));
+    }
+}
@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+
+use geojson::{Feature, FeatureCollection};
+use serde_json::json;
+
+use crate::compression::Compression;
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::format::mvt;
+use crate::format::schema::{ColumnKind, infer_schema};
+use crate::geometry::{Bounds, Unit, clip_geometry, geometry_bbox};
+use crate::tile::{MAX_ZOOM, TileId, from_mercator, to_mercator};
+
+const HEADER_LEN: usize = 127;
+
+/// The header and root directory must fit in the first 16 KiB.
+const MAX_ROOT_LEN: usize = 16_384 - HEADER_LEN;
+
+/// Features are clipped to their tile plus this fraction of its size on each
+/// side, so renderers can draw strokes across tile edges.
+const TILE_BUFFER: f64 = 64.0 / mvt::EXTENT as f64;
+
+// Compression and tile type codes of the PMTiles header
+const COMPRESSION_GZIP: u8 = 2;
+const TILE_TYPE_MVT: u8 = 1;
+
+/// Encodes a feature collection as a PMTiles (version 3) archive of vector
+/// tiles for every zoom level between `min_zoom` and `max_zoom`.
+///
+/// Each feature is clipped into every tile its bounding box touches. Tiles,
+/// directories and metadata are gzip-compressed, and tiles with identical
+/// contents are stored once.
+pub fn encode(
+    fc: &FeatureCollection,
+    min_zoom: u8,
+    max_zoom: u8,
+    epsg: Option<u32>,
+) -> RandomGeojsonResult<Vec<u8>> {
+    if min_zoom > max_zoom || max_zoom > MAX_ZOOM {
+        return Err(RandomGeojsonError::InvalidArgument(format!(
+            "Zoom range must satisfy minzoom <= maxzoom <= {}",
+            MAX_ZOOM
+        )));
+    }
+    if !matches!(epsg, Some(4326) | Some(3857)) {
+        return Err(RandomGeojsonError::UnsupportedFormat(
+            "PMTiles output requires WGS84 or Web Mercator coordinates".to_string(),
+        ));
+    }
+
+    // Reproject everything to Web Mercator once, so tiles can be cut directly
+    let features: Vec<Feature> = fc
+        .features
+        .iter()
+        .map(|f| Feature {
+            geometry: f.geometry.as_ref().map(|g| {
+                let mut value = g.value.clone();
+                map_positions(&mut value, &mut |p| {
+                    let (x, y) = to_mercator(epsg, p).expect("EPSG code was checked");
+                    p[0] = x;
+                    p[1] = y;
+                });
+                geojson::Geometry::new(value)
+            }),
+            ..f.clone()
+        })
+        .collect();
+
+    let mut extent: Option<[f64; 4]> = None;
+    let mut tiles: HashMap<TileId, Vec<usize>> = HashMap::new();
+    for (i, feature) in features.iter().enumerate() {
+        let Some(geometry) = &feature.geometry else {
+            continue;
+        };
+        let Some(b) = geometry_bbox(&geometry.value) else {
+            continue;
+        };
+        let e = extent.get_or_insert(b);
+        *e = [
+            e[0].min(b[0]),
+            e[1].min(b[1]),
+            e[2].max(b[2]),
+            e[3].max(b[3]),
+        ];
+        for z in min_zoom..=max_zoom {
+            let (min, max) = (
+                TileId::containing(z, b[0], b[3]),
+                TileId::containing(z, b[2], b[1]),
+            );
+            for x in min.x..=max.x {
+                for y in min.y..=max.y {
+                    tiles.entry(TileId { z, x, y }).or_default().push(i);
+                }
+            }
+        }
+    }
+
+    let mut tile_ids: Vec<(u64, TileId)> = tiles.keys().map(|&t| (tile_id(t), t)).collect();
+    tile_ids.sort_unstable_by_key(|&(id, _)| id);
+
+    let mut data = Vec::new();
+    let mut contents: HashMap<Vec<u8>, (u64, u32)> = HashMap::new();
+    let mut entries: Vec<Entry> = Vec::new();
+    for (id, tile) in tile_ids {
+        let bounds = tile.mercator_bounds();
+        let buffer = bounds.width() * TILE_BUFFER;
+        let clip_bounds = Bounds::new(
+            bounds.min_x - buffer,
+            bounds.min_y - buffer,
+            bounds.max_x + buffer,
+            bounds.max_y + buffer,
+            Unit::Meters,
+        );
+        let clipped: Vec<Feature> = tiles[&tile]
+            .iter()
+            .filter_map(|&i| {
+                let feature = &features[i];
+                let geometry = feature.geometry.as_ref()?;
+                let value = clip_geometry(&geometry.value, &clip_bounds)?;
+                Some(Feature {
+                    geometry: Some(geojson::Geometry::new(value)),
+                    ..feature.clone()
+                })
+            })
+            .collect();
+        if clipped.is_empty() {
+            continue;
+        }
+
+        let tile_data = mvt::encode_features(&clipped, tile, Some(3857))?;
+        let tile_data = Compression::Gzip.compress(&tile_data, 6)?;
+        let (offset, length) = *contents.entry(tile_data).or_insert_with_key(|tile_data| {
+            let offset = data.len() as u64;
+            data.extend_from_slice(tile_data);
+            (offset, tile_data.len() as u32)
+        });
+
+        // Consecutive tiles with the same contents share one run
+        match entries.last_mut() {
+            Some(last)
+                if last.offset == offset && last.tile_id + u64::from(last.run_length) == id =>
+            {
+                last.run_length += 1;
+            }
+            _ => entries.push(Entry {
+                tile_id: id,
+                offset,
+                length,
+                run_length: 1,
+            }),
+        }
+    }
+
+    let (root, leaves) = build_directories(&entries)?;
+    let metadata = Compression::Gzip.compress(&metadata(fc, min_zoom, max_zoom).into_bytes(), 6)?;
+
+    let extent = extent.unwrap_or_default();
+    let (min_lon, min_lat) = from_mercator(extent[0], extent[1]);
+    let (max_lon, max_lat) = from_mercator(extent[2], extent[3]);
+
+    let root_offset = HEADER_LEN as u64;
+    let metadata_offset = root_offset + root.len() as u64;
+    let leaves_offset = metadata_offset + metadata.len() as u64;
+    let data_offset = leaves_offset + leaves.len() as u64;
+    let addressed: u64 = entries.iter().map(|e| u64::from(e.run_length)).sum();
+
+    let mut out = Vec::with_capacity(data_offset as usize + data.len());
+    out.extend_from_slice(b"PMTiles");
+    out.push(3);
+    for value in [
+        root_offset,
+        root.len() as u64,
+        metadata_offset,
+        metadata.len() as u64,
+        leaves_offset,
+        leaves.len() as u64,
+        data_offset,
+        data.len() as u64,
+        addressed,
+        entries.len() as u64,
+        contents.len() as u64,
+    ] {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    // Clustered, internal compression, tile compression and tile type
+    out.extend_from_slice(&[1, COMPRESSION_GZIP, COMPRESSION_GZIP, TILE_TYPE_MVT]);
+    out.extend_from_slice(&[min_zoom, max_zoom]);
+    for degrees in [min_lon, min_lat, max_lon, max_lat] {
+        out.extend_from_slice(&e7(degrees).to_le_bytes());
+    }
+    out.push(min_zoom);
+    out.extend_from_slice(&e7((min_lon + max_lon) / 2.0).to_le_bytes());
+    out.extend_from_slice(&e7((min_lat + max_lat) / 2.0).to_le_bytes());
+    debug_assert_eq!(out.len(), HEADER_LEN);
+
+    out.extend_from_slice(&root);
+    out.extend_from_slice(&metadata);
+    out.extend_from_slice(&leaves);
+    out.extend_from_slice(&data);
+    Ok(out)
+}
+
+/// An entry of a PMTiles directory. Entries with a run length of zero point
+/// to leaf directories instead of tiles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Entry {
+    tile_id: u64,
+    offset: u64,
+    length: u32,
+    run_length: u32,
+}
+
+/// Position of a tile along the Hilbert curves of all zoom levels.
+pub fn tile_id(tile: TileId) -> u64 {
+    // Tiles of the lower zoom levels come first
+    let base = ((1u64 << (2 * u32::from(tile.z))) - 1) / 3;
+    let n = 1u64 << tile.z;
+    let (mut x, mut y) = (u64::from(tile.x), u64::from(tile.y));
+    let mut d = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = u64::from(x & s > 0);
+        let ry = u64::from(y & s > 0);
+        d += s * s * ((3 * rx) ^ ry);
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    base + d
+}
+
+// Serializes and compresses a directory.
+fn serialize_directory(entries: &[Entry]) -> RandomGeojsonResult<Vec<u8>> {
+    let mut out = Vec::new();
+    write_varint(&mut out, entries.len() as u64);
+    let mut last_id = 0;
+    for entry in entries {
+        write_varint(&mut out, entry.tile_id - last_id);
+        last_id = entry.tile_id;
+    }
+    for entry in entries {
+        write_varint(&mut out, u64::from(entry.run_length));
+    }
+    for entry in entries {
+        write_varint(&mut out, u64::from(entry.length));
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        // Zero means "right after the previous entry"
+        let contiguous =
+            i > 0 && entry.offset == entries[i - 1].offset + u64::from(entries[i - 1].length);
+        write_varint(&mut out, if contiguous { 0 } else { entry.offset + 1 });
+    }
+    Compression::Gzip.compress(&out, 6)
+}
+
+/// Builds the root directory, moving entries into leaf directories when they
+/// do not fit in the root.
+fn build_directories(entries: &[Entry]) -> RandomGeojsonResult<(Vec<u8>, Vec<u8>)> {
+    let root = serialize_directory(entries)?;
+    if root.len() <= MAX_ROOT_LEN {
+        return Ok((root, Vec::new()));
+    }
+
+    let mut leaf_size = 4096;
+    loop {
+        let mut leaves = Vec::new();
+        let mut root_entries = Vec::new();
+        for chunk in entries.chunks(leaf_size) {
+            let leaf = serialize_directory(chunk)?;
+            root_entries.push(Entry {
+                tile_id: chunk[0].tile_id,
+                offset: leaves.len() as u64,
+                length: leaf.len() as u32,
+                run_length: 0,
+            });
+            leaves.extend_from_slice(&leaf);
+        }
+        let root = serialize_directory(&root_entries)?;
+        if root.len() <= MAX_ROOT_LEN {
+            return Ok((root, leaves));
+        }
+        leaf_size *= 2;
+    }
+}
+
+// TileJSON-style metadata describing the layer.
+fn metadata(fc: &FeatureCollection, min_zoom: u8, max_zoom: u8) -> String {
+    let mut fields = serde_json::Map::new();
+    if fc.features.iter().any(|f| f.id.is_some()) {
+        fields.insert("id".to_string(), json!("String"));
+    }
+    for column in infer_schema(&fc.features) {
+        let kind = match column.kind {
+            ColumnKind::Bool => "Boolean",
+            ColumnKind::Int | ColumnKind::Float => "Number",
+            ColumnKind::String | ColumnKind::Json => "String",
+        };
+        fields.insert(column.name, json!(kind));
+    }
+    json!({
+        "name": "random",
+        "description": "Random features generated by random-geojson",
+        "vector_layers": [{
+            "id": "random",
+            "fields": fields,
+            "minzoom": min_zoom,
+            "maxzoom": max_zoom,
+        }],
+    })
+    .to_string()
+}
+
+fn map_positions(value: &mut geojson::Value, f: &mut impl FnMut(&mut Vec<f64>)) {
+    use geojson::Value;
+    match value {
+        Value::Point(p) => f(p),
+        Value::MultiPoint(ps) | Value::LineString(ps) => ps.iter_mut().for_each(f),
+        Value::MultiLineString(ls) | Value::Polygon(ls) => ls.iter_mut().flatten().for_each(f),
+        Value::MultiPolygon(polygons) => polygons.iter_mut().flatten().flatten().for_each(f),
+        Value::GeometryCollection(geometries) => geometries
+            .iter_mut()
+            .for_each(|g| map_positions(&mut g.value, f)),
+    }
+}
+
+fn e7(degrees: f64) -> i32 {
+    (degrees * 1e7).round() as i32
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::Geometry;
+
+    use super::*;
+
+    fn read_varint(bytes: &[u8], pos: &mut usize) -> u64 {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = bytes[*pos];
+            *pos += 1;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte < 0x80 {
+                return value;
+            }
+            shift += 7;
+        }
+    }
+
+    #[test]
+    fn test_tile_id() {
+        let id = |z, x, y| tile_id(TileId { z, x, y });
+        assert_eq!(id(0, 0, 0), 0);
+        assert_eq!(id(1, 0, 0), 1);
+        assert_eq!(id(1, 0, 1), 2);
+        assert_eq!(id(1, 1, 1), 3);
+        assert_eq!(id(1, 1, 0), 4);
+        assert_eq!(id(2, 0, 0), 5);
+    }
+
+    #[test]
+    fn test_encode_archive() {
+        let fc = FeatureCollection {
+            features: vec![Feature {
+                geometry: Some(Geometry::new(geojson::Value::Point(vec![10.0, 10.0]))),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let archive = encode(&fc, 0, 2, Some(4326)).unwrap();
+        assert_eq!(&archive[..8], b"PMTiles\x03");
+        let u64_at = |i: usize| u64::from_le_bytes(archive[i..i + 8].try_into().unwrap());
+        // One tile per zoom level
+        assert_eq!(u64_at(72), 3);
+        assert_eq!((archive[100], archive[101]), (0, 2));
+
+        let (root_offset, root_len) = (u64_at(8) as usize, u64_at(16) as usize);
+        let root = Compression::Gzip
+            .decompress(&archive[root_offset..root_offset + root_len])
+            .unwrap();
+        let mut pos = 0;
+        assert_eq!(read_varint(&root, &mut pos), 3);
+        let mut ids = Vec::new();
+        let mut last = 0;
+        for _ in 0..3 {
+            last += read_varint(&root, &mut pos);
+            ids.push(last);
+        }
+        let expected: Vec<u64> = [(0, 0, 0), (1, 1, 0), (2, 2, 1)]
+            .iter()
+            .map(|&(z, x, y)| tile_id(TileId { z, x, y }))
+            .collect();
+        assert_eq!(ids, expected);
+    }
+
+    #[test]
+    fn test_leaf_directories() {
+        let entries: Vec<Entry> = (0..20_000)
+            .map(|i| Entry {
+                tile_id: i * 7,
+                offset: i * 1000,
+                length: 900 + (i % 50) as u32,
+                run_length: 1,
+            })
+            .collect();
+        let (root, leaves) = build_directories(&entries).unwrap();
+        assert!(root.len() <= MAX_ROOT_LEN);
+        assert!(!leaves.is_empty());
+    }
+}
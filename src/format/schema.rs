@@ -0,0 +1,112 @@
+use geojson::Feature;
+use serde_json::Value;
+
+/// Type of a property column shared by all features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Bool,
+    Int,
+    Float,
+    String,
+    /// Mixed types, arrays or objects, stored as JSON text.
+    Json,
+}
+
+impl ColumnKind {
+    fn of(value: &Value) -> Option<ColumnKind> {
+        match value {
+            Value::Null => None,
+            Value::Bool(_) => Some(ColumnKind::Bool),
+            Value::Number(n) if n.is_f64() => Some(ColumnKind::Float),
+            Value::Number(_) => Some(ColumnKind::Int),
+            Value::String(_) => Some(ColumnKind::String),
+            Value::Array(_) | Value::Object(_) => Some(ColumnKind::Json),
+        }
+    }
+
+    fn merge(self, other: ColumnKind) -> ColumnKind {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (ColumnKind::Int, ColumnKind::Float) | (ColumnKind::Float, ColumnKind::Int) => {
+                ColumnKind::Float
+            }
+            _ => ColumnKind::Json,
+        }
+    }
+}
+
+/// A property column of a tabular output format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub name: String,
+    pub kind: ColumnKind,
+}
+
+/// Infers one column per property key, in order of first appearance.
+///
+/// Keys whose values have different types across features become
+/// [`ColumnKind::Json`] columns; keys that are only ever null become strings.
+pub fn infer_schema<'a>(features: impl IntoIterator<Item = &'a Feature>) -> Vec<Column> {
+    let mut columns: Vec<(String, Option<ColumnKind>)> = Vec::new();
+    for properties in features.into_iter().filter_map(|f| f.properties.as_ref()) {
+        for (key, value) in properties {
+            let kind = ColumnKind::of(value);
+            match columns.iter_mut().find(|(name, _)| name == key) {
+                Some((_, existing)) => {
+                    *existing = match (*existing, kind) {
+                        (Some(a), Some(b)) => Some(a.merge(b)),
+                        (a, b) => a.or(b),
+                    }
+                }
+                None => columns.push((key.clone(), kind)),
+            }
+        }
+    }
+    columns
+        .into_iter()
+        .map(|(name, kind)| Column {
+            name,
+            kind: kind.unwrap_or(ColumnKind::String),
+        })
+        .collect()
+}
+
+/// Renders a value as text for string or JSON columns.
+pub fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::FeatureCollection;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_infer_schema() {
+        let feature = |value: Value| Feature {
+            properties: value.as_object().cloned(),
+            ..Default::default()
+        };
+        let fc = FeatureCollection {
+            features: vec![
+                feature(json!({"a": 1, "b": "x", "c": true, "d": null})),
+                feature(json!({"a": 1.5, "b": 2, "e": [1]})),
+            ],
+            ..Default::default()
+        };
+        let kinds: Vec<(String, ColumnKind)> = infer_schema(&fc.features)
+            .into_iter()
+            .map(|c| (c.name, c.kind))
+            .collect();
+        assert!(kinds.contains(&("a".to_string(), ColumnKind::Float)));
+        assert!(kinds.contains(&("b".to_string(), ColumnKind::Json)));
+        assert!(kinds.contains(&("c".to_string(), ColumnKind::Bool)));
+        assert!(kinds.contains(&("d".to_string(), ColumnKind::String)));
+        assert!(kinds.contains(&("e".to_string(), ColumnKind::Json)));
+    }
+}
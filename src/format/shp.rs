@@ -0,0 +1,520 @@
+use std::io::{Cursor, Write};
+
+use geojson::{Feature, FeatureCollection};
+use serde_json::Value;
+use zip::write::{SimpleFileOptions, ZipWriter};
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::format::schema::{Column, ColumnKind, infer_schema, value_to_text};
+use crate::format::{civil_from_days, unix_time};
+use crate::geometry::geometry_bbox;
+use crate::random;
+
+/// dBase field names are limited to 10 characters.
+const MAX_FIELD_NAME_LEN: usize = 10;
+
+/// dBase character fields are limited to 254 bytes.
+const MAX_CHAR_FIELD_LEN: usize = 254;
+
+const WGS84_PRJ: &str = r#"GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]]"#;
+
+const WEB_MERCATOR_PRJ: &str = r#"PROJCS["WGS_1984_Web_Mercator_Auxiliary_Sphere",GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]],PROJECTION["Mercator_Auxiliary_Sphere"],PARAMETER["False_Easting",0.0],PARAMETER["False_Northing",0.0],PARAMETER["Central_Meridian",0.0],PARAMETER["Standard_Parallel_1",0.0],PARAMETER["Auxiliary_Sphere_Type",0.0],UNIT["Meter",1.0]]"#;
+
+/// Shape types used for the generated geometries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShapeType {
+    Point = 1,
+    PolyLine = 3,
+    Polygon = 5,
+    MultiPoint = 8,
+}
+
+impl ShapeType {
+    fn of(value: &geojson::Value) -> Option<ShapeType> {
+        match value {
+            geojson::Value::Point(_) => Some(ShapeType::Point),
+            geojson::Value::MultiPoint(_) => Some(ShapeType::MultiPoint),
+            geojson::Value::LineString(_) | geojson::Value::MultiLineString(_) => {
+                Some(ShapeType::PolyLine)
+            }
+            geojson::Value::Polygon(_) | geojson::Value::MultiPolygon(_) => {
+                Some(ShapeType::Polygon)
+            }
+            geojson::Value::GeometryCollection(_) => None,
+        }
+    }
+
+    fn layer_name(&self) -> &'static str {
+        match self {
+            ShapeType::Point => "random_point",
+            ShapeType::MultiPoint => "random_multipoint",
+            ShapeType::PolyLine => "random_linestring",
+            ShapeType::Polygon => "random_polygon",
+        }
+    }
+}
+
+/// Encodes a feature collection as a zip archive of shapefiles.
+///
+/// Shapefiles cannot mix geometry types, so features are split into one file
+/// set (`.shp`, `.shx`, `.dbf`, `.cpg` and, for known CRSs, `.prj`) per shape
+/// type, e.g. `random_point.shp` and `random_polygon.shp`. Property names are
+/// truncated to the 10 characters allowed in the DBF, keeping them unique.
+/// Features without a geometry are skipped.
+pub fn encode(fc: &FeatureCollection, epsg: Option<u32>) -> RandomGeojsonResult<Vec<u8>> {
+    let mut layers: Vec<(ShapeType, Vec<&Feature>)> = Vec::new();
+    for feature in &fc.features {
+        let Some(geometry) = &feature.geometry else {
+            continue;
+        };
+        let shape_type = ShapeType::of(&geometry.value).ok_or_else(|| {
+            RandomGeojsonError::UnsupportedFormat(
+                "Shapefiles cannot store GeometryCollection geometries".to_string(),
+            )
+        })?;
+        match layers.iter_mut().find(|(t, _)| *t == shape_type) {
+            Some((_, features)) => features.push(feature),
+            None => layers.push((shape_type, vec![feature])),
+        }
+    }
+
+    let prj = match epsg {
+        Some(4326) => Some(WGS84_PRJ),
+        Some(3857) => Some(WEB_MERCATOR_PRJ),
+        _ => None,
+    };
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let mut add = |name: String, data: &[u8]| -> zip::result::ZipResult<()> {
+        zip.start_file(name, SimpleFileOptions::default())?;
+        zip.write_all(data)?;
+        Ok(())
+    };
+    let written = layers.iter().try_for_each(|(shape_type, features)| {
+        let name = shape_type.layer_name();
+        let (shp, shx) = write_shapes(*shape_type, features);
+        add(format!("{}.shp", name), &shp)?;
+        add(format!("{}.shx", name), &shx)?;
+        add(format!("{}.dbf", name), &write_dbf(features))?;
+        add(format!("{}.cpg", name), b"UTF-8")?;
+        if let Some(prj) = prj {
+            add(format!("{}.prj", name), prj.as_bytes())?;
+        }
+        Ok(())
+    });
+    written
+        .and_then(|_| zip.finish())
+        .map(Cursor::into_inner)
+        .map_err(|e: zip::result::ZipError| {
+            RandomGeojsonError::Serialize(format!("Failed to write Shapefile: {}", e))
+        })
+}
+
+/// Writes the `.shp` and `.shx` files of one layer.
+fn write_shapes(shape_type: ShapeType, features: &[&Feature]) -> (Vec<u8>, Vec<u8>) {
+    let mut records = Vec::new();
+    let mut index = Vec::new();
+    let mut extent: Option<[f64; 4]> = None;
+
+    for (i, feature) in features.iter().enumerate() {
+        let value = &feature
+            .geometry
+            .as_ref()
+            .expect("grouped features have geometries")
+            .value;
+        let bbox = geometry_bbox(value);
+        if let Some(b) = bbox {
+            let e = extent.get_or_insert(b);
+            *e = [
+                e[0].min(b[0]),
+                e[1].min(b[1]),
+                e[2].max(b[2]),
+                e[3].max(b[3]),
+            ];
+        }
+        let content = match bbox {
+            Some(bbox) => shape_content(shape_type, value, bbox),
+            // Null shape
+            None => 0i32.to_le_bytes().to_vec(),
+        };
+
+        // Offsets and lengths are measured in 16-bit words
+        let offset = (100 + records.len()) / 2;
+        index.extend_from_slice(&(offset as i32).to_be_bytes());
+        index.extend_from_slice(&((content.len() / 2) as i32).to_be_bytes());
+
+        records.extend_from_slice(&(i as i32 + 1).to_be_bytes());
+        records.extend_from_slice(&((content.len() / 2) as i32).to_be_bytes());
+        records.extend_from_slice(&content);
+    }
+
+    let extent = extent.unwrap_or_default();
+    let mut shp = file_header(shape_type, 100 + records.len(), extent);
+    shp.extend_from_slice(&records);
+    let mut shx = file_header(shape_type, 100 + index.len(), extent);
+    shx.extend_from_slice(&index);
+    (shp, shx)
+}
+
+fn file_header(shape_type: ShapeType, file_len: usize, bbox: [f64; 4]) -> Vec<u8> {
+    let mut header = Vec::with_capacity(100);
+    header.extend_from_slice(&9994i32.to_be_bytes());
+    header.extend_from_slice(&[0; 20]);
+    header.extend_from_slice(&((file_len / 2) as i32).to_be_bytes());
+    header.extend_from_slice(&1000i32.to_le_bytes());
+    header.extend_from_slice(&(shape_type as i32).to_le_bytes());
+    for v in bbox {
+        header.extend_from_slice(&v.to_le_bytes());
+    }
+    // Z and M ranges are unused
+    header.extend_from_slice(&[0; 32]);
+    header
+}
+
+fn shape_content(shape_type: ShapeType, value: &geojson::Value, bbox: [f64; 4]) -> Vec<u8> {
+    let mut content = (shape_type as i32).to_le_bytes().to_vec();
+    let push_point = |content: &mut Vec<u8>, p: &[f64]| {
+        content.extend_from_slice(&p[0].to_le_bytes());
+        content.extend_from_slice(&p[1].to_le_bytes());
+    };
+
+    if let geojson::Value::Point(p) = value {
+        push_point(&mut content, p);
+        return content;
+    }
+
+    let parts: Vec<Vec<Vec<f64>>> = match value {
+        geojson::Value::MultiPoint(points) => vec![points.clone()],
+        geojson::Value::LineString(line) => vec![line.clone()],
+        geojson::Value::MultiLineString(lines) => lines.clone(),
+        geojson::Value::Polygon(rings) => oriented_rings(rings),
+        geojson::Value::MultiPolygon(polygons) => {
+            polygons.iter().flat_map(|p| oriented_rings(p)).collect()
+        }
+        geojson::Value::Point(_) | geojson::Value::GeometryCollection(_) => unreachable!(),
+    };
+
+    for v in bbox {
+        content.extend_from_slice(&v.to_le_bytes());
+    }
+    if shape_type != ShapeType::MultiPoint {
+        content.extend_from_slice(&(parts.len() as i32).to_le_bytes());
+    }
+    let num_points: usize = parts.iter().map(Vec::len).sum();
+    content.extend_from_slice(&(num_points as i32).to_le_bytes());
+    if shape_type != ShapeType::MultiPoint {
+        let mut start = 0;
+        for part in &parts {
+            content.extend_from_slice(&(start as i32).to_le_bytes());
+            start += part.len();
+        }
+    }
+    for p in parts.iter().flatten() {
+        push_point(&mut content, p);
+    }
+    content
+}
+
+/// Shapefiles expect clockwise outer rings and counter-clockwise holes, the
+/// opposite of the GeoJSON convention.
+fn oriented_rings(rings: &[Vec<Vec<f64>>]) -> Vec<Vec<Vec<f64>>> {
+    rings
+        .iter()
+        .enumerate()
+        .map(|(i, ring)| {
+            let clockwise = signed_area(ring) < 0.0;
+            let mut ring = ring.clone();
+            if clockwise != (i == 0) {
+                ring.reverse();
+            }
+            ring
+        })
+        .collect()
+}
+
+fn signed_area(ring: &[Vec<f64>]) -> f64 {
+    ring.windows(2)
+        .map(|w| w[0][0] * w[1][1] - w[1][0] * w[0][1])
+        .sum::<f64>()
+        / 2.0
+}
+
+/// A DBF field with its (possibly truncated) name and storage size.
+struct Field {
+    column: Column,
+    name: String,
+    length: usize,
+    decimals: usize,
+}
+
+/// Writes the `.dbf` attribute table of one layer.
+fn write_dbf(features: &[&Feature]) -> Vec<u8> {
+    let mut columns = infer_schema(features.iter().copied());
+    // Store feature ids unless a property already uses that name
+    let with_id =
+        features.iter().any(|f| f.id.is_some()) && !columns.iter().any(|c| c.name == "id");
+    if with_id {
+        columns.insert(
+            0,
+            Column {
+                name: "id".to_string(),
+                kind: ColumnKind::String,
+            },
+        );
+    }
+
+    let mut names: Vec<String> = Vec::new();
+    let fields: Vec<Field> = columns
+        .into_iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let name = field_name(&column.name, &names);
+            names.push(name.clone());
+            let (length, decimals) = match column.kind {
+                ColumnKind::Bool => (1, 0),
+                ColumnKind::Int => (20, 0),
+                ColumnKind::Float => (24, 15),
+                ColumnKind::String | ColumnKind::Json => {
+                    let longest = features
+                        .iter()
+                        .filter_map(|f| field_value(f, &column, with_id && i == 0))
+                        .map(|v| value_to_text(&v).len())
+                        .max()
+                        .unwrap_or(0);
+                    (longest.clamp(1, MAX_CHAR_FIELD_LEN), 0)
+                }
+            };
+            Field {
+                column,
+                name,
+                length,
+                decimals,
+            }
+        })
+        .collect();
+
+    let header_len = 32 + 32 * fields.len() + 1;
+    let record_len = 1 + fields.iter().map(|f| f.length).sum::<usize>();
+    // Seeded runs are dated the Unix epoch, so they are byte for byte the
+    // same every day
+    let today = if random::is_seeded() {
+        0
+    } else {
+        unix_time() / 86_400
+    };
+    let (year, month, day) = civil_from_days(today);
+
+    let mut dbf = Vec::with_capacity(header_len + record_len * features.len() + 1);
+    dbf.push(0x03);
+    dbf.extend_from_slice(&[(year - 1900) as u8, month, day]);
+    dbf.extend_from_slice(&(features.len() as u32).to_le_bytes());
+    dbf.extend_from_slice(&(header_len as u16).to_le_bytes());
+    dbf.extend_from_slice(&(record_len as u16).to_le_bytes());
+    dbf.extend_from_slice(&[0; 20]);
+    for field in &fields {
+        let mut name = [0u8; 11];
+        name[..field.name.len()].copy_from_slice(field.name.as_bytes());
+        dbf.extend_from_slice(&name);
+        dbf.push(match field.column.kind {
+            ColumnKind::Bool => b'L',
+            ColumnKind::Int | ColumnKind::Float => b'N',
+            ColumnKind::String | ColumnKind::Json => b'C',
+        });
+        dbf.extend_from_slice(&[0; 4]);
+        dbf.push(field.length as u8);
+        dbf.push(field.decimals as u8);
+        dbf.extend_from_slice(&[0; 14]);
+    }
+    dbf.push(0x0D);
+
+    for feature in features {
+        // Records start with a blank deletion flag
+        dbf.push(b' ');
+        for (i, field) in fields.iter().enumerate() {
+            let value = field_value(feature, &field.column, with_id && i == 0);
+            dbf.extend_from_slice(&field_bytes(field, value.as_ref()));
+        }
+    }
+    dbf.push(0x1A);
+    dbf
+}
+
+fn field_value(feature: &Feature, column: &Column, is_id: bool) -> Option<Value> {
+    if is_id {
+        return feature.id.as_ref().map(|id| match id {
+            geojson::feature::Id::String(s) => Value::String(s.clone()),
+            geojson::feature::Id::Number(n) => Value::Number(n.clone()),
+        });
+    }
+    feature
+        .property(&column.name)
+        .filter(|v| !v.is_null())
+        .cloned()
+}
+
+/// Formats a value into the fixed-width representation of its field. Missing
+/// values are left blank, which readers treat as null.
+fn field_bytes(field: &Field, value: Option<&Value>) -> Vec<u8> {
+    let text = match (field.column.kind, value) {
+        (ColumnKind::Bool, None) => "?".to_string(),
+        (_, None) => String::new(),
+        (ColumnKind::Bool, Some(v)) => {
+            if v.as_bool() == Some(true) { "T" } else { "F" }.to_string()
+        }
+        (ColumnKind::Int, Some(v)) => v.as_i64().map(|n| n.to_string()).unwrap_or_default(),
+        (ColumnKind::Float, Some(v)) => v
+            .as_f64()
+            .map(|n| format_float(n, field.length, field.decimals))
+            .unwrap_or_default(),
+        (ColumnKind::String | ColumnKind::Json, Some(v)) => {
+            truncate(&value_to_text(v), field.length).to_string()
+        }
+    };
+
+    let padding = vec![b' '; field.length - text.len()];
+    match field.column.kind {
+        // Numbers are right-aligned, text left-aligned
+        ColumnKind::Int | ColumnKind::Float => [padding, text.into_bytes()].concat(),
+        _ => [text.into_bytes(), padding].concat(),
+    }
+}
+
+/// Formats a float with as many of `decimals` digits as fit in `width`.
+fn format_float(value: f64, width: usize, decimals: usize) -> String {
+    let integer_digits = format!("{:.0}", value).len();
+    let decimals = decimals.min(width.saturating_sub(integer_digits + 1));
+    let text = format!("{:.*}", decimals, value);
+    if text.len() <= width {
+        text
+    } else {
+        format!("{:e}", value)
+    }
+}
+
+/// Truncates a property name to fit in a DBF field name, appending a numeric
+/// suffix if the truncated name (compared case-insensitively) is already taken.
+fn field_name(name: &str, taken: &[String]) -> String {
+    let is_taken = |candidate: &str| taken.iter().any(|t| t.eq_ignore_ascii_case(candidate));
+    let truncated = truncate(name, MAX_FIELD_NAME_LEN);
+    if !truncated.is_empty() && !is_taken(truncated) {
+        return truncated.to_string();
+    }
+    (1..)
+        .map(|n| {
+            let suffix = format!("_{}", n);
+            format!(
+                "{}{}",
+                truncate(name, MAX_FIELD_NAME_LEN - suffix.len()),
+                suffix
+            )
+        })
+        .find(|candidate| !is_taken(candidate))
+        .expect("a free suffix always exists")
+}
+
+/// Truncates `s` to at most `max_len` bytes without splitting a character.
+fn truncate(s: &str, max_len: usize) -> &str {
+    if s.len() <= max_len {
+        return s;
+    }
+    let mut end = max_len;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use geojson::Geometry;
+    use serde_json::json;
+
+    use super::*;
+
+    fn read_archive(bytes: Vec<u8>) -> Vec<(String, Vec<u8>)> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        (0..archive.len())
+            .map(|i| {
+                let mut file = archive.by_index(i).unwrap();
+                let mut data = Vec::new();
+                file.read_to_end(&mut data).unwrap();
+                (file.name().unwrap().to_string(), data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_encode_splits_by_geometry_type() {
+        let feature = |value: geojson::Value| Feature {
+            geometry: Some(Geometry::new(value)),
+            properties: json!({"a_very_long_name": 1, "a_very_long_other": "x"})
+                .as_object()
+                .cloned(),
+            ..Default::default()
+        };
+        let fc = FeatureCollection {
+            features: vec![
+                feature(geojson::Value::Point(vec![1.0, 2.0])),
+                feature(geojson::Value::Polygon(vec![vec![
+                    vec![0.0, 0.0],
+                    vec![1.0, 0.0],
+                    vec![1.0, 1.0],
+                    vec![0.0, 0.0],
+                ]])),
+                feature(geojson::Value::Point(vec![3.0, 4.0])),
+            ],
+            ..Default::default()
+        };
+        let files = read_archive(encode(&fc, Some(4326)).unwrap());
+        let names: Vec<&str> = files.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "random_point.shp",
+                "random_point.shx",
+                "random_point.dbf",
+                "random_point.cpg",
+                "random_point.prj",
+                "random_polygon.shp",
+                "random_polygon.shx",
+                "random_polygon.dbf",
+                "random_polygon.cpg",
+                "random_polygon.prj",
+            ]
+        );
+
+        let (_, shp) = &files[0];
+        assert_eq!(&shp[..4], &9994i32.to_be_bytes());
+        assert_eq!(i32::from_le_bytes(shp[32..36].try_into().unwrap()), 1);
+        // Header plus two point records of 8 + 20 bytes
+        assert_eq!(shp.len(), 100 + 2 * 28);
+
+        let (_, dbf) = &files[2];
+        assert_eq!(u32::from_le_bytes(dbf[4..8].try_into().unwrap()), 2);
+        assert_eq!(&dbf[32..42], b"a_very_lon");
+        assert_eq!(&dbf[64..74], b"a_very_l_1");
+
+        // The outer ring is written clockwise
+        let (_, polygon) = &files[5];
+        let x = |i: usize| f64::from_le_bytes(polygon[i..i + 8].try_into().unwrap());
+        let points = 100 + 8 + 4 + 32 + 4 + 4 + 4;
+        assert_eq!((x(points + 16), x(points + 24)), (1.0, 1.0));
+
+        // Seeded runs are dated the Unix epoch, and reproducible
+        let seeded = || random::with_seed(7, || encode(&fc, Some(4326)).unwrap());
+        assert_eq!(seeded(), seeded());
+        let (_, dbf) = &read_archive(seeded())[2];
+        assert_eq!(&dbf[1..4], &[70, 1, 1]);
+    }
+
+    #[test]
+    fn test_field_name() {
+        let taken = vec!["population".to_string()];
+        assert_eq!(field_name("name", &taken), "name");
+        assert_eq!(field_name("population_2020", &taken), "populati_1");
+        assert_eq!(field_name("POPULATION", &taken), "POPULATI_1");
+        assert_eq!(field_name("ÅÅÅÅÅÅ", &[]), "ÅÅÅÅÅ");
+    }
+}
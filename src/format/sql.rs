@@ -0,0 +1,258 @@
+use geojson::FeatureCollection;
+use serde_json::Value;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::format::schema::{Column, ColumnKind, infer_schema, value_to_text};
+use crate::format::wkb;
+
+/// Number of rows per INSERT statement.
+const INSERT_BATCH_SIZE: usize = 1000;
+
+/// WKB type flag marking an EWKB geometry that carries an SRID.
+const EWKB_SRID_FLAG: u32 = 0x2000_0000;
+
+/// How rows are loaded by the generated SQL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SqlMode {
+    /// Multi-row `INSERT` statements using `ST_GeomFromGeoJSON`.
+    #[default]
+    Insert,
+    /// A `COPY ... FROM STDIN` block with hex EWKB geometries.
+    Copy,
+}
+
+impl std::str::FromStr for SqlMode {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "insert" => Ok(SqlMode::Insert),
+            "copy" => Ok(SqlMode::Copy),
+            _ => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid SQL mode: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Encodes a feature collection as a PostGIS script that creates `table`
+/// (optionally schema-qualified, e.g. "test.random") and loads the features,
+/// ready to be piped into `psql`.
+pub fn encode(
+    fc: &FeatureCollection,
+    table: &str,
+    mode: SqlMode,
+    epsg: Option<u32>,
+) -> RandomGeojsonResult<Vec<u8>> {
+    if table.is_empty() || table.split('.').any(str::is_empty) {
+        return Err(RandomGeojsonError::InvalidArgument(format!(
+            "Invalid table name: {}",
+            table
+        )));
+    }
+    let table = table
+        .split('.')
+        .map(quote_identifier)
+        .collect::<Vec<_>>()
+        .join(".");
+    let srid = epsg.unwrap_or(0);
+
+    let mut columns = infer_schema(&fc.features);
+    columns.retain(|c| c.name != "geom");
+    let with_id =
+        fc.features.iter().any(|f| f.id.is_some()) && !columns.iter().any(|c| c.name == "id");
+    if with_id {
+        columns.insert(
+            0,
+            Column {
+                name: "id".to_string(),
+                kind: ColumnKind::String,
+            },
+        );
+    }
+
+    let mut out = String::from("BEGIN;\n");
+    let definitions: Vec<String> = columns
+        .iter()
+        .map(|c| {
+            let sql_type = match c.kind {
+                ColumnKind::Bool => "boolean",
+                ColumnKind::Int => "bigint",
+                ColumnKind::Float => "double precision",
+                ColumnKind::String => "text",
+                ColumnKind::Json => "jsonb",
+            };
+            format!("{} {}", quote_identifier(&c.name), sql_type)
+        })
+        .chain([format!("geom geometry(Geometry, {})", srid)])
+        .collect();
+    out.push_str(&format!(
+        "CREATE TABLE {} (\n    {}\n);\n",
+        table,
+        definitions.join(",\n    ")
+    ));
+
+    let column_list: String = columns
+        .iter()
+        .map(|c| quote_identifier(&c.name))
+        .chain(["geom".to_string()])
+        .collect::<Vec<_>>()
+        .join(", ");
+    let rows = fc.features.iter().map(|feature| {
+        let values: Vec<Option<Value>> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                if with_id && i == 0 {
+                    feature.id.as_ref().map(|id| match id {
+                        geojson::feature::Id::String(s) => Value::String(s.clone()),
+                        geojson::feature::Id::Number(n) => Value::Number(n.clone()),
+                    })
+                } else {
+                    feature
+                        .property(&column.name)
+                        .filter(|v| !v.is_null())
+                        .cloned()
+                }
+            })
+            .collect();
+        (values, feature.geometry.as_ref())
+    });
+
+    match mode {
+        SqlMode::Insert => {
+            let rows: Vec<String> = rows
+                .map(|(values, geometry)| {
+                    let mut literals: Vec<String> = values
+                        .iter()
+                        .zip(&columns)
+                        .map(|(v, c)| literal(v.as_ref(), c.kind))
+                        .collect();
+                    literals.push(match geometry {
+                        Some(geometry) => format!(
+                            "ST_SetSRID(ST_GeomFromGeoJSON({}), {})",
+                            quote_literal(&geometry.to_string()),
+                            srid
+                        ),
+                        None => "NULL".to_string(),
+                    });
+                    format!("({})", literals.join(", "))
+                })
+                .collect();
+            for batch in rows.chunks(INSERT_BATCH_SIZE) {
+                out.push_str(&format!(
+                    "INSERT INTO {} ({}) VALUES\n{};\n",
+                    table,
+                    column_list,
+                    batch.join(",\n")
+                ));
+            }
+        }
+        SqlMode::Copy => {
+            out.push_str(&format!("COPY {} ({}) FROM STDIN;\n", table, column_list));
+            for (values, geometry) in rows {
+                let mut fields: Vec<String> = values
+                    .iter()
+                    .zip(&columns)
+                    .map(|(v, c)| copy_field(v.as_ref(), c.kind))
+                    .collect();
+                fields.push(match geometry {
+                    Some(geometry) => ewkb_hex(&geometry.value, srid),
+                    None => "\\N".to_string(),
+                });
+                out.push_str(&fields.join("\t"));
+                out.push('\n');
+            }
+            out.push_str("\\.\n");
+        }
+    }
+    out.push_str("COMMIT;\n");
+    Ok(out.into_bytes())
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn quote_literal(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+// Renders a value as an SQL literal for an INSERT statement.
+fn literal(value: Option<&Value>, kind: ColumnKind) -> String {
+    match (kind, value) {
+        (_, None) => "NULL".to_string(),
+        (ColumnKind::Bool | ColumnKind::Int | ColumnKind::Float, Some(v @ Value::Bool(_)))
+        | (ColumnKind::Bool | ColumnKind::Int | ColumnKind::Float, Some(v @ Value::Number(_))) => {
+            v.to_string()
+        }
+        (ColumnKind::Json, Some(v)) => format!("{}::jsonb", quote_literal(&v.to_string())),
+        (_, Some(v)) => quote_literal(&value_to_text(v)),
+    }
+}
+
+// Renders a value as a field of COPY's text format.
+fn copy_field(value: Option<&Value>, kind: ColumnKind) -> String {
+    let text = match (kind, value) {
+        (_, None) => return "\\N".to_string(),
+        (ColumnKind::Json, Some(v)) => v.to_string(),
+        (_, Some(v)) => value_to_text(v),
+    };
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+// Hex-encodes a geometry as EWKB, setting the SRID on the outer geometry.
+fn ewkb_hex(value: &geojson::Value, srid: u32) -> String {
+    let wkb = wkb::encode_geometry(value);
+    let geometry_type = u32::from_le_bytes(wkb[1..5].try_into().expect("WKB has a header"));
+    let mut ewkb = Vec::with_capacity(wkb.len() + 4);
+    ewkb.push(wkb[0]);
+    ewkb.extend_from_slice(&(geometry_type | EWKB_SRID_FLAG).to_le_bytes());
+    ewkb.extend_from_slice(&srid.to_le_bytes());
+    ewkb.extend_from_slice(&wkb[5..]);
+    ewkb.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Feature, Geometry};
+    use serde_json::json;
+
+    use super::*;
+
+    fn collection() -> FeatureCollection {
+        FeatureCollection {
+            features: vec![Feature {
+                id: Some(geojson::feature::Id::String("a".to_string())),
+                geometry: Some(Geometry::new(geojson::Value::Point(vec![1.0, 2.0]))),
+                properties: json!({"name": "O'Brien\tjr", "n": 3}).as_object().cloned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_encode_insert() {
+        let sql = encode(&collection(), "test.random", SqlMode::Insert, Some(4326)).unwrap();
+        let sql = String::from_utf8(sql).unwrap();
+        assert!(sql.contains("CREATE TABLE \"test\".\"random\" (\n    \"id\" text,\n    \"n\" bigint,\n    \"name\" text,\n    geom geometry(Geometry, 4326)\n);"));
+        assert!(sql.contains("('a', 3, 'O''Brien\tjr', ST_SetSRID(ST_GeomFromGeoJSON("));
+        assert!(sql.ends_with("COMMIT;\n"));
+        assert!(encode(&collection(), "test.", SqlMode::Insert, None).is_err());
+    }
+
+    #[test]
+    fn test_encode_copy() {
+        let sql = encode(&collection(), "random", SqlMode::Copy, Some(4326)).unwrap();
+        let sql = String::from_utf8(sql).unwrap();
+        assert!(sql.contains("COPY \"random\" (\"id\", \"n\", \"name\", geom) FROM STDIN;\n"));
+        // Point with the SRID flag and SRID 4326 (0x10E6)
+        assert!(sql.contains("a\t3\tO'Brien\\tjr\t0101000020E6100000"));
+        assert!(sql.contains("\n\\.\n"));
+    }
+}
@@ -0,0 +1,400 @@
+use std::collections::{HashMap, HashSet};
+
+use geojson::FeatureCollection;
+use serde_json::{Value, json};
+
+use crate::error::RandomGeojsonResult;
+use crate::format::EncodeOptions;
+use crate::format::canonical::to_json;
+
+/// Name of the object holding the features in the topology.
+const OBJECT_NAME: &str = "random";
+
+/// Number of distinct quantized values along each axis.
+const QUANTIZATION: f64 = 1e5;
+
+type Point = (i64, i64);
+
+/// Encodes a feature collection as a quantized TopoJSON topology.
+///
+/// Lines and polygon rings are cut at junctions (points where they meet
+/// with different neighbours), so edges shared by adjacent polygons are
+/// stored once as a single arc referenced by both.
+pub fn encode(fc: &FeatureCollection, options: &EncodeOptions) -> RandomGeojsonResult<Vec<u8>> {
+    let transform = Transform::new(fc);
+
+    // Quantize everything up front, so junctions are found on the same
+    // integer grid the arcs are stored on
+    let geometries: Vec<Option<Quantized>> = fc
+        .features
+        .iter()
+        .map(|f| f.geometry.as_ref().map(|g| quantize(&g.value, &transform)))
+        .collect();
+
+    let mut junctions = Junctions::default();
+    for geometry in geometries.iter().flatten() {
+        geometry.for_each_line(&mut |line, ring| junctions.add(line, ring));
+    }
+
+    let mut arcs = Arcs::default();
+    let objects: Vec<Value> = fc
+        .features
+        .iter()
+        .zip(&geometries)
+        .map(|(feature, geometry)| {
+            let mut object = match geometry {
+                Some(geometry) => arcs.object(geometry, &junctions.points),
+                None => json!({ "type": null }),
+            };
+            if let Some(id) = &feature.id {
+                object["id"] = json!(id);
+            }
+            if let Some(properties) = &feature.properties {
+                object["properties"] = Value::Object(properties.clone());
+            }
+            object
+        })
+        .collect();
+
+    let topology = json!({
+        "type": "Topology",
+        "bbox": transform.bbox,
+        "transform": {
+            "scale": transform.scale,
+            "translate": [transform.bbox[0], transform.bbox[1]],
+        },
+        "objects": {
+            OBJECT_NAME: { "type": "GeometryCollection", "geometries": objects },
+        },
+        "arcs": arcs.encoded(),
+    });
+
+    to_json(&topology, options, "TopoJSON")
+}
+
+/// Quantization transform covering the extent of the collection.
+struct Transform {
+    bbox: [f64; 4],
+    scale: [f64; 2],
+}
+
+impl Transform {
+    fn new(fc: &FeatureCollection) -> Transform {
+        let bbox = fc
+            .features
+            .iter()
+            .filter_map(|f| f.geometry.as_ref())
+            .filter_map(|g| crate::geometry::geometry_bbox(&g.value))
+            .reduce(|a, b| {
+                [
+                    a[0].min(b[0]),
+                    a[1].min(b[1]),
+                    a[2].max(b[2]),
+                    a[3].max(b[3]),
+                ]
+            })
+            .unwrap_or_default();
+        let scale = |min: f64, max: f64| {
+            if max > min {
+                (max - min) / (QUANTIZATION - 1.0)
+            } else {
+                1.0
+            }
+        };
+        Transform {
+            bbox,
+            scale: [scale(bbox[0], bbox[2]), scale(bbox[1], bbox[3])],
+        }
+    }
+
+    fn apply(&self, position: &[f64]) -> Point {
+        (
+            ((position[0] - self.bbox[0]) / self.scale[0]).round() as i64,
+            ((position[1] - self.bbox[1]) / self.scale[1]).round() as i64,
+        )
+    }
+}
+
+/// A geometry on the quantized grid.
+enum Quantized {
+    Point(Point),
+    MultiPoint(Vec<Point>),
+    LineString(Vec<Point>),
+    MultiLineString(Vec<Vec<Point>>),
+    Polygon(Vec<Vec<Point>>),
+    MultiPolygon(Vec<Vec<Vec<Point>>>),
+    GeometryCollection(Vec<Quantized>),
+}
+
+impl Quantized {
+    /// Calls `f` with every line, flagging polygon rings.
+    fn for_each_line(&self, f: &mut impl FnMut(&[Point], bool)) {
+        match self {
+            Quantized::Point(_) | Quantized::MultiPoint(_) => {}
+            Quantized::LineString(line) => f(line, false),
+            Quantized::MultiLineString(lines) => lines.iter().for_each(|l| f(l, false)),
+            Quantized::Polygon(rings) => rings.iter().for_each(|r| f(r, true)),
+            Quantized::MultiPolygon(polygons) => polygons.iter().flatten().for_each(|r| f(r, true)),
+            Quantized::GeometryCollection(geometries) => {
+                geometries.iter().for_each(|g| g.for_each_line(f))
+            }
+        }
+    }
+}
+
+fn quantize(value: &geojson::Value, transform: &Transform) -> Quantized {
+    // Consecutive positions that land on the same grid cell are merged
+    let line = |positions: &[Vec<f64>]| {
+        let mut line: Vec<Point> = positions.iter().map(|p| transform.apply(p)).collect();
+        line.dedup();
+        line
+    };
+    let rings = |rings: &[Vec<Vec<f64>>]| rings.iter().map(|r| line(r)).collect();
+    match value {
+        geojson::Value::Point(p) => Quantized::Point(transform.apply(p)),
+        geojson::Value::MultiPoint(ps) => {
+            Quantized::MultiPoint(ps.iter().map(|p| transform.apply(p)).collect())
+        }
+        geojson::Value::LineString(ps) => Quantized::LineString(line(ps)),
+        geojson::Value::MultiLineString(lines) => {
+            Quantized::MultiLineString(lines.iter().map(|l| line(l)).collect())
+        }
+        geojson::Value::Polygon(r) => Quantized::Polygon(rings(r)),
+        geojson::Value::MultiPolygon(polygons) => {
+            Quantized::MultiPolygon(polygons.iter().map(|p| rings(p)).collect())
+        }
+        geojson::Value::GeometryCollection(geometries) => Quantized::GeometryCollection(
+            geometries
+                .iter()
+                .map(|g| quantize(&g.value, transform))
+                .collect(),
+        ),
+    }
+}
+
+/// Finds the points where lines must be cut into arcs.
+#[derive(Default)]
+struct Junctions {
+    /// Neighbours each point was first seen with, as an unordered pair.
+    neighbours: HashMap<Point, (Point, Point)>,
+    points: HashSet<Point>,
+}
+
+impl Junctions {
+    fn add(&mut self, line: &[Point], ring: bool) {
+        // Rings repeat their first point at the end
+        let line = if ring && line.len() > 1 && line.first() == line.last() {
+            &line[..line.len() - 1]
+        } else {
+            line
+        };
+        let n = line.len();
+        if n == 0 {
+            return;
+        }
+        if !ring {
+            self.points.insert(line[0]);
+            self.points.insert(line[n - 1]);
+        }
+        let interior = if ring { 0..n } else { 1..n.saturating_sub(1) };
+        for i in interior {
+            let previous = line[(i + n - 1) % n];
+            let next = line[(i + 1) % n];
+            let pair = if previous < next {
+                (previous, next)
+            } else {
+                (next, previous)
+            };
+            match self.neighbours.get(&line[i]) {
+                Some(seen) if *seen != pair => {
+                    self.points.insert(line[i]);
+                }
+                Some(_) => {}
+                None => {
+                    self.neighbours.insert(line[i], pair);
+                }
+            }
+        }
+    }
+}
+
+/// Deduplicated arcs, looked up in either direction.
+#[derive(Default)]
+struct Arcs {
+    arcs: Vec<Vec<Point>>,
+    index: HashMap<Vec<Point>, usize>,
+}
+
+impl Arcs {
+    /// Returns the index of `arc`, or the one's complement of the index of
+    /// its reverse, adding it if it is new.
+    fn insert(&mut self, mut arc: Vec<Point>) -> i64 {
+        if arc.len() == 1 {
+            arc.push(arc[0]);
+        }
+        if let Some(&i) = self.index.get(&arc) {
+            return i as i64;
+        }
+        let reversed: Vec<Point> = arc.iter().rev().copied().collect();
+        if let Some(&i) = self.index.get(&reversed) {
+            return !(i as i64);
+        }
+        self.index.insert(arc.clone(), self.arcs.len());
+        self.arcs.push(arc);
+        self.arcs.len() as i64 - 1
+    }
+
+    /// Cuts a line (or ring) at the junctions and returns its arc indices.
+    fn line(&mut self, line: &[Point], ring: bool, junctions: &HashSet<Point>) -> Vec<i64> {
+        if line.is_empty() {
+            return Vec::new();
+        }
+        let mut points = line.to_vec();
+        if ring {
+            if points.len() > 1 && points.first() == points.last() {
+                points.pop();
+            }
+            match points.iter().position(|p| junctions.contains(p)) {
+                // Start the ring at a junction so it can be cut there
+                Some(start) => points.rotate_left(start),
+                // Rings without junctions are only shared if identical, so
+                // start them at a canonical point to detect that
+                None => {
+                    let start = (0..points.len()).min_by_key(|&i| points[i]).unwrap_or(0);
+                    points.rotate_left(start);
+                }
+            }
+            points.push(points[0]);
+        }
+
+        let mut arcs = Vec::new();
+        let mut start = 0;
+        for i in 1..points.len() {
+            if i == points.len() - 1 || junctions.contains(&points[i]) {
+                arcs.push(self.insert(points[start..=i].to_vec()));
+                start = i;
+            }
+        }
+        if arcs.is_empty() {
+            arcs.push(self.insert(points));
+        }
+        arcs
+    }
+
+    /// Builds the TopoJSON geometry object of a quantized geometry.
+    fn object(&mut self, geometry: &Quantized, junctions: &HashSet<Point>) -> Value {
+        let point = |p: &Point| json!([p.0, p.1]);
+        match geometry {
+            Quantized::Point(p) => json!({ "type": "Point", "coordinates": point(p) }),
+            Quantized::MultiPoint(ps) => json!({
+                "type": "MultiPoint",
+                "coordinates": ps.iter().map(point).collect::<Vec<_>>(),
+            }),
+            Quantized::LineString(line) => json!({
+                "type": "LineString",
+                "arcs": self.line(line, false, junctions),
+            }),
+            Quantized::MultiLineString(lines) => json!({
+                "type": "MultiLineString",
+                "arcs": lines.iter().map(|l| self.line(l, false, junctions)).collect::<Vec<_>>(),
+            }),
+            Quantized::Polygon(rings) => json!({
+                "type": "Polygon",
+                "arcs": rings.iter().map(|r| self.line(r, true, junctions)).collect::<Vec<_>>(),
+            }),
+            Quantized::MultiPolygon(polygons) => json!({
+                "type": "MultiPolygon",
+                "arcs": polygons
+                    .iter()
+                    .map(|p| p.iter().map(|r| self.line(r, true, junctions)).collect::<Vec<_>>())
+                    .collect::<Vec<_>>(),
+            }),
+            Quantized::GeometryCollection(geometries) => json!({
+                "type": "GeometryCollection",
+                "geometries": geometries.iter().map(|g| self.object(g, junctions)).collect::<Vec<_>>(),
+            }),
+        }
+    }
+
+    /// Delta-encodes the arcs as required for quantized topologies.
+    fn encoded(&self) -> Vec<Vec<[i64; 2]>> {
+        self.arcs
+            .iter()
+            .map(|arc| {
+                let mut previous = (0, 0);
+                arc.iter()
+                    .map(|&(x, y)| {
+                        let delta = [x - previous.0, y - previous.1];
+                        previous = (x, y);
+                        delta
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Feature, Geometry};
+
+    use super::*;
+
+    fn square(x: f64) -> Feature {
+        Feature {
+            geometry: Some(Geometry::new(geojson::Value::Polygon(vec![vec![
+                vec![x, 0.0],
+                vec![x + 1.0, 0.0],
+                vec![x + 1.0, 1.0],
+                vec![x, 1.0],
+                vec![x, 0.0],
+            ]]))),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_adjacent_polygons_share_an_arc() {
+        let fc = FeatureCollection {
+            features: vec![square(0.0), square(1.0)],
+            ..Default::default()
+        };
+        let topology: Value =
+            serde_json::from_slice(&encode(&fc, &EncodeOptions::default()).unwrap()).unwrap();
+        assert_eq!(topology["type"], "Topology");
+        // Two outer boundaries plus the shared edge
+        assert_eq!(topology["arcs"].as_array().unwrap().len(), 3);
+
+        let geometries = topology["objects"]["random"]["geometries"]
+            .as_array()
+            .unwrap();
+        let arcs = |i: usize| -> Vec<i64> {
+            serde_json::from_value(geometries[i]["arcs"][0].clone()).unwrap()
+        };
+        let (a, b) = (arcs(0), arcs(1));
+        let shared: Vec<i64> = a
+            .iter()
+            .filter(|&&i| b.contains(&!i) || b.contains(&i))
+            .copied()
+            .collect();
+        assert_eq!(shared.len(), 1);
+    }
+
+    #[test]
+    fn test_points_are_quantized() {
+        let point = |x: f64, y: f64| Feature {
+            geometry: Some(Geometry::new(geojson::Value::Point(vec![x, y]))),
+            ..Default::default()
+        };
+        let fc = FeatureCollection {
+            features: vec![point(-10.0, -5.0), point(10.0, 5.0)],
+            ..Default::default()
+        };
+        let topology: Value =
+            serde_json::from_slice(&encode(&fc, &EncodeOptions::default()).unwrap()).unwrap();
+        let geometries = &topology["objects"]["random"]["geometries"];
+        assert_eq!(geometries[0]["coordinates"], json!([0, 0]));
+        assert_eq!(geometries[1]["coordinates"], json!([99999, 99999]));
+        assert_eq!(topology["transform"]["translate"], json!([-10.0, -5.0]));
+    }
+}
@@ -0,0 +1,119 @@
+use geojson::Value;
+
+// ISO WKB geometry type codes
+const WKB_POINT: u32 = 1;
+const WKB_LINESTRING: u32 = 2;
+const WKB_POLYGON: u32 = 3;
+const WKB_MULTIPOINT: u32 = 4;
+const WKB_MULTILINESTRING: u32 = 5;
+const WKB_MULTIPOLYGON: u32 = 6;
+const WKB_GEOMETRYCOLLECTION: u32 = 7;
+
+/// Encodes a GeoJSON geometry as little-endian 2D WKB.
+pub fn encode_geometry(value: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_geometry(&mut out, value);
+    out
+}
+
+fn write_header(out: &mut Vec<u8>, geometry_type: u32) {
+    out.push(1); // little endian
+    out.extend_from_slice(&geometry_type.to_le_bytes());
+}
+
+fn write_count(out: &mut Vec<u8>, count: usize) {
+    out.extend_from_slice(&(count as u32).to_le_bytes());
+}
+
+fn write_position(out: &mut Vec<u8>, position: &[f64]) {
+    let x = position.first().copied().unwrap_or(f64::NAN);
+    let y = position.get(1).copied().unwrap_or(f64::NAN);
+    out.extend_from_slice(&x.to_le_bytes());
+    out.extend_from_slice(&y.to_le_bytes());
+}
+
+fn write_positions(out: &mut Vec<u8>, positions: &[Vec<f64>]) {
+    write_count(out, positions.len());
+    positions.iter().for_each(|p| write_position(out, p));
+}
+
+fn write_rings(out: &mut Vec<u8>, rings: &[Vec<Vec<f64>>]) {
+    write_count(out, rings.len());
+    rings.iter().for_each(|r| write_positions(out, r));
+}
+
+fn write_geometry(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Point(p) => {
+            write_header(out, WKB_POINT);
+            write_position(out, p);
+        }
+        Value::LineString(ps) => {
+            write_header(out, WKB_LINESTRING);
+            write_positions(out, ps);
+        }
+        Value::Polygon(rings) => {
+            write_header(out, WKB_POLYGON);
+            write_rings(out, rings);
+        }
+        Value::MultiPoint(ps) => {
+            write_header(out, WKB_MULTIPOINT);
+            write_count(out, ps.len());
+            for p in ps {
+                write_header(out, WKB_POINT);
+                write_position(out, p);
+            }
+        }
+        Value::MultiLineString(lines) => {
+            write_header(out, WKB_MULTILINESTRING);
+            write_count(out, lines.len());
+            for line in lines {
+                write_header(out, WKB_LINESTRING);
+                write_positions(out, line);
+            }
+        }
+        Value::MultiPolygon(polygons) => {
+            write_header(out, WKB_MULTIPOLYGON);
+            write_count(out, polygons.len());
+            for polygon in polygons {
+                write_header(out, WKB_POLYGON);
+                write_rings(out, polygon);
+            }
+        }
+        Value::GeometryCollection(geometries) => {
+            write_header(out, WKB_GEOMETRYCOLLECTION);
+            write_count(out, geometries.len());
+            geometries
+                .iter()
+                .for_each(|g| write_geometry(out, &g.value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_point() {
+        let wkb = encode_geometry(&Value::Point(vec![1.0, 2.0]));
+        let mut expected = vec![1, 1, 0, 0, 0];
+        expected.extend_from_slice(&1.0f64.to_le_bytes());
+        expected.extend_from_slice(&2.0f64.to_le_bytes());
+        assert_eq!(wkb, expected);
+    }
+
+    #[test]
+    fn test_encode_polygon_length() {
+        let ring = vec![
+            vec![0.0, 0.0],
+            vec![1.0, 0.0],
+            vec![1.0, 1.0],
+            vec![0.0, 0.0],
+        ];
+        let wkb = encode_geometry(&Value::Polygon(vec![ring]));
+        // header + ring count + point count + 4 points
+        assert_eq!(wkb.len(), 5 + 4 + 4 + 4 * 16);
+        assert_eq!(&wkb[1..5], &3u32.to_le_bytes());
+    }
+}
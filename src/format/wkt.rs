@@ -0,0 +1,108 @@
+use geojson::Value;
+
+/// Encodes a GeoJSON geometry as 2D WKT, e.g. `POINT (1 2)`.
+pub fn encode_geometry(value: &Value) -> String {
+    let mut out = String::new();
+    write_geometry(&mut out, value);
+    out
+}
+
+fn write_position(out: &mut String, position: &[f64]) {
+    let x = position.first().copied().unwrap_or(f64::NAN);
+    let y = position.get(1).copied().unwrap_or(f64::NAN);
+    out.push_str(&format!("{} {}", x, y));
+}
+
+// Writes a parenthesized, comma-separated list, or EMPTY if there are no items.
+fn write_list<T>(out: &mut String, items: &[T], mut write_item: impl FnMut(&mut String, &T)) {
+    if items.is_empty() {
+        out.push_str("EMPTY");
+        return;
+    }
+    out.push('(');
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_item(out, item);
+    }
+    out.push(')');
+}
+
+fn write_positions(out: &mut String, positions: &[Vec<f64>]) {
+    write_list(out, positions, |out, p| write_position(out, p));
+}
+
+fn write_rings(out: &mut String, rings: &[Vec<Vec<f64>>]) {
+    write_list(out, rings, |out, r| write_positions(out, r));
+}
+
+fn write_geometry(out: &mut String, value: &Value) {
+    match value {
+        Value::Point(p) if p.is_empty() => out.push_str("POINT EMPTY"),
+        Value::Point(p) => {
+            out.push_str("POINT (");
+            write_position(out, p);
+            out.push(')');
+        }
+        Value::LineString(ps) => {
+            out.push_str("LINESTRING ");
+            write_positions(out, ps);
+        }
+        Value::Polygon(rings) => {
+            out.push_str("POLYGON ");
+            write_rings(out, rings);
+        }
+        Value::MultiPoint(ps) => {
+            out.push_str("MULTIPOINT ");
+            write_list(out, ps, |out, p| {
+                out.push('(');
+                write_position(out, p);
+                out.push(')');
+            });
+        }
+        Value::MultiLineString(lines) => {
+            out.push_str("MULTILINESTRING ");
+            write_rings(out, lines);
+        }
+        Value::MultiPolygon(polygons) => {
+            out.push_str("MULTIPOLYGON ");
+            write_list(out, polygons, |out, p| write_rings(out, p));
+        }
+        Value::GeometryCollection(geometries) => {
+            out.push_str("GEOMETRYCOLLECTION ");
+            write_list(out, geometries, |out, g| write_geometry(out, &g.value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_geometries() {
+        assert_eq!(
+            encode_geometry(&Value::Point(vec![1.5, -2.0])),
+            "POINT (1.5 -2)"
+        );
+        let ring = vec![
+            vec![0.0, 0.0],
+            vec![1.0, 0.0],
+            vec![1.0, 1.0],
+            vec![0.0, 0.0],
+        ];
+        assert_eq!(
+            encode_geometry(&Value::Polygon(vec![ring])),
+            "POLYGON ((0 0, 1 0, 1 1, 0 0))"
+        );
+        assert_eq!(
+            encode_geometry(&Value::MultiPoint(vec![vec![1.0, 2.0], vec![3.0, 4.0]])),
+            "MULTIPOINT ((1 2), (3 4))"
+        );
+        assert_eq!(
+            encode_geometry(&Value::GeometryCollection(vec![])),
+            "GEOMETRYCOLLECTION EMPTY"
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use geojson::feature::Id;
+use geojson::{Feature, FeatureCollection};
+use rand::{Rng, RngCore};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::anchor::{project, project_one};
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::expr::Expr;
+use crate::format::schema::ColumnKind;
+use crate::geometry::{Crs, RandomGeometry, random_geographic_coords};
+use crate::random::{self, random_uuid};
+
+/// A generator of a property of features, for attribute logic of a domain
+/// that the built-in property types don't cover.
+///
+/// ```
+/// use geojson::Feature;
+/// use rand::{Rng, RngCore};
+/// use random_geojson::format::schema::ColumnKind;
+/// use random_geojson::generator::PropertyGenerator;
+/// use serde_json::Value;
+///
+/// struct Lanes;
+///
+/// impl PropertyGenerator for Lanes {
+///     fn name(&self) -> &str {
+///         "lanes"
+///     }
+///
+///     fn kind(&self) -> ColumnKind {
+///         ColumnKind::Int
+///     }
+///
+///     fn generate(&self, rng: &mut dyn RngCore, _feature: &Feature) -> Value {
+///         Value::from(rng.random_range(1..=4))
+///     }
+/// }
+/// ```
+pub trait PropertyGenerator {
+    /// Name of the property.
+    fn name(&self) -> &str;
+
+    /// Type of the values.
+    fn kind(&self) -> ColumnKind;
+
+    /// Generates the value of the property for `feature`, whose properties
+    /// hold those generated before it.
+    fn generate(&self, rng: &mut dyn RngCore, feature: &Feature) -> Value;
+}
+
+/// Adds the properties of `generators` to every feature of `fc`, in the
+/// order given, so each generator sees the values of those before it.
+pub fn add_generated_properties(fc: &mut FeatureCollection, generators: &[&dyn PropertyGenerator]) {
+    if generators.is_empty() {
+        return;
+    }
+    let mut rng = random::rng();
+    for feature in &mut fc.features {
+        for generator in generators {
+            let value = generator.generate(&mut rng, feature);
+            feature
+                .properties
+                .get_or_insert_default()
+                .insert(generator.name().to_string(), value);
+        }
+    }
+}
+
+/// A generator of geometries, for shapes the built-in generators don't
+/// cover, e.g. a parcel along a road.
+///
+/// Generators work in geographic coordinates around an origin that the
+/// crate draws within the area of the CRS, honouring its mask or density
+/// grid, and the crate clamps the result to the area and projects it, so
+/// custom shapes get the same coordinate systems, seeding and output
+/// formats as the built-in ones. [`crate::anchor::offset`] moves a position
+/// by metres.
+///
+/// ```
+/// use geojson::Feature;
+/// use rand::{Rng, RngCore};
+/// use random_geojson::anchor::offset;
+/// use random_geojson::generator::GeometryGenerator;
+/// use random_geojson::geometry::RandomGeometry;
+///
+/// /// A square parcel of 20 to 50 m.
+/// struct Parcel;
+///
+/// impl GeometryGenerator for Parcel {
+///     fn generate(&self, rng: &mut dyn RngCore, (lon, lat): (f64, f64)) -> RandomGeometry {
+///         let size = rng.random_range(20.0..50.0);
+///         let ring = [(0.0, 0.0), (size, 0.0), (size, size), (0.0, size), (0.0, 0.0)]
+///             .map(|(east, north)| {
+///                 let (x, y) = offset(lon, lat, east, north);
+///                 vec![x, y]
+///             });
+///         RandomGeometry::Polygon(vec![ring.to_vec()])
+///     }
+/// }
+/// ```
+pub trait GeometryGenerator {
+    /// Generates a geometry in longitude and latitude near `origin`, a
+    /// random position within the area.
+    fn generate(&self, rng: &mut dyn RngCore, origin: (f64, f64)) -> RandomGeometry;
+}
+
+/// Generates a geometry with `generator` within the area of `crs`, in its
+/// output coordinates.
+pub fn generate_geometry(crs: &Crs, generator: &dyn GeometryGenerator) -> RandomGeometry {
+    let origin = random_geographic_coords(crs);
+    let geographic = |line: Vec<Vec<f64>>| -> Vec<(f64, f64)> {
+        line.into_iter().map(|p| (p[0], p[1])).collect()
+    };
+    match generator.generate(&mut random::rng(), origin) {
+        RandomGeometry::Point(p) => RandomGeometry::Point(project_one(crs, (p[0], p[1]))),
+        RandomGeometry::LineString(line) => {
+            RandomGeometry::LineString(project(crs, &geographic(line)))
+        }
+        RandomGeometry::Polygon(rings) => RandomGeometry::Polygon(
+            rings
+                .into_iter()
+                .map(|ring| project(crs, &geographic(ring)))
+                .collect(),
+        ),
+    }
+}
+
+/// Generates `length` features with the geometries of `geometry` and the
+/// properties of `properties`, each with a random UUID as its id, e.g. for
+/// [`crate::output::serialize`].
+pub fn random_features(
+    crs: &Crs,
+    length: usize,
+    geometry: &dyn GeometryGenerator,
+    properties: &[&dyn PropertyGenerator],
+) -> FeatureCollection {
+    let features = (0..length)
+        .map(|_| Feature {
+            id: Some(Id::String(random_uuid().to_string())),
+            geometry: Some(generate_geometry(crs, geometry).into()),
+            properties: Some(Default::default()),
+            ..Default::default()
+        })
+        .collect();
+    let mut fc = FeatureCollection {
+        bbox: None,
+        features,
+        foreign_members: None,
+    };
+    add_generated_properties(&mut fc, properties);
+    fc
+}
+
+/// A property computed by an [`Expr`] of a generator script.
+///
+/// Besides the properties of the feature, the expression sees `random`, a
+/// number drawn from 0 to 1 for each feature, and `geometry_type`, the type
+/// of its geometry, unless the feature has properties of those names;
+/// `index` is the id of the feature if it is a number. The value is
+/// converted to the type of the generator, and is null if it can't be.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptGenerator {
+    pub name: String,
+    pub kind: ColumnKind,
+    pub expr: Expr,
+}
+
+impl PropertyGenerator for ScriptGenerator {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn kind(&self) -> ColumnKind {
+        self.kind
+    }
+
+    fn generate(&self, rng: &mut dyn RngCore, feature: &Feature) -> Value {
+        let mut properties = feature.properties.clone().unwrap_or_default();
+        let random = rng.random::<f64>();
+        properties
+            .entry("random")
+            .or_insert_with(|| Value::from(random));
+        let geometry_type = feature.geometry.as_ref().map(|g| g.value.type_name());
+        properties
+            .entry("geometry_type")
+            .or_insert_with(|| geometry_type.map_or(Value::Null, Value::from));
+        let index = match &feature.id {
+            Some(Id::Number(n)) => n.as_u64().unwrap_or(0) as usize,
+            _ => 0,
+        };
+        convert(self.kind, self.expr.evaluate(&properties, index))
+    }
+}
+
+// Converts a value to a type, or null.
+fn convert(kind: ColumnKind, value: Value) -> Value {
+    match (kind, value) {
+        (_, Value::Null) => Value::Null,
+        (ColumnKind::Int, Value::Number(n)) => match n.as_i64() {
+            Some(i) => Value::from(i),
+            None => n
+                .as_f64()
+                .map_or(Value::Null, |f| Value::from(f.round() as i64)),
+        },
+        (ColumnKind::Float, Value::Number(n)) => n.as_f64().map_or(Value::Null, Value::from),
+        (ColumnKind::String, Value::String(s)) => Value::String(s),
+        (ColumnKind::String, value) => Value::String(value.to_string()),
+        (ColumnKind::Bool, Value::Bool(b)) => Value::Bool(b),
+        (ColumnKind::Json, value) => value,
+        _ => Value::Null,
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    #[serde(rename = "type")]
+    kind: String,
+    expr: String,
+}
+
+/// Parses a generator script: a TOML table per property, in the order the
+/// properties are generated, with its `type`, one of "int", "float",
+/// "string", "bool" or "json", and the `expr` computing it, e.g.
+///
+/// ```toml
+/// [speed_limit]
+/// type = "int"
+/// expr = 'highway == "motorway" ? 70 : random < 0.5 ? 30 : 50'
+/// ```
+pub fn parse_script(text: &str) -> RandomGeojsonResult<Vec<ScriptGenerator>> {
+    let invalid = |reason: String| {
+        RandomGeojsonError::InvalidInput(format!("Invalid generator script: {}", reason))
+    };
+    let table: toml::Table = toml::from_str(text).map_err(|e| invalid(e.to_string()))?;
+    table
+        .into_iter()
+        .map(|(name, value)| {
+            let entry: Entry = value
+                .try_into()
+                .map_err(|e| invalid(format!("{}: {}", name, e)))?;
+            let kind = match entry.kind.to_lowercase().as_str() {
+                "int" | "integer" => ColumnKind::Int,
+                "float" | "number" => ColumnKind::Float,
+                "string" | "str" => ColumnKind::String,
+                "bool" | "boolean" => ColumnKind::Bool,
+                "json" => ColumnKind::Json,
+                kind => return Err(invalid(format!("{}: unknown type '{}'", name, kind))),
+            };
+            Ok(ScriptGenerator {
+                name,
+                kind,
+                expr: entry.expr.parse()?,
+            })
+        })
+        .collect()
+}
+
+/// Reads a generator script file, see [`parse_script`].
+pub fn read_script(path: &str) -> RandomGeojsonResult<Vec<ScriptGenerator>> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| RandomGeojsonError::Io(format!("Failed to read {}: {}", path, e)))?;
+    parse_script(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Geometry, Value as GeometryValue};
+    use serde_json::json;
+
+    use super::*;
+
+    fn feature(properties: Value) -> Feature {
+        Feature {
+            geometry: Some(Geometry::new(GeometryValue::Point(vec![0.0, 0.0]))),
+            properties: properties.as_object().cloned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let script = parse_script(
+            "[speed_limit]\ntype = \"int\"\nexpr = 'highway == \"motorway\" ? 70 : 30'\n\n\
+             [kind]\ntype = \"string\"\nexpr = 'geometry_type'\n",
+        )
+        .unwrap();
+        let names: Vec<&str> = script.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, ["speed_limit", "kind"]);
+        assert_eq!(script[0].kind, ColumnKind::Int);
+        for text in [
+            "[a]\ntype = \"date\"\nexpr = '1'",
+            "[a]\ntype = \"int\"",
+            "[a]\ntype = \"int\"\nexpr = '1 +'",
+            "[a]\ntype = \"int\"\nexpr = '1'\nmin = 0",
+            "a = 1",
+        ] {
+            assert!(parse_script(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_add_generated_properties() {
+        let script = parse_script(
+            "[speed_limit]\ntype = \"int\"\nexpr = 'highway == \"motorway\" ? 70.4 : 30'\n\n\
+             [fast]\ntype = \"bool\"\nexpr = 'speed_limit > 50'\n\n\
+             [label]\ntype = \"string\"\nexpr = 'geometry_type + \" \" + speed_limit'\n\n\
+             [coin]\ntype = \"float\"\nexpr = 'random'\n",
+        )
+        .unwrap();
+        let generators: Vec<&dyn PropertyGenerator> =
+            script.iter().map(|g| g as &dyn PropertyGenerator).collect();
+        let mut fc = FeatureCollection {
+            bbox: None,
+            features: vec![
+                feature(json!({"highway": "motorway"})),
+                feature(json!({"highway": "residential"})),
+            ],
+            foreign_members: None,
+        };
+        add_generated_properties(&mut fc, &generators);
+        let properties = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(properties["speed_limit"], json!(70));
+        assert_eq!(properties["fast"], json!(true));
+        assert_eq!(properties["label"], json!("Point 70"));
+        assert!((0.0..1.0).contains(&properties["coin"].as_f64().unwrap()));
+        assert!(!properties.contains_key("random"));
+        let properties = fc.features[1].properties.as_ref().unwrap();
+        assert_eq!(properties["fast"], json!(false));
+    }
+
+    // A line 1 km east of the origin.
+    struct East;
+
+    impl GeometryGenerator for East {
+        fn generate(&self, _rng: &mut dyn RngCore, (lon, lat): (f64, f64)) -> RandomGeometry {
+            let (x, y) = crate::anchor::offset(lon, lat, 1000.0, 0.0);
+            RandomGeometry::LineString(vec![vec![lon, lat], vec![x, y]])
+        }
+    }
+
+    #[test]
+    fn test_random_features() {
+        let crs = Crs::WebMercator;
+        let script = parse_script("[n]\ntype = \"int\"\nexpr = '7'\n").unwrap();
+        let fc = random_features(&crs, 20, &East, &[&script[0]]);
+        assert_eq!(fc.features.len(), 20);
+        let extent = crs.extent();
+        for feature in &fc.features {
+            assert_eq!(feature.properties.as_ref().unwrap()["n"], json!(7));
+            let GeometryValue::LineString(line) = &feature.geometry.as_ref().unwrap().value else {
+                panic!("not a line");
+            };
+            // Metres in Web Mercator grow with latitude
+            assert!(line[1][0] - line[0][0] >= 999.0, "{:?}", line);
+            assert!(
+                line.iter().all(|p| extent.contains(p[0], p[1])),
+                "{:?}",
+                line
+            );
+        }
+    }
+}
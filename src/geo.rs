@@ -0,0 +1,91 @@
+use geo_types::{Coord, Geometry, LineString, Point, Polygon};
+
+use crate::geometry::{Crs, RandomGeometry};
+
+impl From<RandomGeometry> for Geometry<f64> {
+    fn from(geometry: RandomGeometry) -> Self {
+        match geometry {
+            RandomGeometry::Point(coords) => Geometry::Point(Point(coord(&coords))),
+            RandomGeometry::LineString(coords) => Geometry::LineString(line_string(&coords)),
+            RandomGeometry::Polygon(rings) => Geometry::Polygon(polygon(&rings)),
+        }
+    }
+}
+
+/// Creates a random Point as a `geo_types::Point`.
+pub fn random_point(crs: &Crs) -> Point<f64> {
+    match RandomGeometry::random_point(crs) {
+        RandomGeometry::Point(coords) => Point(coord(&coords)),
+        _ => unreachable!("random points are points"),
+    }
+}
+
+/// Creates a random LineString with a random number of points as a
+/// `geo_types::LineString`.
+pub fn random_line_string(crs: &Crs) -> LineString<f64> {
+    match RandomGeometry::random_linestring(crs) {
+        RandomGeometry::LineString(coords) => line_string(&coords),
+        _ => unreachable!("random linestrings are linestrings"),
+    }
+}
+
+/// Creates a random Polygon with a random number of points as a
+/// `geo_types::Polygon`.
+pub fn random_polygon(crs: &Crs) -> Polygon<f64> {
+    match RandomGeometry::random_polygon(crs) {
+        RandomGeometry::Polygon(rings) => polygon(&rings),
+        _ => unreachable!("random polygons are polygons"),
+    }
+}
+
+// The x and y of a position; any z is dropped, as geo-types is 2D.
+fn coord(position: &[f64]) -> Coord<f64> {
+    Coord {
+        x: position[0],
+        y: position[1],
+    }
+}
+
+fn line_string(positions: &[Vec<f64>]) -> LineString<f64> {
+    positions.iter().map(|p| coord(p)).collect()
+}
+
+fn polygon(rings: &[Vec<Vec<f64>>]) -> Polygon<f64> {
+    let mut rings = rings.iter().map(|ring| line_string(ring));
+    let exterior = rings.next().unwrap_or_else(|| LineString::new(vec![]));
+    Polygon::new(exterior, rings.collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_random_geometry() {
+        let point = Geometry::from(RandomGeometry::Point(vec![1.0, 2.0, 3.0]));
+        assert_eq!(point, Geometry::Point(Point::new(1.0, 2.0)));
+        let ring = vec![
+            vec![0.0, 0.0],
+            vec![1.0, 0.0],
+            vec![1.0, 1.0],
+            vec![0.0, 0.0],
+        ];
+        let Geometry::Polygon(polygon) = Geometry::from(RandomGeometry::Polygon(vec![ring])) else {
+            panic!("not a polygon");
+        };
+        assert_eq!(polygon.exterior().0.len(), 4);
+        assert!(polygon.interiors().is_empty());
+    }
+
+    #[test]
+    fn test_random_geo_types() {
+        let point = random_point(&Crs::WGS84);
+        assert!((-180.0..=180.0).contains(&point.x()) && (-90.0..=90.0).contains(&point.y()));
+        assert!(random_line_string(&Crs::WGS84).0.len() >= 2);
+        // Polygons are closed rings of at least three distinct vertices
+        let polygon = random_polygon(&Crs::WebMercator);
+        let exterior = &polygon.exterior().0;
+        assert!(exterior.len() >= 4);
+        assert_eq!(exterior.first(), exterior.last());
+    }
+}
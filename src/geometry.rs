@@ -1,35 +1,297 @@
+use std::sync::Arc;
+
+use proj4rs::proj::Proj;
 use rand::Rng;
 
-use crate::RandomGeojsonError;
+use crate::density::DensityGrid;
+use crate::error::RandomGeojsonError;
+use crate::mask::Mask;
+use crate::random;
+
+/// Unit in which the coordinates of a [`Bounds`] are expressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Degrees,
+    Meters,
+    Feet,
+    /// Tile-local grid units, such as the 0..4096 extent of a vector tile.
+    Pixels,
+}
 
-#[derive(Debug, Clone, Copy)]
+/// An axis-aligned extent in an arbitrary planar or geographic coordinate system.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Bounds {
-    pub min_lon: f64,
-    pub max_lon: f64,
-    pub min_lat: f64,
-    pub max_lat: f64,
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+    pub unit: Unit,
 }
 
 pub const WGS84_BOUNDS: Bounds = Bounds {
-    min_lon: -180.0,
-    max_lon: 180.0,
-    min_lat: -90.0,
-    max_lat: 90.0,
+    min_x: -180.0,
+    min_y: -90.0,
+    max_x: 180.0,
+    max_y: 90.0,
+    unit: Unit::Degrees,
 };
 
 pub const WEB_MERCATOR_BOUNDS: Bounds = Bounds {
-    min_lon: -180.0,
-    max_lon: 180.0,
-    min_lat: -85.05112878,
-    max_lat: 85.05112878,
+    min_x: -180.0,
+    min_y: -85.05112878,
+    max_x: 180.0,
+    max_y: 85.05112878,
+    unit: Unit::Degrees,
 };
 
+/// Projected extent of Web Mercator (EPSG:3857) in metres.
+pub const WEB_MERCATOR_EXTENT: Bounds = Bounds {
+    min_x: -20_037_508.342789244,
+    min_y: -20_037_508.342789244,
+    max_x: 20_037_508.342789244,
+    max_y: 20_037_508.342789244,
+    unit: Unit::Meters,
+};
+
+impl Bounds {
+    pub fn new(min_x: f64, min_y: f64, max_x: f64, max_y: f64, unit: Unit) -> Self {
+        Bounds {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+            unit,
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max_y - self.min_y
+    }
+
+    /// Returns true if the coordinate lies within the bounds (edges included).
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        self.min_x <= x && x <= self.max_x && self.min_y <= y && y <= self.max_y
+    }
+
+    /// Returns the overlap of two bounds in the same unit, if any.
+    pub fn intersection(&self, other: &Bounds) -> Option<Bounds> {
+        let bounds = Bounds {
+            min_x: self.min_x.max(other.min_x),
+            min_y: self.min_y.max(other.min_y),
+            max_x: self.max_x.min(other.max_x),
+            max_y: self.max_y.min(other.max_y),
+            unit: self.unit,
+        };
+        (bounds.min_x < bounds.max_x && bounds.min_y < bounds.max_y).then_some(bounds)
+    }
+
+    /// Returns the bounds with both corners passed through `transform`, in the
+    /// unit of the transform's output.
+    ///
+    /// Only meaningful for transforms that preserve axis ordering, such as the
+    /// cylindrical projections provided in this module.
+    pub fn transformed(&self, transform: &dyn CoordTransform) -> Bounds {
+        let (x1, y1) = transform.transform(self.min_x, self.min_y);
+        let (x2, y2) = transform.transform(self.max_x, self.max_y);
+        Bounds {
+            min_x: x1.min(x2),
+            min_y: y1.min(y2),
+            max_x: x1.max(x2),
+            max_y: y1.max(y2),
+            unit: transform.unit(),
+        }
+    }
+}
+
+/// Parses geographic bounds written as "min_x,min_y,max_x,max_y" in degrees.
+impl std::str::FromStr for Bounds {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            RandomGeojsonError::InvalidBounds(format!(
+                "Invalid bounds: {}, expected min_x,min_y,max_x,max_y",
+                s
+            ))
+        };
+        let values: Vec<f64> = s
+            .split(',')
+            .map(|v| v.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid())?;
+        match values.as_slice() {
+            [min_x, min_y, max_x, max_y] if min_x <= max_x && min_y <= max_y => {
+                Ok(Bounds::new(*min_x, *min_y, *max_x, *max_y, Unit::Degrees))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Radius of the sphere used by the Web Mercator projection (EPSG:3857).
+const WEB_MERCATOR_RADIUS: f64 = 6_378_137.0;
+
+/// Maps geographic coordinates (longitude, latitude in degrees) into the
+/// coordinate system of the generated output.
+///
+/// Every generator samples in geographic space and passes each vertex through
+/// the transform of its [`Crs`], so implementing this trait is enough to
+/// generate data in a custom projection.
+pub trait CoordTransform: Send + Sync {
+    fn transform(&self, lon: f64, lat: f64) -> (f64, f64);
+
+    /// Unit of the transformed coordinates.
+    fn unit(&self) -> Unit {
+        Unit::Degrees
+    }
+}
+
+/// Leaves coordinates untouched (WGS84 output).
+pub struct Identity;
+
+impl CoordTransform for Identity {
+    fn transform(&self, lon: f64, lat: f64) -> (f64, f64) {
+        (lon, lat)
+    }
+}
+
+/// Spherical Web Mercator projection, producing metres.
+pub struct WebMercator;
+
+impl CoordTransform for WebMercator {
+    fn transform(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let x = WEB_MERCATOR_RADIUS * lon.to_radians();
+        let y = WEB_MERCATOR_RADIUS
+            * (std::f64::consts::FRAC_PI_4 + lat.to_radians() / 2.0)
+                .tan()
+                .ln();
+        (x, y)
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Meters
+    }
+}
+
+/// Projects coordinates with an arbitrary PROJ string (e.g. `+proj=utm +zone=32`).
+///
+/// Points the projection cannot handle come out as `NaN`, which a CRS made
+/// with [`Crs::from_str`] never samples.
+pub struct ProjTransform {
+    src: Proj,
+    dst: Proj,
+    definition: String,
+}
+
+impl ProjTransform {
+    pub fn new(definition: &str) -> Result<Self, RandomGeojsonError> {
+        let invalid = |e: proj4rs::errors::Error| {
+            RandomGeojsonError::InvalidArgument(format!(
+                "Invalid projection '{}': {}",
+                definition, e
+            ))
+        };
+        let src = Proj::from_proj_string("+proj=longlat +ellps=WGS84 +datum=WGS84 +no_defs")
+            .map_err(invalid)?;
+        let dst = Proj::from_proj_string(definition).map_err(invalid)?;
+        Ok(ProjTransform {
+            src,
+            dst,
+            definition: definition.to_string(),
+        })
+    }
+
+    /// Geographic bounds where the projection is meant to be used: the zone
+    /// of a UTM projection, or else the extent of the positions of a 1°
+    /// grid that project to finite coordinates.
+    pub fn usable_bounds(&self) -> Result<Bounds, RandomGeojsonError> {
+        let parameter = |name: &str| {
+            self.definition.split_whitespace().find_map(|token| {
+                let token = token.strip_prefix('+')?;
+                match token.split_once('=') {
+                    Some((key, value)) => (key == name).then_some(value),
+                    None => (token == name).then_some(""),
+                }
+            })
+        };
+        if parameter("proj") == Some("utm")
+            && let Some(zone) = parameter("zone").and_then(|z| z.parse::<i32>().ok())
+            && (1..=60).contains(&zone)
+        {
+            let west = (zone * 6 - 186) as f64;
+            let (south, north) = match parameter("south") {
+                Some(_) => (-80.0, 0.0),
+                None => (0.0, 84.0),
+            };
+            return Ok(Bounds::new(west, south, west + 6.0, north, Unit::Degrees));
+        }
+        let mut usable: Option<Bounds> = None;
+        for lon in -180..=180 {
+            for lat in -90..=90 {
+                let (lon, lat) = (lon as f64, lat as f64);
+                if !self.is_finite_at(lon, lat) {
+                    continue;
+                }
+                let b = usable.get_or_insert(Bounds::new(lon, lat, lon, lat, Unit::Degrees));
+                b.min_x = b.min_x.min(lon);
+                b.min_y = b.min_y.min(lat);
+                b.max_x = b.max_x.max(lon);
+                b.max_y = b.max_y.max(lat);
+            }
+        }
+        usable
+            .filter(|b| b.width() > 0.0 && b.height() > 0.0)
+            .ok_or_else(|| {
+                RandomGeojsonError::InvalidArgument(format!(
+                    "Projection '{}' gives no finite coordinates",
+                    self.definition
+                ))
+            })
+    }
+
+    // Whether a geographic position projects to finite coordinates.
+    fn is_finite_at(&self, lon: f64, lat: f64) -> bool {
+        let (x, y) = self.transform(lon, lat);
+        x.is_finite() && y.is_finite()
+    }
+}
+
+impl CoordTransform for ProjTransform {
+    fn transform(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let mut point = (lon.to_radians(), lat.to_radians(), 0.0);
+        match proj4rs::transform::transform(&self.src, &self.dst, &mut point) {
+            Ok(()) if self.dst.is_latlong() => (point.0.to_degrees(), point.1.to_degrees()),
+            Ok(()) => (point.0, point.1),
+            Err(_) => (f64::NAN, f64::NAN),
+        }
+    }
+
+    fn unit(&self) -> Unit {
+        match self.dst.units() {
+            _ if self.dst.is_latlong() => Unit::Degrees,
+            "ft" | "us-ft" => Unit::Feet,
+            _ => Unit::Meters,
+        }
+    }
+}
+
+/// Samples a geographic coordinate within the bounds of `crs`, before any
+/// transform, rejecting those outside its mask if it has one.
+pub(crate) fn random_geographic_coords(crs: &Crs) -> (f64, f64) {
+    loop {
+        if let Some(coords) = crs.try_sample() {
+            return coords;
+        }
+    }
+}
+
 fn random_coords(crs: &Crs) -> (f64, f64) {
-    let mut rng = rand::rng();
-    let bounds = crs.bounds();
-    let longitude = rng.random_range(bounds.min_lon..bounds.max_lon);
-    let latitude = rng.random_range(bounds.min_lat..bounds.max_lat);
-    (longitude, latitude)
+    let (longitude, latitude) = random_geographic_coords(crs);
+    crs.transform().transform(longitude, latitude)
 }
 
 pub enum RandomGeometry {
@@ -38,6 +300,204 @@ pub enum RandomGeometry {
     Polygon(Vec<Vec<Vec<f64>>>),
 }
 
+impl From<RandomGeometry> for geojson::Geometry {
+    fn from(geometry: RandomGeometry) -> Self {
+        let value = match geometry {
+            RandomGeometry::Point(coords) => geojson::Value::Point(coords),
+            RandomGeometry::LineString(coords) => geojson::Value::LineString(coords),
+            RandomGeometry::Polygon(coords) => geojson::Value::Polygon(coords),
+        };
+        geojson::Geometry::new(value)
+    }
+}
+
+/// Calls `f` with every position of a GeoJSON geometry value.
+pub fn for_each_position(value: &geojson::Value, f: &mut impl FnMut(&[f64])) {
+    use geojson::Value::*;
+    match value {
+        Point(p) => f(p),
+        MultiPoint(ps) | LineString(ps) => ps.iter().for_each(|p| f(p)),
+        MultiLineString(lines) | Polygon(lines) => lines.iter().flatten().for_each(|p| f(p)),
+        MultiPolygon(polygons) => polygons.iter().flatten().flatten().for_each(|p| f(p)),
+        GeometryCollection(geometries) => geometries
+            .iter()
+            .for_each(|g| for_each_position(&g.value, f)),
+    }
+}
+
+/// Calls `f` with every position of a GeoJSON geometry value, which it may
+/// change.
+pub fn for_each_position_mut(value: &mut geojson::Value, f: &mut impl FnMut(&mut Vec<f64>)) {
+    use geojson::Value::*;
+    match value {
+        Point(p) => f(p),
+        MultiPoint(ps) | LineString(ps) => ps.iter_mut().for_each(&mut *f),
+        MultiLineString(lines) | Polygon(lines) => lines.iter_mut().flatten().for_each(&mut *f),
+        MultiPolygon(polygons) => polygons.iter_mut().flatten().flatten().for_each(&mut *f),
+        GeometryCollection(geometries) => geometries
+            .iter_mut()
+            .for_each(|g| for_each_position_mut(&mut g.value, f)),
+    }
+}
+
+/// Returns the `[min_x, min_y, max_x, max_y]` extent of a GeoJSON geometry
+/// value, or `None` if it has no positions.
+pub fn geometry_bbox(value: &geojson::Value) -> Option<[f64; 4]> {
+    let mut bbox: Option<[f64; 4]> = None;
+    for_each_position(value, &mut |p| {
+        let b = bbox.get_or_insert([p[0], p[1], p[0], p[1]]);
+        b[0] = b[0].min(p[0]);
+        b[1] = b[1].min(p[1]);
+        b[2] = b[2].max(p[0]);
+        b[3] = b[3].max(p[1]);
+    });
+    bbox
+}
+
+/// Clips a geometry to `bounds`, returning `None` if nothing is left.
+///
+/// Lines are split where they leave the bounds, and polygon rings are clipped
+/// with the Sutherland-Hodgman algorithm, which keeps them closed but may
+/// leave zero-width edges along the bounds where a ring exits and re-enters.
+pub fn clip_geometry(value: &geojson::Value, bounds: &Bounds) -> Option<geojson::Value> {
+    use geojson::Value;
+    let polygon = |rings: &[Vec<Vec<f64>>]| -> Option<Vec<Vec<Vec<f64>>>> {
+        let mut clipped = rings.iter().map(|r| clip_ring(r, bounds));
+        let exterior = clipped.next().flatten()?;
+        Some(std::iter::once(exterior).chain(clipped.flatten()).collect())
+    };
+    let lines = |parts: Vec<Vec<Vec<f64>>>| match parts.len() {
+        0 => None,
+        1 => parts.into_iter().next().map(Value::LineString),
+        _ => Some(Value::MultiLineString(parts)),
+    };
+    match value {
+        Value::Point(p) => bounds.contains(p[0], p[1]).then(|| value.clone()),
+        Value::MultiPoint(ps) => {
+            let ps: Vec<_> = ps
+                .iter()
+                .filter(|p| bounds.contains(p[0], p[1]))
+                .cloned()
+                .collect();
+            (!ps.is_empty()).then_some(Value::MultiPoint(ps))
+        }
+        Value::LineString(line) => lines(clip_line(line, bounds)),
+        Value::MultiLineString(ls) => lines(ls.iter().flat_map(|l| clip_line(l, bounds)).collect()),
+        Value::Polygon(rings) => polygon(rings).map(Value::Polygon),
+        Value::MultiPolygon(polygons) => {
+            let polygons: Vec<_> = polygons.iter().filter_map(|p| polygon(p)).collect();
+            (!polygons.is_empty()).then_some(Value::MultiPolygon(polygons))
+        }
+        Value::GeometryCollection(geometries) => {
+            let geometries: Vec<_> = geometries
+                .iter()
+                .filter_map(|g| clip_geometry(&g.value, bounds).map(geojson::Geometry::new))
+                .collect();
+            (!geometries.is_empty()).then_some(Value::GeometryCollection(geometries))
+        }
+    }
+}
+
+// Splits a line into the parts inside `bounds` (Liang-Barsky).
+fn clip_line(line: &[Vec<f64>], bounds: &Bounds) -> Vec<Vec<Vec<f64>>> {
+    let mut parts: Vec<Vec<Vec<f64>>> = Vec::new();
+    let mut current: Vec<Vec<f64>> = Vec::new();
+    for segment in line.windows(2) {
+        let (a, b) = (&segment[0], &segment[1]);
+        let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+        let (mut t0, mut t1) = (0.0f64, 1.0f64);
+        let edges = [
+            (-dx, a[0] - bounds.min_x),
+            (dx, bounds.max_x - a[0]),
+            (-dy, a[1] - bounds.min_y),
+            (dy, bounds.max_y - a[1]),
+        ];
+        let visible = edges.iter().all(|&(p, q)| {
+            if p == 0.0 {
+                return q >= 0.0;
+            }
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+            t0 <= t1
+        });
+        if !visible {
+            if current.len() > 1 {
+                parts.push(std::mem::take(&mut current));
+            }
+            current.clear();
+            continue;
+        }
+        let start = vec![a[0] + t0 * dx, a[1] + t0 * dy];
+        let end = vec![a[0] + t1 * dx, a[1] + t1 * dy];
+        if current.last() != Some(&start) {
+            if current.len() > 1 {
+                parts.push(std::mem::take(&mut current));
+            }
+            current = vec![start];
+        }
+        current.push(end);
+        // The line leaves the bounds inside this segment
+        if t1 < 1.0 {
+            parts.push(std::mem::take(&mut current));
+        }
+    }
+    if current.len() > 1 {
+        parts.push(current);
+    }
+    parts
+}
+
+// Clips a closed ring to `bounds` (Sutherland-Hodgman), or `None` if it
+// does not overlap them.
+fn clip_ring(ring: &[Vec<f64>], bounds: &Bounds) -> Option<Vec<Vec<f64>>> {
+    let mut points: Vec<Vec<f64>> = ring.to_vec();
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    // Each edge is an axis (0 = x, 1 = y), a limit and whether to keep
+    // the points below it
+    let edges = [
+        (0, bounds.min_x, false),
+        (0, bounds.max_x, true),
+        (1, bounds.min_y, false),
+        (1, bounds.max_y, true),
+    ];
+    for (axis, limit, below) in edges {
+        let inside = |p: &Vec<f64>| {
+            if below {
+                p[axis] <= limit
+            } else {
+                p[axis] >= limit
+            }
+        };
+        let input = std::mem::take(&mut points);
+        for (i, current) in input.iter().enumerate() {
+            let previous = &input[(i + input.len() - 1) % input.len()];
+            if inside(current) != inside(previous) {
+                let t = (limit - previous[axis]) / (current[axis] - previous[axis]);
+                let mut p = vec![
+                    previous[0] + t * (current[0] - previous[0]),
+                    previous[1] + t * (current[1] - previous[1]),
+                ];
+                p[axis] = limit;
+                points.push(p);
+            }
+            if inside(current) {
+                points.push(current.clone());
+            }
+        }
+    }
+    if points.len() < 3 {
+        return None;
+    }
+    points.push(points[0].clone());
+    Some(points)
+}
+
 impl RandomGeometry {
     /// Creates a random Point geometry.
     pub fn random_point(crs: &Crs) -> RandomGeometry {
@@ -47,25 +507,12 @@ impl RandomGeometry {
 
     /// Creates a random LineString geometry with a random number of points.
     pub fn random_linestring(crs: &Crs) -> Self {
-        let num_points = rand::rng().random_range(2..10);
-        let coords: Vec<Vec<f64>> = (0..num_points)
-            .map(|_| {
-                let (lon, lat) = random_coords(crs);
-                vec![lon, lat]
-            })
-            .collect();
-        RandomGeometry::LineString(coords)
+        RandomGeometry::LineString(transform_all(crs, &random_vertices(crs, 2..10)))
     }
 
     /// Creates a random Polygon geometry with a random number of points.
     pub fn random_polygon(crs: &Crs) -> Self {
-        let num_points = rand::rng().random_range(3..10);
-        let mut coords: Vec<Vec<f64>> = (0..num_points)
-            .map(|_| {
-                let (lon, lat) = random_coords(crs);
-                vec![lon, lat]
-            })
-            .collect();
+        let mut coords = transform_all(crs, &random_vertices(crs, 3..10));
 
         // Close the ring by repeating the first point
         if let Some(first) = coords.first().cloned() {
@@ -76,16 +523,230 @@ impl RandomGeometry {
     }
 }
 
+/// Samples a random number of geographic coordinates within `crs`.
+pub(crate) fn random_vertices(crs: &Crs, count: std::ops::Range<usize>) -> Vec<(f64, f64)> {
+    let num_points = random::rng().random_range(count);
+    (0..num_points)
+        .map(|_| random_geographic_coords(crs))
+        .collect()
+}
+
+fn transform_all(crs: &Crs, coords: &[(f64, f64)]) -> Vec<Vec<f64>> {
+    coords
+        .iter()
+        .map(|&(lon, lat)| {
+            let (x, y) = crs.transform().transform(lon, lat);
+            vec![x, y]
+        })
+        .collect()
+}
+
 pub enum Crs {
     WGS84,
     WebMercator,
+    /// A user-provided projection. `bounds` are geographic and describe where
+    /// coordinates are sampled before being passed through `transform`.
+    Custom {
+        bounds: Bounds,
+        transform: Box<dyn CoordTransform>,
+    },
+    /// Another coordinate system, sampled only within `bounds` (geographic).
+    Clipped {
+        crs: Box<Crs>,
+        bounds: Bounds,
+    },
+    /// Another coordinate system, sampled only inside the polygons of `mask`
+    /// (geographic), within `bounds`.
+    Within {
+        crs: Box<Crs>,
+        bounds: Bounds,
+        mask: Arc<Mask>,
+    },
+    /// Another coordinate system, never sampled inside the polygons of `mask`
+    /// (geographic).
+    Excluding {
+        crs: Box<Crs>,
+        mask: Arc<Mask>,
+    },
+    /// Another coordinate system, sampled in proportion to the weights of
+    /// the cells of `grid` (geographic), within `bounds`.
+    Weighted {
+        crs: Box<Crs>,
+        bounds: Bounds,
+        grid: Arc<DensityGrid>,
+    },
 }
 
 impl Crs {
+    /// Creates a custom coordinate system sampling within `bounds`.
+    pub fn custom(bounds: Bounds, transform: impl CoordTransform + 'static) -> Self {
+        Crs::Custom {
+            bounds,
+            transform: Box::new(transform),
+        }
+    }
+
+    /// Restricts sampling to the part of the CRS bounds inside `bounds`
+    /// (geographic), keeping the transform and EPSG code.
+    pub fn clipped(self, bounds: Bounds) -> Result<Self, RandomGeojsonError> {
+        let bounds = self.bounds().intersection(&bounds).ok_or_else(|| {
+            RandomGeojsonError::InvalidBounds(
+                "Bounds do not overlap the coordinate system".to_string(),
+            )
+        })?;
+        let crs = Crs::Clipped {
+            crs: Box::new(self),
+            bounds,
+        };
+        crs.check_samplable()?;
+        Ok(crs)
+    }
+
+    /// Restricts sampling to the inside of `mask`, keeping the transform and
+    /// EPSG code.
+    pub fn within(self, mask: Arc<Mask>) -> Result<Self, RandomGeojsonError> {
+        let bounds = self.bounds().intersection(&mask.bounds()).ok_or_else(|| {
+            RandomGeojsonError::InvalidBounds(
+                "Mask does not overlap the coordinate system".to_string(),
+            )
+        })?;
+        let crs = Crs::Within {
+            crs: Box::new(self),
+            bounds,
+            mask,
+        };
+        crs.check_samplable()?;
+        Ok(crs)
+    }
+
+    /// Keeps sampling out of `mask`, keeping the transform and EPSG code.
+    pub fn excluding(self, mask: Arc<Mask>) -> Result<Self, RandomGeojsonError> {
+        let crs = Crs::Excluding {
+            crs: Box::new(self),
+            mask,
+        };
+        crs.check_samplable()?;
+        Ok(crs)
+    }
+
+    /// Samples in proportion to the weights of `grid`, keeping the
+    /// transform, EPSG code and masks.
+    pub fn weighted(self, grid: Arc<DensityGrid>) -> Result<Self, RandomGeojsonError> {
+        let bounds = self.bounds().intersection(&grid.bounds()).ok_or_else(|| {
+            RandomGeojsonError::InvalidBounds(
+                "Weight grid does not overlap the coordinate system".to_string(),
+            )
+        })?;
+        let crs = Crs::Weighted {
+            crs: Box::new(self),
+            bounds,
+            grid,
+        };
+        crs.check_samplable()?;
+        Ok(crs)
+    }
+
+    // Rejection sampling never ends if no coordinate is accepted, so a few
+    // thousand tries must find one.
+    fn check_samplable(&self) -> Result<(), RandomGeojsonError> {
+        if !(0..10_000).any(|_| self.try_sample().is_some()) {
+            return Err(RandomGeojsonError::InvalidBounds(
+                "The mask leaves no room to generate coordinates".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Draws a geographic coordinate, or `None` if it was rejected.
+    fn try_sample(&self) -> Option<(f64, f64)> {
+        let (lon, lat) = self.candidate(&self.bounds())?;
+        self.accepts(lon, lat).then_some((lon, lat))
+    }
+
+    // Draws a coordinate within `bounds` to be checked by `accepts`. Masks
+    // propose coordinates near their polygons, which matters for masks with
+    // a large bounding box but a small area, such as Fiji or an archipelago.
+    fn candidate(&self, bounds: &Bounds) -> Option<(f64, f64)> {
+        match self {
+            Crs::Within { mask, .. } => mask.candidate(bounds),
+            Crs::Weighted { grid, .. } => grid.candidate(bounds),
+            Crs::Clipped { crs, .. } | Crs::Excluding { crs, .. } => crs.candidate(bounds),
+            Crs::WGS84 | Crs::WebMercator | Crs::Custom { .. } => {
+                let mut rng = random::rng();
+                Some((
+                    rng.random_range(bounds.min_x..bounds.max_x),
+                    rng.random_range(bounds.min_y..bounds.max_y),
+                ))
+            }
+        }
+    }
+
+    /// Returns true if a geographic coordinate within the bounds may be
+    /// generated, i.e. it satisfies any mask.
+    pub fn accepts(&self, lon: f64, lat: f64) -> bool {
+        match self {
+            Crs::WGS84 | Crs::WebMercator => true,
+            // Projections can fail, e.g. far from the zone of a UTM
+            // projection, and those positions are drawn again
+            Crs::Custom { transform, .. } => {
+                let (x, y) = transform.transform(lon, lat);
+                x.is_finite() && y.is_finite()
+            }
+            Crs::Clipped { crs, .. } | Crs::Weighted { crs, .. } => crs.accepts(lon, lat),
+            Crs::Within { crs, mask, .. } => mask.contains(lon, lat) && crs.accepts(lon, lat),
+            Crs::Excluding { crs, mask } => !mask.contains(lon, lat) && crs.accepts(lon, lat),
+        }
+    }
+
+    /// Geographic bounds that coordinates are sampled from.
     pub fn bounds(&self) -> Bounds {
         match self {
             Crs::WGS84 => WGS84_BOUNDS,
             Crs::WebMercator => WEB_MERCATOR_BOUNDS,
+            Crs::Custom { bounds, .. }
+            | Crs::Clipped { bounds, .. }
+            | Crs::Within { bounds, .. }
+            | Crs::Weighted { bounds, .. } => *bounds,
+            Crs::Excluding { crs, .. } => crs.bounds(),
+        }
+    }
+
+    /// Extent of the generated coordinates, in the output unit of the CRS.
+    pub fn extent(&self) -> Bounds {
+        match self {
+            Crs::WGS84 => WGS84_BOUNDS,
+            Crs::WebMercator => WEB_MERCATOR_EXTENT,
+            Crs::Custom { bounds, transform } => bounds.transformed(transform.as_ref()),
+            Crs::Clipped { crs, bounds }
+            | Crs::Within { crs, bounds, .. }
+            | Crs::Weighted { crs, bounds, .. } => bounds.transformed(crs.transform()),
+            Crs::Excluding { crs, .. } => crs.extent(),
+        }
+    }
+
+    /// EPSG code of the output coordinates, if the CRS has one.
+    pub fn epsg(&self) -> Option<u32> {
+        match self {
+            Crs::WGS84 => Some(4326),
+            Crs::WebMercator => Some(3857),
+            Crs::Custom { .. } => None,
+            Crs::Clipped { crs, .. }
+            | Crs::Within { crs, .. }
+            | Crs::Excluding { crs, .. }
+            | Crs::Weighted { crs, .. } => crs.epsg(),
+        }
+    }
+
+    /// Transform applied to every generated coordinate.
+    pub fn transform(&self) -> &dyn CoordTransform {
+        match self {
+            Crs::WGS84 => &Identity,
+            Crs::WebMercator => &WebMercator,
+            Crs::Custom { transform, .. } => transform.as_ref(),
+            Crs::Clipped { crs, .. }
+            | Crs::Within { crs, .. }
+            | Crs::Excluding { crs, .. }
+            | Crs::Weighted { crs, .. } => crs.transform(),
         }
     }
 }
@@ -97,6 +758,10 @@ impl std::str::FromStr for Crs {
         match s.to_lowercase().as_str() {
             "wgs84" | "4326" => Ok(Crs::WGS84),
             "webmercator" | "web_mercator" | "3857" => Ok(Crs::WebMercator),
+            p if p.starts_with("+proj=") => {
+                let transform = ProjTransform::new(s)?;
+                Ok(Crs::custom(transform.usable_bounds()?, transform))
+            }
             _ => Err(RandomGeojsonError::InvalidArgument(format!(
                 "Invalid coordinate system: {}",
                 s
@@ -129,17 +794,12 @@ mod tests {
 
     fn assert_coords_in_bounds(coords: &[f64], bounds: Bounds) {
         assert!(coords.len() == 2);
-        let (lon, lat) = (coords[0], coords[1]);
-        assert!(
-            bounds.min_lon <= lon && lon <= bounds.max_lon,
-            "Longitude {} out of bounds {:?}",
-            lon,
-            bounds
-        );
+        let (x, y) = (coords[0], coords[1]);
         assert!(
-            bounds.min_lat <= lat && lat <= bounds.max_lat,
-            "Latitude {} out of bounds {:?}",
-            lat,
+            bounds.contains(x, y),
+            "Coordinate ({}, {}) out of bounds {:?}",
+            x,
+            y,
             bounds
         );
     }
@@ -155,10 +815,187 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_crs_from_str_proj() {
+        let crs = "+proj=utm +zone=32 +datum=WGS84".parse::<Crs>().unwrap();
+        assert!(matches!(crs, Crs::Custom { .. }));
+        assert_eq!(crs.extent().unit, Unit::Meters);
+        assert!("+proj=notaprojection".parse::<Crs>().is_err());
+    }
+
+    #[test]
+    fn test_proj_coordinates_finite() {
+        let crs = "+proj=utm +zone=32".parse::<Crs>().unwrap();
+        let bounds = crs.bounds();
+        assert_eq!(
+            (bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y),
+            (6.0, 0.0, 12.0, 84.0)
+        );
+        let south = "+proj=utm +zone=60 +south".parse::<Crs>().unwrap().bounds();
+        assert_eq!((south.min_x, south.max_x, south.max_y), (174.0, 180.0, 0.0));
+        for _ in 0..2000 {
+            for geometry in [
+                RandomGeometry::random_point(&crs),
+                RandomGeometry::random_linestring(&crs),
+                RandomGeometry::random_polygon(&crs),
+            ] {
+                let geometry = geojson::Geometry::from(geometry);
+                for_each_position(&geometry.value, &mut |p| {
+                    assert!(p.iter().all(|c| c.is_finite()), "{:?}", p)
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn test_web_mercator_extent_matches_transformed_bounds() {
+        let transformed = WEB_MERCATOR_BOUNDS.transformed(&WebMercator);
+        assert_eq!(transformed.unit, WEB_MERCATOR_EXTENT.unit);
+        assert!((transformed.max_x - WEB_MERCATOR_EXTENT.max_x).abs() < 1e-6);
+        assert!((transformed.max_y - WEB_MERCATOR_EXTENT.max_y).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_web_mercator_transform() {
+        let (x, y) = WebMercator.transform(0.0, 0.0);
+        assert!(x.abs() < 1e-6 && y.abs() < 1e-6);
+        let (x, y) = WebMercator.transform(180.0, 85.05112878);
+        assert!((x - 20_037_508.34).abs() < 0.01);
+        assert!((y - 20_037_508.34).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_custom_transform_applied() {
+        struct Shift;
+        impl CoordTransform for Shift {
+            fn transform(&self, lon: f64, lat: f64) -> (f64, f64) {
+                (lon + 1000.0, lat + 1000.0)
+            }
+        }
+        let crs = Crs::custom(WGS84_BOUNDS, Shift);
+        let bounds = crs.extent();
+        if let RandomGeometry::Polygon(rings) = RandomGeometry::random_polygon(&crs) {
+            for coord in &rings[0] {
+                assert_coords_in_bounds(coord, bounds);
+            }
+        } else {
+            panic!("Expected Polygon geometry");
+        }
+    }
+
+    #[test]
+    fn test_clip_geometry() {
+        let bounds = Bounds::new(0.0, 0.0, 10.0, 10.0, Unit::Degrees);
+        let line = geojson::Value::LineString(vec![
+            vec![-5.0, 5.0],
+            vec![5.0, 5.0],
+            vec![5.0, 15.0],
+            vec![8.0, 15.0],
+            vec![8.0, 5.0],
+        ]);
+        match clip_geometry(&line, &bounds) {
+            Some(geojson::Value::MultiLineString(parts)) => {
+                assert_eq!(parts.len(), 2);
+                assert_eq!(
+                    parts[0],
+                    vec![vec![0.0, 5.0], vec![5.0, 5.0], vec![5.0, 10.0]]
+                );
+                assert_eq!(parts[1], vec![vec![8.0, 10.0], vec![8.0, 5.0]]);
+            }
+            other => panic!("Expected MultiLineString, got {:?}", other),
+        }
+
+        let square = |min: f64, max: f64| {
+            geojson::Value::Polygon(vec![vec![
+                vec![min, min],
+                vec![max, min],
+                vec![max, max],
+                vec![min, max],
+                vec![min, min],
+            ]])
+        };
+        match clip_geometry(&square(-5.0, 5.0), &bounds) {
+            Some(geojson::Value::Polygon(rings)) => {
+                let bbox = geometry_bbox(&geojson::Value::Polygon(rings.clone())).unwrap();
+                assert_eq!(bbox, [0.0, 0.0, 5.0, 5.0]);
+                assert_eq!(rings[0].first(), rings[0].last());
+            }
+            other => panic!("Expected Polygon, got {:?}", other),
+        }
+        assert!(clip_geometry(&square(20.0, 30.0), &bounds).is_none());
+    }
+
+    #[test]
+    fn test_clipped_crs() {
+        let bounds = Bounds::new(0.0, 0.0, 10.0, 100.0, Unit::Degrees);
+        let crs = Crs::WebMercator.clipped(bounds).unwrap();
+        assert_eq!(crs.epsg(), Some(3857));
+        assert_eq!(crs.bounds().max_y, WEB_MERCATOR_BOUNDS.max_y);
+        assert_eq!(crs.extent().min_x, 0.0);
+
+        let outside = Bounds::new(200.0, 0.0, 210.0, 10.0, Unit::Degrees);
+        assert!(Crs::WGS84.clipped(outside).is_err());
+    }
+
+    #[test]
+    fn test_within_crs() {
+        // A triangle, which leaves half of its bounding box outside
+        let triangle = vec![vec![
+            vec![0.0, 0.0],
+            vec![10.0, 0.0],
+            vec![0.0, 10.0],
+            vec![0.0, 0.0],
+        ]];
+        let mask = Arc::new(Mask::new(vec![triangle]).unwrap());
+        let crs = Crs::WGS84.within(mask.clone()).unwrap();
+        assert_eq!(
+            crs.bounds(),
+            Bounds::new(0.0, 0.0, 10.0, 10.0, Unit::Degrees)
+        );
+        for _ in 0..100 {
+            let RandomGeometry::LineString(line) = RandomGeometry::random_linestring(&crs) else {
+                unreachable!()
+            };
+            assert!(line.iter().all(|p| p[0] + p[1] <= 10.0));
+        }
+
+        let outside = Bounds::new(20.0, 20.0, 30.0, 30.0, Unit::Degrees);
+        let clipped = Crs::WGS84.clipped(outside).unwrap();
+        assert!(clipped.within(mask).is_err());
+    }
+
+    #[test]
+    fn test_excluding_crs() {
+        let bounds = Bounds::new(0.0, 0.0, 10.0, 10.0, Unit::Degrees);
+        let square = |min: f64, max: f64| {
+            vec![vec![
+                vec![min, min],
+                vec![max, min],
+                vec![max, max],
+                vec![min, max],
+                vec![min, min],
+            ]]
+        };
+        let hole = Arc::new(Mask::new(vec![square(2.0, 8.0)]).unwrap());
+        let crs = Crs::WGS84.clipped(bounds).unwrap().excluding(hole).unwrap();
+        assert_eq!(crs.bounds(), bounds);
+        for _ in 0..200 {
+            let RandomGeometry::Point(p) = RandomGeometry::random_point(&crs) else {
+                unreachable!()
+            };
+            assert!(!(2.0..=8.0).contains(&p[0]) || !(2.0..=8.0).contains(&p[1]));
+        }
+
+        let everything = Arc::new(Mask::new(vec![square(-1.0, 11.0)]).unwrap());
+        let clipped = Crs::WGS84.clipped(bounds).unwrap();
+        assert!(clipped.excluding(everything).is_err());
+    }
+
     #[test]
     fn test_random_linestring_within_bounds() {
         let crs = Crs::WebMercator;
-        let bounds = crs.bounds();
+        let bounds = crs.extent();
+        assert_eq!(bounds.unit, Unit::Meters);
         if let RandomGeometry::LineString(coords) = RandomGeometry::random_linestring(&crs) {
             assert!(coords.len() >= 2);
             for coord in coords {
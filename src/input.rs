@@ -0,0 +1,171 @@
+use std::io::{BufRead, BufReader, Read};
+
+use geojson::{Feature, FeatureCollection, FeatureReader, GeoJson};
+
+use crate::compression::Compression;
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+
+/// Reads the features of an existing GeoJSON file: a `FeatureCollection`, a
+/// single `Feature` or newline-delimited `Feature`s.
+///
+/// `.gz` and `.zst` files are decompressed, and `-` reads standard input.
+pub fn read_features(path: &str) -> RandomGeojsonResult<FeatureCollection> {
+    let bytes = if path == "-" {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes).map(|_| bytes)
+    } else {
+        std::fs::read(path)
+    }
+    .map_err(|e| RandomGeojsonError::Io(format!("Failed to read {}: {}", path, e)))?;
+    let bytes = Compression::from_path(path).decompress(&bytes)?;
+    parse_features(&bytes).map_err(|e| {
+        RandomGeojsonError::InvalidInput(format!("Invalid GeoJSON in {}: {}", path, e))
+    })
+}
+
+/// Calls `f` with every feature of a `FeatureCollection` or newline-delimited
+/// `Feature`s, reading the file as a stream so it never has to fit in memory.
+///
+/// Compression and `-` are handled as by [`read_features`].
+pub fn for_each_feature(path: &str, mut f: impl FnMut(Feature)) -> RandomGeojsonResult<()> {
+    let failed = |e: &dyn std::fmt::Display| {
+        RandomGeojsonError::Io(format!("Failed to read {}: {}", path, e))
+    };
+    let invalid = |e: &dyn std::fmt::Display| {
+        RandomGeojsonError::InvalidInput(format!("Invalid GeoJSON in {}: {}", path, e))
+    };
+    let file: Box<dyn Read> = if path == "-" {
+        Box::new(std::io::stdin())
+    } else {
+        Box::new(std::fs::File::open(path).map_err(|e| failed(&e))?)
+    };
+    let mut reader = BufReader::new(Compression::from_path(path).decoder(file)?);
+
+    let mut first = String::new();
+    let mut line_number = 0;
+    while first.trim().is_empty() {
+        first.clear();
+        line_number += 1;
+        if reader.read_line(&mut first).map_err(|e| failed(&e))? == 0 {
+            return Err(invalid(&"the file is empty"));
+        }
+    }
+
+    // Newline-delimited files hold a whole Feature on their first line
+    if let Ok(feature) = serde_json::from_str::<Feature>(&first) {
+        f(feature);
+        for line in reader.lines() {
+            let line = line.map_err(|e| failed(&e))?;
+            line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let feature = serde_json::from_str::<Feature>(&line)
+                .map_err(|e| invalid(&format!("line {}: {}", line_number, e)))?;
+            f(feature);
+        }
+        return Ok(());
+    }
+    for feature in FeatureReader::from_reader(first.as_bytes().chain(reader)).features() {
+        f(feature.map_err(|e| invalid(&e))?);
+    }
+    Ok(())
+}
+
+/// Parses GeoJSON text as [`read_features`] does.
+pub fn parse_features(bytes: &[u8]) -> Result<FeatureCollection, String> {
+    let error = match serde_json::from_slice::<GeoJson>(bytes) {
+        Ok(GeoJson::FeatureCollection(fc)) => return Ok(fc),
+        Ok(GeoJson::Feature(feature)) => return Ok(feature_collection(vec![feature])),
+        Ok(GeoJson::Geometry(_)) => return Err("expected features, found a geometry".to_string()),
+        Err(e) => e,
+    };
+
+    // Newline-delimited features do not parse as a single document
+    let text = String::from_utf8_lossy(bytes);
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .collect();
+    if lines.len() < 2 {
+        return Err(error.to_string());
+    }
+    let features = lines
+        .into_iter()
+        .map(|(i, line)| {
+            serde_json::from_str::<Feature>(line).map_err(|e| format!("line {}: {}", i + 1, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(feature_collection(features))
+}
+
+fn feature_collection(features: Vec<Feature>) -> FeatureCollection {
+    FeatureCollection {
+        features,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_each_feature() {
+        let point = r#"{"type":"Feature","geometry":{"type":"Point","coordinates":[1,2]},"properties":null}"#;
+        let dir = std::env::temp_dir().join(format!("random-geojson-input-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let count = |name: &str, text: &str| {
+            let path = dir.join(name);
+            std::fs::write(&path, text).unwrap();
+            let mut n = 0;
+            for_each_feature(path.to_str().unwrap(), |_| n += 1).map(|_| n)
+        };
+
+        let pretty = format!(
+            "{{\n  \"type\": \"FeatureCollection\",\n  \"features\": [{},\n{}]\n}}\n",
+            point, point
+        );
+        assert_eq!(count("pretty.geojson", &pretty).unwrap(), 2);
+        let compact = format!(r#"{{"type":"FeatureCollection","features":[{}]}}"#, point);
+        assert_eq!(count("compact.geojson", &compact).unwrap(), 1);
+        let ndjson = format!("\n{}\n{}\n\n{}\n", point, point, point);
+        assert_eq!(count("features.ndjson", &ndjson).unwrap(), 3);
+        let broken = format!("{}\n{{\n", point);
+        let error = count("broken.ndjson", &broken).unwrap_err().to_string();
+        assert!(error.contains("line 2"), "{}", error);
+        assert!(count("empty.geojson", "").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_features() {
+        let point = r#"{"type":"Feature","geometry":{"type":"Point","coordinates":[1,2]},"properties":null}"#;
+        let collection = format!(
+            r#"{{"type":"FeatureCollection","features":[{},{}]}}"#,
+            point, point
+        );
+        assert_eq!(
+            parse_features(collection.as_bytes())
+                .unwrap()
+                .features
+                .len(),
+            2
+        );
+        assert_eq!(parse_features(point.as_bytes()).unwrap().features.len(), 1);
+
+        let ndjson = format!("{}\n\n{}\n{}\n", point, point, point);
+        assert_eq!(parse_features(ndjson.as_bytes()).unwrap().features.len(), 3);
+        let broken = format!("{}\n{{\"type\":\n", point);
+        assert!(
+            parse_features(broken.as_bytes())
+                .unwrap_err()
+                .starts_with("line 2")
+        );
+
+        assert!(parse_features(br#"{"type":"Point","coordinates":[1,2]}"#).is_err());
+        assert!(parse_features(b"not json").is_err());
+    }
+}
@@ -0,0 +1,340 @@
+use geojson::{Feature, FeatureCollection, JsonValue, Value};
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::error::RandomGeojsonError;
+use crate::geometry::{Bounds, for_each_position};
+use crate::random;
+
+/// A way of making a feature invalid, for fuzzing GeoJSON readers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Malformation {
+    /// A polygon ring whose last position is not its first.
+    UnclosedRing,
+    /// A polygon whose exterior ring crosses itself, as a bow tie over its
+    /// extent.
+    SelfIntersection,
+    /// A position of a line or ring repeated right after itself.
+    DuplicatePoints,
+    /// A geometry with no coordinates at all.
+    EmptyCoordinates,
+    /// A feature whose geometry is `null`.
+    NullGeometry,
+    /// A position outside the extent of the coordinate system.
+    OutOfRange,
+    /// A coordinate that is NaN or infinite, written as `null` in JSON,
+    /// which has no such numbers.
+    NonFinite,
+}
+
+/// Every [`Malformation`], in the order they are documented.
+pub const MALFORMATIONS: [Malformation; 7] = [
+    Malformation::UnclosedRing,
+    Malformation::SelfIntersection,
+    Malformation::DuplicatePoints,
+    Malformation::EmptyCoordinates,
+    Malformation::NullGeometry,
+    Malformation::OutOfRange,
+    Malformation::NonFinite,
+];
+
+impl Malformation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Malformation::UnclosedRing => "unclosed-ring",
+            Malformation::SelfIntersection => "self-intersection",
+            Malformation::DuplicatePoints => "duplicate-points",
+            Malformation::EmptyCoordinates => "empty-coordinates",
+            Malformation::NullGeometry => "null-geometry",
+            Malformation::OutOfRange => "out-of-range",
+            Malformation::NonFinite => "non-finite",
+        }
+    }
+
+    // Whether the malformation can be applied to a geometry.
+    fn applies_to(&self, value: Option<&Value>) -> bool {
+        let Some(value) = value else {
+            return false;
+        };
+        match self {
+            Malformation::UnclosedRing | Malformation::SelfIntersection => {
+                matches!(value, Value::Polygon(rings) if !rings.is_empty() && rings[0].len() >= 4)
+            }
+            Malformation::DuplicatePoints => match value {
+                Value::LineString(line) => !line.is_empty(),
+                Value::Polygon(rings) => !rings.is_empty() && !rings[0].is_empty(),
+                _ => false,
+            },
+            Malformation::EmptyCoordinates | Malformation::NullGeometry => true,
+            Malformation::OutOfRange | Malformation::NonFinite => {
+                let mut any = false;
+                for_each_position(value, &mut |p| any |= p.len() >= 2);
+                any
+            }
+        }
+    }
+
+    // Applies the malformation, which must apply to the feature's geometry.
+    fn apply(&self, feature: &mut Feature, extent: &Bounds) {
+        let mut rng = random::rng();
+        if *self == Malformation::NullGeometry {
+            feature.geometry = None;
+            return;
+        }
+        let Some(geometry) = &mut feature.geometry else {
+            return;
+        };
+        let value = &mut geometry.value;
+        match self {
+            Malformation::UnclosedRing => {
+                if let Value::Polygon(rings) = value {
+                    rings[0].pop();
+                }
+            }
+            Malformation::SelfIntersection => {
+                if let Value::Polygon(rings) = value {
+                    let (min_x, min_y, max_x, max_y) = rings[0].iter().fold(
+                        (
+                            f64::INFINITY,
+                            f64::INFINITY,
+                            f64::NEG_INFINITY,
+                            f64::NEG_INFINITY,
+                        ),
+                        |b, p| (b.0.min(p[0]), b.1.min(p[1]), b.2.max(p[0]), b.3.max(p[1])),
+                    );
+                    rings[0] = vec![
+                        vec![min_x, min_y],
+                        vec![max_x, max_y],
+                        vec![max_x, min_y],
+                        vec![min_x, max_y],
+                        vec![min_x, min_y],
+                    ];
+                }
+            }
+            Malformation::DuplicatePoints => {
+                let line = match value {
+                    Value::LineString(line) => line,
+                    Value::Polygon(rings) => &mut rings[0],
+                    _ => return,
+                };
+                // Not after the closing position, so rings stay closed
+                let i = rng.random_range(0..line.len().saturating_sub(1).max(1));
+                line.insert(i + 1, line[i].clone());
+            }
+            Malformation::EmptyCoordinates => {
+                *value = match value {
+                    Value::Point(_) => Value::Point(Vec::new()),
+                    Value::MultiPoint(_) => Value::MultiPoint(Vec::new()),
+                    Value::LineString(_) => Value::LineString(Vec::new()),
+                    Value::MultiLineString(_) => Value::MultiLineString(Vec::new()),
+                    Value::Polygon(_) => Value::Polygon(Vec::new()),
+                    Value::MultiPolygon(_) => Value::MultiPolygon(Vec::new()),
+                    Value::GeometryCollection(_) => Value::GeometryCollection(Vec::new()),
+                };
+            }
+            Malformation::OutOfRange => {
+                if let Some(p) = some_position(value) {
+                    let axis = rng.random_range(0..2);
+                    let (min, max) = if axis == 0 {
+                        (extent.min_x, extent.max_x)
+                    } else {
+                        (extent.min_y, extent.max_y)
+                    };
+                    // Up to the size of the extent past one of its edges
+                    let past = (max - min) * rng.random_range(0.01..1.0);
+                    p[axis] = if rng.random_bool(0.5) {
+                        max + past
+                    } else {
+                        min - past
+                    };
+                }
+            }
+            Malformation::NonFinite => {
+                if let Some(p) = some_position(value) {
+                    let axis = rng.random_range(0..2);
+                    p[axis] = *[f64::NAN, f64::INFINITY, f64::NEG_INFINITY]
+                        .choose(&mut rng)
+                        .unwrap();
+                }
+            }
+            Malformation::NullGeometry => unreachable!(),
+        }
+        // Bounding boxes would give the changes away
+        geometry.bbox = None;
+        feature.bbox = None;
+    }
+}
+
+impl std::str::FromStr for Malformation {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_lowercase().replace('_', "-");
+        MALFORMATIONS
+            .into_iter()
+            .find(|m| m.as_str() == normalized)
+            .ok_or_else(|| {
+                RandomGeojsonError::InvalidArgument(format!(
+                    "Invalid malformation: {} (expected one of: {})",
+                    s,
+                    MALFORMATIONS.map(|m| m.as_str()).join(", ")
+                ))
+            })
+    }
+}
+
+// The second position of the first line or ring if there is one, so that
+// rings stay closed, or else the first position.
+fn some_position(value: &mut Value) -> Option<&mut Vec<f64>> {
+    fn second(ps: &mut [Vec<f64>]) -> Option<&mut Vec<f64>> {
+        let i = ps.len().min(2).checked_sub(1)?;
+        ps.get_mut(i)
+    }
+    match value {
+        Value::Point(p) => (p.len() >= 2).then_some(p),
+        Value::MultiPoint(ps) | Value::LineString(ps) => second(ps),
+        Value::MultiLineString(lines) | Value::Polygon(lines) => second(lines.first_mut()?),
+        Value::MultiPolygon(polygons) => second(polygons.first_mut()?.first_mut()?),
+        Value::GeometryCollection(geometries) => some_position(&mut geometries.first_mut()?.value),
+    }
+}
+
+/// Makes each feature of `fc` invalid with probability `rate`, using one of
+/// `kinds` that applies to its geometry, and records its name in an
+/// `invalid` property. Features none of `kinds` applies to stay valid.
+///
+/// `extent` is that of the coordinate system, for out-of-range positions.
+/// Returns the number of features made invalid.
+pub fn inject(
+    fc: &mut FeatureCollection,
+    rate: f64,
+    kinds: &[Malformation],
+    extent: &Bounds,
+) -> usize {
+    let mut rng = random::rng();
+    let mut injected = 0;
+    for feature in &mut fc.features {
+        if !rng.random_bool(rate) {
+            continue;
+        }
+        let value = feature.geometry.as_ref().map(|g| &g.value);
+        let applicable: Vec<Malformation> = kinds
+            .iter()
+            .copied()
+            .filter(|kind| kind.applies_to(value))
+            .collect();
+        let Some(kind) = applicable.choose(&mut rng).copied() else {
+            continue;
+        };
+        kind.apply(feature, extent);
+        feature
+            .properties
+            .get_or_insert_default()
+            .insert("invalid".to_string(), JsonValue::from(kind.as_str()));
+        injected += 1;
+    }
+    if injected > 0 {
+        fc.bbox = None;
+    }
+    injected
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::Geometry;
+
+    use crate::geometry::{Unit, WGS84_BOUNDS};
+
+    use super::*;
+
+    fn square() -> Feature {
+        let ring = vec![
+            vec![0.0, 0.0],
+            vec![1.0, 0.0],
+            vec![1.0, 1.0],
+            vec![0.0, 1.0],
+            vec![0.0, 0.0],
+        ];
+        Feature {
+            geometry: Some(Geometry::new(Value::Polygon(vec![ring]))),
+            ..Default::default()
+        }
+    }
+
+    fn collection(features: Vec<Feature>) -> FeatureCollection {
+        FeatureCollection {
+            features,
+            ..Default::default()
+        }
+    }
+
+    fn ring(feature: &Feature) -> &Vec<Vec<f64>> {
+        match &feature.geometry.as_ref().unwrap().value {
+            Value::Polygon(rings) => &rings[0],
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_malformations() {
+        let extent = Bounds::new(-1.0, -1.0, 2.0, 2.0, Unit::Degrees);
+        let run = |kind: Malformation| {
+            let mut fc = collection(vec![square()]);
+            assert_eq!(inject(&mut fc, 1.0, &[kind], &extent), 1);
+            let feature = fc.features.remove(0);
+            assert_eq!(feature.property("invalid").unwrap(), kind.as_str());
+            feature
+        };
+
+        let unclosed = run(Malformation::UnclosedRing);
+        assert_ne!(ring(&unclosed).first(), ring(&unclosed).last());
+        let bow_tie = run(Malformation::SelfIntersection);
+        assert_eq!(ring(&bow_tie)[1], vec![1.0, 1.0]);
+        let duplicated = run(Malformation::DuplicatePoints);
+        assert_eq!(ring(&duplicated).len(), 6);
+        assert_eq!(ring(&duplicated).first(), ring(&duplicated).last());
+        assert!(ring(&duplicated).windows(2).any(|w| w[0] == w[1]));
+        let empty = run(Malformation::EmptyCoordinates);
+        assert_eq!(empty.geometry.unwrap().value, Value::Polygon(vec![]));
+        assert!(run(Malformation::NullGeometry).geometry.is_none());
+        let outside = run(Malformation::OutOfRange);
+        assert!(!extent.contains(ring(&outside)[1][0], ring(&outside)[1][1]));
+        assert_eq!(ring(&outside).first(), ring(&outside).last());
+        let non_finite = run(Malformation::NonFinite);
+        assert!(ring(&non_finite)[1].iter().any(|c| !c.is_finite()));
+    }
+
+    #[test]
+    fn test_inject() {
+        let point = || Feature {
+            geometry: Some(Geometry::new(Value::Point(vec![1.0, 2.0]))),
+            ..Default::default()
+        };
+        let mut fc = collection((0..1000).map(|_| point()).collect());
+        let injected = inject(&mut fc, 0.1, &MALFORMATIONS, &WGS84_BOUNDS);
+        assert!((50..150).contains(&injected), "{}", injected);
+        assert_eq!(
+            fc.features
+                .iter()
+                .filter(|f| f.property("invalid").is_some())
+                .count(),
+            injected
+        );
+        // Points have no rings to leave open
+        let mut fc = collection(vec![point()]);
+        assert_eq!(
+            inject(&mut fc, 1.0, &[Malformation::UnclosedRing], &WGS84_BOUNDS),
+            0
+        );
+        assert!(fc.features[0].properties.is_none());
+    }
+
+    #[test]
+    fn test_malformation_from_str() {
+        assert_eq!(
+            "unclosed_ring".parse::<Malformation>().unwrap(),
+            Malformation::UnclosedRing
+        );
+        assert!("broken".parse::<Malformation>().is_err());
+    }
+}
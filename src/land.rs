@@ -0,0 +1,122 @@
+use std::sync::{Arc, OnceLock};
+
+use geojson::{Feature, FeatureCollection, GeoJson};
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::mask::Mask;
+
+/// Country outlines at 1:110m scale, derived from Natural Earth (public
+/// domain) with coordinates rounded to about 100 m. Each feature has `name`,
+/// `iso_a2`, `iso_a3` and `continent` properties.
+const COUNTRIES: &str = include_str!("../data/countries.geojson");
+
+/// Continents as named in the `continent` property of the countries.
+pub const CONTINENTS: [&str; 7] = [
+    "africa",
+    "antarctica",
+    "asia",
+    "europe",
+    "north-america",
+    "oceania",
+    "south-america",
+];
+
+fn countries() -> &'static [Feature] {
+    static COLLECTION: OnceLock<FeatureCollection> = OnceLock::new();
+    &COLLECTION
+        .get_or_init(|| COUNTRIES.parse().expect("embedded countries are valid"))
+        .features
+}
+
+fn mask(features: impl Iterator<Item = &'static Feature>) -> Mask {
+    let fc = FeatureCollection {
+        features: features.cloned().collect(),
+        bbox: None,
+        foreign_members: None,
+    };
+    Mask::from_geojson(GeoJson::FeatureCollection(fc)).expect("embedded countries have polygons")
+}
+
+fn property(feature: &'static Feature, name: &str) -> Option<&'static str> {
+    feature.property(name)?.as_str()
+}
+
+/// A mask of the land masses of the world, parsed on first use.
+///
+/// At this resolution small islands are missing and coastlines are only
+/// accurate to a few kilometres.
+pub fn land() -> Arc<Mask> {
+    static LAND: OnceLock<Arc<Mask>> = OnceLock::new();
+    LAND.get_or_init(|| Arc::new(mask(countries().iter())))
+        .clone()
+}
+
+/// A mask of one country, given by its ISO 3166-1 alpha-2 or alpha-3 code
+/// (e.g. "DE" or "DEU") or its English name, in any case.
+pub fn country(name: &str) -> RandomGeojsonResult<Arc<Mask>> {
+    let matches = |feature: &'static Feature| {
+        ["iso_a2", "iso_a3", "name"]
+            .iter()
+            .any(|key| property(feature, key).is_some_and(|v| v.eq_ignore_ascii_case(name)))
+    };
+    let feature = countries()
+        .iter()
+        .find(|f| matches(f))
+        .ok_or_else(|| RandomGeojsonError::InvalidArgument(format!("Unknown country: {}", name)))?;
+    Ok(Arc::new(mask(std::iter::once(feature))))
+}
+
+/// A mask of the countries of a continent, one of [`CONTINENTS`]; spaces or
+/// underscores may separate words, e.g. "North America".
+pub fn continent(name: &str) -> RandomGeojsonResult<Arc<Mask>> {
+    let normalized = name.to_lowercase().replace([' ', '_'], "-");
+    if !CONTINENTS.contains(&normalized.as_str()) {
+        return Err(RandomGeojsonError::InvalidArgument(format!(
+            "Unknown continent: {}, expected one of: {}",
+            name,
+            CONTINENTS.join(", ")
+        )));
+    }
+    let features = countries()
+        .iter()
+        .filter(|f| property(f, "continent") == Some(normalized.as_str()));
+    Ok(Arc::new(mask(features)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_land() {
+        let land = land();
+        // Madrid, Nairobi and Sydney
+        assert!(land.contains(-3.70, 40.42));
+        assert!(land.contains(36.82, -1.29));
+        assert!(land.contains(151.0, -33.87));
+        // Middle of the Atlantic and Pacific
+        assert!(!land.contains(-30.0, 30.0));
+        assert!(!land.contains(-150.0, 0.0));
+    }
+
+    #[test]
+    fn test_country() {
+        // Berlin and Paris
+        let germany = country("DE").unwrap();
+        assert!(germany.contains(13.40, 52.52));
+        assert!(!germany.contains(2.35, 48.86));
+        assert!(country("deu").unwrap().contains(13.40, 52.52));
+        assert!(country("germany").unwrap().contains(13.40, 52.52));
+        assert!(country("XX").is_err());
+    }
+
+    #[test]
+    fn test_continent() {
+        let europe = continent("Europe").unwrap();
+        assert!(europe.contains(2.35, 48.86));
+        assert!(!europe.contains(36.82, -1.29));
+        // Mexico City
+        assert!(continent("North America").unwrap().contains(-99.13, 19.43));
+        assert!(continent("atlantis").is_err());
+    }
+}
@@ -0,0 +1,58 @@
+pub mod anchor;
+pub mod anonymize;
+pub mod completions;
+pub mod compression;
+pub mod computed;
+pub mod config;
+pub mod coverage;
+pub mod datasource;
+pub mod density;
+pub mod edge_case;
+pub mod error;
+pub mod expr;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod format;
+pub mod generator;
+pub mod geo;
+pub mod geometry;
+pub mod input;
+pub mod invalid;
+pub mod land;
+pub mod logging;
+pub mod mask;
+pub mod measure;
+pub mod members;
+pub mod merge;
+pub mod metadata;
+pub mod network;
+pub mod options;
+pub mod output;
+pub mod overlap;
+pub mod pattern;
+pub mod perturb;
+pub mod post;
+pub mod preset;
+pub mod progress;
+pub mod property;
+pub mod random;
+pub mod region;
+pub mod remote;
+pub mod sample;
+pub mod series;
+pub mod serve;
+pub mod shape;
+pub mod simplify;
+pub mod snap;
+pub mod sort;
+pub mod spacing;
+pub mod stats;
+pub mod stream;
+pub mod stress;
+pub mod template;
+pub mod tile;
+pub mod trajectory;
+pub mod validate;
+pub mod walk;
+#[cfg(feature = "wasm")]
+pub mod wasm;
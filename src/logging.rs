@@ -0,0 +1,112 @@
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// Writes `tracing` events up to a level to stderr, one line each, with the
+/// time since the logger was installed.
+///
+/// Spans are only given ids, as the tool logs plain events.
+struct StderrLogger {
+    level: Level,
+    start: Instant,
+    next_span: AtomicU64,
+}
+
+/// Collects the fields of an event, the message first.
+#[derive(Default)]
+struct Fields {
+    message: String,
+    rest: String,
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.rest, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.rest, " {}={}", field.name(), value);
+        }
+    }
+}
+
+impl Subscriber for StderrLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= self.level
+    }
+
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let _ = writeln!(
+            std::io::stderr().lock(),
+            "[{:>8.3}s {:>5}] {}{}",
+            self.start.elapsed().as_secs_f64(),
+            event.metadata().level(),
+            fields.message,
+            fields.rest
+        );
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+/// The most detailed level logged for `--verbose` given `verbose` times, or
+/// only errors when `quiet`: warnings by default, then info, debug and
+/// trace.
+pub fn level(verbose: u8, quiet: bool) -> Level {
+    match (quiet, verbose) {
+        (true, _) => Level::ERROR,
+        (false, 0) => Level::WARN,
+        (false, 1) => Level::INFO,
+        (false, 2) => Level::DEBUG,
+        _ => Level::TRACE,
+    }
+}
+
+/// Logs events up to `level` to stderr for the rest of the process. Does
+/// nothing if a logger was installed before.
+pub fn init(level: Level) {
+    let logger = StderrLogger {
+        level,
+        start: Instant::now(),
+        next_span: AtomicU64::new(1),
+    };
+    let _ = tracing::subscriber::set_global_default(logger);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level() {
+        assert_eq!(level(0, false), Level::WARN);
+        assert_eq!(level(1, false), Level::INFO);
+        assert_eq!(level(2, false), Level::DEBUG);
+        assert_eq!(level(5, false), Level::TRACE);
+        assert_eq!(level(2, true), Level::ERROR);
+    }
+}
@@ -1,25 +1,352 @@
-mod error;
-mod geometry;
+use std::ffi::OsString;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use clap::Parser;
-use error::{RandomGeojsonError, RandomGeojsonResult};
-use geojson::Value::{LineString, Point, Polygon};
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
 use geojson::feature::Id;
-use geojson::{Feature, FeatureCollection, Geometry, JsonObject};
-use geometry::{Crs, RandomGeometry};
+use geojson::{Feature, FeatureCollection, GeoJson, JsonObject};
 use rand::Rng;
-use uuid::Uuid;
+use random_geojson::anchor::random_site;
+use random_geojson::anonymize::anonymize;
+use random_geojson::completions::Shell;
+use random_geojson::compression::Compression;
+use random_geojson::computed::{
+    ComputedProperty, add_computed_properties, parse_computed_properties,
+};
+use random_geojson::config;
+use random_geojson::coverage::Coverage;
+use random_geojson::density::DensityGrid;
+use random_geojson::edge_case::{EdgeCase, edge_cases};
+use random_geojson::error::{
+    EXIT_IO, EXIT_SERIALIZE, ErrorFormat, RandomGeojsonError, RandomGeojsonResult, error_json,
+};
+use random_geojson::format::sql::SqlMode;
+use random_geojson::format::{EncodeOptions, Format};
+use random_geojson::generator::{
+    PropertyGenerator, ScriptGenerator, add_generated_properties, read_script,
+};
+use random_geojson::geometry::{Bounds, Crs, RandomGeometry};
+use random_geojson::input::{for_each_feature, read_features};
+use random_geojson::invalid::{MALFORMATIONS, Malformation, inject};
+use random_geojson::land;
+use random_geojson::logging;
+use random_geojson::mask::Mask;
+use random_geojson::measure::{SizeRange, parse_area, parse_length};
+use random_geojson::members::{MissingMembers, duplicate_ids};
+use random_geojson::merge::{DuplicateIds, merge};
+use random_geojson::metadata::{Metadata, sidecar_path};
+use random_geojson::network::random_network;
+use random_geojson::output::{
+    OutputOptions, append_geojson_to_file, check_overwrite, layer_path, save_to_file, serialize,
+    shard_path, split_by_type, write_output,
+};
+use random_geojson::overlap::PolygonIndex;
+use random_geojson::perturb::{PerturbOptions, perturb};
+use random_geojson::post::{self, PostOptions, Poster};
+use random_geojson::preset::Preset;
+use random_geojson::progress::{Progress, human_bytes};
+use random_geojson::property::{
+    Language, PropertyCount, PropertySpec, PropertyTypes, Wordlist, finish_properties,
+    random_property_value, use_languages, use_wordlist, validate_property_specs,
+};
+use random_geojson::random::{self, random_uuid};
+use random_geojson::region::random_regions;
+use random_geojson::remote::Upload;
+use random_geojson::sample::{SampleSize, Sampler};
+use random_geojson::serve::{ServeOptions, serve};
+use random_geojson::shape::{
+    LineShape, LineStyle, PolygonShape, PolygonStyle, RadiusRange, random_buffer,
+};
+use random_geojson::simplify::{densify, simplify};
+use random_geojson::snap::snap_to_grid;
+use random_geojson::sort::SortOrder;
+use random_geojson::spacing::{Distance, PointSpacing};
+use random_geojson::stats::Stats;
+use random_geojson::stream::{Rate, StreamOptions, pace, stream};
+use random_geojson::stress::{stress_linestring, stress_polygon};
+use random_geojson::tile::TileId;
+use random_geojson::trajectory::{SpeedRange, fix_point, random_trajectory, trajectory_linestring};
+use random_geojson::validate::Assertion;
+use random_geojson::walk::moving_points;
+use tracing::{debug, info, warn};
+
+// Exit code of the validate subcommand when an assertion fails
+const EXIT_ASSERTION_FAILED: i32 = 1;
 
 #[derive(Parser, Debug)]
 #[command(
     name = "Random Geojson",
     about = "Random Geojson is a tool to generate random geojson data.",
-    version
+    version,
+    args_conflicts_with_subcommands = true
 )]
 pub struct Cli {
-    /// Number of properties (optional, defaults to 0)
-    #[arg(long, default_value_t = 0, value_parser = validate_zero_or_more)]
-    pub num_properties: usize,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Read settings from a TOML, YAML or JSON file, keyed by long flag name;
+    /// flags given on the command line override them (optional)
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+
+    /// Hide the progress bar shown on a terminal while generating a file and
+    /// log only errors (optional, defaults to false)
+    #[arg(long, short, global = true, default_value_t = false)]
+    pub quiet: bool,
+
+    /// Log the effective options, the time taken by each phase and the size
+    /// of the output to stderr; repeat for more detail, e.g. -vv (optional)
+    #[arg(long, short, global = true, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Report errors on stderr as text or as JSON objects with a code, a
+    /// message and the flag at fault (optional, defaults to "text")
+    /// Possible values: "text", "json"
+    #[arg(long, global = true, default_value = "text")]
+    pub error_format: ErrorFormat,
+
+    /// Options of the generate subcommand, which also runs when no
+    /// subcommand is given
+    #[command(flatten)]
+    pub generate: GenerateArgs,
+}
+
+impl Cli {
+    // Log lines would break up the progress bar
+    fn show_progress(&self) -> bool {
+        !self.quiet && self.verbose == 0
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct GenerateArgs {
+    #[command(flatten)]
+    pub features: FeatureArgs,
+
+    /// Output format (optional, defaults to "geojson")
+    /// Possible values: "geojson", "fgb", "geoparquet", "gpkg", "shp", "csv",
+    /// "topojson", "kml", "kmz", "gpx", "mvt", "pmtiles", "sql", "wkt", "wkb"
+    #[arg(long, default_value = "geojson")]
+    pub format: Format,
+
+    /// Add "lon" and "lat" columns for Point geometries to CSV output
+    /// (optional, defaults to false)
+    #[arg(long, default_value_t = false)]
+    pub lon_lat: bool,
+
+    /// Generate features only inside the given XYZ tile, e.g. "14/8185/5448".
+    /// Required by "mvt" output, which encodes that tile (optional)
+    #[arg(long)]
+    pub tile: Option<TileId>,
+
+    /// Lowest zoom level of PMTiles output (optional, defaults to 0)
+    #[arg(long, default_value_t = 0)]
+    pub minzoom: u8,
+
+    /// Highest zoom level of PMTiles output (optional, defaults to 6)
+    #[arg(long, default_value_t = 6)]
+    pub maxzoom: u8,
+
+    /// Table created by SQL output, optionally schema-qualified
+    /// (optional, defaults to "random")
+    #[arg(long, default_value = "random")]
+    pub table: String,
+
+    /// How SQL output loads the rows (optional, defaults to "insert")
+    /// Possible values: "insert", "copy"
+    #[arg(long, default_value = "insert")]
+    pub sql_mode: SqlMode,
+
+    /// Output GeoJSON or TopoJSON in pretty print (optional, defaults to false)
+    #[arg(long, default_value_t = false)]
+    pub pretty: bool,
+
+    /// Output GeoJSON or TopoJSON in a canonical form that diffs cleanly when
+    /// regenerated: keys sorted, floats rounded to 7 decimal places without
+    /// exponents, and a final newline (optional, defaults to false)
+    #[arg(long, default_value_t = false)]
+    pub canonical: bool,
+
+    /// Compress the output (optional, defaults to "none")
+    /// Possible values: "none", "gzip", "zstd"
+    #[arg(long, default_value = "none")]
+    pub compress: Compression,
+
+    /// Compression level (optional, defaults to 6 for gzip and 3 for zstd)
+    #[arg(long, requires = "compress", allow_negative_numbers = true)]
+    pub compress_level: Option<i32>,
+
+    /// Split the output into files of at most N features each, named
+    /// e.g. "random-0001.geojson" (optional)
+    #[arg(long, value_parser = validate_one_or_more, conflicts_with = "shards")]
+    pub features_per_file: Option<usize>,
+
+    /// Split the output evenly into N files (optional)
+    #[arg(long, value_parser = validate_one_or_more)]
+    pub shards: Option<usize>,
+
+    /// Write each of the --snapshots to its own file, named e.g.
+    /// "random-0001.geojson" (optional, defaults to false)
+    #[arg(long, default_value_t = false, requires = "snapshots", conflicts_with_all = ["features_per_file", "shards"])]
+    pub snapshot_files: bool,
+
+    /// Write each kind of geometry to its own file, named e.g.
+    /// "random-points.geojson", "random-linestrings.geojson" and
+    /// "random-polygons.geojson", for consumers that need homogeneous layers
+    /// (optional, defaults to false)
+    #[arg(long, default_value_t = false, conflicts_with_all = ["features_per_file", "shards", "snapshot_files"])]
+    pub split_by_type: bool,
+
+    /// Order the features spatially before writing, by the centroid of their
+    /// geometry (optional, defaults to "none")
+    /// Possible values: "hilbert" (along a Hilbert curve over their extent),
+    /// "geohash" (by geohash), "none" (in the order generated)
+    #[arg(long, default_value = "none", conflicts_with_all = ["rate", "post_url"])]
+    pub sort: SortOrder,
+
+    /// Overwrite the output file if it already exists (optional, defaults to false)
+    #[arg(long, default_value_t = false)]
+    pub force: bool,
+
+    /// Seed the random number generator, so the same options and seed always
+    /// generate the same features (optional)
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Record the tool version, seed, time, options and feature count: as a
+    /// "random_geojson" member of a GeoJSON FeatureCollection, or else in a
+    /// "<output>.meta.json" file next to the output. Without --seed, a random
+    /// seed is chosen and recorded (optional, defaults to false)
+    #[arg(long, default_value_t = false, conflicts_with = "append")]
+    pub metadata: bool,
+
+    /// Append the generated features to the FeatureCollection in the output
+    /// file, creating it if needed (optional, defaults to false)
+    #[arg(long, default_value_t = false, conflicts_with_all = ["force", "features_per_file", "shards", "snapshot_files", "split_by_type"])]
+    pub append: bool,
+
+    /// Instead of writing a file, generate the dataset at each of the
+    /// --scaling-sizes and print a table of output bytes and wall time
+    /// (optional, defaults to false)
+    #[arg(long, default_value_t = false)]
+    pub scaling_report: bool,
+
+    /// Validate the options and print the effective configuration with an
+    /// estimate of the output size, from a small sample that is not written,
+    /// instead of generating the dataset (optional, defaults to false)
+    #[arg(long, default_value_t = false, conflicts_with_all = ["scaling_report", "append"])]
+    pub dry_run: bool,
+
+    /// Comma-separated feature counts used by --scaling-report
+    /// (optional, defaults to "1000,10000,100000")
+    #[arg(long, value_delimiter = ',', default_value = "1000,10000,100000", value_parser = validate_one_or_more)]
+    pub scaling_sizes: Vec<usize>,
+
+    /// Comma-separated formats compared by --scaling-report
+    /// (optional, defaults to --format)
+    #[arg(long, value_delimiter = ',')]
+    pub scaling_formats: Vec<Format>,
+
+    /// Emit features at the given rate, e.g. "500/s", as newline-delimited
+    /// GeoJSON to stdout or in batches to --post-url, until --length features
+    /// are sent or forever if --length is not given (optional)
+    #[arg(long, conflicts_with_all = ["scaling_report", "append", "features_per_file", "shards"])]
+    pub rate: Option<Rate>,
+
+    /// Send the features in batches to this HTTP endpoint with POST requests
+    /// instead of writing a file, each batch encoded in --format (optional)
+    #[arg(long, conflicts_with_all = ["scaling_report", "append", "features_per_file", "shards", "output_file"])]
+    pub post_url: Option<String>,
+
+    /// Number of features per POST request (optional, defaults to 1000)
+    #[arg(long, default_value_t = 1000, value_parser = validate_one_or_more)]
+    pub batch_size: usize,
+
+    /// Number of POST requests in flight at once (optional, defaults to 4)
+    #[arg(long, default_value_t = 4, value_parser = validate_one_or_more)]
+    pub concurrency: usize,
+
+    /// Number of times a failed POST request is retried, with exponential
+    /// backoff, for connection errors, 429 and 5xx responses (optional,
+    /// defaults to 3)
+    #[arg(long, default_value_t = 3)]
+    pub retries: u32,
+
+    // File name to save the generated data (optional, defaults to "random.<format extension>")
+    #[arg(short, long)]
+    pub output_file: Option<String>,
+}
+
+/// Options describing the features to generate.
+#[derive(Args, Debug, Clone)]
+pub struct FeatureArgs {
+    /// Number of random properties of each feature, a single value or a
+    /// range such as "2..8" for features with varying property sets
+    /// (optional, defaults to 0)
+    #[arg(long, default_value = "0")]
+    pub num_properties: PropertyCount,
+
+    /// Draw the keys of the --num-properties random properties of each
+    /// feature from a pool of this many, "prop1" to "propN", so features have
+    /// different keys and the union schema is wider than any one of them
+    /// (optional)
+    #[arg(long, value_parser = validate_one_or_more)]
+    pub sparse_properties: Option<usize>,
+
+    /// Comma-separated numbers of random properties of each type, e.g.
+    /// "int=3,string=2,bool=1,float=2", named "prop1" to "propN" in that
+    /// order so every feature has the same column types, instead of
+    /// --num-properties of random types (optional)
+    #[arg(long, conflicts_with_all = ["num_properties", "sparse_properties"])]
+    pub property_types: Option<PropertyTypes>,
+
+    /// Draw random string properties from this file instead of English
+    /// words: one word or phrase per line, optionally followed by a tab and a
+    /// weight (optional)
+    #[arg(long, value_parser = read_wordlist)]
+    pub wordlist: Option<Arc<Wordlist>>,
+
+    /// Comma-separated languages of the words of random string properties,
+    /// each string in one of them (optional, defaults to "en")
+    /// Possible values: "de", "en", "es", "fr", "ja", "ru", "zh"
+    #[arg(long, value_delimiter = ',', conflicts_with = "wordlist")]
+    pub lang: Vec<Language>,
+
+    /// Add a named property of the given type to every feature, e.g.
+    /// "height:float" (optional, repeatable)
+    /// Types: "int", "float", "int(<min>,<max>)" and "float(<min>,<max>)"
+    /// for numbers in a range, e.g. "speed:float(0,130)", "string", "bool",
+    /// "bool(<probability>)" for flags true that often, e.g.
+    /// "is_active:bool(0.9)", "probability", "probability(<bool property>)"
+    /// for scores calibrated against that label, "pattern(<regex>)" for
+    /// strings matching a regular expression, e.g.
+    /// "plate:pattern([A-Z]{2}-[0-9]{4})", "template(<text>)" for strings
+    /// made of other properties, e.g. "label:template({name} ({population}))",
+    /// "expr(<expression>)" for values computed from other properties,
+    /// e.g. "density:expr(population / area_km2)", or
+    /// "series(<length>,<distribution>[,<interval>])" for arrays of numbers
+    /// such as "readings:series(24,normal(20,5),1h)"
+    /// Append ":unique" so that no two features share a value, e.g.
+    /// "code:string:unique"
+    #[arg(long)]
+    pub property: Vec<PropertySpec>,
+
+    /// Comma-separated properties derived from the geometry of each feature,
+    /// e.g. "geohash:7" for the geohash of its centroid with 7 characters, or
+    /// "elevation:noise(scale=0.5,octaves=4,min=0,max=1000)" for coherent
+    /// noise at its centroid, so nearby features get similar values
+    /// (optional)
+    #[arg(long, value_parser = parse_computed_properties)]
+    pub computed_properties: Vec<::std::vec::Vec<ComputedProperty>>,
+
+    /// Add the properties of a TOML generator script to every feature, each
+    /// a table with a "type" and an "expr" computing it from the other
+    /// properties, "random" and "geometry_type" (optional)
+    #[arg(long, value_parser = read_generators)]
+    pub generators: Option<Arc<Vec<ScriptGenerator>>>,
 
     /// Length of data (optional, defaults to 100)
     #[arg(long, default_value_t = 100, value_parser = validate_zero_or_more)]
@@ -31,124 +358,1804 @@ pub struct Cli {
     pub geometry_type: String,
 
     /// Coordinate system to use (optional, defaults to "WGS84")
-    /// Possible values: "WGS84", "WebMercator", "4326", "3857"
+    /// Possible values: "WGS84", "WebMercator", "4326", "3857" or a PROJ string such as "+proj=utm +zone=32"
     #[arg(long, default_value = "WGS84", value_parser = validate_coordinate_system)]
     pub coordinate_system: String,
 
-    /// Output GeoJSON format in pretty print (optional, defaults to false)
+    /// Only generate coordinates inside the Polygons and MultiPolygons of this
+    /// GeoJSON file, in WGS84 (optional)
+    #[arg(long, value_parser = read_mask)]
+    pub within: Option<Arc<Mask>>,
+
+    /// Never generate coordinates inside the Polygons and MultiPolygons of
+    /// this GeoJSON file, in WGS84 (optional)
+    #[arg(long, value_parser = read_mask)]
+    pub exclude: Option<Arc<Mask>>,
+
+    /// Only generate coordinates inside a country, given by its ISO 3166-1
+    /// code, e.g. "DE" or "DEU", or its English name (optional)
+    #[arg(long, value_parser = land::country, conflicts_with = "continent")]
+    pub country: Option<Arc<Mask>>,
+
+    /// Only generate coordinates inside a continent (optional)
+    /// Possible values: "africa", "antarctica", "asia", "europe",
+    /// "north-america", "oceania", "south-america"
+    #[arg(long, value_parser = land::continent)]
+    pub continent: Option<Arc<Mask>>,
+
+    /// Only generate coordinates on land, using a built-in low-resolution
+    /// land mask (optional, defaults to false)
+    #[arg(long, default_value_t = false)]
+    pub land_only: bool,
+
+    /// Sample coordinates in proportion to the weights of the cells of this
+    /// GeoTIFF or JSON grid in WGS84, e.g. a population raster (optional)
+    #[arg(long, value_parser = read_density_grid)]
+    pub density_weights: Option<Arc<DensityGrid>>,
+
+    /// Keep generated points at least this far apart, e.g. "500m", "2km" or
+    /// "0.01deg"; a bare number is in metres (optional)
+    #[arg(long)]
+    pub min_distance: Option<Distance>,
+
+    /// Keep generated polygons from overlapping each other, shrinking those
+    /// that would (optional, defaults to false)
+    #[arg(long, default_value_t = false)]
+    pub no_overlap: bool,
+
+    /// Shape of generated polygons (optional, defaults to "random")
+    /// Possible values: "random" (vertices anywhere in the area), "footprint"
+    /// (building outlines of 50 to 2000 m²), "circle", "ngon" (regular
+    /// polygons of --vertices vertices), "star" (of --vertices points),
+    /// "concave" (irregular notched outlines)
+    #[arg(long, default_value = "random")]
+    pub polygon_style: PolygonStyle,
+
+    /// Radius of circle, ngon, star and concave polygons in metres, a single value or a
+    /// range such as "100..5000" or "1km..5km" (optional, defaults to
+    /// 100..5000)
+    #[arg(long, default_value = "100..5000")]
+    pub radius: RadiusRange,
+
+    /// Number of vertices of ngon polygons and points of star polygons
+    /// (optional, defaults to 6)
+    #[arg(long, default_value_t = 6, value_parser = validate_polygon_vertices)]
+    pub vertices: usize,
+
+    /// How deep star and concave polygons are notched, from 0 (not at all)
+    /// to just below 1 (down to their centre) (optional, defaults to 0.5)
+    #[arg(long, default_value_t = 0.5, value_parser = validate_concavity)]
+    pub concavity: f64,
+
+    /// Shape of generated LineStrings (optional, defaults to "random")
+    /// Possible values: "random" (straight segments), "fractal" (segments
+    /// made wiggly like coastlines by midpoint displacement)
+    #[arg(long, default_value = "random")]
+    pub line_style: LineStyle,
+
+    /// How far fractal LineStrings stray from straight segments, from 0
+    /// (not at all) to 1 (most) (optional, defaults to 0.5)
+    #[arg(long, default_value_t = 0.5, value_parser = validate_probability)]
+    pub roughness: f64,
+
+    /// Emit generated Points as their geodesic buffers: round polygons of
+    /// this radius in metres, a single value or a range such as "100..5000"
+    /// or "1km..5km" (optional)
+    #[arg(long)]
+    pub buffer: Option<RadiusRange>,
+
+    /// Generate LineStrings as random walks with steps of up to this length,
+    /// e.g. "50m", "1km" or "0.001deg", instead of vertices anywhere in the
+    /// area (optional)
+    #[arg(long, value_parser = validate_positive_distance)]
+    pub walk: Option<Distance>,
+
+    /// Smallest area of generated polygons, e.g. "500m2", "1ha" or "1km2";
+    /// polygons outside the range are rescaled to a random area within it,
+    /// though --no-overlap may shrink them further (optional)
+    #[arg(long, value_parser = parse_area)]
+    pub min_area: Option<f64>,
+
+    /// Largest area of generated polygons, e.g. "2000m2" or "5km2" (optional)
+    #[arg(long, value_parser = parse_area)]
+    pub max_area: Option<f64>,
+
+    /// Shortest length of generated LineStrings, e.g. "100m" or "2km";
+    /// LineStrings outside the range are rescaled to a random length within
+    /// it (optional)
+    #[arg(long, value_parser = parse_length)]
+    pub min_line_length: Option<f64>,
+
+    /// Longest length of generated LineStrings, e.g. "500m" or "10km"
+    /// (optional)
+    #[arg(long, value_parser = parse_length)]
+    pub max_line_length: Option<f64>,
+
+    /// Generate groups of related features around shared anchor points instead
+    /// of independent features (optional, overrides --length and --geometry-type)
+    #[arg(long, value_parser = validate_zero_or_more)]
+    pub anchored_groups: Option<usize>,
+
+    /// Generate believable demo data from a built-in profile instead of random
+    /// geometries (optional, overrides --geometry-type)
+    /// Possible values: "cities" (clustered points with name and population),
+    /// "roads" (connected linestrings with highway class), "buildings"
+    /// (rectangles and L and T shapes with height), "lakes" (irregular
+    /// polygons with area)
+    #[arg(long, conflicts_with = "anchored_groups")]
+    pub preset: Option<Preset>,
+
+    /// Generate a layer of polygons covering the area without gaps or
+    /// overlaps instead of random geometries (optional, overrides
+    /// --geometry-type)
+    /// Possible values: "voronoi" (the cells of --length random seed points),
+    /// "hexgrid" (a grid of hexagons), "squaregrid" (a grid of squares),
+    /// "delaunay" (the triangles between --length random seed points)
+    #[arg(long, conflicts_with_all = ["anchored_groups", "preset"])]
+    pub coverage: Option<Coverage>,
+
+    /// Generate simulated GPS traces instead of random geometries:
+    /// LineStrings of fixes --interval seconds apart moving at --speed, with
+    /// per-vertex times (optional, overrides --geometry-type)
+    #[arg(long, conflicts_with_all = ["anchored_groups", "preset", "coverage"])]
+    pub trajectory: bool,
+
+    /// Speed of --trajectory traces in km/h, a single value or a range such
+    /// as "30..80" (optional, defaults to 5..50)
+    #[arg(long, default_value = "5..50")]
+    pub speed: SpeedRange,
+
+    /// Seconds between two fixes of a --trajectory trace (optional, defaults
+    /// to 5)
+    #[arg(long, default_value_t = 5.0, value_parser = validate_positive)]
+    pub interval: f64,
+
+    /// Write every fix of a --trajectory trace as a Point with track_id,
+    /// sequence, timestamp and speed instead of one LineString per trace
+    /// (optional, defaults to false)
+    #[arg(long, default_value_t = false, requires = "trajectory")]
+    pub trajectory_points: bool,
+
+    /// Generate --length points moving over N moments instead of random
+    /// geometries, each with object_id and t properties (optional,
+    /// overrides --geometry-type)
+    #[arg(long, value_parser = validate_one_or_more, conflicts_with_all = ["anchored_groups", "preset", "coverage", "trajectory"])]
+    pub snapshots: Option<usize>,
+
+    /// Largest distance a point moves between two --snapshots, e.g. "100m",
+    /// "2km" or "0.001deg" (optional, defaults to 100m)
+    #[arg(long, default_value = "100m")]
+    pub snapshot_step: Distance,
+
+    /// Generate a connected road network between --length random junctions
+    /// instead of random geometries: LineStrings that share the coordinates
+    /// of the junctions at their ends (optional, overrides --geometry-type)
+    #[arg(long, default_value_t = false, conflicts_with_all = ["anchored_groups", "preset", "coverage", "trajectory", "snapshots"])]
+    pub network: bool,
+
+    /// Give every road of a --network the from_node and to_node ids of the
+    /// junctions it connects (optional, defaults to false)
+    #[arg(long, default_value_t = false, requires = "network")]
+    pub node_ids: bool,
+
+    /// Generate geometries that often break downstream code instead of
+    /// random geometries, in WGS84 over the whole globe (optional, overrides
+    /// --geometry-type)
+    /// Comma-separated values: "antimeridian" (on and across ±180°
+    /// longitude), "poles" (at, around and over ±90° latitude)
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["anchored_groups", "preset", "coverage", "trajectory", "snapshots", "network"])]
+    pub edge_cases: Vec<EdgeCase>,
+
+    /// Generate a single LineString, Polygon or one of each with this many
+    /// vertices instead of --length random geometries, for benchmarking
+    /// parsers against huge geometries (optional)
+    #[arg(long, value_parser = validate_polygon_vertices, conflicts_with_all = ["anchored_groups", "preset", "coverage", "trajectory", "snapshots", "network", "edge_cases"])]
+    pub stress_vertices: Option<usize>,
+
+    /// Generate N region polygons covering the area and --length points in
+    /// them instead of random geometries, each point with the region_id of
+    /// the region containing it (optional, overrides --geometry-type)
+    #[arg(long, value_parser = validate_one_or_more, conflicts_with_all = ["anchored_groups", "preset", "coverage", "trajectory", "snapshots", "network", "edge_cases", "stress_vertices"])]
+    pub regions: Option<usize>,
+
+    /// Simplify generated lines and polygon rings with the Douglas-Peucker
+    /// algorithm, dropping vertices less than this distance from the
+    /// simplified shape, in the units of the output (optional)
+    #[arg(long, value_parser = validate_positive)]
+    pub simplify: Option<f64>,
+
+    /// Add vertices to generated lines and polygon rings so that no segment
+    /// is longer than this, in the units of the output (optional)
+    #[arg(long, value_parser = validate_positive)]
+    pub densify: Option<f64>,
+
+    /// Round every coordinate to the nearest multiple of this step, in the
+    /// units of the output, e.g. 0.01 degrees, so that nearby vertices
+    /// coincide (optional)
+    #[arg(long, value_parser = validate_positive)]
+    pub snap_grid: Option<f64>,
+
+    /// Share of generated features to make invalid, from 0 to 1, for fuzzing
+    /// GeoJSON readers; each gets an "invalid" property naming what was
+    /// broken (optional, defaults to 0)
+    #[arg(long, default_value_t = 0.0, value_parser = validate_probability)]
+    pub invalid_rate: f64,
+
+    /// Share of features, from 0 to 1, given a null geometry, which RFC 7946
+    /// allows (optional, defaults to 0)
+    #[arg(long, default_value_t = 0.0, value_parser = validate_probability)]
+    pub null_geometry_rate: f64,
+
+    /// Share of features, from 0 to 1, given null properties, which RFC 7946
+    /// allows (optional, defaults to 0)
+    #[arg(long, default_value_t = 0.0, value_parser = validate_probability)]
+    pub null_properties_rate: f64,
+
+    /// Share of features, from 0 to 1, written without an id, which RFC 7946
+    /// allows (optional, defaults to 0)
+    #[arg(long, default_value_t = 0.0, value_parser = validate_probability)]
+    pub missing_id_rate: f64,
+
+    /// Share of features, from 0 to 1, reusing the id of an earlier feature
+    /// (optional, defaults to 0)
+    #[arg(long, default_value_t = 0.0, value_parser = validate_probability)]
+    pub duplicate_id_rate: f64,
+
+    /// Comma-separated ways of making features invalid with --invalid-rate
+    /// (optional, defaults to all of them)
+    /// Possible values: "unclosed-ring", "self-intersection",
+    /// "duplicate-points", "empty-coordinates", "null-geometry",
+    /// "out-of-range", "non-finite"
+    #[arg(long, value_delimiter = ',', requires = "invalid_rate")]
+    pub invalid_kinds: Vec<Malformation>,
+
+    /// Side length of the cells of a --coverage grid, e.g. "500m", "2km" or
+    /// "0.1deg" (optional, defaults to a size giving about --length cells)
+    #[arg(long, requires = "coverage")]
+    pub cell_size: Option<Distance>,
+
+    /// Number of features per anchored group: a building, then alternating
+    /// entrances and access paths (optional, defaults to 5)
+    #[arg(long, default_value_t = 5, value_parser = validate_zero_or_more)]
+    pub features_per_anchor: usize,
+}
+
+impl FeatureArgs {
+    // Whether the features are random geometries of --geometry-type rather
+    // than those of a mode that overrides it.
+    fn uses_geometry_type(&self) -> bool {
+        self.anchored_groups.is_none()
+            && self.coverage.is_none()
+            && !self.trajectory
+            && self.snapshots.is_none()
+            && !self.network
+            && self.edge_cases.is_empty()
+            && self.stress_vertices.is_none()
+            && self.regions.is_none()
+            && self.preset.is_none()
+    }
+
+    // The coordinate system to generate in, restricted by any masks.
+    fn crs(&self) -> RandomGeojsonResult<Crs> {
+        let mut crs: Crs = self.coordinate_system.parse()?;
+        if let Some(mask) = &self.within {
+            crs = crs.within(mask.clone())?;
+        }
+        if self.land_only {
+            crs = crs.within(land::land())?;
+        }
+        if let Some(mask) = self.country.as_ref().or(self.continent.as_ref()) {
+            crs = crs.within(mask.clone())?;
+        }
+        if let Some(mask) = &self.exclude {
+            crs = crs.excluding(mask.clone())?;
+        }
+        // Weights propose the coordinates that masks then accept
+        if let Some(grid) = &self.density_weights {
+            crs = crs.weighted(grid.clone())?;
+        }
+        Ok(crs)
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Generate random features and write them to a file, the default when
+    /// no subcommand is given
+    Generate(GenerateArgs),
+    /// Check assertions against a GeoJSON FeatureCollection.
+    /// Exits with 0 if all assertions hold, 1 if any fails, 2 for invalid
+    /// arguments, 3 if the file cannot be read and 4 if it is not valid GeoJSON
+    Validate(ValidateArgs),
+    /// Apply random noise to the coordinates of an existing GeoJSON file,
+    /// optionally shuffling property values between features
+    Perturb(PerturbArgs),
+    /// Write a uniform random sample of the features of an existing GeoJSON
+    /// file, streamed so huge files fit in memory
+    Sample(SampleArgs),
+    /// Replace every property value of an existing GeoJSON file with random
+    /// data of the same type, keeping the geometries
+    Anonymize(AnonymizeArgs),
+    /// Concatenate the features of several GeoJSON files into one
+    Merge(MergeArgs),
+    /// Summarize a GeoJSON file: feature count, geometry types, bbox,
+    /// property keys and types, and size
+    Stats(StatsArgs),
+    /// Measure how fast features of each geometry type are generated and
+    /// encoded in each format, in memory without writing anything
+    Bench(BenchArgs),
+    /// Serve freshly generated FeatureCollections over HTTP at
+    /// "/random?length=100&geometry_type=Point&bbox=-10,40,10,55"; the
+    /// generation options act as defaults for parameters a request omits
+    Serve(ServeArgs),
+    /// Stream features moving along random trajectories over a WebSocket,
+    /// one GeoJSON Feature per message, to simulate vehicle or IoT feeds
+    Stream(StreamArgs),
+    /// Print a script completing the subcommands and flags of this tool in
+    /// a shell, e.g. "random-geojson completions bash > ~/.bash_completion"
+    Completions(CompletionsArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ValidateArgs {
+    /// GeoJSON file to check (".gz" and ".zst" files are decompressed)
+    pub file: String,
+
+    /// Assertion to check (repeatable), e.g. "count>=1000",
+    /// "bbox within -10,40,10,55", "types in Point,Polygon" or
+    /// "property name exists"
+    #[arg(long = "assert")]
+    pub assertions: Vec<Assertion>,
+}
+
+#[derive(Args, Debug)]
+pub struct PerturbArgs {
+    /// GeoJSON file to perturb: a FeatureCollection, a Feature or
+    /// newline-delimited Features (".gz" and ".zst" files are decompressed,
+    /// "-" reads stdin)
+    pub file: String,
+
+    /// Largest offset added to each coordinate, in the units of the file,
+    /// e.g. 0.001 degrees (optional, defaults to 0.0001)
+    #[arg(long, default_value_t = 0.0001, value_parser = validate_non_negative)]
+    pub jitter: f64,
+
+    /// Shuffle the values of each property between features (optional,
+    /// defaults to false)
+    #[arg(long, default_value_t = false)]
+    pub shuffle_properties: bool,
+
+    #[command(flatten)]
+    pub output: FileOutputArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct AnonymizeArgs {
+    /// GeoJSON file to anonymize: a FeatureCollection, a Feature or
+    /// newline-delimited Features (".gz" and ".zst" files are decompressed,
+    /// "-" reads stdin)
+    pub file: String,
+
+    /// Also remove feature ids (optional, defaults to false)
+    #[arg(long, default_value_t = false)]
+    pub drop_ids: bool,
+
+    #[command(flatten)]
+    pub output: FileOutputArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct MergeArgs {
+    /// GeoJSON files to merge, in order: FeatureCollections, Features or
+    /// newline-delimited Features (".gz" and ".zst" files are decompressed,
+    /// "-" reads stdin)
+    #[arg(required = true)]
+    pub files: Vec<String>,
+
+    /// What to do with a feature whose id an earlier feature already has
+    /// (optional, defaults to "keep")
+    /// Possible values: "keep", "error", "skip", "regenerate"
+    #[arg(long, default_value = "keep")]
+    pub duplicate_ids: DuplicateIds,
+
+    #[command(flatten)]
+    pub output: FileOutputArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    /// GeoJSON file to summarize: a FeatureCollection or newline-delimited
+    /// Features, read as a stream (".gz" and ".zst" files are decompressed,
+    /// "-" reads stdin)
+    pub file: String,
+
+    /// Print the summary as JSON (optional, defaults to false)
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct CompletionsArgs {
+    /// Shell to complete in
+    /// Possible values: "bash", "zsh", "fish", "powershell"
+    pub shell: Shell,
+}
+
+#[derive(Args, Debug)]
+pub struct BenchArgs {
+    /// Comma-separated formats to encode the features in (optional,
+    /// defaults to every format but the tiled "mvt" and "pmtiles")
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_value = "geojson,fgb,geoparquet,gpkg,shp,csv,topojson,kml,kmz,gpx,sql,wkt,wkb"
+    )]
+    pub formats: Vec<Format>,
+
+    /// Number of times each measurement is taken, keeping the fastest
+    /// (optional, defaults to 3)
+    #[arg(long, default_value_t = 3, value_parser = validate_one_or_more)]
+    pub iterations: usize,
+
+    /// Print the results as JSON (optional, defaults to false)
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+
+    #[command(flatten)]
+    pub features: FeatureArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct SampleArgs {
+    /// GeoJSON file to sample: a FeatureCollection or newline-delimited
+    /// Features, read as a stream (".gz" and ".zst" files are decompressed,
+    /// "-" reads stdin)
+    pub file: String,
+
+    /// Keep each feature with this probability, e.g. 0.1
+    #[arg(long, value_parser = validate_fraction, required_unless_present = "count")]
+    pub fraction: Option<f64>,
+
+    /// Keep exactly this many features, chosen uniformly (all of them if the
+    /// file has fewer)
+    #[arg(long, conflicts_with = "fraction")]
+    pub count: Option<usize>,
+
+    #[command(flatten)]
+    pub output: FileOutputArgs,
+}
+
+/// Where the subcommands processing existing files write their result.
+#[derive(Args, Debug)]
+pub struct FileOutputArgs {
+    /// Output format (optional, defaults to "geojson")
+    #[arg(long, default_value = "geojson")]
+    pub format: Format,
+
+    /// Output GeoJSON or TopoJSON in pretty print (optional, defaults to false)
     #[arg(long, default_value_t = false)]
     pub pretty: bool,
 
-    // File name to save the generated GeoJSON (optional, defaults to "random.geojson")
-    #[arg(short, long, default_value = "random.geojson")]
+    /// Overwrite the output file if it already exists (optional, defaults to false)
+    #[arg(long, default_value_t = false)]
+    pub force: bool,
+
+    /// File to write the result to; ".gz" and ".zst" names are compressed
+    #[arg(short, long)]
     pub output_file: String,
 }
 
-fn main() -> RandomGeojsonResult<()> {
-    let cli = Cli::parse();
+#[derive(Args, Debug)]
+pub struct ServeArgs {
+    /// Address to listen on (optional, defaults to "127.0.0.1")
+    #[arg(long, default_value = "127.0.0.1")]
+    pub host: String,
 
-    let mut fc = FeatureCollection::default();
+    /// Port to listen on (optional, defaults to 8080)
+    #[arg(long, default_value_t = 8080)]
+    pub port: u16,
 
-    for _ in 0..cli.length {
-        let mut feature = Feature {
-            id: Some(Id::String(Uuid::new_v4().to_string())),
-            ..Default::default()
+    /// Number of requests handled concurrently (optional, defaults to 4)
+    #[arg(long, default_value_t = 4, value_parser = validate_one_or_more)]
+    pub threads: usize,
+
+    /// Largest "length" a request may ask for (optional, defaults to 10000)
+    #[arg(long, default_value_t = 10_000)]
+    pub max_length: usize,
+
+    #[command(flatten)]
+    pub features: FeatureArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct StreamArgs {
+    /// Address to listen on (optional, defaults to "127.0.0.1")
+    #[arg(long, default_value = "127.0.0.1")]
+    pub host: String,
+
+    /// Port to listen on (optional, defaults to 8081)
+    #[arg(long, default_value_t = 8081)]
+    pub port: u16,
+
+    /// Number of moving features sent to each client (optional, defaults to 10)
+    #[arg(long, default_value_t = 10, value_parser = validate_one_or_more)]
+    pub count: usize,
+
+    /// How often each feature reports its position, e.g. "2/s" or "30/m"
+    /// (optional, defaults to "1/s")
+    #[arg(long, default_value = "1/s")]
+    pub rate: Rate,
+
+    /// Keep the features within "minx,miny,maxx,maxy" in degrees, e.g.
+    /// "-0.2,51.4,0.1,51.6" (optional, defaults to the coordinate system bounds)
+    #[arg(long, allow_hyphen_values = true)]
+    pub bbox: Option<Bounds>,
+
+    /// Coordinate system to use (optional, defaults to "WGS84")
+    /// Possible values: "WGS84", "WebMercator", "4326", "3857" or a PROJ string such as "+proj=utm +zone=32"
+    #[arg(long, default_value = "WGS84", value_parser = validate_coordinate_system)]
+    pub coordinate_system: String,
+}
+
+fn main() {
+    let mut error_format = error_format_arg();
+    let result = parse_args(error_format).and_then(|matches| {
+        let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        error_format = cli.error_format;
+        run(&cli, &matches)
+    });
+    if let Err(error) = result {
+        error_format.report(&error);
+        std::process::exit(error.exit_code());
+    }
+}
+
+// Runs the subcommand given on the command line. `matches` are those of the
+// whole command line.
+fn run(cli: &Cli, matches: &ArgMatches) -> RandomGeojsonResult<()> {
+    logging::init(logging::level(cli.verbose, cli.quiet));
+
+    match &cli.command {
+        None => run_generate(&cli.generate, matches, cli.show_progress()),
+        Some(Command::Generate(args)) => {
+            let (_, matches) = matches.subcommand().expect("a subcommand was given");
+            run_generate(args, matches, cli.show_progress())
+        }
+        Some(Command::Validate(args)) => std::process::exit(validate(args)),
+        Some(Command::Perturb(args)) => run_perturb(args),
+        Some(Command::Sample(args)) => run_sample(args),
+        Some(Command::Anonymize(args)) => run_anonymize(args),
+        Some(Command::Merge(args)) => run_merge(args),
+        Some(Command::Stats(args)) => run_stats(args),
+        Some(Command::Bench(args)) => {
+            let (_, matches) = matches.subcommand().expect("a subcommand was given");
+            run_bench(args, matches)
+        }
+        Some(Command::Serve(args)) => serve_random(args),
+        Some(Command::Completions(args)) => {
+            print!("{}", args.shell.script(&Cli::command(), "random-geojson"));
+            Ok(())
+        }
+        Some(Command::Stream(args)) => stream(&StreamOptions {
+            address: format!("{}:{}", args.host, args.port),
+            coordinate_system: args.coordinate_system.clone(),
+            bbox: args.bbox,
+            count: args.count,
+            rate: args.rate,
+        }),
+    }
+}
+
+// Runs the generate subcommand. `matches` are those of its arguments.
+fn run_generate(
+    cli: &GenerateArgs,
+    matches: &ArgMatches,
+    show_progress: bool,
+) -> RandomGeojsonResult<()> {
+    // Metadata records a seed so the output can be generated again
+    let seed = cli
+        .seed
+        .or_else(|| cli.metadata.then(|| random::rng().random()));
+    match seed {
+        Some(seed) => {
+            info!("Seed: {}", seed);
+            random::with_seed(seed, || {
+                generate_output(cli, matches, show_progress, Some(seed))
+            })
+        }
+        None => generate_output(cli, matches, show_progress, None),
+    }
+}
+
+// Runs the generate subcommand with the random number generator seeded with
+// `seed`, if given.
+fn generate_output(
+    cli: &GenerateArgs,
+    matches: &ArgMatches,
+    show_progress: bool,
+    seed: Option<u64>,
+) -> RandomGeojsonResult<()> {
+    debug!("Options: {:?}", cli);
+    let compression_level = cli
+        .compress_level
+        .unwrap_or_else(|| cli.compress.default_level());
+    cli.compress.validate_level(compression_level)?;
+    let mut crs: Crs = cli.features.crs()?;
+    if let Some(tile) = cli.tile {
+        crs = crs.clipped(tile.bounds())?;
+    }
+    let output_options = OutputOptions {
+        format: cli.format,
+        encode: EncodeOptions {
+            pretty: cli.pretty,
+            canonical: cli.canonical,
+            epsg: crs.epsg(),
+            lon_lat: cli.lon_lat,
+            table: cli.table.clone(),
+            sql_mode: cli.sql_mode,
+            tile: cli.tile,
+            min_zoom: cli.minzoom,
+            max_zoom: cli.maxzoom,
+        },
+        compression: cli.compress,
+        compression_level,
+        force: cli.force,
+    };
+    validate_property_specs(&cli.features.property)?;
+    if cli.canonical && !matches!(cli.format, Format::GeoJson | Format::TopoJson) {
+        return Err(RandomGeojsonError::UnsupportedFormat(
+            "--canonical needs --format geojson or topojson".to_string(),
+        ));
+    }
+    if cli.features.invalid_rate > 0.0 && cli.format != Format::GeoJson {
+        return Err(RandomGeojsonError::UnsupportedFormat(
+            "--invalid-rate needs --format geojson, other formats cannot hold invalid geometries"
+                .to_string(),
+        ));
+    }
+    let output_file = cli
+        .output_file
+        .clone()
+        .unwrap_or_else(|| format!("random.{}", cli.format.extension()));
+
+    if cli.scaling_report {
+        return scaling_report(cli, &crs, &output_options);
+    }
+
+    if cli.dry_run {
+        return dry_run(cli, &crs, &output_options, &output_file);
+    }
+
+    if let Some(url) = &cli.post_url {
+        post::validate_url(url)?;
+        let limit = match cli.rate {
+            // Emit forever unless a length was asked for
+            Some(_) => (matches.value_source("length") == Some(ValueSource::CommandLine))
+                .then_some(cli.features.length),
+            None => Some(cli.features.length),
         };
+        return post_features(cli, &crs, &output_options, url, limit);
+    }
 
-        let crs: Crs = cli.coordinate_system.parse()?;
+    if let Some(rate) = cli.rate {
+        // Emit forever unless a length was asked for
+        let limit = (matches.value_source("length") == Some(ValueSource::CommandLine))
+            .then_some(cli.features.length);
+        return emit_ndjson(cli, &crs, rate, limit);
+    }
 
-        // Generate a random WGS84 coordinate
-        let geometry = match cli.geometry_type.to_lowercase().as_str() {
-            "point" => match RandomGeometry::random_point(&crs) {
-                RandomGeometry::Point(coords) => Geometry {
-                    bbox: None,
-                    value: Point(coords),
-                    foreign_members: None,
-                },
-                _ => unreachable!(),
-            },
-            "linestring" => match RandomGeometry::random_linestring(&crs) {
-                RandomGeometry::LineString(coords) => Geometry {
-                    bbox: None,
-                    value: LineString(coords),
-                    foreign_members: None,
-                },
-                _ => unreachable!(),
-            },
-            "polygon" => match RandomGeometry::random_polygon(&crs) {
-                RandomGeometry::Polygon(coords) => Geometry {
-                    bbox: None,
-                    value: Polygon(coords),
-                    foreign_members: None,
-                },
-                _ => unreachable!(),
-            },
-            "all" => {
-                let mut rng = rand::rng();
-                match rng.random_range(0..3) {
-                    0 => match RandomGeometry::random_point(&crs) {
-                        RandomGeometry::Point(coords) => Geometry {
-                            bbox: None,
-                            value: Point(coords),
-                            foreign_members: None,
-                        },
-                        _ => unreachable!(),
-                    },
-                    1 => match RandomGeometry::random_linestring(&crs) {
-                        RandomGeometry::LineString(coords) => Geometry {
-                            bbox: None,
-                            value: LineString(coords),
-                            foreign_members: None,
-                        },
-                        _ => unreachable!(),
-                    },
-                    2 => match RandomGeometry::random_polygon(&crs) {
-                        RandomGeometry::Polygon(coords) => Geometry {
-                            bbox: None,
-                            value: Polygon(coords),
-                            foreign_members: None,
-                        },
-                        _ => unreachable!(),
-                    },
-                    _ => unreachable!(),
+    let progress = Progress::new(cli.features.length, show_progress);
+    let start = Instant::now();
+    let mut fc = generate(&cli.features, &crs, None, &progress)?;
+    info!(
+        "Generated {} features in {:.2?}",
+        fc.features.len(),
+        start.elapsed()
+    );
+    cli.sort.sort(&mut fc, crs.epsg())?;
+    progress.message("Writing");
+    let metadata = seed.filter(|_| cli.metadata).map(|seed| {
+        let command = GenerateArgs::augment_args(clap::Command::new("generate"));
+        let mut options = config::settings(&command, matches);
+        options.insert("seed".to_string(), seed.to_string().into());
+        Metadata {
+            seed,
+            features: fc.features.len(),
+            options,
+        }
+    });
+
+    if cli.split_by_type {
+        let layers = split_by_type(&fc);
+        let written: usize = layers.iter().map(|(_, layer)| layer.features.len()).sum();
+        if written < fc.features.len() {
+            warn!(
+                "Left out {} features without a geometry",
+                fc.features.len() - written
+            );
+        }
+        let mut paths: Vec<String> = layers
+            .iter()
+            .map(|(layer, _)| cli.compress.output_path(&layer_path(&output_file, layer)))
+            .collect();
+        if metadata.is_some() {
+            paths.push(sidecar_path(&output_file));
+        }
+        check_overwrite(&paths, cli.force)?;
+        let mut bytes = 0;
+        for ((_, collection), output_file) in layers.iter().zip(&paths) {
+            bytes += save_to_file(collection, output_file, &output_options)?;
+        }
+        if let Some(metadata) = &metadata {
+            write_sidecar(metadata, &output_file, cli.force)?;
+        }
+        progress.finish(fc.features.len(), bytes);
+        return Ok(());
+    }
+
+    // Split the features into shards, each saved as its own FeatureCollection
+    let features_per_file = match (cli.features_per_file, cli.shards) {
+        (Some(n), _) => Some(n),
+        (None, Some(shards)) => Some(fc.features.len().div_ceil(shards).max(1)),
+        (None, None) => cli.snapshot_files.then_some(cli.features.length.max(1)),
+    };
+
+    if let Some(features_per_file) = features_per_file {
+        let chunks: Vec<_> = fc.features.chunks(features_per_file).collect();
+        let mut paths: Vec<String> = (1..=chunks.len())
+            .map(|i| cli.compress.output_path(&shard_path(&output_file, i)))
+            .collect();
+        if metadata.is_some() {
+            paths.push(sidecar_path(&output_file));
+        }
+        check_overwrite(&paths, cli.force)?;
+        let mut bytes = 0;
+        for (features, output_file) in chunks.into_iter().zip(&paths) {
+            let shard = FeatureCollection {
+                features: features.to_vec(),
+                ..Default::default()
+            };
+            bytes += save_to_file(&shard, output_file, &output_options)?;
+        }
+        if let Some(metadata) = &metadata {
+            write_sidecar(metadata, &output_file, cli.force)?;
+        }
+        progress.finish(fc.features.len(), bytes);
+        return Ok(());
+    }
+
+    // Save the generated GeoJSON to a file
+    let output_file = cli.compress.output_path(&output_file);
+    let sidecar = match &metadata {
+        Some(metadata) if cli.format == Format::GeoJson => {
+            metadata.embed(&mut fc);
+            None
+        }
+        metadata => metadata.as_ref(),
+    };
+    // Appending adds to an existing file, but never to an existing sidecar
+    let mut paths = Vec::new();
+    if !cli.append {
+        paths.push(output_file.clone());
+    }
+    if sidecar.is_some() {
+        paths.push(sidecar_path(&output_file));
+    }
+    check_overwrite(&paths, cli.force)?;
+    let bytes = if cli.append {
+        append_geojson_to_file(&fc, &output_file, &output_options)?
+    } else {
+        save_to_file(&fc, &output_file, &output_options)?
+    };
+    if let Some(metadata) = sidecar {
+        write_sidecar(metadata, &output_file, cli.force)?;
+    }
+    progress.finish(fc.features.len(), bytes);
+
+    Ok(())
+}
+
+// Writes the metadata of an output that cannot hold it next to it.
+fn write_sidecar(metadata: &Metadata, output_file: &str, force: bool) -> RandomGeojsonResult<()> {
+    let body = serde_json::to_vec_pretty(&metadata.to_json()).map_err(|e| {
+        RandomGeojsonError::Serialize(format!("Failed to serialize metadata: {}", e))
+    })?;
+    let upload = Upload {
+        body: &body,
+        content_type: "application/json",
+        content_encoding: None,
+        overwrite: force,
+    };
+    write_output(&sidecar_path(output_file), &upload)
+}
+
+// The --error-format given on the command line, read before parsing it so
+// that parsing errors are reported in that format too.
+fn error_format_arg() -> ErrorFormat {
+    let args: Vec<String> = std::env::args_os()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    args.iter()
+        .enumerate()
+        .find_map(|(i, arg)| match arg.strip_prefix("--error-format")? {
+            "" => args.get(i + 1).map(String::as_str),
+            value => value.strip_prefix('='),
+        })
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_default()
+}
+
+// Exits on an error parsing the command line, reported in `format` unless
+// it is asking for help or the version.
+fn exit_on_parse_error(error: clap::Error, format: ErrorFormat) -> ! {
+    let informational = matches!(
+        error.kind(),
+        ErrorKind::DisplayHelp
+            | ErrorKind::DisplayVersion
+            | ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+    );
+    if format == ErrorFormat::Text || informational {
+        error.exit();
+    }
+    let rendered = error.render().to_string();
+    let line = rendered.lines().next().unwrap_or_default();
+    let message = line.strip_prefix("error: ").unwrap_or(line);
+    // e.g. "--length <LENGTH>"
+    let flag = match error.get(ContextKind::InvalidArg) {
+        Some(ContextValue::String(arg)) => arg.split_whitespace().next(),
+        _ => None,
+    };
+    eprintln!("{}", error_json("invalid_argument", message, flag));
+    std::process::exit(error.exit_code());
+}
+
+// Parses the command line, taking the flags it leaves out from the --config
+// file if one is given.
+fn parse_args(error_format: ErrorFormat) -> RandomGeojsonResult<ArgMatches> {
+    let parse_error = |e| exit_on_parse_error(e, error_format);
+    let matches = Cli::command().try_get_matches().unwrap_or_else(parse_error);
+    let Some(path) = matches.get_one::<String>("config") else {
+        return Ok(matches);
+    };
+    let settings = config::read(path)?;
+    let mut args: Vec<OsString> = std::env::args_os().collect();
+    args.extend(
+        config::args(&Cli::command(), &matches, &settings)?
+            .into_iter()
+            .map(OsString::from),
+    );
+    Ok(Cli::command()
+        .try_get_matches_from(args)
+        .unwrap_or_else(parse_error))
+}
+
+// Generates the feature collection described by the CLI options. `size`
+// overrides the number of features to generate.
+fn generate(
+    cli: &FeatureArgs,
+    crs: &Crs,
+    size: Option<usize>,
+    progress: &Progress,
+) -> RandomGeojsonResult<FeatureCollection> {
+    if let Some(pool) = cli.sparse_properties
+        && pool < cli.num_properties.max
+    {
+        return Err(RandomGeojsonError::InvalidArgument(format!(
+            "--sparse-properties {} is smaller than the --num-properties of a feature, up to {}",
+            pool, cli.num_properties.max
+        )));
+    }
+    if let Some(wordlist) = &cli.wordlist {
+        use_wordlist(wordlist.clone());
+    }
+    use_languages(&cli.lang);
+    let mut fc = FeatureCollection::default();
+
+    if let Some(groups) = cli.anchored_groups {
+        let groups = size.map_or(groups, |n| n.div_ceil(cli.features_per_anchor.max(1)));
+        for anchor_id in 0..groups {
+            for site_feature in random_site(crs, cli.features_per_anchor) {
+                let mut properties = random_properties(cli).unwrap_or_default();
+                properties.insert("anchor_id".to_string(), anchor_id.into());
+                properties.insert("role".to_string(), site_feature.role.as_str().into());
+                fc.features
+                    .push(new_feature(site_feature.geometry, Some(properties)));
+            }
+        }
+        if let Some(n) = size {
+            fc.features.truncate(n);
+        }
+    } else if let Some(coverage) = cli.coverage {
+        for geometry in coverage.generate(crs, size.unwrap_or(cli.length), cli.cell_size)? {
+            let properties = random_properties(cli);
+            fc.features.push(new_feature(geometry, properties));
+        }
+    } else if cli.trajectory {
+        // --length counts traces, a requested size counts features
+        for track_id in 0.. {
+            if size.map_or(track_id == cli.length, |n| fc.features.len() >= n) {
+                break;
+            }
+            let fixes = random_trajectory(crs, cli.speed, cli.interval);
+            let extra = random_properties(cli).unwrap_or_default();
+            if cli.trajectory_points {
+                for (sequence, fix) in fixes.iter().enumerate() {
+                    let (geometry, mut properties) = fix_point(crs, track_id, sequence, fix);
+                    properties.extend(extra.clone());
+                    fc.features.push(new_feature(geometry, Some(properties)));
                 }
+            } else {
+                let (geometry, mut properties) = trajectory_linestring(crs, &fixes);
+                properties.extend(extra);
+                fc.features.push(new_feature(geometry, Some(properties)));
+            }
+        }
+        if let Some(n) = size {
+            fc.features.truncate(n);
+        }
+    } else if let Some(snapshots) = cli.snapshots {
+        let objects = size.map_or(cli.length, |n| n.div_ceil(snapshots));
+        let moments = moving_points(crs, objects, snapshots, cli.snapshot_step);
+        // Properties other than t stay the same for an object at every moment
+        let properties: Vec<JsonObject> = (0..objects)
+            .map(|object_id| {
+                let mut properties = random_properties(cli).unwrap_or_default();
+                properties.insert("object_id".to_string(), object_id.into());
+                properties
+            })
+            .collect();
+        for (t, points) in moments.into_iter().enumerate() {
+            for (point, properties) in points.into_iter().zip(&properties) {
+                let mut properties = properties.clone();
+                properties.insert("t".to_string(), t.into());
+                fc.features.push(new_feature(point, Some(properties)));
+            }
+        }
+        if let Some(n) = size {
+            fc.features.truncate(n);
+        }
+    } else if cli.network {
+        for edge in random_network(crs, size.unwrap_or(cli.length)) {
+            let mut properties = random_properties(cli);
+            if cli.node_ids {
+                let properties = properties.get_or_insert_default();
+                properties.insert("from_node".to_string(), edge.from_node.into());
+                properties.insert("to_node".to_string(), edge.to_node.into());
             }
+            fc.features.push(new_feature(edge.geometry, properties));
+        }
+    } else if !cli.edge_cases.is_empty() {
+        if !matches!(crs, Crs::WGS84) {
+            return Err(RandomGeojsonError::InvalidArgument(
+                "--edge-cases covers the whole globe in WGS84 and cannot be combined with another coordinate system or area"
+                    .to_string(),
+            ));
+        }
+        for feature in edge_cases(&cli.edge_cases, size.unwrap_or(cli.length)) {
+            let mut properties = random_properties(cli).unwrap_or_default();
+            properties.insert("edge_case".to_string(), feature.edge_case.as_str().into());
+            properties.insert("fixture".to_string(), feature.fixture.into());
+            let geometry = geojson::Geometry::new(feature.geometry);
+            fc.features.push(new_feature(geometry, Some(properties)));
+        }
+    } else if let Some(vertices) = cli.stress_vertices {
+        let geometries = match cli.geometry_type.to_lowercase().as_str() {
+            "linestring" => vec![stress_linestring(crs, vertices)],
+            "polygon" => vec![stress_polygon(crs, vertices)],
+            "all" => vec![
+                stress_linestring(crs, vertices),
+                stress_polygon(crs, vertices),
+            ],
             _ => {
                 return Err(RandomGeojsonError::InvalidArgument(
-                    "Invalid geometry type".to_string(),
+                    "--stress-vertices needs --geometry-type LineString, Polygon or All"
+                        .to_string(),
                 ));
             }
         };
+        for geometry in geometries {
+            let properties = random_properties(cli);
+            fc.features.push(new_feature(geometry, properties));
+        }
+    } else if let Some(regions) = cli.regions {
+        // A requested size counts the regions and points together
+        let points = size.map_or(cli.length, |n| n.saturating_sub(regions));
+        let layers = random_regions(crs, regions, points);
+        let polygons = layers.polygons.into_iter().enumerate();
+        let polygons = polygons.map(|(region_id, polygon)| ("regions", region_id, polygon));
+        let points = layers.points.into_iter();
+        let points = points.map(|(point, region_id)| ("points", region_id, point));
+        for (layer, region_id, geometry) in polygons.chain(points) {
+            let mut properties = random_properties(cli).unwrap_or_default();
+            properties.insert("layer".to_string(), layer.into());
+            properties.insert("region_id".to_string(), region_id.into());
+            fc.features.push(new_feature(geometry, Some(properties)));
+        }
+        if let Some(n) = size {
+            fc.features.truncate(n);
+        }
+    } else if let Some(preset) = cli.preset {
+        for feature in preset.generate(crs, size.unwrap_or(cli.length)) {
+            let mut properties = feature.properties;
+            if let Some(extra) = random_properties(cli) {
+                properties.extend(extra);
+            }
+            fc.features
+                .push(new_feature(feature.geometry, Some(properties)));
+        }
+    } else {
+        let area = SizeRange::new(cli.min_area, cli.max_area)?;
+        if let Some(area) = &area {
+            area.check_area(crs)?;
+        }
+        let mut placement = Placement {
+            spacing: cli.min_distance.map(PointSpacing::new),
+            buffer: cli.buffer,
+            polygons: cli.no_overlap.then(PolygonIndex::new),
+            shape: PolygonShape {
+                style: cli.polygon_style,
+                radius: cli.radius,
+                vertices: cli.vertices,
+                concavity: cli.concavity,
+                area,
+            },
+            line: LineShape {
+                style: cli.line_style,
+                roughness: cli.roughness,
+                walk: cli.walk,
+                length: SizeRange::new(cli.min_line_length, cli.max_line_length)?,
+            },
+        };
+        for _ in 0..size.unwrap_or(cli.length) {
+            let geometry = random_geometry(&cli.geometry_type, crs, &mut placement)?;
+            let properties = random_properties(cli);
 
-        feature.geometry = Some(geometry);
+            // Add the feature to the feature collection
+            fc.features.push(new_feature(geometry, properties));
+            progress.set(fc.features.len());
+        }
+    }
+    progress.set(fc.features.len());
 
-        // Generate random properties
-        if cli.num_properties > 0 {
-            let mut properties = JsonObject::new();
+    if let Some(tolerance) = cli.simplify {
+        simplify(&mut fc, tolerance);
+    }
+    if let Some(max_length) = cli.densify {
+        densify(&mut fc, max_length);
+    }
+    if let Some(step) = cli.snap_grid {
+        snap_to_grid(&mut fc, step);
+    }
+    add_computed_properties(&mut fc, &cli.computed_properties.concat(), crs.epsg())?;
+    finish_properties(&mut fc, &cli.property)?;
+    if let Some(script) = &cli.generators {
+        let generators: Vec<&dyn PropertyGenerator> =
+            script.iter().map(|g| g as &dyn PropertyGenerator).collect();
+        add_generated_properties(&mut fc, &generators);
+    }
+    if cli.duplicate_id_rate > 0.0 {
+        duplicate_ids(&mut fc, cli.duplicate_id_rate);
+    }
+    let missing = MissingMembers {
+        geometry: cli.null_geometry_rate,
+        properties: cli.null_properties_rate,
+        id: cli.missing_id_rate,
+    };
+    if !missing.is_none() {
+        missing.apply(&mut fc);
+    }
+    if cli.invalid_rate > 0.0 {
+        let kinds = if cli.invalid_kinds.is_empty() {
+            &MALFORMATIONS[..]
+        } else {
+            &cli.invalid_kinds
+        };
+        inject(&mut fc, cli.invalid_rate, kinds, &crs.extent());
+    }
+
+    Ok(fc)
+}
 
-            for i in 1..=cli.num_properties {
-                let key = format!("prop{}", i);
-                let value = random_property_value();
-                properties.insert(key, value);
+// Sends generated features to `url` in batches of --batch-size, paced at
+// --rate if given, and prints a summary to stderr.
+fn post_features(
+    cli: &GenerateArgs,
+    crs: &Crs,
+    output_options: &OutputOptions,
+    url: &str,
+    limit: Option<usize>,
+) -> RandomGeojsonResult<()> {
+    let poster = Poster::new(PostOptions {
+        url: url.to_string(),
+        concurrency: cli.concurrency,
+        retries: cli.retries,
+        content_type: cli.format.media_type(),
+        content_encoding: cli.compress.content_encoding(),
+    });
+    let start = Instant::now();
+    let send = |features: Vec<Feature>| -> RandomGeojsonResult<()> {
+        let n = features.len();
+        let fc = FeatureCollection {
+            features,
+            ..Default::default()
+        };
+        poster.send(serialize(&fc, output_options)?, n);
+        Ok(())
+    };
+
+    match cli.rate {
+        Some(rate) => {
+            // Send full batches, or whatever is pending once a second so slow
+            // rates still reach the endpoint promptly
+            let mut pending = Vec::new();
+            let mut last_send = Instant::now();
+            pace(rate, limit, |n| {
+                pending
+                    .extend(generate(&cli.features, crs, Some(n), &Progress::hidden())?.features);
+                while pending.len() >= cli.batch_size {
+                    let rest = pending.split_off(cli.batch_size);
+                    send(std::mem::replace(&mut pending, rest))?;
+                    last_send = Instant::now();
+                }
+                if !pending.is_empty() && last_send.elapsed() >= Duration::from_secs(1) {
+                    send(std::mem::take(&mut pending))?;
+                    last_send = Instant::now();
+                }
+                Ok(())
+            })?;
+            if !pending.is_empty() {
+                send(pending)?;
+            }
+        }
+        None => {
+            let length = limit.unwrap_or(cli.features.length);
+            for batch_start in (0..length).step_by(cli.batch_size) {
+                let n = cli.batch_size.min(length - batch_start);
+                send(generate(&cli.features, crs, Some(n), &Progress::hidden())?.features)?;
             }
+        }
+    }
 
-            feature.properties = Some(properties);
+    let stats = poster.finish();
+    eprintln!(
+        "Posted {} features in {} batches ({} failed, {} retries) in {:.1} s",
+        stats.features,
+        stats.batches,
+        stats.failed_batches,
+        stats.retries,
+        start.elapsed().as_secs_f64()
+    );
+    if stats.failed_batches > 0 {
+        return Err(RandomGeojsonError::Network(format!(
+            "{} of {} batches could not be posted",
+            stats.failed_batches, stats.batches
+        )));
+    }
+    Ok(())
+}
+
+// Writes generated features to stdout as NDJSON, one feature per line, paced
+// at `rate`. Stops quietly when stdout is closed, e.g. by `head`.
+fn emit_ndjson(
+    cli: &GenerateArgs,
+    crs: &Crs,
+    rate: Rate,
+    limit: Option<usize>,
+) -> RandomGeojsonResult<()> {
+    if cli.format != Format::GeoJson || cli.output_file.is_some() {
+        return Err(RandomGeojsonError::InvalidArgument(
+            "--rate writes NDJSON to stdout, so it cannot be combined with --format or --output-file"
+                .to_string(),
+        ));
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    let mut closed = false;
+    let result = pace(rate, limit, |n| {
+        let mut lines = Vec::new();
+        for feature in generate(&cli.features, crs, Some(n), &Progress::hidden())?.features {
+            serde_json::to_writer(&mut lines, &feature).expect("features serialize");
+            lines.push(b'\n');
         }
+        stdout
+            .write_all(&lines)
+            .and_then(|_| stdout.flush())
+            .map_err(|e| {
+                closed = e.kind() == std::io::ErrorKind::BrokenPipe;
+                RandomGeojsonError::Io(format!("Failed to write to stdout: {}", e))
+            })
+    });
+    if closed { Ok(()) } else { result }
+}
 
-        // Add the feature to the feature collection
-        fc.features.push(feature);
+// Generates the dataset at each of the scaling report sizes and prints the
+// output size and wall time of every run.
+fn scaling_report(
+    cli: &GenerateArgs,
+    crs: &Crs,
+    output_options: &OutputOptions,
+) -> RandomGeojsonResult<()> {
+    let formats = if cli.scaling_formats.is_empty() {
+        vec![cli.format]
+    } else {
+        cli.scaling_formats.clone()
+    };
+
+    println!(
+        "{:<12} {:>10} {:>14} {:>14} {:>14} {:>10}",
+        "format", "features", "bytes", "generate_ms", "serialize_ms", "bytes/feat"
+    );
+    for &size in &cli.scaling_sizes {
+        let start = Instant::now();
+        let fc = generate(&cli.features, crs, Some(size), &Progress::hidden())?;
+        let generated = start.elapsed();
+
+        for &format in &formats {
+            let options = OutputOptions {
+                format,
+                ..output_options.clone()
+            };
+            let start = Instant::now();
+            let bytes = serialize(&fc, &options)?;
+            let serialized = start.elapsed();
+
+            let name = match options.compression.extension() {
+                Some(ext) => format!("{}.{}", format.extension(), ext),
+                None => format.extension().to_string(),
+            };
+            println!(
+                "{:<12} {:>10} {:>14} {:>14.1} {:>14.1} {:>10.1}",
+                name,
+                fc.features.len(),
+                bytes.len(),
+                generated.as_secs_f64() * 1000.0,
+                serialized.as_secs_f64() * 1000.0,
+                bytes.len() as f64 / fc.features.len().max(1) as f64
+            );
+        }
     }
 
-    // Save the generated GeoJSON to a file
-    if cli.pretty {
-        save_geojson_to_file(&fc, &cli.output_file, true)?;
+    Ok(())
+}
+
+// Largest number of features or vertices generated to estimate a dry run.
+const DRY_RUN_SAMPLE: usize = 1000;
+
+// Prints what the generate subcommand would do, estimating the number of
+// features and the output size from a sample generated with the same options.
+fn dry_run(
+    cli: &GenerateArgs,
+    crs: &Crs,
+    output_options: &OutputOptions,
+    output_file: &str,
+) -> RandomGeojsonResult<()> {
+    let (sample_args, scale) = dry_run_sample(&cli.features);
+    let fc = generate(&sample_args, crs, None, &Progress::hidden())?;
+    let bytes = serialize(&fc, output_options)?.len();
+    let features = if cli.features.stress_vertices.is_some() {
+        fc.features.len()
+    } else {
+        (fc.features.len() as f64 * scale).round() as usize
+    };
+    let mut stats = Stats::default();
+    fc.features.iter().for_each(|f| stats.add(f));
+
+    println!("Dry run, nothing was written");
+    println!("{:<20} {}", "mode", generation_mode(&cli.features));
+    println!("{:<20} {}", "features", features);
+    let name = match output_options.compression.extension() {
+        Some(ext) => format!("{}.{}", cli.format.extension(), ext),
+        None => cli.format.extension().to_string(),
+    };
+    println!("{:<20} {}", "format", name);
+    let files = match (cli.features_per_file, cli.shards) {
+        (Some(n), _) => features.div_ceil(n).max(1),
+        (None, Some(shards)) => shards.min(features.max(1)),
+        (None, None) if cli.snapshot_files => cli.features.snapshots.unwrap_or(1),
+        (None, None) => 1,
+    };
+    if files > 1 {
+        println!(
+            "{:<20} {} .. {} ({} files)",
+            "output",
+            cli.compress.output_path(&shard_path(output_file, 1)),
+            cli.compress.output_path(&shard_path(output_file, files)),
+            files
+        );
+    } else {
+        let path = cli.compress.output_path(output_file);
+        let exists = !cli.force && std::path::Path::new(&path).exists();
+        println!(
+            "{:<20} {}{}",
+            "output",
+            path,
+            if exists {
+                " (exists, needs --force)"
+            } else {
+                ""
+            }
+        );
+    }
+    let bounds = crs.bounds();
+    println!(
+        "{:<20} {}, {}, {}, {}",
+        "bounds (degrees)", bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y
+    );
+    let extent = crs.extent();
+    let epsg = crs
+        .epsg()
+        .map_or("custom".to_string(), |code| format!("EPSG:{}", code));
+    println!(
+        "{:<20} {}, {}, {}, {}",
+        format!("extent ({})", epsg),
+        extent.min_x,
+        extent.min_y,
+        extent.max_x,
+        extent.max_y
+    );
+    println!(
+        "{:<20} {} (from a sample of {} features)",
+        "estimated size",
+        human_bytes((bytes as f64 * scale).round() as usize),
+        fc.features.len()
+    );
+    println!("geometry types");
+    for (name, count) in &stats.geometry_types {
+        let share = 100.0 * *count as f64 / stats.features.max(1) as f64;
+        println!("  {:<20} {:>5.1}%", name, share);
+    }
+    println!("properties");
+    for (key, types) in &stats.properties {
+        let types: Vec<&str> = types.keys().copied().collect();
+        println!("  {:<20} {}", key, types.join(", "));
+    }
+    Ok(())
+}
+
+// Options generating a smaller dataset like the one described by `cli`, and
+// how many times larger the output of `cli` is than that of the sample.
+fn dry_run_sample(cli: &FeatureArgs) -> (FeatureArgs, f64) {
+    let mut sample = cli.clone();
+    let scale = if let Some(vertices) = cli.stress_vertices {
+        sample.stress_vertices = Some(vertices.min(DRY_RUN_SAMPLE));
+        vertices as f64 / vertices.min(DRY_RUN_SAMPLE) as f64
+    } else if let Some(groups) = cli.anchored_groups {
+        let sample_groups = groups.min((DRY_RUN_SAMPLE / cli.features_per_anchor.max(1)).max(1));
+        sample.anchored_groups = Some(sample_groups);
+        groups as f64 / sample_groups.max(1) as f64
+    } else if cli.coverage.is_some() && cli.cell_size.is_some() {
+        // The cell size alone sets the number of cells
+        1.0
+    } else {
+        sample.length = cli.length.min(DRY_RUN_SAMPLE);
+        cli.length as f64 / sample.length.max(1) as f64
+    };
+    (sample, scale)
+}
+
+// Describes what kind of features the options generate.
+fn generation_mode(cli: &FeatureArgs) -> String {
+    if let Some(groups) = cli.anchored_groups {
+        format!(
+            "{} anchored groups of {} features",
+            groups, cli.features_per_anchor
+        )
+    } else if let Some(coverage) = cli.coverage {
+        format!("{} coverage", coverage.as_str())
+    } else if cli.trajectory {
+        let unit = if cli.trajectory_points {
+            "fixes"
+        } else {
+            "traces"
+        };
+        format!("trajectory {} of {} traces", unit, cli.length)
+    } else if let Some(snapshots) = cli.snapshots {
+        format!("{} points over {} snapshots", cli.length, snapshots)
+    } else if cli.network {
+        format!("road network between {} junctions", cli.length)
+    } else if !cli.edge_cases.is_empty() {
+        let cases: Vec<&str> = cli.edge_cases.iter().map(|c| c.as_str()).collect();
+        format!("edge cases ({})", cases.join(", "))
+    } else if let Some(vertices) = cli.stress_vertices {
+        format!("stress geometries of {} vertices", vertices)
+    } else if let Some(regions) = cli.regions {
+        format!("{} points in {} regions", cli.length, regions)
+    } else if let Some(preset) = cli.preset {
+        format!("{} preset", preset.as_str())
+    } else {
+        format!("random {}", cli.geometry_type)
+    }
+}
+
+// Number of features benchmarked unless --length is given.
+const BENCH_LENGTH: usize = 10_000;
+
+// Runs the bench subcommand: generates the features of each geometry type,
+// or of the --geometry-type or mode given, and encodes them in each format,
+// printing the throughput of both. `matches` are those of its arguments.
+fn run_bench(args: &BenchArgs, matches: &ArgMatches) -> RandomGeojsonResult<()> {
+    validate_property_specs(&args.features.property)?;
+    let crs = args.features.crs()?;
+    let mut features = args.features.clone();
+    if matches.value_source("length") != Some(ValueSource::CommandLine) {
+        features.length = BENCH_LENGTH;
+    }
+    let runs: Vec<FeatureArgs> = if features.uses_geometry_type()
+        && matches.value_source("geometry_type") != Some(ValueSource::CommandLine)
+    {
+        ["Point", "LineString", "Polygon"]
+            .into_iter()
+            .map(|geometry_type| FeatureArgs {
+                geometry_type: geometry_type.to_string(),
+                ..features.clone()
+            })
+            .collect()
+    } else {
+        vec![features]
+    };
+    let encode_options = EncodeOptions {
+        epsg: crs.epsg(),
+        ..Default::default()
+    };
+
+    let mut results = Vec::new();
+    if !args.json {
+        println!(
+            "{:<24} {:<10} {:>10} {:>12} {:>14} {:>14} {:>10}",
+            "generator", "format", "features", "bytes", "generate/s", "encode/s", "MB/s"
+        );
+    }
+    for run in &runs {
+        let mut fc = FeatureCollection::default();
+        let generated = fastest(args.iterations, || {
+            fc = generate(run, &crs, None, &Progress::hidden())?;
+            Ok(())
+        })?;
+        let count = fc.features.len();
+        let mode = generation_mode(run);
+        for &format in &args.formats {
+            let mut bytes = 0;
+            let encoded = fastest(args.iterations, || {
+                bytes = format.encode(&fc, &encode_options)?.len();
+                Ok(())
+            })?;
+            let rate = |n: f64, time: Duration| n / time.as_secs_f64().max(1e-9);
+            let generate_rate = rate(count as f64, generated);
+            let encode_rate = rate(count as f64, encoded);
+            let megabytes = rate(bytes as f64 / 1e6, encoded);
+            if args.json {
+                results.push(serde_json::json!({
+                    "generator": mode,
+                    "format": format.extension(),
+                    "features": count,
+                    "bytes": bytes,
+                    "generate_per_sec": generate_rate,
+                    "encode_per_sec": encode_rate,
+                    "encode_mb_per_sec": megabytes,
+                }));
+            } else {
+                println!(
+                    "{:<24} {:<10} {:>10} {:>12} {:>14.0} {:>14.0} {:>10.1}",
+                    mode,
+                    format.extension(),
+                    count,
+                    bytes,
+                    generate_rate,
+                    encode_rate,
+                    megabytes
+                );
+            }
+        }
+    }
+    if args.json {
+        println!("{:#}", serde_json::Value::Array(results));
+    }
+    Ok(())
+}
+
+// Runs `f` `iterations` times and returns the shortest time it took.
+fn fastest(
+    iterations: usize,
+    mut f: impl FnMut() -> RandomGeojsonResult<()>,
+) -> RandomGeojsonResult<Duration> {
+    let mut best = Duration::MAX;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        f()?;
+        best = best.min(start.elapsed());
+    }
+    Ok(best)
+}
+
+// Runs the serve subcommand, generating a new feature collection per request.
+fn serve_random(args: &ServeArgs) -> RandomGeojsonResult<()> {
+    debug!("Options: {:?}", args);
+    validate_property_specs(&args.features.property)?;
+    let crs: Crs = args.features.crs()?;
+    let options = ServeOptions {
+        address: format!("{}:{}", args.host, args.port),
+        threads: args.threads,
+        max_length: args.max_length,
+        collection_size: args.features.length,
+        extent: crs.bounds(),
+        epsg: crs.epsg(),
+    };
+    serve(&options, |params| {
+        let mut generate_args = args.features.clone();
+        if let Some(length) = params.length {
+            generate_args.length = length;
+        }
+        if let Some(geometry_type) = &params.geometry_type {
+            generate_args.geometry_type = validate_geometry_type(geometry_type)?;
+        }
+        if let Some(num_properties) = params.num_properties {
+            generate_args.num_properties = num_properties.into();
+        }
+        let mut crs: Crs = generate_args.crs()?;
+        if let Some(bbox) = params.bbox {
+            crs = crs.clipped(bbox)?;
+        }
+        let start = Instant::now();
+        let fc = generate(&generate_args, &crs, None, &Progress::hidden())?;
+        info!(
+            "Generated {} features for a request in {:.2?}",
+            fc.features.len(),
+            start.elapsed()
+        );
+        Ok(fc)
+    })
+}
+
+// Runs the perturb subcommand.
+fn run_perturb(args: &PerturbArgs) -> RandomGeojsonResult<()> {
+    let mut fc = read_features(&args.file)?;
+    perturb(
+        &mut fc,
+        &PerturbOptions {
+            jitter: args.jitter,
+            shuffle_properties: args.shuffle_properties,
+        },
+    );
+    write_features(&fc, &args.output)
+}
+
+// Runs the sample subcommand.
+fn run_sample(args: &SampleArgs) -> RandomGeojsonResult<()> {
+    let size = match (args.count, args.fraction) {
+        (Some(count), _) => SampleSize::Count(count),
+        (None, Some(fraction)) => SampleSize::Fraction(fraction),
+        (None, None) => unreachable!("clap requires --count or --fraction"),
+    };
+    let mut sampler = Sampler::new(size);
+    for_each_feature(&args.file, |feature| sampler.push(feature))?;
+    eprintln!("Sampled from {} features", sampler.seen());
+    let fc = FeatureCollection {
+        features: sampler.finish(),
+        ..Default::default()
+    };
+    write_features(&fc, &args.output)
+}
+
+// Runs the anonymize subcommand.
+fn run_anonymize(args: &AnonymizeArgs) -> RandomGeojsonResult<()> {
+    let mut fc = read_features(&args.file)?;
+    anonymize(&mut fc, args.drop_ids);
+    write_features(&fc, &args.output)
+}
+
+// Runs the merge subcommand.
+fn run_merge(args: &MergeArgs) -> RandomGeojsonResult<()> {
+    let collections = args
+        .files
+        .iter()
+        .map(|file| read_features(file))
+        .collect::<RandomGeojsonResult<Vec<_>>>()?;
+    let fc = merge(collections, args.duplicate_ids)?;
+    write_features(&fc, &args.output)
+}
+
+// Runs the stats subcommand.
+fn run_stats(args: &StatsArgs) -> RandomGeojsonResult<()> {
+    let mut stats = Stats {
+        size: std::fs::metadata(&args.file).ok().map(|m| m.len()),
+        ..Default::default()
+    };
+    for_each_feature(&args.file, |feature| stats.add(&feature))?;
+    if args.json {
+        println!("{:#}", stats.to_json());
     } else {
-        save_geojson_to_file(&fc, &cli.output_file, false)?;
+        print!("{}", stats);
     }
+    Ok(())
+}
 
+// Saves the result of a subcommand processing an existing file.
+fn write_features(fc: &FeatureCollection, output: &FileOutputArgs) -> RandomGeojsonResult<()> {
+    let compression = Compression::from_path(&output.output_file);
+    let output_options = OutputOptions {
+        format: output.format,
+        encode: EncodeOptions {
+            pretty: output.pretty,
+            // GeoJSON coordinates are WGS84
+            epsg: Some(4326),
+            ..Default::default()
+        },
+        compression,
+        compression_level: compression.default_level(),
+        force: output.force,
+    };
+    save_to_file(fc, &output.output_file, &output_options)?;
     Ok(())
 }
 
+// Runs the validate subcommand, returning the process exit code.
+fn validate(args: &ValidateArgs) -> i32 {
+    let bytes = match std::fs::read(&args.file) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", args.file, e);
+            return EXIT_IO;
+        }
+    };
+    let fc = Compression::from_path(&args.file)
+        .decompress(&bytes)
+        .and_then(|bytes| {
+            serde_json::from_slice::<GeoJson>(&bytes)
+                .map_err(|e| RandomGeojsonError::InvalidInput(e.to_string()))
+        })
+        .and_then(|geojson| {
+            FeatureCollection::try_from(geojson).map_err(|_| {
+                RandomGeojsonError::InvalidInput("not a FeatureCollection".to_string())
+            })
+        });
+    let fc = match fc {
+        Ok(fc) => fc,
+        Err(e) => {
+            eprintln!("Invalid GeoJSON in {}: {}", args.file, e);
+            return EXIT_SERIALIZE;
+        }
+    };
+
+    let mut failed = false;
+    for assertion in &args.assertions {
+        if let Err(reason) = assertion.check(&fc) {
+            eprintln!("FAIL {}: {}", assertion, reason);
+            failed = true;
+        }
+    }
+
+    if failed { EXIT_ASSERTION_FAILED } else { 0 }
+}
+
+// How the geometries of a collection are laid out, and where the ones
+// generated so far are, for those generated next to keep their distance.
+struct Placement {
+    spacing: Option<PointSpacing>,
+    buffer: Option<RadiusRange>,
+    polygons: Option<PolygonIndex>,
+    shape: PolygonShape,
+    line: LineShape,
+}
+
+impl Placement {
+    fn point(&mut self, crs: &Crs) -> RandomGeojsonResult<RandomGeometry> {
+        if let Some(radius) = &self.buffer {
+            return random_buffer(crs, self.spacing.as_mut(), radius);
+        }
+        match &mut self.spacing {
+            Some(spacing) => spacing.random_point(crs),
+            None => Ok(RandomGeometry::random_point(crs)),
+        }
+    }
+
+    fn linestring(&self, crs: &Crs) -> RandomGeojsonResult<RandomGeometry> {
+        self.line.random_linestring(crs)
+    }
+
+    fn polygon(&mut self, crs: &Crs) -> RandomGeojsonResult<RandomGeometry> {
+        match &mut self.polygons {
+            Some(polygons) => polygons.random_polygon(crs, &self.shape),
+            None => self.shape.random_polygon(crs),
+        }
+    }
+}
+
+// Generates a random geometry of the requested type.
+fn random_geometry(
+    geometry_type: &str,
+    crs: &Crs,
+    placement: &mut Placement,
+) -> RandomGeojsonResult<RandomGeometry> {
+    let geometry = match geometry_type.to_lowercase().as_str() {
+        "point" => placement.point(crs)?,
+        "linestring" => placement.linestring(crs)?,
+        "polygon" => placement.polygon(crs)?,
+        "all" => {
+            let mut rng = random::rng();
+            match rng.random_range(0..3) {
+                0 => placement.point(crs)?,
+                1 => placement.linestring(crs)?,
+                2 => placement.polygon(crs)?,
+                _ => unreachable!(),
+            }
+        }
+        _ => {
+            return Err(RandomGeojsonError::InvalidArgument(
+                "Invalid geometry type".to_string(),
+            ));
+        }
+    };
+    Ok(geometry)
+}
+
+// Generates the random properties of --property-types or a number of them
+// drawn from --num-properties, followed by the user-defined ones, or none if
+// there can be neither.
+fn random_properties(cli: &FeatureArgs) -> Option<JsonObject> {
+    let specs = &cli.property;
+    if cli.num_properties.max == 0 && cli.property_types.is_none() && specs.is_empty() {
+        return None;
+    }
+
+    let mut properties = JsonObject::new();
+    if let Some(types) = &cli.property_types {
+        types.add_to(&mut properties);
+    }
+    let count = cli.num_properties.draw();
+    let keys = match cli.sparse_properties {
+        Some(pool) => sparse_keys(pool, count),
+        None => (1..=count).collect(),
+    };
+    for i in keys {
+        let key = format!("prop{}", i);
+        let value = random_property_value();
+        properties.insert(key, value);
+    }
+    for spec in specs {
+        spec.add_to(&mut properties);
+    }
+    Some(properties)
+}
+
+// Picks `count` distinct numbers from 1 to `pool` at random, in increasing
+// order.
+fn sparse_keys(pool: usize, count: usize) -> Vec<usize> {
+    let mut keys = rand::seq::index::sample(&mut random::rng(), pool, count.min(pool)).into_vec();
+    keys.sort_unstable();
+    keys.into_iter().map(|i| i + 1).collect()
+}
+
+// Builds a feature with a random UUID.
+fn new_feature(geometry: impl Into<geojson::Geometry>, properties: Option<JsonObject>) -> Feature {
+    Feature {
+        id: Some(Id::String(random_uuid().to_string())),
+        geometry: Some(geometry.into()),
+        properties,
+        ..Default::default()
+    }
+}
+
+// Validates that the value is enough vertices for a polygon.
+fn validate_polygon_vertices(value: &str) -> RandomGeojsonResult<usize> {
+    value
+        .parse::<usize>()
+        .ok()
+        .filter(|&n| n >= 3)
+        .ok_or_else(|| {
+            RandomGeojsonError::InvalidArgument("A polygon needs at least 3 vertices".to_string())
+        })
+}
+
+// Validates that the value is in [0, 1).
+fn validate_concavity(value: &str) -> RandomGeojsonResult<f64> {
+    match value.parse::<f64>() {
+        Ok(n) if (0.0..1.0).contains(&n) => Ok(n),
+        _ => Err(RandomGeojsonError::InvalidArgument(
+            "Value must be at least 0 and less than 1".to_string(),
+        )),
+    }
+}
+
 // Validates that the value is zero or more.
 fn validate_zero_or_more(value: &str) -> RandomGeojsonResult<usize> {
     value
@@ -170,45 +2177,86 @@ fn validate_geometry_type(value: &str) -> RandomGeojsonResult<String> {
 fn validate_coordinate_system(value: &str) -> RandomGeojsonResult<String> {
     match value.to_lowercase().as_str() {
         "wgs84" | "webmercator" | "4326" | "3857" => Ok(value.to_string()),
+        v if v.starts_with("+proj=") => Ok(value.to_string()),
         _ => Err(RandomGeojsonError::InvalidArgument(
-            "Coordinate system must be one of: WGS84, WebMercator, 4326, 3857".to_string(),
+            "Coordinate system must be one of: WGS84, WebMercator, 4326, 3857 or a PROJ string"
+                .to_string(),
         )),
     }
 }
 
-fn random_property_value() -> serde_json::Value {
-    let mut rng = rand::rng();
-    match rng.random_range(0..3) {
-        0 => serde_json::Value::Number(rng.random_range(0..1000).into()),
-        1 => serde_json::Value::String(
-            (0..rng.random_range(3..10))
-                .map(|_| random_word::get(random_word::Lang::En))
-                .collect::<Vec<_>>()
-                .join(" "),
-        ),
-        2 => serde_json::Value::Bool(rng.random_bool(0.5)),
-        _ => unreachable!(),
+fn read_mask(path: &str) -> RandomGeojsonResult<Arc<Mask>> {
+    Mask::read(path).map(Arc::new)
+}
+
+fn read_density_grid(path: &str) -> RandomGeojsonResult<Arc<DensityGrid>> {
+    DensityGrid::read(path).map(Arc::new)
+}
+
+fn read_generators(path: &str) -> RandomGeojsonResult<Arc<Vec<ScriptGenerator>>> {
+    read_script(path).map(Arc::new)
+}
+
+fn read_wordlist(path: &str) -> RandomGeojsonResult<Arc<Wordlist>> {
+    Wordlist::read(path).map(Arc::new)
+}
+
+// Validates that the value is a number of 0 or more.
+fn validate_non_negative(value: &str) -> RandomGeojsonResult<f64> {
+    match value.parse::<f64>() {
+        Ok(n) if n >= 0.0 && n.is_finite() => Ok(n),
+        _ => Err(RandomGeojsonError::InvalidArgument(
+            "Value must be a non-negative number".to_string(),
+        )),
     }
 }
 
-// Saves the generated GeoJSON feature collection to a file.
-fn save_geojson_to_file(
-    fc: &FeatureCollection,
-    file_path: &str,
-    pretty: bool,
-) -> RandomGeojsonResult<()> {
-    let geojson_string = if pretty {
-        serde_json::to_string_pretty(fc).map_err(|e| {
-            RandomGeojsonError::InvalidArgument(format!("Failed to serialize GeoJSON: {}", e))
-        })?
-    } else {
-        serde_json::to_string(fc).map_err(|e| {
-            RandomGeojsonError::InvalidArgument(format!("Failed to serialize GeoJSON: {}", e))
-        })?
-    };
+// Validates that the value is a number greater than 0.
+fn validate_positive(value: &str) -> RandomGeojsonResult<f64> {
+    match value.parse::<f64>() {
+        Ok(n) if n > 0.0 && n.is_finite() => Ok(n),
+        _ => Err(RandomGeojsonError::InvalidArgument(
+            "Value must be a positive number".to_string(),
+        )),
+    }
+}
+
+// Validates that the value is a distance greater than 0.
+fn validate_positive_distance(value: &str) -> RandomGeojsonResult<Distance> {
+    match value.parse::<Distance>()? {
+        Distance::Meters(0.0) | Distance::Degrees(0.0) => Err(RandomGeojsonError::InvalidArgument(
+            "Distance must be greater than 0".to_string(),
+        )),
+        distance => Ok(distance),
+    }
+}
 
-    std::fs::write(file_path, geojson_string)
-        .map_err(|e| RandomGeojsonError::InvalidArgument(format!("Failed to write file: {}", e)))?;
+// Validates that the value is in [0, 1].
+fn validate_probability(value: &str) -> RandomGeojsonResult<f64> {
+    match value.parse::<f64>() {
+        Ok(n) if (0.0..=1.0).contains(&n) => Ok(n),
+        _ => Err(RandomGeojsonError::InvalidArgument(
+            "Value must be at least 0 and at most 1".to_string(),
+        )),
+    }
+}
 
-    Ok(())
+// Validates that the value is in (0, 1].
+fn validate_fraction(value: &str) -> RandomGeojsonResult<f64> {
+    match value.parse::<f64>() {
+        Ok(n) if n > 0.0 && n <= 1.0 => Ok(n),
+        _ => Err(RandomGeojsonError::InvalidArgument(
+            "Value must be greater than 0 and at most 1".to_string(),
+        )),
+    }
+}
+
+// Validates that the value is one or more.
+fn validate_one_or_more(value: &str) -> RandomGeojsonResult<usize> {
+    match value.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(RandomGeojsonError::InvalidArgument(
+            "Value must be one or more".to_string(),
+        )),
+    }
 }
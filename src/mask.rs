@@ -0,0 +1,228 @@
+use geojson::GeoJson;
+use rand::Rng;
+
+use crate::compression::Compression;
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::geometry::{Bounds, Unit};
+use crate::random;
+
+type Ring = Vec<Vec<f64>>;
+
+/// An area made of polygons, in geographic coordinates, used to constrain
+/// where coordinates are sampled.
+#[derive(Debug)]
+pub struct Mask {
+    // Each polygon is its rings, exterior first, with its bounding box
+    polygons: Vec<(Vec<Ring>, Bounds)>,
+    bounds: Bounds,
+}
+
+impl Mask {
+    /// Creates a mask from polygons given as rings, exterior first. Polygons
+    /// without a usable exterior ring are ignored.
+    pub fn new(polygons: Vec<Vec<Ring>>) -> RandomGeojsonResult<Self> {
+        let polygons: Vec<_> = polygons
+            .into_iter()
+            .filter_map(|rings| {
+                let bounds = ring_bounds(rings.first()?)?;
+                Some((rings, bounds))
+            })
+            .collect();
+        let bounds = polygons
+            .iter()
+            .map(|(_, b)| *b)
+            .reduce(|a, b| {
+                Bounds::new(
+                    a.min_x.min(b.min_x),
+                    a.min_y.min(b.min_y),
+                    a.max_x.max(b.max_x),
+                    a.max_y.max(b.max_y),
+                    Unit::Degrees,
+                )
+            })
+            .ok_or_else(|| RandomGeojsonError::InvalidInput("Mask has no polygons".to_string()))?;
+        Ok(Mask { polygons, bounds })
+    }
+
+    /// Reads the Polygons and MultiPolygons of a GeoJSON file, which may be a
+    /// FeatureCollection, a Feature or a bare geometry in WGS84. `.gz` and
+    /// `.zst` files are decompressed.
+    pub fn read(path: &str) -> RandomGeojsonResult<Self> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| RandomGeojsonError::Io(format!("Failed to read {}: {}", path, e)))?;
+        let bytes = Compression::from_path(path).decompress(&bytes)?;
+        let geojson = serde_json::from_slice::<GeoJson>(&bytes).map_err(|e| {
+            RandomGeojsonError::InvalidInput(format!("Invalid GeoJSON in {}: {}", path, e))
+        })?;
+        Mask::from_geojson(geojson).map_err(|_| {
+            RandomGeojsonError::InvalidInput(format!(
+                "Mask {} has no Polygon or MultiPolygon geometries",
+                path
+            ))
+        })
+    }
+
+    /// Creates a mask from the Polygons and MultiPolygons of a GeoJSON value.
+    pub fn from_geojson(geojson: GeoJson) -> RandomGeojsonResult<Self> {
+        let mut polygons = Vec::new();
+        match geojson {
+            GeoJson::Geometry(geometry) => collect_polygons(geometry.value, &mut polygons),
+            GeoJson::Feature(feature) => feature
+                .geometry
+                .into_iter()
+                .for_each(|g| collect_polygons(g.value, &mut polygons)),
+            GeoJson::FeatureCollection(fc) => fc
+                .features
+                .into_iter()
+                .filter_map(|f| f.geometry)
+                .for_each(|g| collect_polygons(g.value, &mut polygons)),
+        }
+        Mask::new(polygons)
+    }
+
+    /// Extent of all the polygons.
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    /// Draws a coordinate within `bounds` and the bounding box of one of the
+    /// polygons, or `None` if it was rejected. Candidates that turn out to
+    /// be inside the mask are uniformly distributed over its part within
+    /// `bounds`.
+    pub fn candidate(&self, bounds: &Bounds) -> Option<(f64, f64)> {
+        let boxes: Vec<Bounds> = self
+            .polygons
+            .iter()
+            .filter_map(|(_, b)| b.intersection(bounds))
+            .collect();
+        let total: f64 = boxes.iter().map(|b| b.width() * b.height()).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut rng = random::rng();
+        let mut pick = rng.random_range(0.0..total);
+        let chosen = boxes
+            .iter()
+            .find(|b| {
+                pick -= b.width() * b.height();
+                pick < 0.0
+            })
+            .unwrap_or(&boxes[boxes.len() - 1]);
+        let x = rng.random_range(chosen.min_x..chosen.max_x);
+        let y = rng.random_range(chosen.min_y..chosen.max_y);
+        // Where boxes overlap, a coordinate could be proposed by each of them
+        let overlapping = boxes.iter().filter(|b| b.contains(x, y)).count();
+        (overlapping <= 1 || rng.random_range(0..overlapping) == 0).then_some((x, y))
+    }
+
+    /// Returns true if the coordinate lies inside one of the polygons and
+    /// outside its holes.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        self.polygons.iter().any(|(rings, bounds)| {
+            bounds.contains(x, y)
+                && ring_contains(&rings[0], x, y)
+                && !rings[1..].iter().any(|hole| ring_contains(hole, x, y))
+        })
+    }
+}
+
+fn collect_polygons(value: geojson::Value, polygons: &mut Vec<Vec<Ring>>) {
+    match value {
+        geojson::Value::Polygon(rings) => polygons.push(rings),
+        geojson::Value::MultiPolygon(more) => polygons.extend(more),
+        geojson::Value::GeometryCollection(geometries) => geometries
+            .into_iter()
+            .for_each(|g| collect_polygons(g.value, polygons)),
+        _ => {}
+    }
+}
+
+fn ring_bounds(ring: &Ring) -> Option<Bounds> {
+    if ring.len() < 3 {
+        return None;
+    }
+    let mut bounds = Bounds::new(
+        ring[0][0],
+        ring[0][1],
+        ring[0][0],
+        ring[0][1],
+        Unit::Degrees,
+    );
+    for p in ring {
+        bounds.min_x = bounds.min_x.min(p[0]);
+        bounds.min_y = bounds.min_y.min(p[1]);
+        bounds.max_x = bounds.max_x.max(p[0]);
+        bounds.max_y = bounds.max_y.max(p[1]);
+    }
+    Some(bounds)
+}
+
+/// Even-odd ray casting; the ring may or may not repeat its first position.
+pub(crate) fn ring_contains(ring: &[Vec<f64>], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    let mut j = ring.len() - 1;
+    for i in 0..ring.len() {
+        let (a, b) = (&ring[i], &ring[j]);
+        if (a[1] > y) != (b[1] > y) && x < (b[0] - a[0]) * (y - a[1]) / (b[1] - a[1]) + a[0] {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(min: f64, max: f64) -> Ring {
+        vec![
+            vec![min, min],
+            vec![max, min],
+            vec![max, max],
+            vec![min, max],
+            vec![min, min],
+        ]
+    }
+
+    #[test]
+    fn test_contains() {
+        let mask = Mask::new(vec![
+            vec![square(0.0, 10.0), square(4.0, 6.0)],
+            vec![square(20.0, 30.0)],
+        ])
+        .unwrap();
+        assert!(mask.contains(1.0, 1.0));
+        assert!(!mask.contains(5.0, 5.0));
+        assert!(mask.contains(25.0, 21.0));
+        assert!(!mask.contains(15.0, 15.0));
+        assert_eq!(
+            mask.bounds(),
+            Bounds::new(0.0, 0.0, 30.0, 30.0, Unit::Degrees)
+        );
+        assert!(Mask::new(vec![vec![vec![vec![0.0, 0.0]]]]).is_err());
+    }
+
+    #[test]
+    fn test_candidate() {
+        // Two overlapping squares, which must not make their overlap denser
+        let mask = Mask::new(vec![vec![square(0.0, 2.0)], vec![square(1.0, 3.0)]]).unwrap();
+        let everywhere = Bounds::new(-10.0, -10.0, 10.0, 10.0, Unit::Degrees);
+        let (mut overlap, mut total) = (0, 0);
+        while total < 7000 {
+            let Some((x, y)) = mask.candidate(&everywhere) else {
+                continue;
+            };
+            assert!(mask.contains(x, y));
+            total += 1;
+            if (1.0..=2.0).contains(&x) && (1.0..=2.0).contains(&y) {
+                overlap += 1;
+            }
+        }
+        // The overlap is 1 of the 7 square degrees covered
+        assert!((800..1200).contains(&overlap), "{}", overlap);
+
+        let elsewhere = Bounds::new(5.0, 5.0, 6.0, 6.0, Unit::Degrees);
+        assert!(mask.candidate(&elsewhere).is_none());
+    }
+}
@@ -0,0 +1,309 @@
+use rand::Rng;
+
+use crate::anchor::{METERS_PER_DEGREE, offset};
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::geometry::{Crs, random_geographic_coords};
+use crate::random;
+use crate::spacing::{Distance, EARTH_RADIUS};
+
+/// Rescaling passes of a shape at one position.
+const MAX_RESCALES: usize = 5;
+
+/// Positions and target sizes tried before a shape is deemed not to fit.
+const MAX_FITS: usize = 10;
+
+/// A range of real-world sizes of generated shapes: areas in square metres
+/// or lengths in metres. Either end may be open.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizeRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl SizeRange {
+    /// Creates a range from its optional ends, or `None` if neither is given.
+    pub fn new(min: Option<f64>, max: Option<f64>) -> RandomGeojsonResult<Option<Self>> {
+        if let (Some(min), Some(max)) = (min, max)
+            && min > max
+        {
+            return Err(RandomGeojsonError::InvalidArgument(format!(
+                "Minimum size {} is larger than maximum size {}",
+                min, max
+            )));
+        }
+        if max == Some(0.0) {
+            return Err(RandomGeojsonError::InvalidArgument(
+                "Maximum size must be larger than 0".to_string(),
+            ));
+        }
+        Ok((min.is_some() || max.is_some()).then_some(SizeRange { min, max }))
+    }
+
+    /// Checks that areas in the range fit in the bounds of `crs`.
+    pub fn check_area(&self, crs: &Crs) -> RandomGeojsonResult<()> {
+        let bounds = crs.bounds();
+        let largest = (bounds.max_x - bounds.min_x).to_radians()
+            * (bounds.max_y.to_radians().sin() - bounds.min_y.to_radians().sin())
+            * EARTH_RADIUS
+            * EARTH_RADIUS;
+        match self.min {
+            Some(min) if min > largest => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Minimum area {} m² is larger than the area to generate in, {:.0} m²",
+                min, largest
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn contains(&self, size: f64) -> bool {
+        self.min.is_none_or(|min| size >= min) && self.max.is_none_or(|max| size <= max)
+    }
+
+    // A size drawn log-uniformly within the range, or within a factor of ten
+    // of its only end, so small and large shapes are equally common.
+    fn target(&self) -> f64 {
+        let (low, high) = match (self.min, self.max) {
+            (Some(min), Some(max)) => (min, max),
+            (Some(min), None) => (min, min * 10.0),
+            (None, Some(max)) => (max / 10.0, max),
+            (None, None) => unreachable!("a size range has at least one end"),
+        };
+        let low = if low > 0.0 { low } else { high / 10.0 };
+        if low >= high {
+            return high;
+        }
+        10f64.powf(random::rng().random_range(low.log10()..=high.log10()))
+    }
+
+    /// Returns a polygon outline, given as an open ring of geographic
+    /// coordinates, with an area within the range. Outlines outside it are
+    /// rescaled around their mean vertex to a random area within it and
+    /// moved to a random position in `crs`. Fails if no rescaled outline
+    /// is within the range.
+    pub fn fit_area(
+        &self,
+        crs: &Crs,
+        ring: Vec<(f64, f64)>,
+    ) -> RandomGeojsonResult<Vec<(f64, f64)>> {
+        self.fit(crs, ring, 2.0, ring_area)
+    }
+
+    /// Returns a line of geographic coordinates with a length within the
+    /// range, rescaled and moved like [`SizeRange::fit_area`].
+    pub fn fit_length(
+        &self,
+        crs: &Crs,
+        line: Vec<(f64, f64)>,
+    ) -> RandomGeojsonResult<Vec<(f64, f64)>> {
+        self.fit(crs, line, 1.0, line_length)
+    }
+
+    // Scaling by a factor k changes the size by k to the power `dimension`.
+    // The shape is scaled in local metres, where the curvature of the Earth
+    // makes the size slightly off, so it is measured and scaled again.
+    fn fit(
+        &self,
+        crs: &Crs,
+        shape: Vec<(f64, f64)>,
+        dimension: f64,
+        measure: fn(&[(f64, f64)]) -> f64,
+    ) -> RandomGeojsonResult<Vec<(f64, f64)>> {
+        let size = measure(&shape);
+        if self.contains(size) || size <= 0.0 || shape.is_empty() {
+            return Ok(shape);
+        }
+        let n = shape.len() as f64;
+        let (lon, lat) = shape
+            .iter()
+            .fold((0.0, 0.0), |(x, y), p| (x + p.0 / n, y + p.1 / n));
+        let local: Vec<(f64, f64)> = shape
+            .iter()
+            .map(|p| {
+                (
+                    (p.0 - lon) * METERS_PER_DEGREE * lat.to_radians().cos(),
+                    (p.1 - lat) * METERS_PER_DEGREE,
+                )
+            })
+            .collect();
+        for _ in 0..MAX_FITS {
+            let target = self.target();
+            let (lon, lat) = random_geographic_coords(crs);
+            let mut scale = (target / size).powf(1.0 / dimension);
+            for _ in 0..MAX_RESCALES {
+                let placed: Vec<(f64, f64)> = local
+                    .iter()
+                    .map(|&(east, north)| offset(lon, lat, east * scale, north * scale))
+                    .collect();
+                let size = measure(&placed);
+                if self.contains(size) {
+                    return Ok(placed);
+                }
+                if size <= 0.0 {
+                    break;
+                }
+                scale *= (target / size).powf(1.0 / dimension);
+            }
+        }
+        Err(RandomGeojsonError::InvalidArgument(format!(
+            "Could not fit a shape of size {:.0} into the size range after {} attempts, widen the range",
+            size, MAX_FITS
+        )))
+    }
+}
+
+/// Area in square metres of a ring of geographic coordinates on a spherical
+/// Earth, whether or not it repeats its first position and whichever way it
+/// winds. Self-intersecting rings count the parts they wind around in
+/// opposite directions against each other.
+pub fn ring_area(ring: &[(f64, f64)]) -> f64 {
+    let total: f64 = (0..ring.len())
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            let dlon = (b.0 - a.0 + 540.0).rem_euclid(360.0) - 180.0;
+            dlon.to_radians() * (2.0 + a.1.to_radians().sin() + b.1.to_radians().sin())
+        })
+        .sum();
+    (total * EARTH_RADIUS * EARTH_RADIUS / 2.0).abs()
+}
+
+/// Length in metres of a line of geographic coordinates along great
+/// circles.
+pub fn line_length(line: &[(f64, f64)]) -> f64 {
+    line.windows(2)
+        .map(|w| {
+            let (a, b) = (w[0], w[1]);
+            let (lat1, lat2) = (a.1.to_radians(), b.1.to_radians());
+            let h = ((lat2 - lat1) / 2.0).sin().powi(2)
+                + lat1.cos() * lat2.cos() * ((b.0 - a.0).to_radians() / 2.0).sin().powi(2);
+            2.0 * EARTH_RADIUS * h.sqrt().min(1.0).asin()
+        })
+        .sum()
+}
+
+/// Parses an area such as "1000", "1000m2", "5ha" or "2km2"; a bare number
+/// is in square metres.
+pub fn parse_area(s: &str) -> RandomGeojsonResult<f64> {
+    let s = s.trim();
+    let (number, unit) = s
+        .find(|c: char| c.is_ascii_alphabetic())
+        .map_or((s, ""), |i| s.split_at(i));
+    let invalid = || {
+        RandomGeojsonError::InvalidArgument(format!(
+            "Invalid area: {}, expected e.g. \"1000m2\", \"5ha\" or \"2km2\"",
+            s
+        ))
+    };
+    let value: f64 = number.trim().parse().map_err(|_| invalid())?;
+    if !value.is_finite() || value < 0.0 {
+        return Err(invalid());
+    }
+    match unit.to_lowercase().as_str() {
+        "" | "m2" | "m²" => Ok(value),
+        "ha" => Ok(value * 10_000.0),
+        "km2" | "km²" => Ok(value * 1_000_000.0),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parses a length such as "500m" or "2km"; a bare number is in metres.
+pub fn parse_length(s: &str) -> RandomGeojsonResult<f64> {
+    match s.parse::<Distance>()? {
+        Distance::Meters(meters) => Ok(meters),
+        Distance::Degrees(_) => Err(RandomGeojsonError::InvalidArgument(format!(
+            "Invalid length: {}, expected metres, e.g. \"500m\" or \"2km\"",
+            s
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::{Bounds, Unit};
+
+    use super::*;
+
+    #[test]
+    fn test_ring_area() {
+        // A square degree at the equator is about 111 km by 111 km
+        let square = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.0, 0.0)];
+        let area = ring_area(&square);
+        assert!((area - 1.2364e10).abs() < 1e7, "{}", area);
+        let mut reversed = square;
+        reversed.reverse();
+        assert_eq!(ring_area(&reversed), area);
+        // Across the antimeridian
+        let across = [(179.5, 0.0), (-179.5, 0.0), (-179.5, 1.0), (179.5, 1.0)];
+        assert!((ring_area(&across) - area).abs() < 1e7);
+    }
+
+    #[test]
+    fn test_line_length() {
+        // A quarter of the equator
+        let length = line_length(&[(0.0, 0.0), (45.0, 0.0), (90.0, 0.0)]);
+        assert!((length - EARTH_RADIUS * std::f64::consts::FRAC_PI_2).abs() < 1e-6);
+        assert_eq!(line_length(&[(1.0, 1.0)]), 0.0);
+    }
+
+    #[test]
+    fn test_fit() {
+        let bounds = Bounds::new(-10.0, 40.0, 10.0, 60.0, Unit::Degrees);
+        let crs = Crs::WGS84.clipped(bounds).unwrap();
+        let areas = SizeRange::new(Some(900.0), Some(1100.0)).unwrap().unwrap();
+        let lengths = SizeRange::new(Some(100.0), None).unwrap().unwrap();
+        for _ in 0..100 {
+            // A simple ring, by the angle of its vertices around their mean,
+            // as self-intersecting ones cancel out most of their area
+            let mut ring: Vec<(f64, f64)> =
+                (0..5).map(|_| random_geographic_coords(&crs)).collect();
+            let (x, y) = ring
+                .iter()
+                .fold((0.0, 0.0), |(x, y), p| (x + p.0 / 5.0, y + p.1 / 5.0));
+            ring.sort_by(|a, b| {
+                (a.1 - y)
+                    .atan2(a.0 - x)
+                    .total_cmp(&(b.1 - y).atan2(b.0 - x))
+            });
+            assert!(areas.contains(ring_area(&areas.fit_area(&crs, ring).unwrap())));
+            let line = vec![(0.0, 50.0), (0.0, 50.0001)];
+            let length = line_length(&lengths.fit_length(&crs, line).unwrap());
+            assert!((100.0..=1000.0).contains(&length), "{}", length);
+        }
+        // In range already
+        let line = vec![(0.0, 50.0), (0.0, 51.0)];
+        assert_eq!(lengths.fit_length(&crs, line.clone()).unwrap(), line);
+        // Lines of a single position can't be stretched
+        let point = vec![(0.0, 50.0), (0.0, 50.0)];
+        assert_eq!(lengths.fit_length(&crs, point.clone()).unwrap(), point);
+    }
+
+    #[test]
+    fn test_check_area() {
+        let crs = Crs::WGS84;
+        let earth = SizeRange::new(Some(parse_area("600000000km2").unwrap()), None);
+        assert!(earth.unwrap().unwrap().check_area(&crs).is_err());
+        let world = SizeRange::new(Some(parse_area("500000000km2").unwrap()), None);
+        assert!(world.unwrap().unwrap().check_area(&crs).is_ok());
+        let bounds = Bounds::new(-10.0, 40.0, 10.0, 60.0, Unit::Degrees);
+        let crs = Crs::WGS84.clipped(bounds).unwrap();
+        let europe = SizeRange::new(Some(parse_area("10000000km2").unwrap()), None);
+        assert!(europe.unwrap().unwrap().check_area(&crs).is_err());
+        // Fits the bounds, but rescaling can't reach nearly the whole Earth
+        let areas = SizeRange::new(Some(4.5e14), Some(4.6e14)).unwrap().unwrap();
+        assert!(areas.check_area(&Crs::WGS84).is_ok());
+        let ring = vec![(0.0, 50.0), (1.0, 50.0), (1.0, 51.0), (0.0, 51.0)];
+        assert!(areas.fit_area(&Crs::WGS84, ring).is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_area("1000").unwrap(), 1000.0);
+        assert_eq!(parse_area("2 km2").unwrap(), 2_000_000.0);
+        assert_eq!(parse_area("1.5ha").unwrap(), 15_000.0);
+        assert_eq!(parse_area("10m²").unwrap(), 10.0);
+        assert!(parse_area("1 acre").is_err());
+        assert_eq!(parse_length("2km").unwrap(), 2000.0);
+        assert!(parse_length("1deg").is_err());
+        assert!(SizeRange::new(Some(2.0), Some(1.0)).is_err());
+        assert_eq!(SizeRange::new(None, None).unwrap(), None);
+    }
+}
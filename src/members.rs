@@ -0,0 +1,117 @@
+use geojson::FeatureCollection;
+use rand::Rng;
+
+use crate::random;
+
+/// Shares of features, each from 0 to 1, left without the members of a
+/// Feature that RFC 7946 lets be null or absent but many readers expect.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MissingMembers {
+    /// Features with `"geometry": null`.
+    pub geometry: f64,
+    /// Features with `"properties": null`.
+    pub properties: f64,
+    /// Features without an `id`.
+    pub id: f64,
+}
+
+impl MissingMembers {
+    /// Returns true if no member is ever dropped.
+    pub fn is_none(&self) -> bool {
+        self.geometry <= 0.0 && self.properties <= 0.0 && self.id <= 0.0
+    }
+
+    /// Drops the members of each feature of `fc` independently, each with
+    /// its own probability.
+    pub fn apply(&self, fc: &mut FeatureCollection) {
+        let mut rng = random::rng();
+        for feature in &mut fc.features {
+            if rng.random_bool(self.geometry) {
+                feature.geometry = None;
+                feature.bbox = None;
+            }
+            if rng.random_bool(self.properties) {
+                feature.properties = None;
+            }
+            if rng.random_bool(self.id) {
+                feature.id = None;
+            }
+        }
+    }
+}
+
+/// Gives each feature of `fc` after the first, with probability `rate`, the
+/// id of a random earlier feature, so that ids collide. Returns the number
+/// of features given a reused id.
+pub fn duplicate_ids(fc: &mut FeatureCollection, rate: f64) -> usize {
+    let mut rng = random::rng();
+    let mut duplicated = 0;
+    for i in 1..fc.features.len() {
+        if !rng.random_bool(rate) {
+            continue;
+        }
+        let earlier = rng.random_range(0..i);
+        if let Some(id) = fc.features[earlier].id.clone() {
+            fc.features[i].id = Some(id);
+            duplicated += 1;
+        }
+    }
+    duplicated
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Feature, Geometry, Value, feature::Id};
+
+    use super::*;
+
+    fn collection(n: usize) -> FeatureCollection {
+        let feature = Feature {
+            id: Some(Id::Number(1.into())),
+            geometry: Some(Geometry::new(Value::Point(vec![1.0, 2.0]))),
+            properties: Some(Default::default()),
+            ..Default::default()
+        };
+        FeatureCollection {
+            features: vec![feature; n],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_missing_members() {
+        let mut fc = collection(1000);
+        let missing = MissingMembers {
+            geometry: 0.1,
+            properties: 1.0,
+            id: 0.0,
+        };
+        assert!(!missing.is_none());
+        missing.apply(&mut fc);
+        let nulls = fc.features.iter().filter(|f| f.geometry.is_none()).count();
+        assert!((50..150).contains(&nulls), "{}", nulls);
+        assert!(fc.features.iter().all(|f| f.properties.is_none()));
+        assert!(fc.features.iter().all(|f| f.id.is_some()));
+
+        let json = serde_json::to_value(&fc.features[0]).unwrap();
+        assert_eq!(json["properties"], serde_json::Value::Null);
+        assert!(MissingMembers::default().is_none());
+    }
+
+    #[test]
+    fn test_duplicate_ids() {
+        let mut fc = collection(1000);
+        for (i, feature) in fc.features.iter_mut().enumerate() {
+            feature.id = Some(Id::Number(i.into()));
+        }
+        let duplicated = duplicate_ids(&mut fc, 0.1);
+        assert!((50..150).contains(&duplicated), "{}", duplicated);
+        let ids: std::collections::HashSet<_> = fc
+            .features
+            .iter()
+            .map(|f| serde_json::to_string(&f.id).unwrap())
+            .collect();
+        assert_eq!(ids.len(), 1000 - duplicated);
+        assert_eq!(duplicate_ids(&mut collection(1), 1.0), 0);
+    }
+}
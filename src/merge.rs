@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+
+use geojson::FeatureCollection;
+use geojson::feature::Id;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::random::random_uuid;
+
+/// What `merge` does with a feature whose id an earlier feature already has.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DuplicateIds {
+    /// Keep both features as they are.
+    #[default]
+    Keep,
+    /// Fail the merge.
+    Error,
+    /// Leave out the later feature.
+    Skip,
+    /// Give the later feature a new random UUID.
+    Regenerate,
+}
+
+impl std::str::FromStr for DuplicateIds {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "keep" => Ok(DuplicateIds::Keep),
+            "error" => Ok(DuplicateIds::Error),
+            "skip" => Ok(DuplicateIds::Skip),
+            "regenerate" => Ok(DuplicateIds::Regenerate),
+            _ => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid duplicate ids mode: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Concatenates the features of `collections`, in order, into a single
+/// collection, handling repeated feature ids as `duplicates` says.
+///
+/// Foreign members and bounding boxes of the inputs are not carried over.
+pub fn merge(
+    collections: impl IntoIterator<Item = FeatureCollection>,
+    duplicates: DuplicateIds,
+) -> RandomGeojsonResult<FeatureCollection> {
+    let mut seen = HashSet::new();
+    let mut features = Vec::new();
+    for fc in collections {
+        for mut feature in fc.features {
+            let duplicate = duplicates != DuplicateIds::Keep
+                && feature
+                    .id
+                    .as_ref()
+                    .is_some_and(|id| !seen.insert(id_key(id)));
+            if duplicate {
+                match duplicates {
+                    DuplicateIds::Keep => unreachable!(),
+                    DuplicateIds::Error => {
+                        return Err(RandomGeojsonError::InvalidInput(format!(
+                            "Duplicate feature id: {}",
+                            id_key(feature.id.as_ref().unwrap())
+                        )));
+                    }
+                    DuplicateIds::Skip => continue,
+                    DuplicateIds::Regenerate => {
+                        let id = Id::String(random_uuid().to_string());
+                        seen.insert(id_key(&id));
+                        feature.id = Some(id);
+                    }
+                }
+            }
+            features.push(feature);
+        }
+    }
+    Ok(FeatureCollection {
+        features,
+        bbox: None,
+        foreign_members: None,
+    })
+}
+
+// The id as JSON, which tells the string "1" and the number 1 apart.
+fn id_key(id: &Id) -> String {
+    match id {
+        Id::String(s) => serde_json::Value::from(s.as_str()).to_string(),
+        Id::Number(n) => n.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::Feature;
+
+    use super::*;
+
+    fn collection(ids: &[Id]) -> FeatureCollection {
+        FeatureCollection {
+            features: ids
+                .iter()
+                .map(|id| Feature {
+                    id: Some(id.clone()),
+                    ..Default::default()
+                })
+                .collect(),
+            bbox: None,
+            foreign_members: None,
+        }
+    }
+
+    fn merged_ids(duplicates: DuplicateIds) -> RandomGeojsonResult<Vec<Id>> {
+        let a = collection(&[Id::String("a".to_string()), Id::Number(1.into())]);
+        let b = collection(&[Id::String("1".to_string()), Id::String("a".to_string())]);
+        merge([a, b], duplicates).map(|fc| fc.features.into_iter().map(|f| f.id.unwrap()).collect())
+    }
+
+    #[test]
+    fn test_merge() {
+        assert_eq!(merged_ids(DuplicateIds::Keep).unwrap().len(), 4);
+        assert_eq!(
+            merged_ids(DuplicateIds::Skip).unwrap(),
+            [
+                Id::String("a".to_string()),
+                Id::Number(1.into()),
+                Id::String("1".to_string()),
+            ]
+        );
+        let error = merged_ids(DuplicateIds::Error).unwrap_err().to_string();
+        assert!(error.contains("\"a\""), "{}", error);
+
+        let regenerated = merged_ids(DuplicateIds::Regenerate).unwrap();
+        assert_eq!(regenerated.len(), 4);
+        assert_ne!(regenerated[3], Id::String("a".to_string()));
+    }
+}
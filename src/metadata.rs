@@ -0,0 +1,73 @@
+use geojson::{FeatureCollection, JsonObject, JsonValue};
+use serde_json::json;
+
+use crate::format::unix_time;
+use crate::trajectory::timestamp;
+
+/// Foreign member of a FeatureCollection holding its [`Metadata`].
+pub const MEMBER: &str = "random_geojson";
+
+/// How a dataset was generated, enough to generate the same one again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metadata {
+    /// Seed of the random number generator.
+    pub seed: u64,
+    /// Number of features generated.
+    pub features: usize,
+    /// Generation options keyed by long flag name, as read by --config.
+    pub options: JsonObject,
+}
+
+impl Metadata {
+    /// The metadata as a JSON object, with the version of the tool and the
+    /// current time.
+    pub fn to_json(&self) -> JsonValue {
+        json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "seed": self.seed,
+            "generated_at": timestamp(unix_time()),
+            "features": self.features,
+            "options": self.options,
+        })
+    }
+
+    /// Records the metadata in `fc` as its [`MEMBER`] foreign member.
+    pub fn embed(&self, fc: &mut FeatureCollection) {
+        fc.foreign_members
+            .get_or_insert_default()
+            .insert(MEMBER.to_string(), self.to_json());
+    }
+}
+
+/// The sidecar file holding the metadata of an output that cannot, e.g.
+/// "random.fgb.meta.json" for "random.fgb".
+pub fn sidecar_path(output_file: &str) -> String {
+    format!("{}.meta.json", output_file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embed() {
+        let metadata = Metadata {
+            seed: 42,
+            features: 0,
+            options: JsonObject::from_iter([("length".to_string(), "0".into())]),
+        };
+        let mut fc = FeatureCollection::default();
+        metadata.embed(&mut fc);
+        let json = serde_json::to_value(&fc).unwrap();
+        assert_eq!(json[MEMBER]["seed"], 42);
+        assert_eq!(json[MEMBER]["options"]["length"], "0");
+        assert_eq!(json[MEMBER]["version"], env!("CARGO_PKG_VERSION"));
+        assert!(
+            json[MEMBER]["generated_at"]
+                .as_str()
+                .unwrap()
+                .ends_with('Z')
+        );
+        assert_eq!(sidecar_path("out/random.fgb"), "out/random.fgb.meta.json");
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use crate::anchor::project_one;
+use crate::coverage::delaunay;
+use crate::geometry::{Crs, RandomGeometry, random_geographic_coords};
+use crate::random;
+
+/// Share of the edges between neighbouring junctions that are not needed to
+/// connect the network but are added as roads anyway, to make loops.
+const LOOPS: f64 = 0.3;
+
+/// A road between two junctions of a network.
+pub struct NetworkEdge {
+    pub geometry: RandomGeometry,
+    pub from_node: usize,
+    pub to_node: usize,
+}
+
+/// Generates a connected road network between `nodes` random junctions,
+/// numbered from 0, as straight LineStrings that only meet at their ends.
+///
+/// Roads are edges of the Delaunay triangulation of the junctions, so they
+/// never cross: the shortest ones that connect every junction (a minimum
+/// spanning tree) and a share of the others.
+pub fn random_network(crs: &Crs, nodes: usize) -> Vec<NetworkEdge> {
+    if nodes < 2 {
+        return Vec::new();
+    }
+    let bounds = crs.bounds();
+    let mut points: Vec<(f64, f64)> = (0..nodes).map(|_| random_geographic_coords(crs)).collect();
+    let key = |(x, y): (f64, f64)| (x.to_bits(), y.to_bits());
+    let mut ids: HashMap<(u64, u64), usize> = HashMap::new();
+    for (i, &p) in points.iter().enumerate() {
+        ids.entry(key(p)).or_insert(i);
+    }
+
+    // The triangulation also connects the corners of the bounds, which only
+    // become junctions if the network cannot be connected without them
+    let mut edges = Vec::new();
+    for triangle in delaunay(&points, &bounds) {
+        for k in 0..3 {
+            let (a, b) = (triangle[k], triangle[(k + 1) % 3]);
+            let [a, b] = [a, b].map(|p| {
+                *ids.entry(key(p)).or_insert_with(|| {
+                    points.push(p);
+                    points.len() - 1
+                })
+            });
+            if a < b {
+                edges.push((a, b));
+            }
+        }
+    }
+    edges.sort_by(|&(a, b), &(c, d)| {
+        let corner = |x: usize, y: usize| x >= nodes || y >= nodes;
+        let length = |x: usize, y: usize| {
+            let (p, q) = (points[x], points[y]);
+            let scale = ((p.1 + q.1) / 2.0).to_radians().cos();
+            ((q.0 - p.0) * scale).hypot(q.1 - p.1)
+        };
+        (corner(a, b), length(a, b))
+            .partial_cmp(&(corner(c, d), length(c, d)))
+            .unwrap()
+    });
+
+    let mut rng = random::rng();
+    let mut parents: Vec<usize> = (0..points.len()).collect();
+    let mut network = Vec::new();
+    let junctions: Vec<usize> = ids.values().copied().filter(|&i| i < nodes).collect();
+    for (a, b) in edges {
+        let corner = a >= nodes || b >= nodes;
+        if corner {
+            let first = root(&mut parents, junctions[0]);
+            if junctions.iter().all(|&j| root(&mut parents, j) == first) {
+                break;
+            }
+        }
+        let (root_a, root_b) = (root(&mut parents, a), root(&mut parents, b));
+        let connects = root_a != root_b;
+        if connects {
+            parents[root_a] = root_b;
+        }
+        let loop_road = !corner && rng.random_bool(LOOPS);
+        if connects || loop_road {
+            let (from_node, to_node) = if rng.random_bool(0.5) { (a, b) } else { (b, a) };
+            network.push(NetworkEdge {
+                geometry: RandomGeometry::LineString(vec![
+                    project_one(crs, points[from_node]),
+                    project_one(crs, points[to_node]),
+                ]),
+                from_node,
+                to_node,
+            });
+        }
+    }
+    network
+}
+
+// Root of the set of a junction, halving the path to it.
+fn root(parents: &mut [usize], mut node: usize) -> usize {
+    while parents[node] != node {
+        parents[node] = parents[parents[node]];
+        node = parents[node];
+    }
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::{Bounds, Unit};
+
+    use super::*;
+
+    #[test]
+    fn test_random_network_is_connected() {
+        let bounds = Bounds::new(0.0, 0.0, 1.0, 1.0, Unit::Degrees);
+        let crs = Crs::WGS84.clipped(bounds).unwrap();
+        let network = random_network(&crs, 200);
+        let mut parents: Vec<usize> = (0..204).collect();
+        let mut positions = HashMap::new();
+        for edge in &network {
+            let RandomGeometry::LineString(coords) = &edge.geometry else {
+                unreachable!("roads are linestrings")
+            };
+            // Every junction has a single position shared by its roads
+            for (node, coord) in [(edge.from_node, &coords[0]), (edge.to_node, &coords[1])] {
+                assert_eq!(positions.entry(node).or_insert(coord.clone()), coord);
+            }
+            let (a, b) = (
+                root(&mut parents, edge.from_node),
+                root(&mut parents, edge.to_node),
+            );
+            parents[a] = b;
+        }
+        assert!((0..200).all(|n| positions.contains_key(&n)));
+        assert_eq!(positions.len(), 200);
+        let first = root(&mut parents, 0);
+        assert!((0..200).all(|n| root(&mut parents, n) == first));
+        // A spanning tree plus loops
+        assert!(network.len() > 199 + 50, "{}", network.len());
+        assert!(random_network(&crs, 1).is_empty());
+    }
+}
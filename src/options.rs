@@ -0,0 +1,99 @@
+use geozero::GeozeroDatasource;
+use geozero::geojson::GeoJsonWriter;
+use serde::Deserialize;
+
+use crate::datasource::{GeometryKind, RandomFeatures};
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::geometry::{Bounds, Crs, Unit};
+use crate::random;
+
+/// Options of [`generate_json`] for embedding the generator, as a JSON
+/// object with camelCase keys, all optional, e.g. `{"length": 50,
+/// "geometryType": "Point", "bbox": [-10, 40, 10, 55], "seed": 42}`.
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct Options {
+    pub length: usize,
+    /// "Point", "LineString", "Polygon" or "All".
+    pub geometry_type: String,
+    /// "WGS84", "WebMercator", "4326", "3857" or a PROJ string.
+    pub coordinate_system: String,
+    pub num_properties: usize,
+    /// Geographic `[west, south, east, north]` to generate within.
+    pub bbox: Option<[f64; 4]>,
+    pub seed: Option<u64>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            length: 100,
+            geometry_type: "All".to_string(),
+            coordinate_system: "WGS84".to_string(),
+            num_properties: 0,
+            bbox: None,
+            seed: None,
+        }
+    }
+}
+
+/// Generates a random GeoJSON FeatureCollection as text from options given
+/// as JSON text, see [`Options`].
+pub fn generate_json(options: &str) -> RandomGeojsonResult<String> {
+    let options: Options = serde_json::from_str(options)
+        .map_err(|e| RandomGeojsonError::InvalidArgument(format!("Invalid options: {}", e)))?;
+    let mut crs: Crs = options.coordinate_system.parse()?;
+    if let Some([min_x, min_y, max_x, max_y]) = options.bbox {
+        crs = crs.clipped(Bounds {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+            unit: Unit::Degrees,
+        })?;
+    }
+    let mut features = RandomFeatures {
+        geometry_kind: options.geometry_type.parse::<GeometryKind>()?,
+        num_properties: options.num_properties,
+        ..RandomFeatures::new(&crs, options.length)
+    };
+    let mut json = Vec::new();
+    let mut write = || {
+        features
+            .process(&mut GeoJsonWriter::new(&mut json))
+            .map_err(|e| RandomGeojsonError::Serialize(e.to_string()))
+    };
+    match options.seed {
+        Some(seed) => random::with_seed(seed, write)?,
+        None => write()?,
+    }
+    String::from_utf8(json).map_err(|e| RandomGeojsonError::Serialize(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::FeatureCollection;
+
+    use super::*;
+
+    #[test]
+    fn test_generate_json() {
+        let options = r#"{"length": 25, "geometryType": "point", "bbox": [-10, 40, 10, 55], "numProperties": 2, "seed": 1}"#;
+        let json = generate_json(options).unwrap();
+        assert_eq!(json, generate_json(options).unwrap());
+        let fc: FeatureCollection = json.parse().unwrap();
+        assert_eq!(fc.features.len(), 25);
+        for feature in &fc.features {
+            let geojson::Value::Point(p) = &feature.geometry.as_ref().unwrap().value else {
+                panic!("not a point");
+            };
+            assert!((-10.0..=10.0).contains(&p[0]) && (40.0..=55.0).contains(&p[1]));
+            assert_eq!(feature.properties.as_ref().unwrap().len(), 2);
+        }
+
+        let fc: FeatureCollection = generate_json("{}").unwrap().parse().unwrap();
+        assert_eq!(fc.features.len(), 100);
+        assert!(generate_json(r#"{"lenght": 5}"#).is_err());
+        assert!(generate_json(r#"{"geometryType": "Circle"}"#).is_err());
+    }
+}
@@ -0,0 +1,340 @@
+use std::io::Write;
+use std::path::Path;
+use std::time::Instant;
+
+use geojson::{Feature, FeatureCollection, GeoJson, Value};
+use tracing::info;
+
+use crate::compression::Compression;
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::format::{EncodeOptions, Format};
+use crate::progress::human_bytes;
+use crate::remote::{self, ObjectUrl, Upload};
+
+/// How generated data is written to disk.
+#[derive(Debug, Clone)]
+pub struct OutputOptions {
+    pub format: Format,
+    /// Format-specific settings, such as pretty-printing or the EPSG code of
+    /// the generated coordinates.
+    pub encode: EncodeOptions,
+    pub compression: Compression,
+    pub compression_level: i32,
+    /// Overwrite existing files instead of refusing to write.
+    pub force: bool,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        OutputOptions {
+            format: Format::GeoJson,
+            encode: EncodeOptions::default(),
+            compression: Compression::None,
+            compression_level: Compression::None.default_level(),
+            force: false,
+        }
+    }
+}
+
+/// Saves the generated feature collection to a file in the configured format
+/// and returns the number of bytes written.
+///
+/// `s3://`, `gs://` and `az://` paths are uploaded to object storage instead.
+pub fn save_to_file(
+    fc: &FeatureCollection,
+    file_path: &str,
+    options: &OutputOptions,
+) -> RandomGeojsonResult<usize> {
+    let start = Instant::now();
+    let bytes = serialize(fc, options)?;
+    info!(
+        "Encoded {} features as {} in {:.2?}",
+        fc.features.len(),
+        options.format.extension(),
+        start.elapsed()
+    );
+    let start = Instant::now();
+    let upload = Upload {
+        body: &bytes,
+        content_type: options.format.media_type(),
+        content_encoding: options.compression.content_encoding(),
+        overwrite: options.force,
+    };
+    write_output(file_path, &upload)?;
+    info!(
+        "Wrote {} to {} in {:.2?}",
+        human_bytes(bytes.len()),
+        file_path,
+        start.elapsed()
+    );
+    Ok(bytes.len())
+}
+
+/// Writes the body of `upload` to `file_path`, or uploads it to object storage
+/// if `file_path` is an `s3://`, `gs://` or `az://` URL.
+pub fn write_output(file_path: &str, upload: &Upload) -> RandomGeojsonResult<()> {
+    match ObjectUrl::parse(file_path)? {
+        Some(url) => remote::upload(&url, upload),
+        None => write_file(file_path, upload.body, upload.overwrite),
+    }
+}
+
+/// Serializes (and compresses, if requested) a feature collection.
+pub fn serialize(fc: &FeatureCollection, options: &OutputOptions) -> RandomGeojsonResult<Vec<u8>> {
+    let bytes = options.format.encode(fc, &options.encode)?;
+    options
+        .compression
+        .compress(&bytes, options.compression_level)
+}
+
+/// Appends the features of `fc` to the FeatureCollection stored at
+/// `file_path`, creating the file if it does not exist yet, and returns the
+/// size of the file in bytes.
+pub fn append_geojson_to_file(
+    fc: &FeatureCollection,
+    file_path: &str,
+    options: &OutputOptions,
+) -> RandomGeojsonResult<usize> {
+    if options.format != Format::GeoJson {
+        return Err(RandomGeojsonError::UnsupportedFormat(
+            "--append is only supported for GeoJSON output".to_string(),
+        ));
+    }
+    if ObjectUrl::parse(file_path)?.is_some() {
+        return Err(RandomGeojsonError::InvalidArgument(
+            "--append is not supported for object storage output".to_string(),
+        ));
+    }
+
+    let mut combined = match std::fs::read(file_path) {
+        Ok(bytes) => {
+            let bytes = options.compression.decompress(&bytes)?;
+            let geojson: GeoJson = serde_json::from_slice(&bytes).map_err(|e| {
+                RandomGeojsonError::InvalidInput(format!(
+                    "Failed to parse {} as GeoJSON: {}",
+                    file_path, e
+                ))
+            })?;
+            FeatureCollection::try_from(geojson).map_err(|_| {
+                RandomGeojsonError::InvalidInput(format!(
+                    "Cannot append to {}: not a FeatureCollection",
+                    file_path
+                ))
+            })?
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => FeatureCollection::default(),
+        Err(e) => {
+            return Err(RandomGeojsonError::Io(format!(
+                "Failed to read file: {}",
+                e
+            )));
+        }
+    };
+
+    combined.features.extend(fc.features.iter().cloned());
+
+    let options = OutputOptions {
+        force: true,
+        ..options.clone()
+    };
+    save_to_file(&combined, file_path, &options)
+}
+
+/// Writes `bytes` to `file_path` atomically, refusing to replace an existing
+/// file unless `force` is set.
+///
+/// The data is written to a temporary file next to the target and renamed into
+/// place, so an interrupted run never leaves a truncated file behind.
+pub fn write_file(file_path: &str, bytes: &[u8], force: bool) -> RandomGeojsonResult<()> {
+    check_overwrite(&[file_path], force)?;
+    let path = Path::new(file_path);
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| {
+            RandomGeojsonError::InvalidArgument(format!("Invalid output path: {}", file_path))
+        })?
+        .to_string_lossy();
+    let tmp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let write = || -> std::io::Result<()> {
+        let mut file = std::fs::File::create(&tmp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, path)
+    };
+    write().map_err(|e| {
+        let _ = std::fs::remove_file(&tmp_path);
+        RandomGeojsonError::Io(format!("Failed to write file: {}", e))
+    })
+}
+
+/// Fails if any local file of `file_paths` already exists, unless `force` is
+/// set, so that outputs of several files can be checked before writing any.
+/// Uploads check for existing objects themselves.
+pub fn check_overwrite<P: AsRef<str>>(file_paths: &[P], force: bool) -> RandomGeojsonResult<()> {
+    if force {
+        return Ok(());
+    }
+    for file_path in file_paths {
+        let file_path = file_path.as_ref();
+        if ObjectUrl::parse(file_path)?.is_none() && Path::new(file_path).exists() {
+            return Err(RandomGeojsonError::Io(format!(
+                "File {} already exists, use --force to overwrite it or --append to add to it",
+                file_path
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Inserts a 1-based shard number before the extension, e.g. "random-0001.geojson".
+pub fn shard_path(file_path: &str, index: usize) -> String {
+    suffixed_path(file_path, &format!("{:04}", index))
+}
+
+/// Inserts the name of a layer of [`split_by_type`] before the extension,
+/// e.g. "random-points.geojson".
+pub fn layer_path(file_path: &str, layer: &str) -> String {
+    suffixed_path(file_path, layer)
+}
+
+/// Splits the features of `fc` into layers of a single kind of geometry:
+/// "points", "linestrings", "polygons" and "geometrycollections", in that
+/// order and leaving out empty ones. Multi-geometries join the layer of
+/// their kind, as shapefiles allow; features without a geometry are left
+/// out.
+pub fn split_by_type(fc: &FeatureCollection) -> Vec<(&'static str, FeatureCollection)> {
+    let layers = ["points", "linestrings", "polygons", "geometrycollections"];
+    let mut features: [Vec<Feature>; 4] = Default::default();
+    for feature in &fc.features {
+        let Some(geometry) = &feature.geometry else {
+            continue;
+        };
+        let layer = match geometry.value {
+            Value::Point(_) | Value::MultiPoint(_) => 0,
+            Value::LineString(_) | Value::MultiLineString(_) => 1,
+            Value::Polygon(_) | Value::MultiPolygon(_) => 2,
+            Value::GeometryCollection(_) => 3,
+        };
+        features[layer].push(feature.clone());
+    }
+    layers
+        .into_iter()
+        .zip(features)
+        .filter(|(_, features)| !features.is_empty())
+        .map(|(layer, features)| {
+            let collection = FeatureCollection {
+                features,
+                ..Default::default()
+            };
+            (layer, collection)
+        })
+        .collect()
+}
+
+// Inserts "-<suffix>" before the extension of the file name.
+fn suffixed_path(file_path: &str, suffix: &str) -> String {
+    let path = Path::new(file_path);
+    let file_name = path
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let sharded = match file_name.split_once('.') {
+        Some((stem, ext)) => format!("{}-{}.{}", stem, suffix, ext),
+        None => format!("{}-{}", file_name, suffix),
+    };
+    path.with_file_name(sharded).to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shard_path() {
+        assert_eq!(shard_path("random.geojson", 1), "random-0001.geojson");
+        assert_eq!(shard_path("out/a.geojson", 12), "out/a-0012.geojson");
+        assert_eq!(shard_path("data", 3), "data-0003");
+        assert_eq!(
+            layer_path("out/a.geojson", "points"),
+            "out/a-points.geojson"
+        );
+    }
+
+    #[test]
+    fn test_split_by_type() {
+        let feature = |value| geojson::Feature::from(geojson::Geometry::new(value));
+        let fc = FeatureCollection {
+            features: vec![
+                feature(Value::Polygon(vec![])),
+                feature(Value::Point(vec![0.0, 0.0])),
+                geojson::Feature::default(),
+                feature(Value::MultiPoint(vec![])),
+            ],
+            ..Default::default()
+        };
+        let layers = split_by_type(&fc);
+        let sizes: Vec<_> = layers
+            .iter()
+            .map(|(layer, fc)| (*layer, fc.features.len()))
+            .collect();
+        assert_eq!(sizes, [("points", 2), ("polygons", 1)]);
+    }
+
+    #[test]
+    fn test_append_geojson_to_file() {
+        let path = std::env::temp_dir().join(format!(
+            "random-geojson-append-{}.geojson.gz",
+            std::process::id()
+        ));
+        let path = path.to_str().unwrap();
+        let options = OutputOptions {
+            compression: Compression::Gzip,
+            compression_level: 6,
+            ..Default::default()
+        };
+        let fc = FeatureCollection {
+            features: vec![geojson::Feature::default(); 2],
+            ..Default::default()
+        };
+
+        append_geojson_to_file(&fc, path, &options).unwrap();
+        append_geojson_to_file(&fc, path, &options).unwrap();
+
+        let bytes = Compression::Gzip
+            .decompress(&std::fs::read(path).unwrap())
+            .unwrap();
+        let stored: FeatureCollection = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(stored.features.len(), 4);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_write_file_refuses_overwrite() {
+        let dir = std::env::temp_dir().join(format!("random-geojson-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.geojson");
+        let path = path.to_str().unwrap();
+
+        write_file(path, b"first", false).unwrap();
+        assert!(matches!(
+            write_file(path, b"second", false),
+            Err(RandomGeojsonError::Io(_))
+        ));
+        let other = dir.join("other.geojson");
+        let other = other.to_str().unwrap();
+        assert!(check_overwrite(&[other, path], false).is_err());
+        assert!(check_overwrite(&[other], false).is_ok());
+        assert!(check_overwrite(&[path], true).is_ok());
+        assert_eq!(std::fs::read(path).unwrap(), b"first");
+
+        write_file(path, b"second", true).unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), b"second");
+        // No temporary files are left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
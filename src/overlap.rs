@@ -0,0 +1,186 @@
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::geometry::{Crs, RandomGeometry};
+use crate::mask::ring_contains;
+use crate::shape::PolygonShape;
+
+/// Random polygons drawn before giving up on finding room for one.
+const MAX_ATTEMPTS: usize = 100;
+
+/// Times an overlapping polygon is halved in size before drawing another.
+const MAX_SHRINKS: usize = 20;
+
+type Ring = Vec<Vec<f64>>;
+
+/// Keeps generated polygons from overlapping each other, for parcel-like
+/// data where they must not.
+///
+/// A new polygon that overlaps an earlier one is shrunk towards the mean of
+/// its vertices until it fits, and drawn again if it never does. Shrinking
+/// keeps the vertices inside the bounding box of the original ones.
+#[derive(Default)]
+pub struct PolygonIndex {
+    polygons: Vec<(Ring, [f64; 4])>,
+}
+
+impl PolygonIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if the closed ring shares any area or boundary with a
+    /// polygon recorded so far.
+    pub fn overlaps(&self, ring: &[Vec<f64>]) -> bool {
+        let bbox = ring_bbox(ring);
+        self.polygons.iter().any(|(other, other_bbox)| {
+            bbox[0] <= other_bbox[2]
+                && other_bbox[0] <= bbox[2]
+                && bbox[1] <= other_bbox[3]
+                && other_bbox[1] <= bbox[3]
+                && rings_overlap(ring, other)
+        })
+    }
+
+    /// Records a closed ring.
+    pub fn insert(&mut self, ring: Ring) {
+        let bbox = ring_bbox(&ring);
+        self.polygons.push((ring, bbox));
+    }
+
+    /// Generates a random Polygon of `shape` that does not overlap the
+    /// polygons generated before.
+    pub fn random_polygon(
+        &mut self,
+        crs: &Crs,
+        shape: &PolygonShape,
+    ) -> RandomGeojsonResult<RandomGeometry> {
+        for _ in 0..MAX_ATTEMPTS {
+            let RandomGeometry::Polygon(mut rings) = shape.random_polygon(crs)? else {
+                unreachable!("random_polygon returns polygons")
+            };
+            let ring = &mut rings[0];
+            let n = (ring.len() - 1) as f64;
+            let cx = ring[..ring.len() - 1].iter().map(|p| p[0]).sum::<f64>() / n;
+            let cy = ring[..ring.len() - 1].iter().map(|p| p[1]).sum::<f64>() / n;
+            for _ in 0..=MAX_SHRINKS {
+                if !self.overlaps(ring) {
+                    self.insert(ring.clone());
+                    return Ok(RandomGeometry::Polygon(rings));
+                }
+                for p in ring.iter_mut() {
+                    p[0] = cx + (p[0] - cx) / 2.0;
+                    p[1] = cy + (p[1] - cy) / 2.0;
+                }
+            }
+        }
+        Err(RandomGeojsonError::InvalidArgument(format!(
+            "No room left for another non-overlapping polygon after {} polygons, generate fewer features",
+            self.polygons.len()
+        )))
+    }
+}
+
+fn ring_bbox(ring: &[Vec<f64>]) -> [f64; 4] {
+    ring.iter().fold(
+        [
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
+        ],
+        |b, p| {
+            [
+                b[0].min(p[0]),
+                b[1].min(p[1]),
+                b[2].max(p[0]),
+                b[3].max(p[1]),
+            ]
+        },
+    )
+}
+
+// Two closed rings overlap if any of their edges touch or cross, or if one
+// lies entirely inside the other.
+fn rings_overlap(a: &[Vec<f64>], b: &[Vec<f64>]) -> bool {
+    let crossing = a.windows(2).any(|e| {
+        b.windows(2)
+            .any(|f| segments_intersect(&e[0], &e[1], &f[0], &f[1]))
+    });
+    crossing || ring_contains(b, a[0][0], a[0][1]) || ring_contains(a, b[0][0], b[0][1])
+}
+
+// Whether segments pq and rs share a point, collinear overlaps included.
+pub(crate) fn segments_intersect(p: &[f64], q: &[f64], r: &[f64], s: &[f64]) -> bool {
+    let orientation = |a: &[f64], b: &[f64], c: &[f64]| {
+        let cross = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+        cross.partial_cmp(&0.0).map_or(0, |o| o as i8)
+    };
+    let on_segment = |a: &[f64], b: &[f64], c: &[f64]| {
+        c[0] >= a[0].min(b[0])
+            && c[0] <= a[0].max(b[0])
+            && c[1] >= a[1].min(b[1])
+            && c[1] <= a[1].max(b[1])
+    };
+    let (o1, o2) = (orientation(p, q, r), orientation(p, q, s));
+    let (o3, o4) = (orientation(r, s, p), orientation(r, s, q));
+    if o1 != o2 && o3 != o4 {
+        return true;
+    }
+    (o1 == 0 && on_segment(p, q, r))
+        || (o2 == 0 && on_segment(p, q, s))
+        || (o3 == 0 && on_segment(r, s, p))
+        || (o4 == 0 && on_segment(r, s, q))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::{Bounds, Unit};
+
+    use super::*;
+
+    fn square(x: f64, y: f64, size: f64) -> Ring {
+        vec![
+            vec![x, y],
+            vec![x + size, y],
+            vec![x + size, y + size],
+            vec![x, y + size],
+            vec![x, y],
+        ]
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let mut index = PolygonIndex::new();
+        index.insert(square(0.0, 0.0, 10.0));
+        assert!(index.overlaps(&square(5.0, 5.0, 10.0)));
+        // Inside, containing and touching
+        assert!(index.overlaps(&square(2.0, 2.0, 1.0)));
+        assert!(index.overlaps(&square(-5.0, -5.0, 20.0)));
+        assert!(index.overlaps(&square(10.0, 0.0, 5.0)));
+        assert!(!index.overlaps(&square(11.0, 0.0, 5.0)));
+        assert!(!index.overlaps(&square(8.0, 12.0, 1.0)));
+    }
+
+    #[test]
+    fn test_random_polygons_do_not_overlap() {
+        let bounds = Bounds::new(0.0, 0.0, 10.0, 10.0, Unit::Degrees);
+        let crs = Crs::WGS84.clipped(bounds).unwrap();
+        let mut index = PolygonIndex::new();
+        let rings: Vec<Ring> = (0..50)
+            .map(|_| {
+                match index
+                    .random_polygon(&crs, &PolygonShape::default())
+                    .unwrap()
+                {
+                    RandomGeometry::Polygon(mut rings) => rings.remove(0),
+                    _ => unreachable!(),
+                }
+            })
+            .collect();
+        for (i, a) in rings.iter().enumerate() {
+            assert_eq!(a.first(), a.last());
+            for b in &rings[i + 1..] {
+                assert!(!rings_overlap(a, b));
+            }
+        }
+    }
+}
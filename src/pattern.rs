@@ -0,0 +1,318 @@
+use rand::Rng;
+
+use crate::error::RandomGeojsonError;
+use crate::random;
+
+/// Most repetitions of `*`, `+` and `{n,}`.
+const MAX_REPEAT: u32 = 8;
+
+/// Characters `.` and negated classes draw from: printable ASCII.
+const PRINTABLE: (char, char) = (' ', '~');
+
+/// A regular expression to generate matching strings from, e.g.
+/// `[A-Z]{2}-[0-9]{4}` for licence plates.
+///
+/// Supports literals, escapes, `.`, character classes with ranges and
+/// negation, `\d`, `\w` and `\s` and their negations, groups with
+/// alternation, and the quantifiers `?`, `*`, `+`, `{n}`, `{n,}` and
+/// `{n,m}`. Anchors are accepted and ignored, as every generated string is
+/// a whole match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    alternatives: Vec<Vec<Node>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Literal(char),
+    /// Any character of these inclusive ranges.
+    Class(Vec<(char, char)>),
+    Group(Vec<Vec<Node>>),
+    Repeat(Box<Node>, u32, u32),
+}
+
+impl Pattern {
+    /// Generates a string matching the pattern.
+    pub fn generate(&self) -> String {
+        let mut out = String::new();
+        generate_alternatives(&self.alternatives, &mut out);
+        out
+    }
+}
+
+impl std::str::FromStr for Pattern {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+        };
+        let alternatives = parser.alternatives().map_err(|reason| {
+            RandomGeojsonError::InvalidArgument(format!("Invalid pattern '{}': {}", s, reason))
+        })?;
+        if parser.pos < parser.chars.len() {
+            return Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid pattern '{}': unmatched ')'",
+                s
+            )));
+        }
+        Ok(Pattern { alternatives })
+    }
+}
+
+fn generate_alternatives(alternatives: &[Vec<Node>], out: &mut String) {
+    let sequence = &alternatives[random::rng().random_range(0..alternatives.len())];
+    for node in sequence {
+        generate_node(node, out);
+    }
+}
+
+fn generate_node(node: &Node, out: &mut String) {
+    match node {
+        Node::Literal(c) => out.push(*c),
+        Node::Class(ranges) => {
+            let size = |&(low, high): &(char, char)| high as u32 - low as u32 + 1;
+            let mut index = random::rng().random_range(0..ranges.iter().map(size).sum::<u32>());
+            for range in ranges {
+                if index < size(range) {
+                    out.extend(char::from_u32(range.0 as u32 + index));
+                    return;
+                }
+                index -= size(range);
+            }
+        }
+        Node::Group(alternatives) => generate_alternatives(alternatives, out),
+        Node::Repeat(node, min, max) => {
+            for _ in 0..random::rng().random_range(*min..=*max) {
+                generate_node(node, out);
+            }
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    // Sequences separated by "|", up to a ")" or the end.
+    fn alternatives(&mut self) -> Result<Vec<Vec<Node>>, String> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.eat('|') {
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, String> {
+        let mut sequence = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            self.pos += 1;
+            let atom = match c {
+                '^' | '$' => continue,
+                '(' => {
+                    // Non-capturing groups are groups all the same
+                    if self.peek() == Some('?') && self.chars.get(self.pos + 1) == Some(&':') {
+                        self.pos += 2;
+                    }
+                    let alternatives = self.alternatives()?;
+                    if !self.eat(')') {
+                        return Err("missing ')'".to_string());
+                    }
+                    Node::Group(alternatives)
+                }
+                '[' => self.class()?,
+                '.' => Node::Class(vec![PRINTABLE]),
+                '\\' => match self.escape()? {
+                    Escape::Char(c) => Node::Literal(c),
+                    Escape::Class(ranges) => Node::Class(ranges),
+                },
+                '?' | '*' | '+' | '{' => return Err(format!("nothing to repeat before '{}'", c)),
+                c => Node::Literal(c),
+            };
+            sequence.push(self.quantified(atom)?);
+        }
+        Ok(sequence)
+    }
+
+    fn quantified(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('?') => (0, 1),
+            Some('*') => (0, MAX_REPEAT),
+            Some('+') => (1, MAX_REPEAT),
+            Some('{') => {
+                self.pos += 1;
+                let mut body = String::new();
+                loop {
+                    match self.next() {
+                        Some('}') => break,
+                        Some(c) => body.push(c),
+                        None => return Err("missing '}'".to_string()),
+                    }
+                }
+                let invalid = || format!("invalid repetition {{{}}}", body);
+                let number = |s: &str| s.trim().parse::<u32>().map_err(|_| invalid());
+                let (min, max) = match body.split_once(',') {
+                    None => (number(&body)?, number(&body)?),
+                    Some((min, "")) => (number(min)?, number(min)? + MAX_REPEAT),
+                    Some((min, max)) => (number(min)?, number(max)?),
+                };
+                if min > max {
+                    return Err(invalid());
+                }
+                // Lazy repetitions match the same strings
+                self.eat('?');
+                return Ok(Node::Repeat(Box::new(atom), min, max));
+            }
+            _ => return Ok(atom),
+        };
+        self.pos += 1;
+        self.eat('?');
+        Ok(Node::Repeat(Box::new(atom), min, max))
+    }
+
+    // A character class, after its "[".
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.next().ok_or("missing ']'")?;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let low = match c {
+                '\\' => match self.escape()? {
+                    Escape::Char(c) => c,
+                    Escape::Class(class) => {
+                        ranges.extend(class);
+                        continue;
+                    }
+                },
+                c => c,
+            };
+            let is_range = self.peek() == Some('-') && self.chars.get(self.pos + 1) != Some(&']');
+            if !is_range {
+                ranges.push((low, low));
+                continue;
+            }
+            self.pos += 1;
+            let high = match self.next().ok_or("missing ']'")? {
+                '\\' => match self.escape()? {
+                    Escape::Char(c) => c,
+                    Escape::Class(_) => return Err("invalid range in class".to_string()),
+                },
+                c => c,
+            };
+            if low > high {
+                return Err(format!("invalid range {}-{}", low, high));
+            }
+            ranges.push((low, high));
+        }
+        let ranges = if negated { complement(&ranges) } else { ranges };
+        if ranges.is_empty() {
+            return Err("class matches no printable character".to_string());
+        }
+        Ok(Node::Class(ranges))
+    }
+
+    // An escape, after its "\".
+    fn escape(&mut self) -> Result<Escape, String> {
+        let digits = vec![('0', '9')];
+        let word = vec![('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+        let space = vec![(' ', ' ')];
+        Ok(match self.next().ok_or("trailing '\\'")? {
+            'd' => Escape::Class(digits),
+            'w' => Escape::Class(word),
+            's' => Escape::Class(space),
+            'D' => Escape::Class(complement(&digits)),
+            'W' => Escape::Class(complement(&word)),
+            'S' => Escape::Class(complement(&space)),
+            'n' => Escape::Char('\n'),
+            't' => Escape::Char('\t'),
+            c => Escape::Char(c),
+        })
+    }
+}
+
+enum Escape {
+    Char(char),
+    Class(Vec<(char, char)>),
+}
+
+// The printable ASCII characters outside the ranges, as ranges.
+fn complement(ranges: &[(char, char)]) -> Vec<(char, char)> {
+    let mut complement: Vec<(char, char)> = Vec::new();
+    for c in PRINTABLE.0..=PRINTABLE.1 {
+        if ranges.iter().any(|&(low, high)| (low..=high).contains(&c)) {
+            continue;
+        }
+        match complement.last_mut() {
+            Some((_, high)) if *high as u32 + 1 == c as u32 => *high = c,
+            _ => complement.push((c, c)),
+        }
+    }
+    complement
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(pattern: &str) -> Vec<String> {
+        let pattern: Pattern = pattern.parse().unwrap();
+        (0..200).map(|_| pattern.generate()).collect()
+    }
+
+    #[test]
+    fn test_generate() {
+        for plate in samples("^[A-Z]{2}-[0-9]{4}$") {
+            let chars: Vec<char> = plate.chars().collect();
+            assert_eq!(chars.len(), 7, "{}", plate);
+            assert!(chars[..2].iter().all(char::is_ascii_uppercase), "{}", plate);
+            assert_eq!(chars[2], '-');
+            assert!(chars[3..].iter().all(char::is_ascii_digit), "{}", plate);
+        }
+        let words = samples("(?:foo|ba[rz])\\.x?");
+        assert!(words.iter().all(|w| {
+            ["foo.", "bar.", "baz.", "foo.x", "bar.x", "baz.x"].contains(&w.as_str())
+        }));
+        assert!(words.iter().any(|w| w.starts_with("foo")));
+        assert!(samples("[^a-z]\\d+").iter().all(|s| {
+            !s.starts_with(|c: char| c.is_ascii_lowercase())
+                && s.len() >= 2
+                && s.len() <= 1 + MAX_REPEAT as usize
+        }));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for pattern in ["[A-Z", "a{3,1}", "(ab", "ab)", "*a", "[z-a]", "a{x}"] {
+            assert!(pattern.parse::<Pattern>().is_err(), "{}", pattern);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use geojson::{FeatureCollection, Position};
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::geometry::geometry_bbox;
+use crate::random;
+
+/// How `perturb` changes an existing dataset.
+#[derive(Debug, Clone, Copy)]
+pub struct PerturbOptions {
+    /// Largest offset added to each coordinate, in the units of the data.
+    pub jitter: f64,
+    /// Shuffle the values of each property between features.
+    pub shuffle_properties: bool,
+}
+
+/// Applies random noise to every position of `fc`, keeping rings closed, and
+/// optionally shuffles property values between features.
+///
+/// Shuffling keeps the values of each property but breaks their association
+/// with the geometries and with the other properties.
+pub fn perturb(fc: &mut FeatureCollection, options: &PerturbOptions) {
+    for feature in &mut fc.features {
+        if let Some(geometry) = &mut feature.geometry {
+            jitter_value(&mut geometry.value, options.jitter);
+            // Bounding boxes are recomputed rather than left stale
+            if geometry.bbox.is_some() {
+                geometry.bbox = geometry_bbox(&geometry.value).map(Vec::from);
+            }
+            if feature.bbox.is_some() {
+                feature.bbox = geometry_bbox(&geometry.value).map(Vec::from);
+            }
+        }
+    }
+    if fc.bbox.is_some() {
+        fc.bbox = collection_bbox(fc);
+    }
+    if options.shuffle_properties {
+        shuffle_properties(fc);
+    }
+}
+
+fn jitter_value(value: &mut geojson::Value, jitter: f64) {
+    use geojson::Value::*;
+    match value {
+        Point(p) => jitter_position(p, jitter),
+        MultiPoint(ps) | LineString(ps) => ps.iter_mut().for_each(|p| jitter_position(p, jitter)),
+        MultiLineString(lines) => lines
+            .iter_mut()
+            .flatten()
+            .for_each(|p| jitter_position(p, jitter)),
+        Polygon(rings) => rings.iter_mut().for_each(|ring| jitter_ring(ring, jitter)),
+        MultiPolygon(polygons) => polygons
+            .iter_mut()
+            .flatten()
+            .for_each(|ring| jitter_ring(ring, jitter)),
+        GeometryCollection(geometries) => geometries
+            .iter_mut()
+            .for_each(|g| jitter_value(&mut g.value, jitter)),
+    }
+}
+
+// Moves x and y by up to `jitter`; any z is left alone.
+fn jitter_position(position: &mut Position, jitter: f64) {
+    if jitter <= 0.0 {
+        return;
+    }
+    let mut rng = random::rng();
+    for coordinate in position.iter_mut().take(2) {
+        *coordinate += rng.random_range(-jitter..=jitter);
+    }
+}
+
+// Jitters a ring, moving its closing position along with the first one.
+fn jitter_ring(ring: &mut [Position], jitter: f64) {
+    let closed = ring.len() > 1 && ring.first() == ring.last();
+    let n = ring.len() - usize::from(closed);
+    ring[..n]
+        .iter_mut()
+        .for_each(|p| jitter_position(p, jitter));
+    if closed {
+        ring[n] = ring[0].clone();
+    }
+}
+
+fn collection_bbox(fc: &FeatureCollection) -> Option<Vec<f64>> {
+    fc.features
+        .iter()
+        .filter_map(|f| f.geometry.as_ref())
+        .filter_map(|g| geometry_bbox(&g.value))
+        .reduce(|a, b| {
+            [
+                a[0].min(b[0]),
+                a[1].min(b[1]),
+                a[2].max(b[2]),
+                a[3].max(b[3]),
+            ]
+        })
+        .map(Vec::from)
+}
+
+// Permutes the values of every property among the features that have it.
+fn shuffle_properties(fc: &mut FeatureCollection) {
+    let mut keys: Vec<String> = fc
+        .features
+        .iter()
+        .filter_map(|f| f.properties.as_ref())
+        .flat_map(|p| p.keys().cloned())
+        .collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut rng = random::rng();
+    for key in keys {
+        let holders: Vec<usize> = (0..fc.features.len())
+            .filter(|&i| {
+                fc.features[i]
+                    .properties
+                    .as_ref()
+                    .is_some_and(|p| p.contains_key(&key))
+            })
+            .collect();
+        let mut values: Vec<_> = holders
+            .iter()
+            .map(|&i| fc.features[i].properties.as_ref().unwrap()[&key].clone())
+            .collect();
+        values.shuffle(&mut rng);
+        for (i, value) in holders.into_iter().zip(values) {
+            fc.features[i]
+                .properties
+                .as_mut()
+                .unwrap()
+                .insert(key.clone(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Feature, Geometry, JsonObject};
+
+    use super::*;
+
+    fn feature(value: geojson::Value, id: i64) -> Feature {
+        let mut properties = JsonObject::new();
+        properties.insert("id".to_string(), id.into());
+        properties.insert("double".to_string(), (id * 2).into());
+        Feature {
+            geometry: Some(Geometry::new(value)),
+            properties: Some(properties),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_perturb_geometries() {
+        let ring = vec![
+            vec![0.0, 0.0],
+            vec![1.0, 0.0],
+            vec![1.0, 1.0],
+            vec![0.0, 0.0],
+        ];
+        let mut fc = FeatureCollection {
+            features: vec![
+                feature(geojson::Value::Point(vec![10.0, 20.0, 30.0]), 0),
+                feature(geojson::Value::Polygon(vec![ring.clone()]), 1),
+            ],
+            bbox: Some(vec![0.0, 0.0, 10.0, 20.0]),
+            foreign_members: None,
+        };
+        perturb(
+            &mut fc,
+            &PerturbOptions {
+                jitter: 0.01,
+                shuffle_properties: false,
+            },
+        );
+
+        let geojson::Value::Point(point) = &fc.features[0].geometry.as_ref().unwrap().value else {
+            unreachable!()
+        };
+        assert_ne!(point[..2], [10.0, 20.0]);
+        assert!((point[0] - 10.0).abs() <= 0.01 && (point[1] - 20.0).abs() <= 0.01);
+        assert_eq!(point[2], 30.0);
+        let geojson::Value::Polygon(rings) = &fc.features[1].geometry.as_ref().unwrap().value
+        else {
+            unreachable!()
+        };
+        assert_eq!(rings[0].first(), rings[0].last());
+        assert_ne!(rings[0], ring);
+        let bbox = fc.bbox.unwrap();
+        assert!(bbox[2] > 9.9 && bbox[2] < 10.1);
+    }
+
+    #[test]
+    fn test_shuffle_properties() {
+        let mut fc = FeatureCollection {
+            features: (0..50)
+                .map(|i| feature(geojson::Value::Point(vec![0.0, 0.0]), i))
+                .collect(),
+            bbox: None,
+            foreign_members: None,
+        };
+        perturb(
+            &mut fc,
+            &PerturbOptions {
+                jitter: 0.0,
+                shuffle_properties: true,
+            },
+        );
+        let ids: Vec<i64> = fc
+            .features
+            .iter()
+            .map(|f| f.properties.as_ref().unwrap()["id"].as_i64().unwrap())
+            .collect();
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..50).collect::<Vec<_>>());
+        assert_ne!(ids, sorted);
+    }
+}
@@ -0,0 +1,253 @@
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use rand::Rng;
+use tracing::{error, warn};
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::random;
+
+/// Delay before the first retry, doubled on every further attempt.
+const BASE_BACKOFF: Duration = Duration::from_millis(200);
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Settings of the HTTP POST sink.
+#[derive(Debug, Clone)]
+pub struct PostOptions {
+    pub url: String,
+    /// Number of requests in flight at once.
+    pub concurrency: usize,
+    /// Attempts after the first one before a batch counts as failed.
+    pub retries: u32,
+    pub content_type: &'static str,
+    pub content_encoding: Option<&'static str>,
+}
+
+/// Counts of what a [`Poster`] sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PostStats {
+    pub batches: usize,
+    pub features: usize,
+    pub failed_batches: usize,
+    pub retries: usize,
+}
+
+/// Sends request bodies to an HTTP endpoint from a pool of worker threads,
+/// retrying failed requests with exponential backoff.
+///
+/// [`Poster::send`] blocks while all workers are busy, so generation never
+/// runs far ahead of the endpoint.
+pub struct Poster {
+    sender: SyncSender<(Vec<u8>, usize)>,
+    workers: Vec<JoinHandle<()>>,
+    stats: Arc<Mutex<PostStats>>,
+}
+
+impl Poster {
+    pub fn new(options: PostOptions) -> Self {
+        let concurrency = options.concurrency.max(1);
+        let (sender, receiver) = sync_channel::<(Vec<u8>, usize)>(concurrency);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let stats = Arc::new(Mutex::new(PostStats::default()));
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_global(Some(REQUEST_TIMEOUT))
+            .build()
+            .into();
+        let options = Arc::new(options);
+        let workers = (0..concurrency)
+            .map(|_| {
+                let (receiver, stats, agent, options) = (
+                    Arc::clone(&receiver),
+                    Arc::clone(&stats),
+                    agent.clone(),
+                    Arc::clone(&options),
+                );
+                std::thread::spawn(move || work(&receiver, &stats, &agent, &options))
+            })
+            .collect();
+        Poster {
+            sender,
+            workers,
+            stats,
+        }
+    }
+
+    /// Queues a request body holding `features` features.
+    pub fn send(&self, body: Vec<u8>, features: usize) {
+        // Workers only stop once the sender is dropped
+        self.sender
+            .send((body, features))
+            .expect("workers outlive the sender");
+    }
+
+    /// Waits for all queued requests and returns what was sent.
+    pub fn finish(self) -> PostStats {
+        drop(self.sender);
+        for worker in self.workers {
+            let _ = worker.join();
+        }
+        *self.stats.lock().unwrap()
+    }
+}
+
+fn work(
+    receiver: &Mutex<Receiver<(Vec<u8>, usize)>>,
+    stats: &Mutex<PostStats>,
+    agent: &ureq::Agent,
+    options: &PostOptions,
+) {
+    loop {
+        // Hold the lock only while waiting for the next body
+        let next = receiver.lock().unwrap().recv();
+        let Ok((body, features)) = next else {
+            return;
+        };
+        let mut attempt = 0;
+        let result = loop {
+            match post(agent, options, &body) {
+                Err(Retry::Later(reason)) if attempt < options.retries => {
+                    std::thread::sleep(backoff(attempt));
+                    attempt += 1;
+                    warn!(
+                        "Retrying ({}/{}) after: {}",
+                        attempt, options.retries, reason
+                    );
+                }
+                result => break result,
+            }
+        };
+
+        let mut stats = stats.lock().unwrap();
+        stats.batches += 1;
+        stats.retries += attempt as usize;
+        match result {
+            Ok(()) => stats.features += features,
+            Err(Retry::Later(reason) | Retry::Never(reason)) => {
+                error!(
+                    "Failed to post a batch of {} features: {}",
+                    features, reason
+                );
+                stats.failed_batches += 1;
+            }
+        }
+    }
+}
+
+/// Why a request failed, and whether trying again could help.
+enum Retry {
+    Later(String),
+    Never(String),
+}
+
+fn post(agent: &ureq::Agent, options: &PostOptions, body: &[u8]) -> Result<(), Retry> {
+    let mut request = agent
+        .post(&options.url)
+        .header("Content-Type", options.content_type);
+    if let Some(encoding) = options.content_encoding {
+        request = request.header("Content-Encoding", encoding);
+    }
+    let response = request
+        .send(body)
+        .map_err(|e| Retry::Later(e.to_string()))?;
+    let status = response.status();
+    match status.as_u16() {
+        200..=299 => Ok(()),
+        // Overloaded or failing servers may recover
+        429 | 500..=599 => Err(Retry::Later(format!("HTTP {}", status))),
+        _ => Err(Retry::Never(format!("HTTP {}", status))),
+    }
+}
+
+// Delay before retry `attempt` (0-based): exponential with jitter, so
+// concurrent workers do not retry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let delay = BASE_BACKOFF
+        .saturating_mul(1 << attempt.min(16))
+        .min(MAX_BACKOFF);
+    delay.mul_f64(random::rng().random_range(0.5..1.0))
+}
+
+/// Validates a POST URL before anything is generated.
+pub fn validate_url(url: &str) -> RandomGeojsonResult<()> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(RandomGeojsonError::InvalidArgument(format!(
+            "POST URL must start with http:// or https://: {}",
+            url
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        assert!(backoff(0) <= BASE_BACKOFF && backoff(0) >= BASE_BACKOFF / 2);
+        assert!(backoff(3) >= BASE_BACKOFF * 4);
+        assert!(backoff(30) <= MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_post_retries_server_errors() {
+        // A server failing the first request and accepting the second
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ingest", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let mut bodies = Vec::new();
+            for status in ["503 Service Unavailable", "204 No Content"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 4096];
+                // Read until the whole body arrived
+                while !String::from_utf8_lossy(&request).ends_with("[1,2]") {
+                    let n = stream.read(&mut buffer).unwrap();
+                    request.extend_from_slice(&buffer[..n]);
+                }
+                bodies.push(String::from_utf8(request).unwrap());
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                )
+                .unwrap();
+            }
+            bodies
+        });
+
+        let poster = Poster::new(PostOptions {
+            url,
+            concurrency: 1,
+            retries: 2,
+            content_type: "application/json",
+            content_encoding: None,
+        });
+        poster.send(b"[1,2]".to_vec(), 2);
+        let stats = poster.finish();
+        assert_eq!(
+            stats,
+            PostStats {
+                batches: 1,
+                features: 2,
+                failed_batches: 0,
+                retries: 1,
+            }
+        );
+        let bodies = server.join().unwrap();
+        assert!(bodies[0].starts_with("POST /ingest"));
+        assert!(
+            bodies[0]
+                .to_lowercase()
+                .contains("content-type: application/json")
+        );
+    }
+}
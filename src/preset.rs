@@ -0,0 +1,406 @@
+use std::f64::consts::TAU;
+
+use geojson::JsonObject;
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use serde_json::Value;
+
+use crate::anchor::{offset, project, project_one};
+use crate::error::RandomGeojsonError;
+use crate::geometry::{Crs, RandomGeometry, random_geographic_coords};
+use crate::random::{self, Random};
+use crate::shape::footprint;
+
+/// A built-in profile of believable demo data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// Clusters of points around a hub city, with `name` and `population`.
+    Cities,
+    /// Connected road networks, with `highway` class, `lanes` and `maxspeed`.
+    Roads,
+    /// Towns of rotated rectangles and L and T shapes of 50 to 2000 m², with
+    /// `building` type, `height` and `levels`.
+    Buildings,
+    /// Irregular lake outlines, with `name`, `water` and `area_km2`.
+    Lakes,
+}
+
+/// A feature generated by a [`Preset`].
+pub struct PresetFeature {
+    pub geometry: RandomGeometry,
+    pub properties: JsonObject,
+}
+
+impl Preset {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Preset::Cities => "cities",
+            Preset::Roads => "roads",
+            Preset::Buildings => "buildings",
+            Preset::Lakes => "lakes",
+        }
+    }
+
+    /// Generates `length` features of this preset.
+    pub fn generate(&self, crs: &Crs, length: usize) -> Vec<PresetFeature> {
+        let mut features = Vec::with_capacity(length);
+        while features.len() < length {
+            let remaining = length - features.len();
+            match self {
+                Preset::Cities => city_cluster(crs, remaining, &mut features),
+                Preset::Roads => road_network(crs, remaining, &mut features),
+                Preset::Buildings => town(crs, remaining, &mut features),
+                Preset::Lakes => features.push(lake(crs)),
+            }
+        }
+        features
+    }
+}
+
+impl std::str::FromStr for Preset {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cities" => Ok(Preset::Cities),
+            "roads" => Ok(Preset::Roads),
+            "buildings" => Ok(Preset::Buildings),
+            "lakes" => Ok(Preset::Lakes),
+            _ => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid preset: {} (expected cities, roads, buildings or lakes)",
+                s
+            ))),
+        }
+    }
+}
+
+// Up to `max` cities around a hub, denser near it.
+fn city_cluster(crs: &Crs, max: usize, features: &mut Vec<PresetFeature>) {
+    let mut rng = random::rng();
+    let (lon, lat) = random_geographic_coords(crs);
+    for i in 0..rng.random_range(5..=30).min(max) {
+        let (position, population) = if i == 0 {
+            ((0.0, 0.0), 10f64.powf(rng.random_range(5.0..7.0)))
+        } else {
+            let distance = 60_000.0 * rng.random::<f64>().powi(2);
+            (
+                polar(&mut rng, distance),
+                10f64.powf(rng.random_range(3.0..5.5)),
+            )
+        };
+        let mut properties = JsonObject::new();
+        properties.insert("name".to_string(), place_name(&mut rng).into());
+        properties.insert("population".to_string(), (population as u64).into());
+        features.push(PresetFeature {
+            geometry: RandomGeometry::Point(project_one(
+                crs,
+                offset(lon, lat, position.0, position.1),
+            )),
+            properties,
+        });
+    }
+}
+
+/// A road class: OSM `highway` value, relative frequency, lanes, speed limit
+/// in km/h and range of segment lengths in metres.
+struct RoadClass {
+    highway: &'static str,
+    weight: u32,
+    lanes: u32,
+    maxspeed: u32,
+    segment: (f64, f64),
+}
+
+const ROAD_CLASSES: [RoadClass; 6] = [
+    RoadClass {
+        highway: "motorway",
+        weight: 1,
+        lanes: 3,
+        maxspeed: 120,
+        segment: (500.0, 2000.0),
+    },
+    RoadClass {
+        highway: "trunk",
+        weight: 2,
+        lanes: 2,
+        maxspeed: 100,
+        segment: (300.0, 1500.0),
+    },
+    RoadClass {
+        highway: "primary",
+        weight: 4,
+        lanes: 2,
+        maxspeed: 80,
+        segment: (200.0, 800.0),
+    },
+    RoadClass {
+        highway: "secondary",
+        weight: 6,
+        lanes: 2,
+        maxspeed: 60,
+        segment: (150.0, 500.0),
+    },
+    RoadClass {
+        highway: "tertiary",
+        weight: 8,
+        lanes: 1,
+        maxspeed: 50,
+        segment: (100.0, 400.0),
+    },
+    RoadClass {
+        highway: "residential",
+        weight: 20,
+        lanes: 1,
+        maxspeed: 30,
+        segment: (50.0, 200.0),
+    },
+];
+
+// Up to `max` roads, each starting at a vertex of an earlier one so the
+// network is connected.
+fn road_network(crs: &Crs, max: usize, features: &mut Vec<PresetFeature>) {
+    let mut rng = random::rng();
+    let (lon, lat) = random_geographic_coords(crs);
+    let mut junctions = vec![(0.0, 0.0)];
+    for _ in 0..rng.random_range(20..=100).min(max) {
+        let class = ROAD_CLASSES
+            .choose_weighted(&mut rng, |class| class.weight)
+            .expect("the weights are positive");
+        let mut current: (f64, f64) = *junctions.choose(&mut rng).expect("never empty");
+        let mut heading = rng.random_range(0.0..TAU);
+        let mut vertices = vec![current];
+        for _ in 0..rng.random_range(2..=6) {
+            heading += rng.random_range(-0.3..0.3);
+            let step = rng.random_range(class.segment.0..class.segment.1);
+            current = (
+                current.0 + step * heading.cos(),
+                current.1 + step * heading.sin(),
+            );
+            vertices.push(current);
+        }
+        junctions.extend_from_slice(&vertices[1..]);
+
+        let coords: Vec<(f64, f64)> = vertices
+            .iter()
+            .map(|&(east, north)| offset(lon, lat, east, north))
+            .collect();
+        let mut properties = JsonObject::new();
+        properties.insert("highway".to_string(), class.highway.into());
+        properties.insert("lanes".to_string(), class.lanes.into());
+        properties.insert("maxspeed".to_string(), class.maxspeed.into());
+        features.push(PresetFeature {
+            geometry: RandomGeometry::LineString(project(crs, &coords)),
+            properties,
+        });
+    }
+}
+
+/// A building type: OSM `building` value, relative frequency and ranges of
+/// footprint area in square metres and height in metres.
+struct BuildingType {
+    building: &'static str,
+    weight: u32,
+    area: (f64, f64),
+    height: (f64, f64),
+}
+
+const BUILDING_TYPES: [BuildingType; 5] = [
+    BuildingType {
+        building: "house",
+        weight: 60,
+        area: (50.0, 250.0),
+        height: (4.0, 9.0),
+    },
+    BuildingType {
+        building: "apartments",
+        weight: 15,
+        area: (200.0, 1500.0),
+        height: (10.0, 40.0),
+    },
+    BuildingType {
+        building: "commercial",
+        weight: 10,
+        area: (200.0, 2000.0),
+        height: (6.0, 25.0),
+    },
+    BuildingType {
+        building: "industrial",
+        weight: 10,
+        area: (500.0, 2000.0),
+        height: (5.0, 15.0),
+    },
+    BuildingType {
+        building: "retail",
+        weight: 5,
+        area: (100.0, 1000.0),
+        height: (4.0, 10.0),
+    },
+];
+
+// Up to `max` buildings within 1.5 km of a town centre, shaped as
+// rectangles and L and T shapes.
+fn town(crs: &Crs, max: usize, features: &mut Vec<PresetFeature>) {
+    let mut rng = random::rng();
+    let (lon, lat) = random_geographic_coords(crs);
+    for _ in 0..rng.random_range(20..=200).min(max) {
+        let kind = BUILDING_TYPES
+            .choose_weighted(&mut rng, |kind| kind.weight)
+            .expect("the weights are positive");
+        let distance = 1500.0 * rng.random::<f64>().sqrt();
+        let centre = polar(&mut rng, distance);
+        let area = rng.random_range(kind.area.0..kind.area.1);
+        let mut ring: Vec<(f64, f64)> = footprint(area)
+            .into_iter()
+            .map(|(x, y)| offset(lon, lat, centre.0 + x, centre.1 + y))
+            .collect();
+        ring.push(ring[0]);
+
+        let height = round(rng.random_range(kind.height.0..kind.height.1), 1);
+        let mut properties = JsonObject::new();
+        properties.insert("building".to_string(), kind.building.into());
+        properties.insert("height".to_string(), height.into());
+        properties.insert(
+            "levels".to_string(),
+            ((height / 3.0).round().max(1.0) as u32).into(),
+        );
+        features.push(PresetFeature {
+            geometry: RandomGeometry::Polygon(vec![project(crs, &ring)]),
+            properties,
+        });
+    }
+}
+
+// A lake with a wobbly outline 200 m to 5 km across. The radius varies
+// smoothly with the angle, so the outline never crosses itself.
+fn lake(crs: &Crs) -> PresetFeature {
+    let mut rng = random::rng();
+    let (lon, lat) = random_geographic_coords(crs);
+    let radius = 10f64.powf(rng.random_range(2.0..3.4));
+    let phases: [f64; 2] = [rng.random_range(0.0..TAU), rng.random_range(0.0..TAU)];
+    let vertices = rng.random_range(16..=32);
+    let local: Vec<(f64, f64)> = (0..vertices)
+        .map(|i| {
+            let angle = TAU * i as f64 / vertices as f64;
+            let wobble = 0.25 * (2.0 * angle + phases[0]).sin()
+                + 0.15 * (3.0 * angle + phases[1]).sin()
+                + rng.random_range(-0.08..0.08);
+            let r = radius * (1.0 + wobble);
+            (r * angle.cos(), r * angle.sin())
+        })
+        .collect();
+    // Shoelace formula; the vertices run counter-clockwise
+    let area: f64 = (0..local.len())
+        .map(|i| {
+            let (a, b) = (local[i], local[(i + 1) % local.len()]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum::<f64>()
+        / 2.0;
+    let mut ring: Vec<(f64, f64)> = local
+        .iter()
+        .map(|&(east, north)| offset(lon, lat, east, north))
+        .collect();
+    ring.push(ring[0]);
+
+    let (water, name) = match radius {
+        r if r < 300.0 => ("pond", format!("{} Pond", place_name(&mut rng))),
+        _ if rng.random_bool(0.2) => ("reservoir", format!("{} Reservoir", place_name(&mut rng))),
+        _ => ("lake", format!("Lake {}", place_name(&mut rng))),
+    };
+    let mut properties = JsonObject::new();
+    properties.insert("name".to_string(), name.into());
+    properties.insert("water".to_string(), water.into());
+    properties.insert("area_km2".to_string(), Value::from(round(area / 1e6, 3)));
+    PresetFeature {
+        geometry: RandomGeometry::Polygon(vec![project(crs, &ring)]),
+        properties,
+    }
+}
+
+// A local offset in metres `distance` away in a random direction.
+fn polar(rng: &mut Random, distance: f64) -> (f64, f64) {
+    let (sin, cos) = rng.random_range(0.0..TAU).sin_cos();
+    (distance * cos, distance * sin)
+}
+
+// An English-sounding place name such as "Ashford".
+fn place_name(rng: &mut Random) -> String {
+    const STARTS: [&str; 20] = [
+        "Ash", "Bel", "Brook", "Cal", "Dun", "East", "Fair", "Glen", "Hal", "King", "Lin", "Mar",
+        "New", "Oak", "Port", "Red", "Sal", "Stan", "West", "Wood",
+    ];
+    const ENDS: [&str; 15] = [
+        "ford", "ton", "bury", "field", "ham", "mouth", "ville", "wick", "stead", "dale", "port",
+        "haven", "worth", "by", "ley",
+    ];
+    format!(
+        "{}{}",
+        STARTS.choose(rng).expect("never empty"),
+        ENDS.choose(rng).expect("never empty")
+    )
+}
+
+fn round(value: f64, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    (value * factor).round() / factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_properties() {
+        for (preset, keys) in [
+            (Preset::Cities, ["name", "population"].as_slice()),
+            (Preset::Roads, &["highway", "lanes", "maxspeed"]),
+            (Preset::Buildings, &["building", "height", "levels"]),
+            (Preset::Lakes, &["area_km2", "name", "water"]),
+        ] {
+            let features = preset.generate(&Crs::WGS84, 250);
+            assert_eq!(features.len(), 250);
+            for feature in &features {
+                let mut found: Vec<&str> = feature.properties.keys().map(|k| k.as_str()).collect();
+                found.sort();
+                assert_eq!(found, keys, "{}", preset.as_str());
+            }
+        }
+    }
+
+    #[test]
+    fn test_roads_are_connected() {
+        let features = Preset::Roads.generate(&Crs::WGS84, 20);
+        let lines: Vec<Vec<Vec<f64>>> = features
+            .into_iter()
+            .map(|feature| match feature.geometry {
+                RandomGeometry::LineString(line) => line,
+                _ => panic!("roads are linestrings"),
+            })
+            .collect();
+        // At most 20 roads form a single network, and every road after the
+        // first starts on one before it
+        for (i, line) in lines.iter().enumerate().skip(1) {
+            assert!(
+                lines[..i]
+                    .iter()
+                    .any(|earlier| earlier.contains(&line[0]) || earlier[0] == line[0])
+            );
+        }
+    }
+
+    #[test]
+    fn test_buildings_are_footprints() {
+        for feature in Preset::Buildings.generate(&Crs::WGS84, 50) {
+            let RandomGeometry::Polygon(rings) = feature.geometry else {
+                panic!("buildings are polygons");
+            };
+            // Rectangles, L and T shapes
+            assert!([5, 7, 9].contains(&rings[0].len()));
+            assert_eq!(rings[0].first(), rings[0].last());
+            let height = feature.properties["height"].as_f64().unwrap();
+            assert!((4.0..=40.0).contains(&height));
+        }
+        assert_eq!("Lakes".parse::<Preset>().unwrap(), Preset::Lakes);
+        assert!("forests".parse::<Preset>().is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use std::cell::Cell;
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// Least time between two redraws of the bar.
+const REDRAW: Duration = Duration::from_millis(100);
+
+/// Characters of the bar itself.
+const WIDTH: usize = 30;
+
+/// A progress bar on stderr for long generations: features generated out
+/// of the total, their rate and the time left, then what was written.
+///
+/// It draws nothing when hidden or when stderr is not a terminal, so logs
+/// and pipes never see it.
+pub struct Progress {
+    state: Option<State>,
+}
+
+struct State {
+    total: usize,
+    start: Instant,
+    last_draw: Cell<Option<Instant>>,
+}
+
+impl Progress {
+    /// A bar towards `total` features, drawn if `enabled` and stderr is a
+    /// terminal.
+    pub fn new(total: usize, enabled: bool) -> Self {
+        let state = (enabled && std::io::stderr().is_terminal()).then(|| State {
+            total,
+            start: Instant::now(),
+            last_draw: Cell::new(None),
+        });
+        Progress { state }
+    }
+
+    /// A bar that is never drawn.
+    pub fn hidden() -> Self {
+        Progress { state: None }
+    }
+
+    /// Reports that `done` features were generated so far, redrawing the bar
+    /// at most ten times a second.
+    pub fn set(&self, done: usize) {
+        let Some(state) = &self.state else {
+            return;
+        };
+        let now = Instant::now();
+        if state
+            .last_draw
+            .get()
+            .is_some_and(|last| now - last < REDRAW && done < state.total)
+        {
+            return;
+        }
+        state.last_draw.set(Some(now));
+        draw(&bar(done, state.total, now - state.start));
+    }
+
+    /// Replaces the bar with a short status, e.g. "Encoding".
+    pub fn message(&self, message: &str) {
+        if self.state.is_some() {
+            draw(&format!("{}...", message));
+        }
+    }
+
+    /// Replaces the bar with a summary of the features generated and the
+    /// bytes written.
+    pub fn finish(&self, features: usize, bytes: usize) {
+        let Some(state) = &self.state else {
+            return;
+        };
+        draw(&format!(
+            "Generated {} features, wrote {} in {}",
+            features,
+            human_bytes(bytes),
+            human_duration(state.start.elapsed())
+        ));
+        eprintln!();
+    }
+}
+
+fn draw(line: &str) {
+    let mut stderr = std::io::stderr().lock();
+    // Erase to the end of the line, which may be longer than the new one
+    let _ = write!(stderr, "\r{}\x1b[K", line);
+    let _ = stderr.flush();
+}
+
+// The bar line after `elapsed` with `done` of `total` features generated.
+fn bar(done: usize, total: usize, elapsed: Duration) -> String {
+    let share = if total == 0 {
+        1.0
+    } else {
+        (done as f64 / total as f64).min(1.0)
+    };
+    let filled = (share * WIDTH as f64).round() as usize;
+    let rate = done as f64 / elapsed.as_secs_f64().max(1e-9);
+    let eta = if done == 0 {
+        "?".to_string()
+    } else {
+        human_duration(Duration::from_secs_f64(
+            total.saturating_sub(done) as f64 / rate,
+        ))
+    };
+    format!(
+        "[{}{}] {:>3}% {}/{} features, {:.0}/s, ETA {}",
+        "#".repeat(filled),
+        "-".repeat(WIDTH - filled),
+        (share * 100.0).floor(),
+        done,
+        total,
+        rate,
+        eta
+    )
+}
+
+/// Formats a number of bytes with a binary unit, e.g. "1.5 MiB".
+pub fn human_bytes(bytes: usize) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, units[unit])
+    }
+}
+
+/// Formats a duration to the second, or to the millisecond below a second.
+pub fn human_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0 => format!("{}ms", duration.as_millis()),
+        1..60 => format!("{}s", secs),
+        60..3600 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar() {
+        let line = bar(250, 1000, Duration::from_secs(1));
+        assert!(line.starts_with(&format!("[{}{}]", "#".repeat(8), "-".repeat(22))));
+        assert!(
+            line.ends_with(" 25% 250/1000 features, 250/s, ETA 3s"),
+            "{}",
+            line
+        );
+        assert!(bar(0, 10, Duration::ZERO).ends_with("ETA ?"));
+        assert!(bar(0, 0, Duration::ZERO).contains("100%"));
+    }
+
+    #[test]
+    fn test_human_units() {
+        assert_eq!(human_bytes(512), "512 B");
+        assert_eq!(human_bytes(1536 * 1024), "1.5 MiB");
+        assert_eq!(human_duration(Duration::from_millis(250)), "250ms");
+        assert_eq!(human_duration(Duration::from_secs(125)), "2m05s");
+        assert_eq!(human_duration(Duration::from_secs(7260)), "2h01m");
+    }
+}
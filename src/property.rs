@@ -0,0 +1,875 @@
+use std::collections::HashSet;
+use std::sync::{Arc, OnceLock};
+
+use geojson::{FeatureCollection, JsonObject};
+use rand::Rng;
+use rand::distr::weighted::WeightedIndex;
+use serde_json::Value;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::expr::Expr;
+use crate::pattern::Pattern;
+use crate::random;
+use crate::series::Series;
+use crate::template::Template;
+
+/// Word list random strings are drawn from instead of English words, once
+/// one is in use.
+static WORDLIST: OnceLock<Arc<Wordlist>> = OnceLock::new();
+
+/// Languages random strings are made of words of, once any are chosen.
+static LANGUAGES: OnceLock<Vec<Language>> = OnceLock::new();
+
+/// Most values drawn for a feature of a unique property before giving up.
+const MAX_UNIQUE_ATTEMPTS: usize = 1000;
+
+/// A language of the words of random string properties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    German,
+    English,
+    Spanish,
+    French,
+    Japanese,
+    Russian,
+    Chinese,
+}
+
+impl Language {
+    fn words(&self) -> &'static [&'static str] {
+        let lang = match self {
+            Language::German => random_word::Lang::De,
+            Language::English => random_word::Lang::En,
+            Language::Spanish => random_word::Lang::Es,
+            Language::French => random_word::Lang::Fr,
+            Language::Japanese => random_word::Lang::Ja,
+            Language::Russian => random_word::Lang::Ru,
+            Language::Chinese => random_word::Lang::Zh,
+        };
+        random_word::all(lang)
+    }
+}
+
+/// Parses an ISO 639-1 language code, e.g. "de".
+impl std::str::FromStr for Language {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "de" => Ok(Language::German),
+            "en" => Ok(Language::English),
+            "es" => Ok(Language::Spanish),
+            "fr" => Ok(Language::French),
+            "ja" => Ok(Language::Japanese),
+            "ru" => Ok(Language::Russian),
+            "zh" => Ok(Language::Chinese),
+            _ => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid language: {} (expected de, en, es, fr, ja, ru or zh)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Makes random string properties of words of `languages`, each string in
+/// one of them drawn at random, for the rest of the process. Only the first
+/// languages given are used.
+pub fn use_languages(languages: &[Language]) {
+    if !languages.is_empty() {
+        let _ = LANGUAGES.set(languages.to_vec());
+    }
+}
+
+/// Words or phrases for random string properties, such as street names or
+/// species, each drawn with its own weight.
+#[derive(Debug)]
+pub struct Wordlist {
+    entries: Vec<String>,
+    weights: WeightedIndex<f64>,
+}
+
+impl Wordlist {
+    /// Parses a word list of one word or phrase per line, optionally
+    /// followed by a tab and a weight, 1 by default. Blank lines and lines
+    /// starting with "#" are skipped.
+    pub fn parse(text: &str) -> RandomGeojsonResult<Self> {
+        let mut entries = Vec::new();
+        let mut weights = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (entry, weight) = match line.rsplit_once('\t') {
+                Some((entry, weight)) => {
+                    let weight = weight
+                        .trim()
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|w| *w >= 0.0 && w.is_finite())
+                        .ok_or_else(|| {
+                            RandomGeojsonError::InvalidInput(format!(
+                                "Invalid weight on line {} of the word list: {}",
+                                i + 1,
+                                weight
+                            ))
+                        })?;
+                    (entry, weight)
+                }
+                None => (line, 1.0),
+            };
+            entries.push(entry.trim().to_string());
+            weights.push(weight);
+        }
+        let weights = WeightedIndex::new(&weights).map_err(|_| {
+            RandomGeojsonError::InvalidInput(
+                "The word list needs at least one entry with a weight above 0".to_string(),
+            )
+        })?;
+        Ok(Wordlist { entries, weights })
+    }
+
+    /// Reads a word list file, see [`Wordlist::parse`].
+    pub fn read(path: &str) -> RandomGeojsonResult<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| RandomGeojsonError::Io(format!("Failed to read {}: {}", path, e)))?;
+        Wordlist::parse(&text)
+    }
+
+    /// Draws an entry according to the weights.
+    pub fn pick(&self) -> &str {
+        &self.entries[random::rng().sample(&self.weights)]
+    }
+}
+
+/// Draws random string properties from `wordlist`, one entry per string,
+/// for the rest of the process. Only the first word list given is used.
+pub fn use_wordlist(wordlist: Arc<Wordlist>) {
+    let _ = WORDLIST.set(wordlist);
+}
+
+/// Generates a random number, string or boolean value.
+pub fn random_property_value() -> Value {
+    let mut rng = random::rng();
+    match rng.random_range(0..3) {
+        0 => random_int(),
+        1 => random_string(),
+        2 => random_bool(),
+        _ => unreachable!(),
+    }
+}
+
+/// Generates a random value of the same JSON type as `value`: numbers stay
+/// integers or floats, and arrays and objects keep their length and keys with
+/// every member replaced. Nulls are kept.
+pub fn random_value_like(value: &Value) -> Value {
+    match value {
+        Value::Null => Value::Null,
+        Value::Bool(_) => random_bool(),
+        Value::Number(n) if n.is_f64() => random_float(),
+        Value::Number(_) => random_int(),
+        Value::String(_) => random_string(),
+        Value::Array(items) => Value::Array(items.iter().map(random_value_like).collect()),
+        Value::Object(members) => Value::Object(
+            members
+                .iter()
+                .map(|(key, value)| (key.clone(), random_value_like(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn random_int() -> Value {
+    Value::Number(random::rng().random_range(0..1000).into())
+}
+
+fn random_float() -> Value {
+    Value::from(random::rng().random_range(0.0..1000.0))
+}
+
+fn random_int_in(min: i64, max: i64) -> Value {
+    Value::from(random::rng().random_range(min..=max))
+}
+
+fn random_float_in(min: f64, max: f64) -> Value {
+    if min == max {
+        return Value::from(min);
+    }
+    Value::from(random::rng().random_range(min..max))
+}
+
+fn random_string() -> Value {
+    if let Some(wordlist) = WORDLIST.get() {
+        return Value::String(wordlist.pick().to_string());
+    }
+    let words = random_language().words();
+    let mut rng = random::rng();
+    Value::String(
+        (0..rng.random_range(3..10))
+            .map(|_| words[rng.random_range(0..words.len())])
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// Draws a word of the languages in use, or an entry of the word list.
+pub fn random_word() -> String {
+    if let Some(wordlist) = WORDLIST.get() {
+        return wordlist.pick().to_string();
+    }
+    let words = random_language().words();
+    words[random::rng().random_range(0..words.len())].to_string()
+}
+
+fn random_language() -> Language {
+    match LANGUAGES.get() {
+        Some(languages) => languages[random::rng().random_range(0..languages.len())],
+        None => Language::English,
+    }
+}
+
+fn random_bool() -> Value {
+    random_bool_with(0.5)
+}
+
+fn random_bool_with(probability: f64) -> Value {
+    Value::Bool(random::rng().random_bool(probability))
+}
+
+/// Number of random properties of each feature, drawn uniformly from
+/// `min` to `max` inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PropertyCount {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl PropertyCount {
+    /// Draws the number of properties of a feature. A single count takes no
+    /// draw from the random number generator.
+    pub fn draw(&self) -> usize {
+        if self.min == self.max {
+            return self.min;
+        }
+        random::rng().random_range(self.min..=self.max)
+    }
+}
+
+impl From<usize> for PropertyCount {
+    fn from(count: usize) -> Self {
+        PropertyCount {
+            min: count,
+            max: count,
+        }
+    }
+}
+
+/// Parses a count such as "5" or a range such as "2..8".
+impl std::str::FromStr for PropertyCount {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            RandomGeojsonError::InvalidArgument(format!(
+                "Invalid number of properties: {}, expected zero or more such as \"5\" or a range such as \"2..8\"",
+                s
+            ))
+        };
+        let (min, max) = s.split_once("..").unwrap_or((s, s));
+        let count = |value: &str| value.trim().parse::<usize>().map_err(|_| invalid());
+        let (min, max) = (count(min)?, count(max)?);
+        if min > max {
+            return Err(invalid());
+        }
+        Ok(PropertyCount { min, max })
+    }
+}
+
+/// Numbers of random properties of each primitive type, e.g. three ints and
+/// two strings, named `prop1`, `prop2` and so on in the order given, so every
+/// feature has the same columns with the same types.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyTypes(pub Vec<(PropertyKind, usize)>);
+
+impl PropertyTypes {
+    /// Adds the random properties to `properties`.
+    pub fn add_to(&self, properties: &mut JsonObject) {
+        let kinds = self
+            .0
+            .iter()
+            .flat_map(|(kind, count)| std::iter::repeat_n(kind, *count));
+        for (i, kind) in kinds.enumerate() {
+            let value = match kind {
+                PropertyKind::Int { min, max } => random_int_in(*min, *max),
+                PropertyKind::Float { min, max } => random_float_in(*min, *max),
+                PropertyKind::Bool { probability } => random_bool_with(*probability),
+                _ => random_string(),
+            };
+            properties.insert(format!("prop{}", i + 1), value);
+        }
+    }
+
+    /// Total number of properties.
+    pub fn count(&self) -> usize {
+        self.0.iter().map(|(_, count)| count).sum()
+    }
+}
+
+/// Parses comma-separated counts of types, e.g. "int=3,string=2,bool=1".
+impl std::str::FromStr for PropertyTypes {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            RandomGeojsonError::InvalidArgument(format!(
+                "Invalid property types '{}': {}, expected e.g. \"int=3,string=2,bool=1,float=2\"",
+                s, reason
+            ))
+        };
+        let mut types: Vec<(PropertyKind, usize)> = Vec::new();
+        for entry in s.split(',') {
+            let (kind, count) = entry
+                .split_once('=')
+                .ok_or_else(|| invalid("expected type=count"))?;
+            let kind = match kind.trim().to_lowercase().as_str() {
+                "int" | "integer" => PropertyKind::INT,
+                "float" | "number" => PropertyKind::FLOAT,
+                "string" | "str" => PropertyKind::String,
+                "bool" | "boolean" => PropertyKind::BOOL,
+                _ => return Err(invalid("unknown type")),
+            };
+            let count = count
+                .trim()
+                .parse()
+                .map_err(|_| invalid("counts must be zero or more"))?;
+            if types.iter().any(|(k, _)| *k == kind) {
+                return Err(invalid("repeated type"));
+            }
+            types.push((kind, count));
+        }
+        Ok(PropertyTypes(types))
+    }
+}
+
+/// Type of a user-defined property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyKind {
+    /// An integer from `min` to `max` inclusive.
+    Int {
+        min: i64,
+        max: i64,
+    },
+    /// A number from `min` up to `max`.
+    Float {
+        min: f64,
+        max: f64,
+    },
+    String,
+    /// True with the given probability.
+    Bool {
+        probability: f64,
+    },
+    /// A score in [0, 1]. When `label` names a boolean property, scores are
+    /// calibrated against it: features where the label is true (e.g. an
+    /// outlier or defect flag) get low confidence, the others high confidence.
+    Probability {
+        label: Option<String>,
+    },
+    /// A string matching a regular expression, e.g. for ids or postcodes.
+    Pattern(Pattern),
+    /// A string filled in from other properties of the feature and its
+    /// index once it is complete, by [`finish_properties`].
+    Template(Template),
+    /// An array of numbers, with an array of timestamps in a `<name>_times`
+    /// property if the series has an interval.
+    Series(Series),
+    /// A value computed from other properties of the feature and its index
+    /// once it is complete, by [`finish_properties`].
+    Expr(Expr),
+}
+
+impl PropertyKind {
+    /// Integers of the range of random properties, from 0 to 999.
+    pub const INT: PropertyKind = PropertyKind::Int { min: 0, max: 999 };
+
+    /// Numbers of the range of random properties, from 0 up to 1000.
+    pub const FLOAT: PropertyKind = PropertyKind::Float {
+        min: 0.0,
+        max: 1000.0,
+    };
+
+    /// Booleans as likely true as false, like random properties.
+    pub const BOOL: PropertyKind = PropertyKind::Bool { probability: 0.5 };
+
+    /// Names of the properties a derived property refers to, or `None` if
+    /// the property is not derived from others.
+    fn derived_from(&self) -> Option<Vec<&str>> {
+        match self {
+            PropertyKind::Template(template) => Some(template.properties().collect()),
+            PropertyKind::Expr(expr) => Some(expr.properties()),
+            _ => None,
+        }
+    }
+}
+
+/// A named property given on the command line as `name:type`, e.g.
+/// `height:float` or `confidence:probability(is_defect)`, optionally
+/// followed by `:unique`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertySpec {
+    pub name: String,
+    pub kind: PropertyKind,
+    /// No two features of a collection share a value, other than null.
+    pub unique: bool,
+}
+
+impl PropertySpec {
+    /// Adds a value for this property to `properties`, the values already
+    /// generated for the same feature, and the timestamps of a series.
+    pub fn add_to(&self, properties: &mut JsonObject) {
+        let value = self.generate(properties);
+        properties.insert(self.name.clone(), value);
+        if let PropertyKind::Series(series) = &self.kind
+            && let Some(times) = series.timestamps()
+        {
+            properties.insert(format!("{}_times", self.name), times);
+        }
+    }
+
+    /// Generates a value for this property. `properties` holds the values
+    /// already generated for the same feature.
+    pub fn generate(&self, properties: &JsonObject) -> Value {
+        match &self.kind {
+            PropertyKind::Int { min, max } => random_int_in(*min, *max),
+            PropertyKind::Float { min, max } => random_float_in(*min, *max),
+            PropertyKind::String => random_string(),
+            PropertyKind::Bool { probability } => random_bool_with(*probability),
+            PropertyKind::Probability { label } => {
+                let label = label
+                    .as_ref()
+                    .and_then(|l| properties.get(l))
+                    .and_then(Value::as_bool);
+                Value::from(random_probability(label))
+            }
+            PropertyKind::Pattern(pattern) => Value::String(pattern.generate()),
+            PropertyKind::Series(series) => series.values(),
+            // A placeholder, keeping the property in its place
+            PropertyKind::Template(_) | PropertyKind::Expr(_) => Value::Null,
+        }
+    }
+}
+
+/// Draws a probability, optionally conditioned on a ground-truth label.
+///
+/// With a 50/50 label and a uniform prior on the score, the score given a
+/// positive (defect) label follows Beta(1, 2) and given a negative label
+/// Beta(2, 1), so that P(not defect | score = s) = s.
+fn random_probability(label: Option<bool>) -> f64 {
+    let u: f64 = random::rng().random();
+    match label {
+        None => u,
+        Some(true) => 1.0 - u.sqrt(),
+        Some(false) => u.sqrt(),
+    }
+}
+
+impl std::str::FromStr for PropertySpec {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            RandomGeojsonError::InvalidArgument(format!("Invalid property '{}': {}", s, reason))
+        };
+
+        let (name, spec) = s
+            .split_once(':')
+            .ok_or_else(|| invalid("expected name:type"))?;
+        if name.is_empty() {
+            return Err(invalid("missing name"));
+        }
+        let (spec, unique) = match spec.strip_suffix(":unique") {
+            Some(spec) => (spec, true),
+            None => (spec, false),
+        };
+
+        let (kind, args) = match spec.split_once('(') {
+            Some((kind, rest)) => {
+                let args = rest
+                    .strip_suffix(')')
+                    .ok_or_else(|| invalid("missing closing parenthesis"))?;
+                (kind, Some(args.trim()))
+            }
+            None => (spec, None),
+        };
+
+        let kind = match (kind.to_lowercase().as_str(), args) {
+            ("int" | "integer", None) => PropertyKind::INT,
+            ("float" | "number", None) => PropertyKind::FLOAT,
+            ("int" | "integer", Some(range)) => {
+                let (min, max) = parse_range::<i64>(range)
+                    .filter(|(min, max)| min <= max)
+                    .ok_or_else(|| invalid("expected int(min,max) with integers min <= max"))?;
+                PropertyKind::Int { min, max }
+            }
+            ("float" | "number", Some(range)) => {
+                let (min, max) = parse_range::<f64>(range)
+                    .filter(|(min, max)| min.is_finite() && max.is_finite() && min <= max)
+                    .ok_or_else(|| invalid("expected float(min,max) with numbers min <= max"))?;
+                PropertyKind::Float { min, max }
+            }
+            ("string" | "str", None) => PropertyKind::String,
+            ("bool" | "boolean", None) => PropertyKind::BOOL,
+            ("bool" | "boolean", Some(probability)) => {
+                let probability = probability
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|p| (0.0..=1.0).contains(p))
+                    .ok_or_else(|| invalid("expected bool(p) with a probability from 0 to 1"))?;
+                PropertyKind::Bool { probability }
+            }
+            ("probability", None) => PropertyKind::Probability { label: None },
+            ("probability", Some(label)) if !label.is_empty() => PropertyKind::Probability {
+                label: Some(label.to_string()),
+            },
+            ("pattern", Some(pattern)) if !pattern.is_empty() => {
+                PropertyKind::Pattern(pattern.parse()?)
+            }
+            ("series", Some(series)) if !series.is_empty() => PropertyKind::Series(series.parse()?),
+            ("template", Some(template)) if !template.is_empty() => {
+                PropertyKind::Template(template.parse()?)
+            }
+            ("expr", Some(expr)) if !expr.is_empty() => PropertyKind::Expr(expr.parse()?),
+            (_, Some(_)) => return Err(invalid("unexpected arguments")),
+            _ => return Err(invalid("unknown type")),
+        };
+
+        Ok(PropertySpec {
+            name: name.to_string(),
+            kind,
+            unique,
+        })
+    }
+}
+
+// Parses the "min,max" of a range.
+fn parse_range<T: std::str::FromStr>(s: &str) -> Option<(T, T)> {
+    let (min, max) = s.split_once(',')?;
+    Some((min.trim().parse().ok()?, max.trim().parse().ok()?))
+}
+
+/// Checks that probabilities are only calibrated against boolean properties
+/// defined before them, and that templates and expressions only refer to
+/// templates and expressions defined before them, which are derived first.
+pub fn validate_property_specs(specs: &[PropertySpec]) -> RandomGeojsonResult<()> {
+    for (i, spec) in specs.iter().enumerate() {
+        let later = spec.kind.derived_from().and_then(|names| {
+            names.into_iter().find(|&name| {
+                specs[i..]
+                    .iter()
+                    .any(|s| s.name == name && s.kind.derived_from().is_some())
+            })
+        });
+        if let Some(name) = later {
+            return Err(RandomGeojsonError::InvalidArgument(format!(
+                "Property '{}' refers to '{}', a template or expression which must be defined before it",
+                spec.name, name
+            )));
+        }
+        if let PropertyKind::Probability { label: Some(label) } = &spec.kind {
+            let defined = specs[..i]
+                .iter()
+                .any(|s| &s.name == label && matches!(s.kind, PropertyKind::Bool { .. }));
+            if !defined {
+                return Err(RandomGeojsonError::InvalidArgument(format!(
+                    "Property '{}' is calibrated against '{}', which must be a bool property defined before it",
+                    spec.name, label
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Finishes the user-defined properties of every feature of `fc`: draws new
+/// values of unique properties where they repeat, then fills in templates
+/// and expressions in the order given, with the index of the feature in the
+/// collection.
+pub fn finish_properties(
+    fc: &mut FeatureCollection,
+    specs: &[PropertySpec],
+) -> RandomGeojsonResult<()> {
+    for spec in specs {
+        if !spec.unique || spec.kind.derived_from().is_some() {
+            continue;
+        }
+        let mut seen = HashSet::new();
+        for feature in &mut fc.features {
+            let Some(properties) = feature.properties.as_mut() else {
+                continue;
+            };
+            let Some(value) = properties.get(&spec.name).cloned() else {
+                continue;
+            };
+            let mut drawn = Some(value);
+            let value = unique_value(spec, &mut seen, || {
+                drawn.take().unwrap_or_else(|| spec.generate(properties))
+            })?;
+            properties.insert(spec.name.clone(), value);
+        }
+    }
+
+    let derived: Vec<&PropertySpec> = specs
+        .iter()
+        .filter(|spec| spec.kind.derived_from().is_some())
+        .collect();
+    if derived.is_empty() {
+        return Ok(());
+    }
+    let mut seen = vec![HashSet::new(); derived.len()];
+    for (index, feature) in fc.features.iter_mut().enumerate() {
+        let properties = feature.properties.get_or_insert_default();
+        for (spec, seen) in derived.iter().zip(&mut seen) {
+            let derive = || match &spec.kind {
+                PropertyKind::Template(template) => {
+                    Value::String(template.render(properties, index))
+                }
+                PropertyKind::Expr(expr) => expr.evaluate(properties, index),
+                _ => Value::Null,
+            };
+            let value = if spec.unique {
+                unique_value(spec, seen, derive)?
+            } else {
+                derive()
+            };
+            properties.insert(spec.name.clone(), value);
+        }
+    }
+    Ok(())
+}
+
+// Draws values of a unique property until one is not in `seen`, or is null.
+fn unique_value(
+    spec: &PropertySpec,
+    seen: &mut HashSet<String>,
+    mut draw: impl FnMut() -> Value,
+) -> RandomGeojsonResult<Value> {
+    for _ in 0..MAX_UNIQUE_ATTEMPTS {
+        let value = draw();
+        if value.is_null() || seen.insert(value.to_string()) {
+            return Ok(value);
+        }
+    }
+    Err(RandomGeojsonError::InvalidArgument(format!(
+        "Property '{}' ran out of unique values after {} features; its type has too few distinct values",
+        spec.name,
+        seen.len()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language() {
+        let language: Language = "JA".parse().unwrap();
+        assert_eq!(language, Language::Japanese);
+        assert!(language.words().iter().all(|w| !w.is_ascii()));
+        assert!("xx".parse::<Language>().is_err());
+    }
+
+    #[test]
+    fn test_property_types() {
+        let types: PropertyTypes = "int=2, string=1,bool=0,float=1".parse().unwrap();
+        assert_eq!(types.count(), 4);
+        let mut properties = JsonObject::new();
+        types.add_to(&mut properties);
+        let keys: Vec<&str> = properties.keys().map(String::as_str).collect();
+        assert_eq!(keys.len(), 4);
+        assert!(properties["prop1"].is_i64() && properties["prop2"].is_i64());
+        assert!(properties["prop3"].is_string());
+        assert!(properties["prop4"].is_f64());
+        for s in ["int", "int=-1", "date=2", "int=1,int=2", ""] {
+            assert!(s.parse::<PropertyTypes>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_wordlist() {
+        let wordlist =
+            Wordlist::parse("# streets\nMain Street\t3\n\nHigh Street\nMill Lane\t0\n").unwrap();
+        assert_eq!(
+            wordlist.entries,
+            ["Main Street", "High Street", "Mill Lane"]
+        );
+        let picks: Vec<&str> = (0..1000).map(|_| wordlist.pick()).collect();
+        let main = picks.iter().filter(|&&p| p == "Main Street").count();
+        assert!((650..850).contains(&main), "{}", main);
+        assert!(!picks.contains(&"Mill Lane"));
+        assert!(Wordlist::parse("Main Street\tmany\n").is_err());
+        assert!(Wordlist::parse("# only a comment\n").is_err());
+    }
+
+    #[test]
+    fn test_property_count() {
+        assert_eq!(
+            "5".parse::<PropertyCount>().unwrap(),
+            PropertyCount::from(5)
+        );
+        let range: PropertyCount = "2..8".parse().unwrap();
+        assert_eq!(range, PropertyCount { min: 2, max: 8 });
+        assert!((0..100).map(|_| range.draw()).all(|n| (2..=8).contains(&n)));
+        assert!("8..2".parse::<PropertyCount>().is_err());
+        assert!("-1".parse::<PropertyCount>().is_err());
+    }
+
+    #[test]
+    fn test_random_value_like() {
+        let value = serde_json::json!({
+            "name": "Main Street",
+            "lanes": 2,
+            "width": 7.5,
+            "oneway": true,
+            "ref": null,
+            "tags": ["a", 1],
+        });
+        let random = random_value_like(&value);
+        assert!(random["name"].is_string() && random["name"] != value["name"]);
+        assert!(random["lanes"].is_u64());
+        assert!(random["width"].is_f64());
+        assert!(random["oneway"].is_boolean());
+        assert!(random["ref"].is_null());
+        assert!(random["tags"][0].is_string() && random["tags"][1].is_u64());
+    }
+
+    #[test]
+    fn test_property_spec_from_str() {
+        let spec: PropertySpec = "height:float".parse().unwrap();
+        assert_eq!(spec.name, "height");
+        assert_eq!(spec.kind, PropertyKind::FLOAT);
+
+        assert!(!spec.unique);
+        let spec: PropertySpec = "code:string:unique".parse().unwrap();
+        assert_eq!(spec.kind, PropertyKind::String);
+        assert!(spec.unique);
+
+        let spec: PropertySpec = "confidence:probability(is_defect)".parse().unwrap();
+        assert_eq!(
+            spec.kind,
+            PropertyKind::Probability {
+                label: Some("is_defect".to_string())
+            }
+        );
+
+        let spec: PropertySpec = "speed:float(0, 130)".parse().unwrap();
+        assert_eq!(
+            spec.kind,
+            PropertyKind::Float {
+                min: 0.0,
+                max: 130.0
+            }
+        );
+        for _ in 0..100 {
+            let speed = spec.generate(&JsonObject::new()).as_f64().unwrap();
+            assert!((0.0..130.0).contains(&speed), "{}", speed);
+        }
+        let spec: PropertySpec = "lanes:int(-1,2):unique".parse().unwrap();
+        let mut lanes: Vec<i64> = (0..100)
+            .map(|_| spec.generate(&JsonObject::new()).as_i64().unwrap())
+            .collect();
+        lanes.sort_unstable();
+        lanes.dedup();
+        assert_eq!(lanes, [-1, 0, 1, 2]);
+
+        let spec: PropertySpec = "is_active:bool(0.9)".parse().unwrap();
+        assert_eq!(spec.kind, PropertyKind::Bool { probability: 0.9 });
+        let active = (0..1000)
+            .filter(|_| spec.generate(&JsonObject::new()) == Value::Bool(true))
+            .count();
+        assert!((850..=950).contains(&active), "{}", active);
+        let spec: PropertySpec = "never:bool(0)".parse().unwrap();
+        assert_eq!(spec.generate(&JsonObject::new()), Value::Bool(false));
+
+        assert!("height".parse::<PropertySpec>().is_err());
+        assert!(":int".parse::<PropertySpec>().is_err());
+        assert!("a:unknown".parse::<PropertySpec>().is_err());
+        assert!("a:int(3)".parse::<PropertySpec>().is_err());
+        assert!("a:int(2,1)".parse::<PropertySpec>().is_err());
+        assert!("a:int(0,1.5)".parse::<PropertySpec>().is_err());
+        assert!("a:float(0,inf)".parse::<PropertySpec>().is_err());
+        assert!("a:bool(1.5)".parse::<PropertySpec>().is_err());
+        assert!("a:probability(x".parse::<PropertySpec>().is_err());
+    }
+
+    #[test]
+    fn test_validate_property_specs() {
+        let specs: Vec<PropertySpec> = ["is_defect:bool", "confidence:probability(is_defect)"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert!(validate_property_specs(&specs).is_ok());
+
+        let reversed: Vec<PropertySpec> = specs.into_iter().rev().collect();
+        assert!(validate_property_specs(&reversed).is_err());
+
+        let specs: Vec<PropertySpec> = ["label:template({addr} #{index})", "addr:expr(1 + 2)"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert!(validate_property_specs(&specs).is_err());
+        let reversed: Vec<PropertySpec> = specs.into_iter().rev().collect();
+        assert!(validate_property_specs(&reversed).is_ok());
+    }
+
+    #[test]
+    fn test_unique() {
+        let specs: Vec<PropertySpec> = ["id:int:unique", "label:template(n{int(1,60)}):unique"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let features = (0..50)
+            .map(|_| geojson::Feature {
+                properties: Some(JsonObject::from_iter([("id".to_string(), Value::from(7))])),
+                ..Default::default()
+            })
+            .collect();
+        let mut fc = FeatureCollection {
+            features,
+            ..Default::default()
+        };
+        finish_properties(&mut fc, &specs).unwrap();
+        for name in ["id", "label"] {
+            let values: HashSet<String> = fc
+                .features
+                .iter()
+                .map(|f| f.property(name).unwrap().to_string())
+                .collect();
+            assert_eq!(values.len(), 50, "{}", name);
+        }
+
+        let specs = ["flag:bool:unique".parse().unwrap()];
+        let mut fc = FeatureCollection {
+            features: vec![geojson::Feature::default(); 3],
+            ..Default::default()
+        };
+        for feature in &mut fc.features {
+            feature.set_property("flag", true);
+        }
+        assert!(finish_properties(&mut fc, &specs).is_err());
+    }
+
+    #[test]
+    fn test_calibrated_probability() {
+        let (mut defect_sum, mut ok_sum) = (0.0, 0.0);
+        for _ in 0..2000 {
+            let defect = random_probability(Some(true));
+            let ok = random_probability(Some(false));
+            assert!((0.0..=1.0).contains(&defect) && (0.0..=1.0).contains(&ok));
+            defect_sum += defect;
+            ok_sum += ok;
+        }
+        // Means of Beta(1, 2) and Beta(2, 1)
+        assert!((defect_sum / 2000.0 - 1.0 / 3.0).abs() < 0.05);
+        assert!((ok_sum / 2000.0 - 2.0 / 3.0).abs() < 0.05);
+    }
+}
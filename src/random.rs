@@ -0,0 +1,121 @@
+use std::cell::RefCell;
+
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use uuid::Uuid;
+
+/// Namespace of the UUIDs derived from a seed: the version 5 UUID of the
+/// repository URL in the URL namespace.
+const UUID_NAMESPACE: Uuid = Uuid::from_u128(0xaaebe8fb_a32a_59ef_8d6d_62a3f7082c7e);
+
+thread_local! {
+    static SEEDED: RefCell<Option<Seeded>> = const { RefCell::new(None) };
+}
+
+// The state of the current thread inside `with_seed`.
+struct Seeded {
+    seed: u64,
+    rng: StdRng,
+    // UUIDs derived so far
+    uuids: u64,
+}
+
+/// Random number generator behind all generated data.
+///
+/// Draws from the thread RNG, unless the current thread runs inside
+/// [`with_seed`], in which case the draws are reproducible.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Random;
+
+/// Returns the random number generator of the current thread.
+pub fn rng() -> Random {
+    Random
+}
+
+impl Random {
+    fn with<T>(&mut self, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+        SEEDED.with_borrow_mut(|seeded| match seeded {
+            Some(seeded) => f(&mut seeded.rng),
+            None => f(&mut rand::rng()),
+        })
+    }
+}
+
+impl RngCore for Random {
+    fn next_u32(&mut self) -> u32 {
+        self.with(|rng| rng.next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.with(|rng| rng.next_u64())
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        self.with(|rng| rng.fill_bytes(dst))
+    }
+}
+
+/// Runs `f` with the random number generator of the current thread seeded
+/// with `seed`, so everything it generates is the same on every run.
+pub fn with_seed<T>(seed: u64, f: impl FnOnce() -> T) -> T {
+    let previous = SEEDED.replace(Some(Seeded {
+        seed,
+        rng: StdRng::seed_from_u64(seed),
+        uuids: 0,
+    }));
+    let result = f();
+    SEEDED.set(previous);
+    result
+}
+
+/// Whether the current thread runs inside [`with_seed`], for outputs that
+/// would otherwise record the time they were written.
+pub fn is_seeded() -> bool {
+    SEEDED.with_borrow(Option::is_some)
+}
+
+/// Generates a random version 4 UUID, or inside [`with_seed`] the version 5
+/// UUID of the seed and the number of UUIDs derived before.
+///
+/// Derived UUIDs take no draws from [`rng`], so the ids of a seeded run stay
+/// the same when other options change what else is drawn.
+pub fn random_uuid() -> Uuid {
+    let derived = SEEDED.with_borrow_mut(|seeded| {
+        seeded.as_mut().map(|seeded| {
+            let name = format!("{}:{}", seeded.seed, seeded.uuids);
+            seeded.uuids += 1;
+            Uuid::new_v5(&UUID_NAMESPACE, name.as_bytes())
+        })
+    });
+    derived.unwrap_or_else(|| uuid::Builder::from_random_bytes(rand::rng().random()).into_uuid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_seed() {
+        let draw = || (rng().random::<u64>(), random_uuid());
+        assert_eq!(with_seed(7, draw), with_seed(7, draw));
+        assert_ne!(with_seed(7, draw), with_seed(8, draw));
+        assert_eq!(random_uuid().get_version_num(), 4);
+        assert!(with_seed(7, is_seeded) && !is_seeded());
+    }
+
+    #[test]
+    fn test_seeded_uuids() {
+        let uuids = || [random_uuid(), random_uuid()];
+        let [first, second] = with_seed(7, uuids);
+        assert_eq!(first.get_version_num(), 5);
+        assert_ne!(first, second);
+        assert_eq!(with_seed(7, uuids), [first, second]);
+        // Other draws do not shift the ids
+        let after_draws = with_seed(7, || {
+            let _: u64 = rng().random();
+            uuids()
+        });
+        assert_eq!(after_draws, [first, second]);
+        assert_ne!(with_seed(8, uuids), [first, second]);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::anchor::{project, project_one};
+use crate::coverage::voronoi;
+use crate::geometry::{Crs, RandomGeometry, random_geographic_coords};
+
+/// Two related layers: region polygons covering the area and points each
+/// lying in one of them, for spatial-join and foreign-key tests.
+pub struct Regions {
+    /// The Voronoi cells of random seed points, their index being their id.
+    pub polygons: Vec<RandomGeometry>,
+    /// The points with the id of the region containing them.
+    pub points: Vec<(RandomGeometry, usize)>,
+}
+
+/// Generates `regions` region polygons covering the bounds of `crs` and
+/// `points` random points within them.
+///
+/// The region of a point is that of the nearest seed, which is the Voronoi
+/// cell containing it.
+pub fn random_regions(crs: &Crs, regions: usize, points: usize) -> Regions {
+    let seeds: Vec<(f64, f64)> = (0..regions)
+        .map(|_| random_geographic_coords(crs))
+        .collect();
+    let polygons = voronoi(&seeds, &crs.bounds())
+        .into_iter()
+        .map(|mut ring| {
+            ring.push(ring[0]);
+            RandomGeometry::Polygon(vec![project(crs, &ring)])
+        })
+        .collect();
+    let points = (0..points)
+        .map(|_| {
+            let (lon, lat) = random_geographic_coords(crs);
+            let region = nearest(&seeds, lon, lat);
+            (RandomGeometry::Point(project_one(crs, (lon, lat))), region)
+        })
+        .collect();
+    Regions { polygons, points }
+}
+
+// The index of the seed nearest to `x`, `y`.
+fn nearest(seeds: &[(f64, f64)], x: f64, y: f64) -> usize {
+    let distance = |&(sx, sy): &(f64, f64)| (sx - x).powi(2) + (sy - y).powi(2);
+    (0..seeds.len())
+        .min_by(|&a, &b| distance(&seeds[a]).total_cmp(&distance(&seeds[b])))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Whether a point is inside a ring, by counting crossings of a ray.
+    fn contains(ring: &[Vec<f64>], x: f64, y: f64) -> bool {
+        let mut inside = false;
+        for segment in ring.windows(2) {
+            let (a, b) = (&segment[0], &segment[1]);
+            if (a[1] > y) != (b[1] > y) && x < a[0] + (y - a[1]) / (b[1] - a[1]) * (b[0] - a[0]) {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    #[test]
+    fn test_random_regions() {
+        let regions = random_regions(&Crs::WGS84, 12, 200);
+        assert_eq!(regions.polygons.len(), 12);
+        assert_eq!(regions.points.len(), 200);
+        for (point, region) in &regions.points {
+            let RandomGeometry::Point(p) = point else {
+                panic!("not a point");
+            };
+            let RandomGeometry::Polygon(rings) = &regions.polygons[*region] else {
+                panic!("not a polygon");
+            };
+            assert!(contains(&rings[0], p[0], p[1]), "{:?} in {}", p, region);
+        }
+    }
+}
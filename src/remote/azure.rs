@@ -0,0 +1,142 @@
+use ureq::http::Request;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::remote::{PART_SIZE, Upload, agent, check, encode_uri, env};
+
+/// Version of the Blob service REST API the requests are written against.
+const API_VERSION: &str = "2023-11-03";
+
+/// Endpoint and shared access signature of an Azure Blob Storage account.
+#[derive(Debug, Clone)]
+pub struct AzureConfig {
+    pub endpoint: String,
+    /// SAS token granting write access, without the leading `?`.
+    pub sas_token: String,
+}
+
+impl AzureConfig {
+    /// Reads the account from `AZURE_STORAGE_ACCOUNT`, or a full endpoint such
+    /// as that of Azurite from `AZURE_STORAGE_ENDPOINT`, and the SAS token
+    /// from `AZURE_STORAGE_SAS_TOKEN`.
+    pub fn from_env() -> RandomGeojsonResult<Self> {
+        let endpoint = env("AZURE_STORAGE_ENDPOINT").or_else(|| {
+            env("AZURE_STORAGE_ACCOUNT")
+                .map(|account| format!("https://{}.blob.core.windows.net", account))
+        });
+        let (Some(endpoint), Some(sas_token)) = (endpoint, env("AZURE_STORAGE_SAS_TOKEN")) else {
+            return Err(RandomGeojsonError::InvalidArgument(
+                "Set AZURE_STORAGE_ACCOUNT and AZURE_STORAGE_SAS_TOKEN to write to az:// URLs"
+                    .to_string(),
+            ));
+        };
+        Ok(AzureConfig {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            sas_token: sas_token.trim_start_matches('?').to_string(),
+        })
+    }
+
+    fn url(&self, container: &str, blob: &str, query: &str) -> String {
+        let separator = if query.is_empty() { "" } else { "&" };
+        format!(
+            "{}/{}/{}?{}{}{}",
+            self.endpoint,
+            container,
+            encode_uri(blob, true),
+            query,
+            separator,
+            self.sas_token
+        )
+    }
+}
+
+/// Uploads a block blob, with a single request if the body fits in one part
+/// and as a list of blocks otherwise.
+pub fn upload(
+    config: &AzureConfig,
+    container: &str,
+    blob: &str,
+    upload: &Upload,
+    target: &str,
+) -> RandomGeojsonResult<()> {
+    let agent = agent();
+    let invalid = |e: ureq::http::Error| {
+        RandomGeojsonError::Network(format!("Failed to upload {}: {}", target, e))
+    };
+
+    if upload.body.len() <= PART_SIZE {
+        let mut request = Request::put(config.url(container, blob, ""))
+            .header("x-ms-version", API_VERSION)
+            .header("x-ms-blob-type", "BlockBlob")
+            .header("content-type", upload.content_type);
+        if let Some(encoding) = upload.content_encoding {
+            request = request.header("content-encoding", encoding);
+        }
+        if !upload.overwrite {
+            request = request.header("if-none-match", "*");
+        }
+        check(
+            agent.run(request.body(upload.body).map_err(invalid)?),
+            target,
+        )?;
+        return Ok(());
+    }
+
+    let mut block_list = String::from(r#"<?xml version="1.0" encoding="utf-8"?><BlockList>"#);
+    for (i, block) in upload.body.chunks(PART_SIZE).enumerate() {
+        let id = block_id(i);
+        let query = format!("comp=block&blockid={}", id);
+        let request = Request::put(config.url(container, blob, &query))
+            .header("x-ms-version", API_VERSION)
+            .body(block)
+            .map_err(invalid)?;
+        check(agent.run(request), target)?;
+        block_list.push_str(&format!("<Latest>{}</Latest>", id));
+    }
+    block_list.push_str("</BlockList>");
+
+    let mut request = Request::put(config.url(container, blob, "comp=blocklist"))
+        .header("x-ms-version", API_VERSION)
+        .header("content-type", "application/xml")
+        .header("x-ms-blob-content-type", upload.content_type);
+    if let Some(encoding) = upload.content_encoding {
+        request = request.header("x-ms-blob-content-encoding", encoding);
+    }
+    if !upload.overwrite {
+        request = request.header("if-none-match", "*");
+    }
+    let request = request.body(block_list.as_bytes()).map_err(invalid)?;
+    check(agent.run(request), target)?;
+    Ok(())
+}
+
+// Block ids must be Base64 strings of equal length within a blob. Eight
+// digits are one, and need no escaping in the query string.
+fn block_id(index: usize) -> String {
+    format!("{:08}", index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url() {
+        let config = AzureConfig {
+            endpoint: "https://account.blob.core.windows.net".to_string(),
+            sas_token: "sv=2022-11-02&sig=abc".to_string(),
+        };
+        assert_eq!(
+            config.url("fixtures", "data/a b.geojson", ""),
+            "https://account.blob.core.windows.net/fixtures/data/a%20b.geojson?sv=2022-11-02&sig=abc"
+        );
+        assert_eq!(
+            config.url(
+                "fixtures",
+                "a.geojson",
+                &format!("comp=block&blockid={}", block_id(3))
+            ),
+            "https://account.blob.core.windows.net/fixtures/a.geojson\
+             ?comp=block&blockid=00000003&sv=2022-11-02&sig=abc"
+        );
+    }
+}
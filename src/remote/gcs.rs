@@ -0,0 +1,133 @@
+use serde_json::{Map, Value};
+use ureq::http::Request;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::remote::{PART_SIZE, Upload, agent, check, encode_uri, env};
+
+/// Access token and endpoint of Google Cloud Storage.
+#[derive(Debug, Clone)]
+pub struct GcsConfig {
+    /// OAuth 2.0 access token, e.g. from `gcloud auth print-access-token`.
+    pub access_token: String,
+    pub endpoint: String,
+}
+
+impl GcsConfig {
+    /// Reads the token from `GOOGLE_OAUTH_ACCESS_TOKEN`. `STORAGE_EMULATOR_HOST`
+    /// points uploads at an emulator instead of Cloud Storage.
+    pub fn from_env() -> RandomGeojsonResult<Self> {
+        let access_token = env("GOOGLE_OAUTH_ACCESS_TOKEN").ok_or_else(|| {
+            RandomGeojsonError::InvalidArgument(
+                "Set GOOGLE_OAUTH_ACCESS_TOKEN to write to gs:// URLs".to_string(),
+            )
+        })?;
+        let endpoint = match env("STORAGE_EMULATOR_HOST") {
+            Some(host) if host.contains("://") => host,
+            Some(host) => format!("http://{}", host),
+            None => "https://storage.googleapis.com".to_string(),
+        };
+        Ok(GcsConfig {
+            access_token,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+/// Uploads to `key` in `bucket` with a resumable upload, sent in chunks of
+/// [`PART_SIZE`].
+pub fn upload(
+    config: &GcsConfig,
+    bucket: &str,
+    key: &str,
+    upload: &Upload,
+    target: &str,
+) -> RandomGeojsonResult<()> {
+    let agent = agent();
+    let invalid = |e: ureq::http::Error| {
+        RandomGeojsonError::Network(format!("Failed to upload {}: {}", target, e))
+    };
+
+    let mut metadata = Map::new();
+    metadata.insert("contentType".to_string(), upload.content_type.into());
+    if let Some(encoding) = upload.content_encoding {
+        metadata.insert("contentEncoding".to_string(), encoding.into());
+    }
+    let metadata = Value::Object(metadata).to_string();
+    // A generation of 0 only matches objects that do not exist yet
+    let condition = if upload.overwrite {
+        ""
+    } else {
+        "&ifGenerationMatch=0"
+    };
+    let request = Request::post(format!(
+        "{}/upload/storage/v1/b/{}/o?uploadType=resumable&name={}{}",
+        config.endpoint,
+        encode_uri(bucket, false),
+        encode_uri(key, false),
+        condition
+    ))
+    .header("authorization", format!("Bearer {}", config.access_token))
+    .header("content-type", "application/json; charset=UTF-8")
+    .header("x-upload-content-type", upload.content_type)
+    .header("x-upload-content-length", upload.body.len())
+    .body(metadata.as_bytes())
+    .map_err(invalid)?;
+    let response = check(agent.run(request), target)?;
+    let session = response
+        .headers()
+        .get("location")
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| {
+            RandomGeojsonError::Network(format!(
+                "Failed to upload {}: no upload session in the response",
+                target
+            ))
+        })?
+        .to_string();
+
+    let total = upload.body.len();
+    let mut start = 0;
+    loop {
+        let chunk = &upload.body[start..total.min(start + PART_SIZE)];
+        let request = Request::put(&session)
+            .header("content-range", content_range(start, chunk.len(), total))
+            .body(chunk)
+            .map_err(invalid)?;
+        start += chunk.len();
+        let result = agent.run(request);
+        // 308 acknowledges a chunk of an unfinished upload
+        if start < total && matches!(&result, Ok(response) if response.status() == 308) {
+            continue;
+        }
+        check(result, target)?;
+        return Ok(());
+    }
+}
+
+// The Content-Range header of the `len` bytes from `start` of a `total` byte
+// upload.
+fn content_range(start: usize, len: usize, total: usize) -> String {
+    if len == 0 {
+        format!("bytes */{}", total)
+    } else {
+        format!("bytes {}-{}/{}", start, start + len - 1, total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_range() {
+        assert_eq!(
+            content_range(0, PART_SIZE, 10_000_000),
+            "bytes 0-8388607/10000000"
+        );
+        assert_eq!(
+            content_range(PART_SIZE, 1_611_392, 10_000_000),
+            "bytes 8388608-9999999/10000000"
+        );
+        assert_eq!(content_range(0, 0, 0), "bytes */0");
+    }
+}
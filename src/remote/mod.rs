@@ -0,0 +1,205 @@
+pub mod azure;
+pub mod gcs;
+pub mod s3;
+
+use std::fmt;
+use std::time::Duration;
+
+use ureq::Body;
+use ureq::http::Response;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+
+/// Size of the parts of a multipart upload. Large enough for the S3 minimum
+/// of 5 MiB and a multiple of the 256 KiB chunks Cloud Storage expects.
+pub const PART_SIZE: usize = 8 * 1024 * 1024;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Location of an object in cloud storage, such as `s3://bucket/key.geojson`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectUrl {
+    S3 { bucket: String, key: String },
+    Gcs { bucket: String, key: String },
+    Azure { container: String, blob: String },
+}
+
+impl ObjectUrl {
+    /// Parses an `s3://`, `gs://` or `az://` URL. Returns `None` for any other
+    /// path, which is then a local file.
+    pub fn parse(path: &str) -> RandomGeojsonResult<Option<Self>> {
+        let Some((scheme, rest)) = path.split_once("://") else {
+            return Ok(None);
+        };
+        if !matches!(scheme, "s3" | "gs" | "az") {
+            return Ok(None);
+        }
+        let (bucket, key) = match rest.split_once('/') {
+            Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => {
+                (bucket.to_string(), key.to_string())
+            }
+            _ => {
+                return Err(RandomGeojsonError::InvalidArgument(format!(
+                    "Object URL must be {}://bucket/key: {}",
+                    scheme, path
+                )));
+            }
+        };
+        Ok(Some(match scheme {
+            "s3" => ObjectUrl::S3 { bucket, key },
+            "gs" => ObjectUrl::Gcs { bucket, key },
+            _ => ObjectUrl::Azure {
+                container: bucket,
+                blob: key,
+            },
+        }))
+    }
+}
+
+impl fmt::Display for ObjectUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjectUrl::S3 { bucket, key } => write!(f, "s3://{}/{}", bucket, key),
+            ObjectUrl::Gcs { bucket, key } => write!(f, "gs://{}/{}", bucket, key),
+            ObjectUrl::Azure { container, blob } => write!(f, "az://{}/{}", container, blob),
+        }
+    }
+}
+
+/// Serialized output along with the headers it is stored with.
+#[derive(Debug, Clone, Copy)]
+pub struct Upload<'a> {
+    pub body: &'a [u8],
+    pub content_type: &'static str,
+    pub content_encoding: Option<&'static str>,
+    /// Replace an existing object instead of failing.
+    pub overwrite: bool,
+}
+
+/// Uploads `upload` to `url`. Unless `overwrite` is set, the write is
+/// conditional and fails if the object already exists.
+///
+/// Credentials are read from the environment variables of each provider's
+/// own tooling; bodies larger than [`PART_SIZE`] are sent in parts.
+pub fn upload(url: &ObjectUrl, upload: &Upload) -> RandomGeojsonResult<()> {
+    let target = url.to_string();
+    match url {
+        ObjectUrl::S3 { bucket, key } => {
+            s3::upload(&s3::S3Config::from_env()?, bucket, key, upload, &target)
+        }
+        ObjectUrl::Gcs { bucket, key } => {
+            gcs::upload(&gcs::GcsConfig::from_env()?, bucket, key, upload, &target)
+        }
+        ObjectUrl::Azure { container, blob } => azure::upload(
+            &azure::AzureConfig::from_env()?,
+            container,
+            blob,
+            upload,
+            &target,
+        ),
+    }
+}
+
+// HTTP client shared by the providers. Redirects are not followed, as Cloud
+// Storage answers partial uploads with a 308 that is not one.
+fn agent() -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .http_status_as_error(false)
+        .max_redirects(0)
+        .timeout_global(Some(REQUEST_TIMEOUT))
+        .build()
+        .into()
+}
+
+// Turns transport errors and unsuccessful responses into errors naming
+// `target`. Request URLs are left out, as they may hold credentials.
+fn check(
+    result: Result<Response<Body>, ureq::Error>,
+    target: &str,
+) -> RandomGeojsonResult<Response<Body>> {
+    let mut response = result
+        .map_err(|e| RandomGeojsonError::Network(format!("Failed to upload {}: {}", target, e)))?;
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    if response.status() == 412 {
+        return Err(RandomGeojsonError::InvalidArgument(format!(
+            "Object {} already exists, use --force to overwrite it",
+            target
+        )));
+    }
+    let body = response.body_mut().read_to_string().unwrap_or_default();
+    Err(RandomGeojsonError::Network(format!(
+        "Failed to upload {}: HTTP {} {}",
+        target,
+        response.status(),
+        body.trim()
+    )))
+}
+
+fn read_body(mut response: Response<Body>, target: &str) -> RandomGeojsonResult<String> {
+    response
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| RandomGeojsonError::Network(format!("Failed to upload {}: {}", target, e)))
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// Percent-encodes everything but the unreserved characters of RFC 3986, and
+/// `/` if `keep_slash` is set.
+fn encode_uri(s: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(char::from(byte))
+            }
+            b'/' if keep_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_object_url() {
+        assert_eq!(
+            ObjectUrl::parse("s3://fixtures/data/random.geojson").unwrap(),
+            Some(ObjectUrl::S3 {
+                bucket: "fixtures".to_string(),
+                key: "data/random.geojson".to_string(),
+            })
+        );
+        assert_eq!(
+            ObjectUrl::parse("az://container/random.fgb").unwrap(),
+            Some(ObjectUrl::Azure {
+                container: "container".to_string(),
+                blob: "random.fgb".to_string(),
+            })
+        );
+        let gcs = ObjectUrl::parse("gs://bucket/a.geojson").unwrap().unwrap();
+        assert_eq!(gcs.to_string(), "gs://bucket/a.geojson");
+        assert_eq!(ObjectUrl::parse("random.geojson").unwrap(), None);
+        assert_eq!(ObjectUrl::parse("file://random.geojson").unwrap(), None);
+        assert!(ObjectUrl::parse("s3://bucket").is_err());
+        assert!(ObjectUrl::parse("s3://bucket/").is_err());
+    }
+
+    #[test]
+    fn test_encode_uri() {
+        assert_eq!(encode_uri("a b/c~d.json", true), "a%20b/c~d.json");
+        assert_eq!(encode_uri("a b/c", false), "a%20b%2Fc");
+        assert_eq!(encode_uri("é", false), "%C3%A9");
+    }
+}
@@ -0,0 +1,117 @@
+use geojson::Feature;
+use rand::Rng;
+
+use crate::random;
+
+/// How many features a [`Sampler`] keeps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleSize {
+    /// Exactly this many features, or all of them if there are fewer.
+    Count(usize),
+    /// Each feature independently, with this probability.
+    Fraction(f64),
+}
+
+/// Draws a uniform random sample from a stream of features of unknown length,
+/// holding no more than the sample in memory.
+pub struct Sampler {
+    size: SampleSize,
+    seen: usize,
+    // Kept features with their position in the input
+    kept: Vec<(usize, Feature)>,
+}
+
+impl Sampler {
+    pub fn new(size: SampleSize) -> Self {
+        Sampler {
+            size,
+            seen: 0,
+            kept: Vec::new(),
+        }
+    }
+
+    /// Offers the next feature of the input.
+    pub fn push(&mut self, feature: Feature) {
+        let index = self.seen;
+        self.seen += 1;
+        let mut rng = random::rng();
+        match self.size {
+            SampleSize::Fraction(fraction) => {
+                if rng.random_bool(fraction) {
+                    self.kept.push((index, feature));
+                }
+            }
+            // Reservoir sampling: the i-th feature replaces a kept one with
+            // probability count / (i + 1)
+            SampleSize::Count(count) if self.kept.len() < count => {
+                self.kept.push((index, feature));
+            }
+            SampleSize::Count(count) => {
+                let slot = rng.random_range(0..=index);
+                if slot < count {
+                    self.kept[slot] = (index, feature);
+                }
+            }
+        }
+    }
+
+    /// Number of features offered so far.
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    /// Returns the sample, in input order.
+    pub fn finish(mut self) -> Vec<Feature> {
+        self.kept.sort_unstable_by_key(|&(index, _)| index);
+        self.kept.into_iter().map(|(_, feature)| feature).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feature(index: usize) -> Feature {
+        Feature {
+            id: Some(geojson::feature::Id::Number(index.into())),
+            ..Default::default()
+        }
+    }
+
+    fn sample(size: SampleSize, n: usize) -> Vec<usize> {
+        let mut sampler = Sampler::new(size);
+        (0..n).for_each(|i| sampler.push(feature(i)));
+        sampler
+            .finish()
+            .into_iter()
+            .map(|f| match f.id {
+                Some(geojson::feature::Id::Number(n)) => n.as_u64().unwrap() as usize,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_count() {
+        let kept = sample(SampleSize::Count(10), 1000);
+        assert_eq!(kept.len(), 10);
+        assert!(kept.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(sample(SampleSize::Count(10), 3), [0, 1, 2]);
+
+        // Every position is about as likely to be kept
+        let mut hits = [0; 10];
+        for _ in 0..2000 {
+            for i in sample(SampleSize::Count(2), 10) {
+                hits[i] += 1;
+            }
+        }
+        assert!(hits.iter().all(|&h| (250..550).contains(&h)), "{:?}", hits);
+    }
+
+    #[test]
+    fn test_fraction() {
+        let kept = sample(SampleSize::Fraction(0.1), 10_000);
+        assert!((800..1200).contains(&kept.len()));
+        assert!(sample(SampleSize::Fraction(1.0), 50).len() == 50);
+    }
+}
@@ -0,0 +1,225 @@
+use std::f64::consts::TAU;
+
+use rand::Rng;
+use serde_json::Value;
+
+use crate::error::RandomGeojsonError;
+use crate::random;
+use crate::trajectory::{START, YEAR, timestamp};
+
+/// Most values of a series.
+const MAX_LENGTH: usize = 100_000;
+
+/// A distribution the values of a series are drawn from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    /// Independent values of a normal distribution.
+    Normal { mean: f64, sd: f64 },
+    /// Independent values from `min` to `max`.
+    Uniform { min: f64, max: f64 },
+    /// A random walk from `start` whose steps are normal with standard
+    /// deviation `sd`, drifting like a real sensor reading.
+    Walk { start: f64, sd: f64 },
+}
+
+/// An array of values per feature, e.g. the hourly readings of a sensor,
+/// optionally with the timestamps of the values `interval` seconds apart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub length: usize,
+    pub distribution: Distribution,
+    pub interval: Option<i64>,
+}
+
+impl Series {
+    /// Draws the values of a series.
+    pub fn values(&self) -> Value {
+        let mut rng = random::rng();
+        let mut normal = |mean: f64, sd: f64| {
+            // Box-Muller transform
+            let (u, v): (f64, f64) = (1.0 - rng.random::<f64>(), rng.random());
+            mean + sd * (-2.0 * u.ln()).sqrt() * (TAU * v).cos()
+        };
+        let values: Vec<f64> = match self.distribution {
+            Distribution::Normal { mean, sd } => {
+                (0..self.length).map(|_| normal(mean, sd)).collect()
+            }
+            Distribution::Uniform { min, max } => (0..self.length)
+                .map(|_| random::rng().random_range(min..=max))
+                .collect(),
+            Distribution::Walk { start, sd } => {
+                let mut value = start;
+                (0..self.length)
+                    .map(|i| {
+                        if i > 0 {
+                            value += normal(0.0, sd);
+                        }
+                        value
+                    })
+                    .collect()
+            }
+        };
+        values.into()
+    }
+
+    /// Draws the timestamps of the values, from a random time in 2024, if
+    /// the series has an interval.
+    pub fn timestamps(&self) -> Option<Value> {
+        let interval = self.interval?;
+        let start = START + random::rng().random_range(0..YEAR);
+        let times = (0..self.length as i64).map(|i| timestamp(start + i * interval));
+        Some(times.map(Value::from).collect())
+    }
+}
+
+/// Parses the arguments of `series(...)`: a length, a distribution and
+/// optionally an interval, e.g. "24,normal(20,5),1h".
+impl std::str::FromStr for Series {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            RandomGeojsonError::InvalidArgument(format!(
+                "Invalid series '{}': {}, expected e.g. \"24,normal(20,5),1h\"",
+                s, reason
+            ))
+        };
+        let args = split_args(s);
+        let (length, distribution, interval) = match args.as_slice() {
+            [length, distribution] => (length, distribution, None),
+            [length, distribution, interval] => (length, distribution, Some(interval)),
+            _ => return Err(invalid("expected 2 or 3 arguments")),
+        };
+        let length: usize = length
+            .parse()
+            .ok()
+            .filter(|n| (1..=MAX_LENGTH).contains(n))
+            .ok_or_else(|| invalid(&format!("length must be from 1 to {}", MAX_LENGTH)))?;
+        let distribution =
+            parse_distribution(distribution).ok_or_else(|| invalid("unknown distribution"))?;
+        let interval = interval
+            .map(|interval| parse_interval(interval).ok_or_else(|| invalid("invalid interval")))
+            .transpose()?;
+        Ok(Series {
+            length,
+            distribution,
+            interval,
+        })
+    }
+}
+
+// Splits arguments at the commas outside parentheses.
+pub(crate) fn split_args(s: &str) -> Vec<&str> {
+    let mut args = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    args.push(s[start..].trim());
+    args
+}
+
+// Parses "normal(mean,sd)", "uniform(min,max)" or "walk(start,sd)".
+fn parse_distribution(s: &str) -> Option<Distribution> {
+    let (name, args) = s.strip_suffix(')')?.split_once('(')?;
+    let (a, b) = args.split_once(',')?;
+    let (a, b): (f64, f64) = (a.trim().parse().ok()?, b.trim().parse().ok()?);
+    if !(a.is_finite() && b.is_finite()) {
+        return None;
+    }
+    match name.trim().to_lowercase().as_str() {
+        "normal" if b >= 0.0 => Some(Distribution::Normal { mean: a, sd: b }),
+        "uniform" if a <= b => Some(Distribution::Uniform { min: a, max: b }),
+        "walk" if b >= 0.0 => Some(Distribution::Walk { start: a, sd: b }),
+        _ => None,
+    }
+}
+
+// Parses an interval such as "30s", "15m", "1h" or "1d" in seconds.
+fn parse_interval(s: &str) -> Option<i64> {
+    let split = s.find(|c: char| !c.is_ascii_digit())?;
+    let (count, unit) = s.split_at(split);
+    let unit = match unit.trim() {
+        "s" => 1,
+        "m" | "min" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        _ => return None,
+    };
+    count
+        .parse::<i64>()
+        .ok()
+        .filter(|&n| n > 0)?
+        .checked_mul(unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        let series: Series = "24, normal(20, 5), 1h".parse().unwrap();
+        assert_eq!(
+            series,
+            Series {
+                length: 24,
+                distribution: Distribution::Normal {
+                    mean: 20.0,
+                    sd: 5.0
+                },
+                interval: Some(3600),
+            }
+        );
+        let series: Series = "3,walk(-1,0.5)".parse().unwrap();
+        assert_eq!(series.interval, None);
+        for s in [
+            "24",
+            "0,normal(0,1)",
+            "24,normal(0,-1)",
+            "24,gamma(1,1)",
+            "24,uniform(2,1)",
+            "2,normal(0,1),1y",
+        ] {
+            assert!(s.parse::<Series>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_values() {
+        let series: Series = "2000,normal(20,5),15m".parse().unwrap();
+        let values: Vec<f64> = series
+            .values()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_f64().unwrap())
+            .collect();
+        assert_eq!(values.len(), 2000);
+        let mean = values.iter().sum::<f64>() / 2000.0;
+        let sd = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / 2000.0).sqrt();
+        assert!(
+            (mean - 20.0).abs() < 0.5 && (sd - 5.0).abs() < 0.5,
+            "{} {}",
+            mean,
+            sd
+        );
+
+        let times = series.timestamps().unwrap();
+        let times = times.as_array().unwrap();
+        assert_eq!(times.len(), 2000);
+        assert!(times[0].as_str().unwrap().starts_with("2024-"));
+        assert!(times[0].as_str().unwrap() < times[1].as_str().unwrap());
+        let walk: Series = "5,walk(7,0)".parse().unwrap();
+        assert_eq!(walk.values(), serde_json::json!([7.0, 7.0, 7.0, 7.0, 7.0]));
+        assert_eq!(walk.timestamps(), None);
+    }
+}
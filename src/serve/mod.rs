@@ -0,0 +1,438 @@
+pub mod ogc;
+
+use std::collections::HashMap;
+
+use geojson::FeatureCollection;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::format::{mvt, pmtiles};
+use crate::geometry::Bounds;
+use crate::random;
+use crate::tile::TileId;
+
+/// Parameters of a request to the `/random` endpoint. Anything left unset
+/// falls back to the defaults the server was started with.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GenerateParams {
+    pub length: Option<usize>,
+    pub geometry_type: Option<String>,
+    pub num_properties: Option<usize>,
+    /// Geographic bounds the features must be generated in.
+    pub bbox: Option<Bounds>,
+}
+
+impl GenerateParams {
+    /// Parses the parameters from a query string such as
+    /// `length=100&geometry_type=Point&bbox=-10,40,10,55`. Unknown parameters
+    /// are ignored, so clients can add cache busters.
+    pub fn from_query(query: &str) -> RandomGeojsonResult<Self> {
+        let params = parse_query(query);
+        let number = |name: &str| -> RandomGeojsonResult<Option<usize>> {
+            params
+                .get(name)
+                .map(|v| {
+                    v.parse().map_err(|_| {
+                        RandomGeojsonError::InvalidArgument(format!(
+                            "{} must be zero or more",
+                            name
+                        ))
+                    })
+                })
+                .transpose()
+        };
+        Ok(GenerateParams {
+            length: number("length")?,
+            geometry_type: params.get("geometry_type").cloned(),
+            num_properties: number("num_properties")?,
+            bbox: params.get("bbox").map(|v| v.parse()).transpose()?,
+        })
+    }
+}
+
+/// Settings of the HTTP server.
+#[derive(Debug, Clone)]
+pub struct ServeOptions {
+    /// Address to listen on, e.g. "127.0.0.1:8080".
+    pub address: String,
+    /// Number of requests handled concurrently.
+    pub threads: usize,
+    /// Largest number of features a single request may ask for.
+    pub max_length: usize,
+    /// Number of features in the OGC API collection.
+    pub collection_size: usize,
+    /// Geographic extent advertised for the OGC API collection.
+    pub extent: Bounds,
+    /// EPSG code of the generated coordinates, if known.
+    pub epsg: Option<u32>,
+}
+
+/// An HTTP response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    fn geojson(fc: &FeatureCollection) -> Self {
+        Response {
+            status: 200,
+            content_type: "application/geo+json",
+            body: serde_json::to_vec(fc).unwrap_or_default(),
+        }
+    }
+
+    fn json(value: &serde_json::Value) -> Self {
+        Response {
+            status: 200,
+            content_type: "application/json",
+            body: serde_json::to_vec(value).unwrap_or_default(),
+        }
+    }
+
+    fn vector_tile(body: Vec<u8>) -> Self {
+        Response {
+            status: 200,
+            content_type: "application/vnd.mapbox-vector-tile",
+            body,
+        }
+    }
+
+    fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{}\n", message).into_bytes(),
+        }
+    }
+}
+
+/// Answers a GET request for `url` (path and query string), using `generate`
+/// to build the features of each response. `base_url` prefixes the links of
+/// OGC API documents.
+pub fn route(
+    url: &str,
+    base_url: &str,
+    options: &ServeOptions,
+    generate: &impl Fn(&GenerateParams) -> RandomGeojsonResult<FeatureCollection>,
+) -> Response {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').skip(1).collect();
+    match segments.as_slice() {
+        [] => ogc::landing_page(base_url),
+        ["conformance"] => ogc::conformance(),
+        ["collections"] => ogc::collections(base_url, options),
+        ["collections", ogc::COLLECTION_ID] => ogc::collection(base_url, options),
+        ["collections", ogc::COLLECTION_ID, "items"] => {
+            ogc::items(base_url, query, options, generate)
+        }
+        ["collections", ogc::COLLECTION_ID, "items", id] => {
+            ogc::item(base_url, &percent_decode(id), query, options, generate)
+        }
+        ["tiles", z, x, y] => match y.strip_suffix(".mvt").map(|y| format!("{}/{}/{}", z, x, y)) {
+            Some(tile) => match tile.parse() {
+                Ok(tile) => vector_tile(tile, query, options, generate),
+                Err(e) => Response::error(400, e),
+            },
+            None => Response::error(404, format!("Not found: {}", path)),
+        },
+        ["random"] => {
+            let params = match GenerateParams::from_query(query) {
+                Ok(params) => params,
+                Err(e) => return Response::error(400, e),
+            };
+            if params.length.is_some_and(|n| n > options.max_length) {
+                return Response::error(
+                    400,
+                    format!("length must be at most {}", options.max_length),
+                );
+            }
+            match generate(&params) {
+                Ok(fc) => Response::geojson(&fc),
+                Err(e) => Response::error(400, e),
+            }
+        }
+        _ => Response::error(404, format!("Not found: {}", path)),
+    }
+}
+
+/// A vector tile at `/tiles/{z}/{x}/{y}.mvt`, holding features generated
+/// inside the tile. Generation is seeded by the tile, so every request for
+/// the same tile and parameters returns the same features.
+fn vector_tile(
+    tile: TileId,
+    query: &str,
+    options: &ServeOptions,
+    generate: &impl Fn(&GenerateParams) -> RandomGeojsonResult<FeatureCollection>,
+) -> Response {
+    let mut params = match GenerateParams::from_query(query) {
+        Ok(params) => params,
+        Err(e) => return Response::error(400, e),
+    };
+    if params.length.is_some_and(|n| n > options.max_length) {
+        return Response::error(
+            400,
+            format!("length must be at most {}", options.max_length),
+        );
+    }
+    params.bbox = Some(match params.bbox {
+        Some(bbox) => match bbox.intersection(&tile.bounds()) {
+            Some(bbox) => bbox,
+            // Nothing to generate, so the tile is empty
+            None => return Response::vector_tile(Vec::new()),
+        },
+        None => tile.bounds(),
+    });
+
+    let generated = random::with_seed(pmtiles::tile_id(tile), || generate(&params));
+    match generated.and_then(|fc| mvt::encode(&fc, tile, options.epsg)) {
+        Ok(bytes) => Response::vector_tile(bytes),
+        Err(e) => Response::error(400, e),
+    }
+}
+
+/// Starts an HTTP server answering requests with [`route`]. Runs until the
+/// process is stopped.
+pub fn serve(
+    options: &ServeOptions,
+    generate: impl Fn(&GenerateParams) -> RandomGeojsonResult<FeatureCollection> + Sync,
+) -> RandomGeojsonResult<()> {
+    let server = tiny_http::Server::http(&options.address).map_err(|e| {
+        RandomGeojsonError::Network(format!("Failed to listen on {}: {}", options.address, e))
+    })?;
+    eprintln!("Listening on http://{}", options.address);
+
+    std::thread::scope(|scope| {
+        for _ in 0..options.threads.max(1) {
+            scope.spawn(|| {
+                while let Ok(request) = server.recv() {
+                    let host = request
+                        .headers()
+                        .iter()
+                        .find(|h| h.field.equiv("Host"))
+                        .map_or(options.address.as_str(), |h| h.value.as_str());
+                    let base_url = format!("http://{}", host);
+                    let response = if *request.method() == tiny_http::Method::Get {
+                        route(request.url(), &base_url, options, &generate)
+                    } else {
+                        Response::error(405, "Only GET requests are supported")
+                    };
+                    let headers = [
+                        ("Content-Type", response.content_type),
+                        // Let map frontends on other origins fetch the data
+                        ("Access-Control-Allow-Origin", "*"),
+                    ];
+                    let mut http_response = tiny_http::Response::from_data(response.body)
+                        .with_status_code(response.status);
+                    for (name, value) in headers {
+                        if let Ok(header) = tiny_http::Header::from_bytes(name, value) {
+                            http_response.add_header(header);
+                        }
+                    }
+                    // The client may have gone away, which is not our problem
+                    let _ = request.respond(http_response);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+// Splits a query string into percent-decoded parameters.
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let hex = |i: usize| char::from(*bytes.get(i)?).to_digit(16);
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], hex(i + 1), hex(i + 2)) {
+            (b'%', Some(high), Some(low)) => {
+                out.push((high * 16 + low) as u8);
+                i += 2;
+            }
+            (b'+', ..) => out.push(b' '),
+            (byte, ..) => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::Feature;
+
+    use super::*;
+
+    fn options() -> ServeOptions {
+        ServeOptions {
+            address: "127.0.0.1:0".to_string(),
+            threads: 1,
+            max_length: 10,
+            collection_size: 25,
+            extent: crate::geometry::WGS84_BOUNDS,
+            epsg: Some(4326),
+        }
+    }
+
+    fn generate(params: &GenerateParams) -> RandomGeojsonResult<FeatureCollection> {
+        if params.geometry_type.as_deref() == Some("Circle") {
+            return Err(RandomGeojsonError::InvalidArgument(
+                "Invalid geometry type".to_string(),
+            ));
+        }
+        Ok(FeatureCollection {
+            features: vec![Feature::default(); params.length.unwrap_or(1)],
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_params_from_query() {
+        let params =
+            GenerateParams::from_query("length=5&geometry_type=Point&bbox=-10%2C40,10,55&_=1")
+                .unwrap();
+        assert_eq!(params.length, Some(5));
+        assert_eq!(params.geometry_type.as_deref(), Some("Point"));
+        assert_eq!(params.bbox.unwrap().min_y, 40.0);
+        assert!(GenerateParams::from_query("length=-1").is_err());
+        assert!(GenerateParams::from_query("bbox=1,2,3").is_err());
+    }
+
+    #[test]
+    fn test_route() {
+        let response = route("/random?length=3", "", &options(), &generate);
+        assert_eq!(response.status, 200);
+        let fc: FeatureCollection = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(fc.features.len(), 3);
+
+        assert_eq!(
+            route("/random?length=11", "", &options(), &generate).status,
+            400
+        );
+        assert_eq!(
+            route("/random?geometry_type=Circle", "", &options(), &generate).status,
+            400
+        );
+        assert_eq!(route("/other", "", &options(), &generate).status, 404);
+    }
+
+    // Random points of the seeded generator, like the real one
+    fn random_points(params: &GenerateParams) -> RandomGeojsonResult<FeatureCollection> {
+        let crs = crate::geometry::Crs::WGS84;
+        Ok(FeatureCollection {
+            features: (0..params.length.unwrap_or(5))
+                .map(|_| Feature {
+                    geometry: Some(crate::geometry::RandomGeometry::random_point(&crs).into()),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_ogc_items_paging() {
+        let base = "http://localhost";
+        let page = |url: &str| -> serde_json::Value {
+            let response = route(url, base, &options(), &random_points);
+            assert_eq!(response.status, 200);
+            serde_json::from_slice(&response.body).unwrap()
+        };
+
+        let landing = page("/");
+        assert!(landing["links"].as_array().unwrap().len() >= 3);
+        assert_eq!(page("/collections")["collections"][0]["id"], "random");
+
+        let first = page("/collections/random/items?limit=10");
+        assert_eq!(first["numberMatched"], 25);
+        assert_eq!(first["numberReturned"], 10);
+        assert_eq!(page("/collections/random/items?limit=10"), first);
+        let next = first["links"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|l| l["rel"] == "next")
+            .unwrap()["href"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            next,
+            "http://localhost/collections/random/items?limit=10&offset=10"
+        );
+        // Pages are windows of one collection
+        let second = page("/collections/random/items?limit=5&offset=5");
+        assert_eq!(second["features"][0], first["features"][5]);
+        assert_eq!(second["features"][0]["id"], 5);
+
+        let last = page("/collections/random/items?limit=10&offset=20");
+        assert_eq!(last["features"].as_array().unwrap().len(), 5);
+        assert!(
+            last["links"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .all(|l| l["rel"] != "next")
+        );
+
+        let east = page("/collections/random/items?bbox=0,-90,180,90&limit=10");
+        let matched = east["numberMatched"].as_u64().unwrap();
+        assert!(0 < matched && matched < 25, "{}", matched);
+        for feature in east["features"].as_array().unwrap() {
+            assert!(feature["geometry"]["coordinates"][0].as_f64().unwrap() >= 0.0);
+            let item = page(&format!("/collections/random/items/{}", feature["id"]));
+            assert_eq!(item["geometry"], feature["geometry"]);
+        }
+
+        let item = page("/collections/random/items/7");
+        assert_eq!(item["id"], 7);
+        assert_eq!(item["geometry"], first["features"][7]["geometry"]);
+        for url in [
+            "/collections/random/items/abc",
+            "/collections/random/items/25",
+            "/collections/other",
+        ] {
+            assert_eq!(route(url, base, &options(), &random_points).status, 404);
+        }
+    }
+
+    #[test]
+    fn test_vector_tile() {
+        // Features are generated from the seeded generator, like the real one
+        let generate = |params: &GenerateParams| -> RandomGeojsonResult<FeatureCollection> {
+            let crs = crate::geometry::Crs::WGS84.clipped(params.bbox.unwrap())?;
+            Ok(FeatureCollection {
+                features: (0..params.length.unwrap_or(5))
+                    .map(|_| Feature {
+                        geometry: Some(crate::geometry::RandomGeometry::random_point(&crs).into()),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            })
+        };
+        let tile = |url: &str| route(url, "", &options(), &generate);
+
+        let first = tile("/tiles/3/4/2.mvt");
+        assert_eq!(first.status, 200);
+        assert_eq!(first.content_type, "application/vnd.mapbox-vector-tile");
+        assert!(!first.body.is_empty());
+        assert_eq!(tile("/tiles/3/4/2.mvt"), first);
+        assert_ne!(tile("/tiles/3/4/3.mvt").body, first.body);
+        assert_eq!(tile("/tiles/3/9/2.mvt").status, 400);
+        assert_eq!(tile("/tiles/3/4/2.png").status, 404);
+    }
+}
@@ -0,0 +1,308 @@
+use geojson::feature::Id;
+use geojson::{Feature, FeatureCollection};
+use serde_json::{Value, json};
+
+use crate::error::RandomGeojsonResult;
+use crate::geometry::{Bounds, WebMercator, geometry_bbox};
+use crate::random;
+use crate::serve::{GenerateParams, Response, ServeOptions, parse_query};
+
+/// Identifier of the only collection served.
+pub const COLLECTION_ID: &str = "random";
+
+/// Number of items returned when a request has no `limit`.
+const DEFAULT_LIMIT: usize = 10;
+
+const CONFORMANCE: [&str; 2] = [
+    "http://www.opengis.net/spec/ogcapi-features-1/1.0/conf/core",
+    "http://www.opengis.net/spec/ogcapi-features-1/1.0/conf/geojson",
+];
+
+fn link(href: String, rel: &str, media_type: &str, title: &str) -> Value {
+    json!({"href": href, "rel": rel, "type": media_type, "title": title})
+}
+
+/// The landing page at `/`.
+pub fn landing_page(base_url: &str) -> Response {
+    Response::json(&json!({
+        "title": "random-geojson",
+        "description": "Mock OGC API - Features server backed by random features",
+        "links": [
+            link(format!("{}/", base_url), "self", "application/json", "This document"),
+            link(
+                format!("{}/conformance", base_url),
+                "conformance",
+                "application/json",
+                "Conformance classes",
+            ),
+            link(
+                format!("{}/collections", base_url),
+                "data",
+                "application/json",
+                "Collections",
+            ),
+        ],
+    }))
+}
+
+/// The conformance declaration at `/conformance`.
+pub fn conformance() -> Response {
+    Response::json(&json!({ "conformsTo": CONFORMANCE }))
+}
+
+fn collection_description(base_url: &str, options: &ServeOptions) -> Value {
+    let bounds = options.extent;
+    let href = format!("{}/collections/{}", base_url, COLLECTION_ID);
+    json!({
+        "id": COLLECTION_ID,
+        "title": "Random features",
+        "description": format!("{} features generated on the fly", options.collection_size),
+        "extent": {
+            "spatial": {
+                "bbox": [[bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y]],
+                "crs": "http://www.opengis.net/def/crs/OGC/1.3/CRS84",
+            },
+        },
+        "itemType": "feature",
+        "links": [
+            link(href.clone(), "self", "application/json", "This collection"),
+            link(format!("{}/items", href), "items", "application/geo+json", "Items"),
+        ],
+    })
+}
+
+/// The collection list at `/collections`.
+pub fn collections(base_url: &str, options: &ServeOptions) -> Response {
+    Response::json(&json!({
+        "collections": [collection_description(base_url, options)],
+        "links": [link(
+            format!("{}/collections", base_url),
+            "self",
+            "application/json",
+            "This document",
+        )],
+    }))
+}
+
+/// The collection at `/collections/random`.
+pub fn collection(base_url: &str, options: &ServeOptions) -> Response {
+    Response::json(&collection_description(base_url, options))
+}
+
+/// A page of the collection at `/collections/random/items`.
+///
+/// The collection is `collection_size` features, paged with `limit` and
+/// `offset` and filtered by `bbox`. Each feature is generated from a seed
+/// derived from its index, which is also its id, so every request with the
+/// same parameters sees the same collection.
+pub fn items(
+    base_url: &str,
+    query: &str,
+    options: &ServeOptions,
+    generate: &impl Fn(&GenerateParams) -> RandomGeojsonResult<FeatureCollection>,
+) -> Response {
+    let mut params = match GenerateParams::from_query(query) {
+        Ok(params) => params,
+        Err(e) => return Response::error(400, e),
+    };
+    let parsed = parse_query(query);
+    let number = |name: &str, default: usize| match parsed.get(name) {
+        Some(v) => v
+            .parse::<usize>()
+            .map_err(|_| Response::error(400, format!("{} must be a non-negative integer", name))),
+        None => Ok(default),
+    };
+    let (limit, offset) = match (number("limit", DEFAULT_LIMIT), number("offset", 0)) {
+        // Limits above the maximum are lowered rather than rejected, as the
+        // specification requires
+        (Ok(limit), Ok(offset)) => (limit.clamp(1, options.max_length), offset),
+        (Err(response), _) | (_, Err(response)) => return response,
+    };
+    let bbox = match params.bbox.take().map(|bbox| output_bounds(&bbox, options)) {
+        None => None,
+        Some(Some(bbox)) => Some(bbox),
+        Some(None) => {
+            return Response::error(
+                400,
+                "bbox needs WGS84 or Web Mercator coordinates".to_string(),
+            );
+        }
+    };
+
+    let (features, matched) = match &bbox {
+        // Only the features of the page are generated
+        None => {
+            let end = offset.saturating_add(limit).min(options.collection_size);
+            let features = (offset..end)
+                .map(|index| feature_at(index, &params, generate))
+                .collect::<RandomGeojsonResult<Vec<Feature>>>();
+            (features, options.collection_size)
+        }
+        // Every feature is generated to count those matching
+        Some(bbox) => {
+            let mut matched = 0;
+            let mut features = Vec::new();
+            for index in 0..options.collection_size {
+                let feature = match feature_at(index, &params, generate) {
+                    Ok(feature) => feature,
+                    Err(e) => return Response::error(400, e),
+                };
+                if !intersects(&feature, bbox) {
+                    continue;
+                }
+                if matched >= offset && features.len() < limit {
+                    features.push(feature);
+                }
+                matched += 1;
+            }
+            (Ok(features), matched)
+        }
+    };
+    let mut fc = match features {
+        Ok(features) => FeatureCollection {
+            bbox: None,
+            features,
+            foreign_members: None,
+        },
+        Err(e) => return Response::error(400, e),
+    };
+    let returned = fc.features.len();
+
+    // Keep the other parameters, such as bbox, in the paging links
+    let other: Vec<&str> = query
+        .split('&')
+        .filter(|pair| {
+            let key = pair.split_once('=').map_or(*pair, |(key, _)| key);
+            !pair.is_empty() && key != "limit" && key != "offset"
+        })
+        .collect();
+    let href = |offset: usize| {
+        let mut query = other.clone();
+        let paging = format!("limit={}&offset={}", limit, offset);
+        query.push(&paging);
+        format!(
+            "{}/collections/{}/items?{}",
+            base_url,
+            COLLECTION_ID,
+            query.join("&")
+        )
+    };
+    let mut links = vec![link(
+        href(offset),
+        "self",
+        "application/geo+json",
+        "This page",
+    )];
+    if offset + returned < matched {
+        links.push(link(
+            href(offset + returned),
+            "next",
+            "application/geo+json",
+            "Next page",
+        ));
+    }
+    if offset > 0 {
+        links.push(link(
+            href(offset.saturating_sub(limit)),
+            "prev",
+            "application/geo+json",
+            "Previous page",
+        ));
+    }
+
+    let members = fc.foreign_members.get_or_insert_with(Default::default);
+    members.insert("numberMatched".to_string(), matched.into());
+    members.insert("numberReturned".to_string(), returned.into());
+    members.insert("links".to_string(), links.into());
+    Response::geojson(&fc)
+}
+
+/// A single feature at `/collections/random/items/{id}`, the same as in
+/// the pages of the collection.
+pub fn item(
+    base_url: &str,
+    id: &str,
+    query: &str,
+    options: &ServeOptions,
+    generate: &impl Fn(&GenerateParams) -> RandomGeojsonResult<FeatureCollection>,
+) -> Response {
+    let params = match GenerateParams::from_query(query) {
+        Ok(params) => params,
+        Err(e) => return Response::error(400, e),
+    };
+    let Some(index) = id
+        .parse::<usize>()
+        .ok()
+        .filter(|&index| index < options.collection_size)
+    else {
+        return Response::error(404, format!("Feature {} not found", id));
+    };
+    let mut feature = match feature_at(index, &params, generate) {
+        Ok(feature) => feature,
+        Err(e) => return Response::error(400, e),
+    };
+    let items = format!("{}/collections/{}/items", base_url, COLLECTION_ID);
+    let links = vec![
+        link(
+            format!("{}/{}", items, id),
+            "self",
+            "application/geo+json",
+            "This feature",
+        ),
+        link(
+            format!("{}/collections/{}", base_url, COLLECTION_ID),
+            "collection",
+            "application/json",
+            "The collection",
+        ),
+    ];
+    feature
+        .foreign_members
+        .get_or_insert_with(Default::default)
+        .insert("links".to_string(), links.into());
+    Response {
+        status: 200,
+        content_type: "application/geo+json",
+        body: serde_json::to_vec(&feature).unwrap_or_default(),
+    }
+}
+
+// Generates the feature at `index` of the collection, seeded by the index
+// and identified by it.
+fn feature_at(
+    index: usize,
+    params: &GenerateParams,
+    generate: &impl Fn(&GenerateParams) -> RandomGeojsonResult<FeatureCollection>,
+) -> RandomGeojsonResult<Feature> {
+    let params = GenerateParams {
+        length: Some(1),
+        bbox: None,
+        ..params.clone()
+    };
+    let fc = random::with_seed(index as u64, || generate(&params))?;
+    let mut feature = fc.features.into_iter().next().unwrap_or_default();
+    feature.id = Some(Id::Number(index.into()));
+    Ok(feature)
+}
+
+// The geographic `bbox` in the coordinates of the features, if they are
+// WGS84 or Web Mercator.
+fn output_bounds(bbox: &Bounds, options: &ServeOptions) -> Option<Bounds> {
+    match options.epsg {
+        Some(4326) => Some(*bbox),
+        Some(3857) => Some(bbox.transformed(&WebMercator)),
+        _ => None,
+    }
+}
+
+// Whether the bounding box of the geometry of `feature` touches `bbox`.
+fn intersects(feature: &Feature, bbox: &Bounds) -> bool {
+    let Some([min_x, min_y, max_x, max_y]) = feature
+        .geometry
+        .as_ref()
+        .and_then(|geometry| geometry_bbox(&geometry.value))
+    else {
+        return false;
+    };
+    min_x <= bbox.max_x && max_x >= bbox.min_x && min_y <= bbox.max_y && max_y >= bbox.min_y
+}
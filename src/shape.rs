@@ -0,0 +1,640 @@
+use rand::Rng;
+
+use std::f64::consts::TAU;
+
+use crate::anchor::{offset, project};
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::geometry::{Crs, RandomGeometry, random_geographic_coords, random_vertices};
+use crate::measure::SizeRange;
+use crate::random;
+use crate::spacing::{Distance, EARTH_RADIUS, PointSpacing};
+use crate::walk::walk_vertices;
+
+/// Segments of the outline of a circle.
+const CIRCLE_SEGMENTS: usize = 64;
+
+/// Centres tried for a circle, n-gon, star or concave polygon before its
+/// radius is halved on every further try until it fits in the area.
+const MAX_PLACEMENTS: usize = 10;
+
+/// Times the segments of fractal lines are halved, so each one ends up as
+/// 64 segments.
+const FRACTAL_LEVELS: usize = 6;
+
+/// How the outlines of random polygons are shaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PolygonStyle {
+    /// 3 to 9 vertices anywhere in the area, in random order.
+    #[default]
+    Random,
+    /// Building footprints: rotated rectangles and L and T shapes of 50 to
+    /// 2000 m².
+    Footprint,
+    /// Circles, as regular polygons of 64 sides.
+    Circle,
+    /// Regular polygons of a given number of vertices.
+    Ngon,
+    /// Stars with a given number of points.
+    Star,
+    /// Irregular outlines notched towards their centre.
+    Concave,
+}
+
+impl PolygonStyle {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolygonStyle::Random => "random",
+            PolygonStyle::Footprint => "footprint",
+            PolygonStyle::Circle => "circle",
+            PolygonStyle::Ngon => "ngon",
+            PolygonStyle::Star => "star",
+            PolygonStyle::Concave => "concave",
+        }
+    }
+}
+
+/// A polygon style with the parameters of its shapes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolygonShape {
+    pub style: PolygonStyle,
+    /// Range of the radius of circles, n-gons, stars and concave shapes.
+    pub radius: RadiusRange,
+    /// Number of vertices of n-gons and points of stars.
+    pub vertices: usize,
+    /// How far the inner vertices of stars and concave shapes are pulled
+    /// towards the centre, as a share of the radius in [0, 1).
+    pub concavity: f64,
+    /// Range of the area of the polygons in square metres, which rescales
+    /// the shapes of any style that fall outside it.
+    pub area: Option<SizeRange>,
+}
+
+impl Default for PolygonShape {
+    fn default() -> Self {
+        PolygonShape {
+            style: PolygonStyle::Random,
+            radius: RadiusRange {
+                min: 100.0,
+                max: 5000.0,
+            },
+            vertices: 6,
+            concavity: 0.5,
+            area: None,
+        }
+    }
+}
+
+impl PolygonShape {
+    /// Creates a random Polygon geometry of this shape. Fails if its area
+    /// can't be fitted into the range.
+    pub fn random_polygon(&self, crs: &Crs) -> RandomGeojsonResult<RandomGeometry> {
+        let mut ring = self.outline(crs);
+        if let Some(area) = self.area {
+            ring = area.fit_area(crs, ring)?;
+        }
+        ring.push(ring[0]);
+        Ok(RandomGeometry::Polygon(vec![project(crs, &ring)]))
+    }
+
+    // The outline as an open ring of geographic coordinates.
+    fn outline(&self, crs: &Crs) -> Vec<(f64, f64)> {
+        match self.style {
+            PolygonStyle::Random => random_vertices(crs, 3..10),
+            PolygonStyle::Circle
+            | PolygonStyle::Ngon
+            | PolygonStyle::Star
+            | PolygonStyle::Concave => {
+                let mut rng = random::rng();
+                let bounds = crs.bounds();
+                let mut scale = 1.0;
+                // Shapes running off the area would be clamped, or wrapped
+                // across the antimeridian, into self-intersecting outlines
+                for attempt in 1.. {
+                    let (lon, lat) = random_geographic_coords(crs);
+                    let radius = rng.random_range(self.radius.min..=self.radius.max) * scale;
+                    let start = rng.random_range(0.0..TAU);
+                    let mut ring: Vec<(f64, f64)> = self
+                        .radial_outline()
+                        .into_iter()
+                        .map(|(turn, share)| {
+                            let (x, y) = destination(lon, lat, start + TAU * turn, radius * share);
+                            (lon + (x - lon + 540.0).rem_euclid(360.0) - 180.0, y)
+                        })
+                        .collect();
+                    if ring.iter().all(|&(x, y)| bounds.contains(x, y)) {
+                        // Bearings run clockwise, so reverse for a counter-clockwise ring
+                        ring.reverse();
+                        return ring;
+                    }
+                    if attempt >= MAX_PLACEMENTS {
+                        scale /= 2.0;
+                    }
+                }
+                unreachable!("a small enough outline fits around any centre")
+            }
+            PolygonStyle::Footprint => {
+                let mut rng = random::rng();
+                let (lon, lat) = random_geographic_coords(crs);
+                // Log-uniform, so small buildings are as common as in towns
+                let area = 10f64.powf(rng.random_range(50f64.log10()..2000f64.log10()));
+                footprint(area)
+                    .into_iter()
+                    .map(|(east, north)| offset(lon, lat, east, north))
+                    .collect()
+            }
+        }
+    }
+}
+
+impl PolygonShape {
+    // Vertices of a shape around its centre, as the share of a full turn
+    // and of the radius, in increasing order of the turn. Every vertex is
+    // in its own direction, so the outline never crosses itself.
+    fn radial_outline(&self) -> Vec<(f64, f64)> {
+        let mut rng = random::rng();
+        let regular = |n: usize| (0..n).map(move |i| i as f64 / n as f64);
+        match self.style {
+            PolygonStyle::Circle => regular(CIRCLE_SEGMENTS).map(|t| (t, 1.0)).collect(),
+            PolygonStyle::Star => regular(2 * self.vertices)
+                .enumerate()
+                .map(|(i, t)| {
+                    (
+                        t,
+                        if i % 2 == 0 {
+                            1.0
+                        } else {
+                            1.0 - self.concavity
+                        },
+                    )
+                })
+                .collect(),
+            PolygonStyle::Concave => {
+                // Jittered directions keep neighbours less than a half turn apart
+                let n = rng.random_range(8..=24);
+                regular(n)
+                    .map(|t| {
+                        let turn = t + rng.random_range(0.0..0.8) / n as f64;
+                        (turn, 1.0 - self.concavity * rng.random::<f64>())
+                    })
+                    .collect()
+            }
+            _ => regular(self.vertices).map(|t| (t, 1.0)).collect(),
+        }
+    }
+}
+
+/// How random LineStrings are shaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineStyle {
+    /// Straight segments between the vertices.
+    #[default]
+    Random,
+    /// Segments broken up by midpoint displacement into wiggly,
+    /// coastline-like lines.
+    Fractal,
+}
+
+impl LineStyle {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineStyle::Random => "random",
+            LineStyle::Fractal => "fractal",
+        }
+    }
+}
+
+impl std::str::FromStr for LineStyle {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "random" => Ok(LineStyle::Random),
+            "fractal" => Ok(LineStyle::Fractal),
+            _ => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid line style: {} (expected random or fractal)",
+                s
+            ))),
+        }
+    }
+}
+
+/// How random LineStrings are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LineShape {
+    pub style: LineStyle,
+    /// How far fractal lines stray from their segments, from 0 (not at all)
+    /// to 1 (up to half of each subdivided segment at every level).
+    pub roughness: f64,
+    /// Longest step of random walks, or `None` for vertices anywhere in the
+    /// area.
+    pub walk: Option<Distance>,
+    /// Range of the length of the lines in metres, which rescales the lines
+    /// that fall outside it.
+    pub length: Option<SizeRange>,
+}
+
+impl LineShape {
+    /// Creates a random LineString geometry of this shape. Fails if its
+    /// length can't be fitted into the range.
+    pub fn random_linestring(&self, crs: &Crs) -> RandomGeojsonResult<RandomGeometry> {
+        let mut line = match self.walk {
+            Some(step) => walk_vertices(crs, step),
+            None => random_vertices(crs, 2..10),
+        };
+        if self.style == LineStyle::Fractal {
+            line = displace_midpoints(crs, &line, self.roughness);
+        }
+        if let Some(length) = self.length {
+            line = length.fit_length(crs, line)?;
+        }
+        Ok(RandomGeometry::LineString(project(crs, &line)))
+    }
+}
+
+// Subdivides every segment of `line` FRACTAL_LEVELS times, moving each new
+// midpoint sideways by a random share of up to `roughness` of half the
+// segment it splits. As the displacement shrinks with the segments, the line
+// is equally wiggly at every scale, like a coastline.
+fn displace_midpoints(crs: &Crs, line: &[(f64, f64)], roughness: f64) -> Vec<(f64, f64)> {
+    let mut rng = random::rng();
+    let bounds = crs.bounds();
+    let mut line = line.to_vec();
+    for _ in 0..FRACTAL_LEVELS {
+        let mut next = Vec::with_capacity(2 * line.len());
+        for segment in line.windows(2) {
+            let ((x0, y0), (x1, y1)) = (segment[0], segment[1]);
+            let (x, y) = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+            // Perpendicular to the segment with longitudes shrunk towards
+            // the poles, so the line wiggles alike in every direction
+            let scale = y.to_radians().cos().max(1e-6);
+            let (dx, dy) = ((x1 - x0) * scale, y1 - y0);
+            let shift = roughness * rng.random_range(-0.5..=0.5);
+            next.push(segment[0]);
+            next.push((
+                (x - dy * shift / scale).clamp(bounds.min_x, bounds.max_x),
+                (y + dx * shift).clamp(bounds.min_y, bounds.max_y),
+            ));
+        }
+        next.extend(line.last());
+        line = next;
+    }
+    line
+}
+
+impl std::str::FromStr for PolygonStyle {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "random" => Ok(PolygonStyle::Random),
+            "footprint" => Ok(PolygonStyle::Footprint),
+            "circle" => Ok(PolygonStyle::Circle),
+            "ngon" | "n-gon" => Ok(PolygonStyle::Ngon),
+            "star" => Ok(PolygonStyle::Star),
+            "concave" => Ok(PolygonStyle::Concave),
+            _ => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid polygon style: {} (expected random, footprint, circle, ngon, star or concave)",
+                s
+            ))),
+        }
+    }
+}
+
+/// A range of radii in metres.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RadiusRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Parses a radius such as "500" or a range such as "100..5000" or
+/// "1km..5km", in metres unless a unit is given.
+impl std::str::FromStr for RadiusRange {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            RandomGeojsonError::InvalidArgument(format!(
+                "Invalid radius: {}, expected metres such as \"500\", \"100..5000\" or \"1km..5km\"",
+                s
+            ))
+        };
+        let (min, max) = s.split_once("..").unwrap_or((s, s));
+        let meters = |value: &str| match value.parse::<Distance>() {
+            Ok(Distance::Meters(meters)) => Ok(meters),
+            _ => Err(invalid()),
+        };
+        let (min, max) = (meters(min)?, meters(max)?);
+        if min > max || max <= 0.0 {
+            return Err(invalid());
+        }
+        Ok(RadiusRange { min, max })
+    }
+}
+
+/// The position `distance` metres away from a geographic position along a
+/// great circle starting at `bearing`, in radians clockwise from north.
+pub(crate) fn destination(lon: f64, lat: f64, bearing: f64, distance: f64) -> (f64, f64) {
+    let angle = distance / EARTH_RADIUS;
+    let (lon, lat) = (lon.to_radians(), lat.to_radians());
+    let to_lat = (lat.sin() * angle.cos() + lat.cos() * angle.sin() * bearing.cos()).asin();
+    let to_lon = lon
+        + (bearing.sin() * angle.sin() * lat.cos()).atan2(angle.cos() - lat.sin() * to_lat.sin());
+    let to_lon = (to_lon.to_degrees() + 540.0).rem_euclid(360.0) - 180.0;
+    (to_lon, to_lat.to_degrees())
+}
+
+/// Creates the geodesic [`buffer`] of a random point with a radius drawn
+/// from `radius`, at least the distance of `spacing` from the points before
+/// if given.
+pub fn random_buffer(
+    crs: &Crs,
+    spacing: Option<&mut PointSpacing>,
+    radius: &RadiusRange,
+) -> RandomGeojsonResult<RandomGeometry> {
+    let centre = match spacing {
+        Some(spacing) => spacing.random_coords(crs)?,
+        None => random_geographic_coords(crs),
+    };
+    let radius = random::rng().random_range(radius.min..=radius.max);
+    Ok(buffer(crs, centre, radius))
+}
+
+/// The geodesic buffer of a geographic point: a circle of `radius` metres
+/// around it on the surface of the Earth, as a Polygon of 64 sides.
+pub fn buffer(crs: &Crs, (lon, lat): (f64, f64), radius: f64) -> RandomGeometry {
+    let mut ring: Vec<(f64, f64)> = (0..CIRCLE_SEGMENTS)
+        .map(|i| {
+            let bearing = TAU * i as f64 / CIRCLE_SEGMENTS as f64;
+            let (x, y) = destination(lon, lat, bearing, radius);
+            // Kept next to the point rather than wrapped across the
+            // antimeridian, so that projecting clamps rather than twists it
+            (lon + (x - lon + 540.0).rem_euclid(360.0) - 180.0, y)
+        })
+        .collect();
+    // Bearings run clockwise, so reverse for a counter-clockwise ring
+    ring.reverse();
+    ring.push(ring[0]);
+    RandomGeometry::Polygon(vec![project(crs, &ring)])
+}
+
+/// A building outline of `area` square metres in local metres around the
+/// origin, as an open counter-clockwise ring: a rectangle, or an L or T
+/// shape cut from one, randomly rotated.
+pub fn footprint(area: f64) -> Vec<(f64, f64)> {
+    let mut rng = random::rng();
+    let (width, depth) = (rng.random_range(1.0..2.5), 1.0);
+    let outline: Vec<(f64, f64)> = match rng.random_range(0..20) {
+        // An L: a rectangle without its top right corner
+        0..5 => {
+            let (cut_x, cut_y) = (
+                width * rng.random_range(0.3..0.7),
+                depth * rng.random_range(0.3..0.7),
+            );
+            vec![
+                (0.0, 0.0),
+                (width, 0.0),
+                (width, depth - cut_y),
+                (width - cut_x, depth - cut_y),
+                (width - cut_x, depth),
+                (0.0, depth),
+            ]
+        }
+        // A T: a bar along the top over a centred stem
+        5..8 => {
+            let stem = width * rng.random_range(0.3..0.6);
+            let bar = depth * rng.random_range(0.3..0.6);
+            let x = (width - stem) / 2.0;
+            vec![
+                (x, 0.0),
+                (x + stem, 0.0),
+                (x + stem, depth - bar),
+                (width, depth - bar),
+                (width, depth),
+                (0.0, depth),
+                (0.0, depth - bar),
+                (x, depth - bar),
+            ]
+        }
+        _ => vec![(0.0, 0.0), (width, 0.0), (width, depth), (0.0, depth)],
+    };
+    let scale = (area / ring_area(&outline)).sqrt();
+    let (sin, cos) = rng.random_range(0.0..TAU).sin_cos();
+    outline
+        .into_iter()
+        .map(|(x, y)| {
+            let (x, y) = ((x - width / 2.0) * scale, (y - depth / 2.0) * scale);
+            (x * cos - y * sin, x * sin + y * cos)
+        })
+        .collect()
+}
+
+// Shoelace formula, positive for counter-clockwise rings.
+fn ring_area(ring: &[(f64, f64)]) -> f64 {
+    (0..ring.len())
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{Bounds, Unit};
+
+    #[test]
+    fn test_footprint() {
+        let mut shapes = std::collections::BTreeSet::new();
+        for _ in 0..200 {
+            let outline = footprint(500.0);
+            assert!((ring_area(&outline) - 500.0).abs() < 1e-6);
+            // Every corner is a right angle
+            for i in 0..outline.len() {
+                let (a, b, c) = (
+                    outline[i],
+                    outline[(i + 1) % outline.len()],
+                    outline[(i + 2) % outline.len()],
+                );
+                let dot = (b.0 - a.0) * (c.0 - b.0) + (b.1 - a.1) * (c.1 - b.1);
+                assert!(dot.abs() < 1e-6);
+            }
+            shapes.insert(outline.len());
+        }
+        assert_eq!(shapes.into_iter().collect::<Vec<_>>(), vec![4, 6, 8]);
+    }
+
+    #[test]
+    fn test_circle() {
+        let shape = PolygonShape {
+            style: PolygonStyle::Circle,
+            radius: "1km..1km".parse().unwrap(),
+            ..Default::default()
+        };
+        let RandomGeometry::Polygon(rings) = shape.random_polygon(&Crs::WGS84).unwrap() else {
+            unreachable!("circles are polygons")
+        };
+        let ring = &rings[0];
+        assert_eq!(ring.len(), CIRCLE_SEGMENTS + 1);
+        assert_eq!(ring.first(), ring.last());
+        let open: Vec<(f64, f64)> = ring[..CIRCLE_SEGMENTS]
+            .iter()
+            .map(|p| (p[0], p[1]))
+            .collect();
+        assert!(ring_area(&open) > 0.0);
+        // Opposite vertices are a diameter apart: 2 km is 0.018 degrees of
+        // latitude, or more degrees of longitude away from the equator
+        let (a, b) = (&ring[0], &ring[CIRCLE_SEGMENTS / 2]);
+        let lat = (a[1] + b[1]).to_radians() / 2.0;
+        let dx = (b[0] - a[0]) * lat.cos();
+        let diameter = dx.hypot(b[1] - a[1]).to_radians() * EARTH_RADIUS;
+        assert!((diameter - 2000.0).abs() < 10.0, "{}", diameter);
+    }
+
+    #[test]
+    fn test_buffer() {
+        let RandomGeometry::Polygon(rings) = buffer(&Crs::WGS84, (10.0, 60.0), 500.0) else {
+            unreachable!("buffers are polygons")
+        };
+        let ring = &rings[0];
+        assert_eq!(ring.len(), CIRCLE_SEGMENTS + 1);
+        assert_eq!(ring.first(), ring.last());
+        let open: Vec<(f64, f64)> = ring[..CIRCLE_SEGMENTS]
+            .iter()
+            .map(|p| (p[0], p[1]))
+            .collect();
+        assert!(ring_area(&open) > 0.0);
+        for &(x, y) in &open {
+            let dx = (x - 10.0) * 60f64.to_radians().cos();
+            let distance = dx.hypot(y - 60.0).to_radians() * EARTH_RADIUS;
+            assert!((distance - 500.0).abs() < 1.0, "{}", distance);
+        }
+    }
+
+    #[test]
+    fn test_star_and_concave_are_simple() {
+        for style in [PolygonStyle::Star, PolygonStyle::Concave] {
+            for _ in 0..50 {
+                let shape = PolygonShape {
+                    style,
+                    concavity: 0.8,
+                    ..Default::default()
+                };
+                let outline = shape.radial_outline();
+                assert!(outline.windows(2).all(|w| w[0].0 < w[1].0));
+                assert!(outline.iter().all(|&(t, share)| t < 1.0 && share > 0.0));
+                let RandomGeometry::Polygon(rings) = shape.random_polygon(&Crs::WGS84).unwrap()
+                else {
+                    unreachable!("stars are polygons")
+                };
+                let ring = &rings[0];
+                // No two edges that do not share a vertex touch
+                let n = ring.len() - 1;
+                for i in 0..n {
+                    for j in i + 2..n {
+                        if i == 0 && j == n - 1 {
+                            continue;
+                        }
+                        let (p, q, r, s) = (&ring[i], &ring[i + 1], &ring[j], &ring[j + 1]);
+                        assert!(!crate::overlap::segments_intersect(p, q, r, s));
+                    }
+                }
+                // and stars have corners turning inwards
+                let reflex = (0..n).any(|i| {
+                    let (a, b, c) = (&ring[i], &ring[(i + 1) % n], &ring[(i + 2) % n]);
+                    (b[0] - a[0]) * (c[1] - b[1]) - (b[1] - a[1]) * (c[0] - b[0]) < 0.0
+                });
+                assert!(reflex || style == PolygonStyle::Concave);
+            }
+        }
+    }
+
+    #[test]
+    fn test_radius_larger_than_area() {
+        let bounds = Bounds::new(170.0, -10.0, 180.0, 10.0, Unit::Degrees);
+        let crs = Crs::WGS84.clipped(bounds).unwrap();
+        let shape = PolygonShape {
+            style: PolygonStyle::Circle,
+            radius: "5000km".parse().unwrap(),
+            ..Default::default()
+        };
+        for _ in 0..20 {
+            let RandomGeometry::Polygon(rings) = shape.random_polygon(&crs).unwrap() else {
+                unreachable!("circles are polygons")
+            };
+            let open: Vec<(f64, f64)> = rings[0][..CIRCLE_SEGMENTS]
+                .iter()
+                .map(|p| (p[0], p[1]))
+                .collect();
+            assert!(open.iter().all(|&(x, y)| bounds.contains(x, y)));
+            assert!(ring_area(&open) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_destination() {
+        // A quarter of the equator eastwards
+        let quarter = EARTH_RADIUS * std::f64::consts::FRAC_PI_2;
+        let (lon, lat) = destination(0.0, 0.0, std::f64::consts::FRAC_PI_2, quarter);
+        assert!((lon - 90.0).abs() < 1e-9 && lat.abs() < 1e-9);
+        // Across the antimeridian
+        let (lon, _) = destination(179.9, 0.0, std::f64::consts::FRAC_PI_2, 50_000.0);
+        assert!(lon < -179.0, "{}", lon);
+    }
+
+    #[test]
+    fn test_radius_range_from_str() {
+        let range: RadiusRange = "100..5km".parse().unwrap();
+        assert_eq!(
+            range,
+            RadiusRange {
+                min: 100.0,
+                max: 5000.0
+            }
+        );
+        assert!("5000..100".parse::<RadiusRange>().is_err());
+        assert!("0.1deg".parse::<RadiusRange>().is_err());
+    }
+
+    #[test]
+    fn test_polygon_style_from_str() {
+        assert_eq!(
+            "Footprint".parse::<PolygonStyle>().unwrap(),
+            PolygonStyle::Footprint
+        );
+        assert!("blob".parse::<PolygonStyle>().is_err());
+    }
+
+    #[test]
+    fn test_fractal_linestring() {
+        let crs = Crs::WGS84;
+        let line = [(0.0, 0.0), (1.0, 0.0)];
+        let straight = displace_midpoints(&crs, &line, 0.0);
+        assert_eq!(straight.len(), 65);
+        assert!(straight.iter().all(|&(_, y)| y == 0.0));
+        assert_eq!(straight[64], (1.0, 0.0));
+
+        let rough = displace_midpoints(&crs, &line, 1.0);
+        assert_eq!((rough[0], rough[64]), ((0.0, 0.0), (1.0, 0.0)));
+        assert!(rough.iter().any(|&(_, y)| y != 0.0));
+        let length: f64 = rough
+            .windows(2)
+            .map(|s| ((s[1].0 - s[0].0).powi(2) + (s[1].1 - s[0].1).powi(2)).sqrt())
+            .sum();
+        assert!(length > 1.0, "{}", length);
+
+        let shape = LineShape {
+            style: LineStyle::Fractal,
+            roughness: 0.5,
+            ..Default::default()
+        };
+        let RandomGeometry::LineString(coords) = shape.random_linestring(&crs).unwrap() else {
+            panic!("not a LineString");
+        };
+        assert_eq!((coords.len() - 1) % 64, 0);
+        assert_eq!("Fractal".parse::<LineStyle>().unwrap(), LineStyle::Fractal);
+        assert!("smooth".parse::<LineStyle>().is_err());
+    }
+}
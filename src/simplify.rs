@@ -0,0 +1,177 @@
+use geojson::{FeatureCollection, Position, Value};
+
+/// Simplifies the lines and polygon rings of every feature of `fc` with the
+/// Douglas-Peucker algorithm, dropping vertices less than `tolerance` from
+/// the simplified line, in the units of the coordinates.
+///
+/// Rings that would collapse below four positions are left as they are, so
+/// polygons stay valid.
+pub fn simplify(fc: &mut FeatureCollection, tolerance: f64) {
+    for geometry in fc.features.iter_mut().filter_map(|f| f.geometry.as_mut()) {
+        map_lines(&mut geometry.value, &mut |line, ring| {
+            let simplified = douglas_peucker(line, tolerance);
+            if !ring || simplified.len() >= 4 {
+                *line = simplified;
+            }
+        });
+    }
+}
+
+/// Adds vertices to the lines and polygon rings of every feature of `fc`
+/// so that no segment is longer than `max_length`, in the units of the
+/// coordinates, splitting each one into equal parts.
+pub fn densify(fc: &mut FeatureCollection, max_length: f64) {
+    for geometry in fc.features.iter_mut().filter_map(|f| f.geometry.as_mut()) {
+        map_lines(&mut geometry.value, &mut |line, _| {
+            *line = densify_line(line, max_length);
+        });
+    }
+}
+
+// Calls `f` with every line and ring of `value`, and whether it is a ring.
+fn map_lines(value: &mut Value, f: &mut impl FnMut(&mut Vec<Position>, bool)) {
+    match value {
+        Value::Point(_) | Value::MultiPoint(_) => {}
+        Value::LineString(line) => f(line, false),
+        Value::MultiLineString(lines) => lines.iter_mut().for_each(|line| f(line, false)),
+        Value::Polygon(rings) => rings.iter_mut().for_each(|ring| f(ring, true)),
+        Value::MultiPolygon(polygons) => {
+            polygons.iter_mut().flatten().for_each(|ring| f(ring, true))
+        }
+        Value::GeometryCollection(geometries) => geometries
+            .iter_mut()
+            .for_each(|g| map_lines(&mut g.value, f)),
+    }
+}
+
+/// Simplifies a line with the Douglas-Peucker algorithm, keeping its first
+/// and last positions.
+pub fn douglas_peucker(line: &[Position], tolerance: f64) -> Vec<Position> {
+    if line.len() < 3 {
+        return line.to_vec();
+    }
+    let mut keep = vec![false; line.len()];
+    keep[0] = true;
+    keep[line.len() - 1] = true;
+    // Spans still to be checked, as a stack rather than by recursion so
+    // that long lines cannot overflow it
+    let mut spans = vec![(0, line.len() - 1)];
+    while let Some((first, last)) = spans.pop() {
+        let farthest = (first + 1..last)
+            .map(|i| (i, segment_distance(&line[i], &line[first], &line[last])))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((i, distance)) = farthest
+            && distance > tolerance
+        {
+            keep[i] = true;
+            spans.push((first, i));
+            spans.push((i, last));
+        }
+    }
+    line.iter()
+        .zip(keep)
+        .filter(|&(_, keep)| keep)
+        .map(|(p, _)| p.clone())
+        .collect()
+}
+
+// Splits every segment longer than `max_length` into equal parts.
+fn densify_line(line: &[Position], max_length: f64) -> Vec<Position> {
+    let mut dense = Vec::with_capacity(line.len());
+    for segment in line.windows(2) {
+        let (a, b) = (&segment[0], &segment[1]);
+        let length = (b[0] - a[0]).hypot(b[1] - a[1]);
+        let parts = (length / max_length).ceil().max(1.0) as usize;
+        dense.push(a.clone());
+        for i in 1..parts {
+            let t = i as f64 / parts as f64;
+            dense.push(a.iter().zip(b).map(|(a, b)| a + (b - a) * t).collect());
+        }
+    }
+    dense.extend(line.last().cloned());
+    dense
+}
+
+// Distance from `p` to the segment from `a` to `b`.
+fn segment_distance(p: &[f64], a: &[f64], b: &[f64]) -> f64 {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    let squared = dx * dx + dy * dy;
+    let t = if squared > 0.0 {
+        (((p[0] - a[0]) * dx + (p[1] - a[1]) * dy) / squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (p[0] - a[0] - t * dx).hypot(p[1] - a[1] - t * dy)
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Feature, Geometry};
+
+    use super::*;
+
+    fn positions(coords: &[(f64, f64)]) -> Vec<Position> {
+        coords.iter().map(|&(x, y)| vec![x, y]).collect()
+    }
+
+    #[test]
+    fn test_douglas_peucker() {
+        let line = positions(&[(0.0, 0.0), (1.0, 0.1), (2.0, -0.1), (3.0, 5.0), (4.0, 6.0)]);
+        assert_eq!(
+            douglas_peucker(&line, 0.5),
+            positions(&[(0.0, 0.0), (2.0, -0.1), (3.0, 5.0), (4.0, 6.0)])
+        );
+        assert_eq!(
+            douglas_peucker(&line, 10.0),
+            positions(&[(0.0, 0.0), (4.0, 6.0)])
+        );
+        assert_eq!(douglas_peucker(&line, 0.0), line);
+    }
+
+    #[test]
+    fn test_simplify_keeps_rings_valid() {
+        let triangle = positions(&[(0.0, 0.0), (1.0, 0.0), (0.0, 0.01), (0.0, 0.0)]);
+        let mut fc = FeatureCollection {
+            features: vec![Feature::from(Geometry::new(Value::Polygon(vec![
+                triangle.clone(),
+            ])))],
+            ..Default::default()
+        };
+        simplify(&mut fc, 1.0);
+        assert_eq!(
+            fc.features[0].geometry.as_ref().unwrap().value,
+            Value::Polygon(vec![triangle])
+        );
+    }
+
+    #[test]
+    fn test_densify() {
+        let mut fc = FeatureCollection {
+            features: vec![
+                Feature::from(Geometry::new(Value::LineString(positions(&[
+                    (0.0, 0.0),
+                    (1.0, 0.0),
+                    (1.0, 0.25),
+                ])))),
+                Feature::from(Geometry::new(Value::Point(vec![5.0, 5.0]))),
+            ],
+            ..Default::default()
+        };
+        densify(&mut fc, 0.3);
+        assert_eq!(
+            fc.features[0].geometry.as_ref().unwrap().value,
+            Value::LineString(positions(&[
+                (0.0, 0.0),
+                (0.25, 0.0),
+                (0.5, 0.0),
+                (0.75, 0.0),
+                (1.0, 0.0),
+                (1.0, 0.25),
+            ]))
+        );
+        assert_eq!(
+            fc.features[1].geometry.as_ref().unwrap().value,
+            Value::Point(vec![5.0, 5.0])
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use geojson::FeatureCollection;
+
+use crate::geometry::for_each_position_mut;
+
+/// Rounds the x and y of every position of `fc` to the nearest multiple of
+/// `step`, in the units of the coordinates; any z is left alone.
+///
+/// Nearby vertices collapse onto the same grid point, so snapped data has
+/// coincident vertices and may have degenerate lines and rings.
+pub fn snap_to_grid(fc: &mut FeatureCollection, step: f64) {
+    for geometry in fc.features.iter_mut().filter_map(|f| f.geometry.as_mut()) {
+        for_each_position_mut(&mut geometry.value, &mut |p| {
+            for coordinate in p.iter_mut().take(2) {
+                *coordinate = snap(*coordinate, step);
+            }
+        });
+    }
+}
+
+/// Rounds `value` to the nearest multiple of `step`.
+pub fn snap(value: f64, step: f64) -> f64 {
+    let multiple = (value / step).round();
+    // Dividing by a whole inverse, such as 100 for 0.01, gives the closest
+    // float to the decimal (0.3 rather than 0.30000000000000004)
+    let inverse = 1.0 / step;
+    let snapped = if inverse.fract() == 0.0 {
+        multiple / inverse
+    } else {
+        multiple * step
+    };
+    // Rounding a small negative value would otherwise give -0
+    snapped + 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Feature, Geometry, Value};
+
+    use super::*;
+
+    #[test]
+    fn test_snap() {
+        assert_eq!(snap(0.304, 0.01), 0.3);
+        assert_eq!(snap(-12.346, 0.01), -12.35);
+        assert_eq!(snap(-0.001, 0.01).to_string(), "0");
+        assert_eq!(snap(7.4, 2.5), 7.5);
+        assert_eq!(snap(1234.0, 500.0), 1000.0);
+    }
+
+    #[test]
+    fn test_snap_to_grid() {
+        let ring = vec![
+            vec![0.001, 0.002],
+            vec![0.104, 0.003],
+            vec![0.099, 0.096],
+            vec![0.001, 0.002],
+        ];
+        let mut fc = FeatureCollection {
+            features: vec![
+                Feature::from(Geometry::new(Value::Polygon(vec![ring]))),
+                Feature::from(Geometry::new(Value::Point(vec![0.56, 0.44, 12.34]))),
+                Feature::default(),
+            ],
+            ..Default::default()
+        };
+        snap_to_grid(&mut fc, 0.1);
+        let geometry = |i: usize| fc.features[i].geometry.as_ref().unwrap().value.clone();
+        assert_eq!(
+            geometry(0),
+            Value::Polygon(vec![vec![
+                vec![0.0, 0.0],
+                vec![0.1, 0.0],
+                vec![0.1, 0.1],
+                vec![0.0, 0.0],
+            ]])
+        );
+        assert_eq!(geometry(1), Value::Point(vec![0.6, 0.4, 12.34]));
+    }
+}
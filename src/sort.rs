@@ -0,0 +1,220 @@
+use geojson::{Feature, FeatureCollection};
+
+use crate::computed::{centroid, geohash, lon_lat};
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+
+/// Bits of each coordinate on the Hilbert curve.
+const HILBERT_BITS: u32 = 16;
+
+/// Characters of the geohashes features are sorted by.
+const GEOHASH_PRECISION: usize = 12;
+
+/// Order of the features written, by the centroid of their geometry.
+/// Features without a geometry come last, in the order generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// The order generated, which is spatially random.
+    #[default]
+    None,
+    /// Along a Hilbert curve over the extent of the features, so that
+    /// features next to each other in the output are close in space.
+    Hilbert,
+    /// By geohash, a Z-order curve over longitude and latitude.
+    Geohash,
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "none" => Ok(SortOrder::None),
+            "hilbert" => Ok(SortOrder::Hilbert),
+            "geohash" => Ok(SortOrder::Geohash),
+            _ => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid sort order: {} (expected hilbert, geohash or none)",
+                s
+            ))),
+        }
+    }
+}
+
+impl SortOrder {
+    /// Sorts the features of `fc`, whose coordinates are in the EPSG code
+    /// `epsg`. Geohash order needs WGS84 or Web Mercator coordinates.
+    pub fn sort(&self, fc: &mut FeatureCollection, epsg: Option<u32>) -> RandomGeojsonResult<()> {
+        match self {
+            SortOrder::None => {}
+            SortOrder::Hilbert => {
+                let centroids: Vec<_> = fc.features.iter().filter_map(feature_centroid).collect();
+                let Some(extent) = Extent::of(&centroids) else {
+                    return Ok(());
+                };
+                fc.features.sort_by_cached_key(|feature| {
+                    feature_centroid(feature).map_or(u64::MAX, |(x, y)| {
+                        let (x, y) = extent.cell(x, y);
+                        hilbert_index(x, y)
+                    })
+                });
+            }
+            SortOrder::Geohash => {
+                let to_lon_lat = lon_lat(epsg).ok_or_else(|| {
+                    RandomGeojsonError::InvalidArgument(
+                        "Sorting by geohash needs WGS84 or Web Mercator coordinates".to_string(),
+                    )
+                })?;
+                fc.features.sort_by_cached_key(|feature| {
+                    let hash = feature_centroid(feature).map(|(x, y)| {
+                        let (lon, lat) = to_lon_lat(x, y);
+                        geohash(lon, lat, GEOHASH_PRECISION)
+                    });
+                    (hash.is_none(), hash)
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+// The centroid of a feature, if it has a geometry with finite coordinates.
+fn feature_centroid(feature: &Feature) -> Option<(f64, f64)> {
+    let (x, y) = centroid(&feature.geometry.as_ref()?.value)?;
+    (x.is_finite() && y.is_finite()).then_some((x, y))
+}
+
+// The bounding box of the centroids, divided into a grid of Hilbert cells.
+struct Extent {
+    min_x: f64,
+    min_y: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Extent {
+    fn of(points: &[(f64, f64)]) -> Option<Self> {
+        let (first, rest) = points.split_first()?;
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (first.0, first.1, first.0, first.1);
+        for &(x, y) in rest {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+        Some(Extent {
+            min_x,
+            min_y,
+            width: max_x - min_x,
+            height: max_y - min_y,
+        })
+    }
+
+    // The cell of the grid holding a point of the extent.
+    fn cell(&self, x: f64, y: f64) -> (u32, u32) {
+        let last = ((1u32 << HILBERT_BITS) - 1) as f64;
+        let scale = |value: f64, min: f64, size: f64| {
+            if size > 0.0 {
+                (((value - min) / size) * last).round().clamp(0.0, last) as u32
+            } else {
+                0
+            }
+        };
+        (
+            scale(x, self.min_x, self.width),
+            scale(y, self.min_y, self.height),
+        )
+    }
+}
+
+/// The distance along a Hilbert curve filling a grid of 2^16 by 2^16 cells
+/// of the cell at `x`, `y`.
+pub fn hilbert_index(mut x: u32, mut y: u32) -> u64 {
+    let side = 1u32 << HILBERT_BITS;
+    let mut index = 0u64;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = u32::from(x & s > 0);
+        let ry = u32::from(y & s > 0);
+        index += u64::from(s) * u64::from(s) * u64::from((3 * rx) ^ ry);
+        // Rotate the quadrant so the curve inside it runs the right way
+        if ry == 0 {
+            if rx == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Geometry, Value};
+
+    use super::*;
+
+    // Points in each quadrant of the globe, then a feature without geometry.
+    fn quadrants() -> FeatureCollection {
+        let mut features: Vec<Feature> =
+            [(90.0, 45.0), (-90.0, -45.0), (90.0, -45.0), (-90.0, 45.0)]
+                .into_iter()
+                .map(|(x, y)| Feature::from(Geometry::new(Value::Point(vec![x, y]))))
+                .collect();
+        features.insert(1, Feature::default());
+        FeatureCollection {
+            features,
+            ..Default::default()
+        }
+    }
+
+    fn points(fc: &FeatureCollection) -> Vec<Option<(f64, f64)>> {
+        fc.features.iter().map(feature_centroid).collect()
+    }
+
+    #[test]
+    fn test_hilbert_index() {
+        let side = 1 << HILBERT_BITS;
+        assert_eq!(hilbert_index(0, 0), 0);
+        assert_eq!(hilbert_index(side - 1, 0), (1u64 << (2 * HILBERT_BITS)) - 1);
+        // Consecutive cells are neighbours
+        let mut cells: Vec<(u32, u32)> = (0..8).flat_map(|x| (0..8).map(move |y| (x, y))).collect();
+        cells.sort_by_key(|&(x, y)| hilbert_index(x, y));
+        assert!(
+            cells
+                .windows(2)
+                .all(|w| w[0].0.abs_diff(w[1].0) + w[0].1.abs_diff(w[1].1) == 1)
+        );
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut fc = quadrants();
+        SortOrder::Hilbert.sort(&mut fc, Some(4326)).unwrap();
+        assert_eq!(
+            points(&fc),
+            [
+                Some((-90.0, -45.0)),
+                Some((-90.0, 45.0)),
+                Some((90.0, 45.0)),
+                Some((90.0, -45.0)),
+                None
+            ]
+        );
+
+        let mut fc = quadrants();
+        SortOrder::Geohash.sort(&mut fc, Some(4326)).unwrap();
+        assert_eq!(
+            points(&fc),
+            [
+                Some((-90.0, -45.0)),
+                Some((-90.0, 45.0)),
+                Some((90.0, -45.0)),
+                Some((90.0, 45.0)),
+                None
+            ]
+        );
+        assert!(SortOrder::Geohash.sort(&mut fc, None).is_err());
+        assert_eq!("Hilbert".parse::<SortOrder>().unwrap(), SortOrder::Hilbert);
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::geometry::{Crs, RandomGeometry, random_geographic_coords};
+
+/// Mean radius of the Earth in metres.
+pub(crate) const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Candidates drawn for a point before giving up on finding room for it.
+const MAX_ATTEMPTS: usize = 1000;
+
+/// A distance between generated coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distance {
+    /// Along the surface of the Earth.
+    Meters(f64),
+    /// Straight-line in longitude and latitude.
+    Degrees(f64),
+}
+
+impl Distance {
+    /// The distance multiplied by `factor`, in the same unit.
+    pub fn scale(self, factor: f64) -> Self {
+        match self {
+            Distance::Meters(meters) => Distance::Meters(meters * factor),
+            Distance::Degrees(degrees) => Distance::Degrees(degrees * factor),
+        }
+    }
+}
+
+/// Parses a distance such as "500m", "2km" or "0.01deg"; a bare number is in
+/// metres.
+impl std::str::FromStr for Distance {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (number, unit) = s
+            .find(|c: char| c.is_ascii_alphabetic())
+            .map_or((s, ""), |i| s.split_at(i));
+        let invalid = || {
+            RandomGeojsonError::InvalidArgument(format!(
+                "Invalid distance: {}, expected e.g. \"500m\", \"2km\" or \"0.01deg\"",
+                s
+            ))
+        };
+        let value: f64 = number.trim().parse().map_err(|_| invalid())?;
+        if !value.is_finite() || value < 0.0 {
+            return Err(invalid());
+        }
+        match unit.to_lowercase().as_str() {
+            "" | "m" => Ok(Distance::Meters(value)),
+            "km" => Ok(Distance::Meters(value * 1000.0)),
+            "deg" | "degrees" => Ok(Distance::Degrees(value)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Keeps generated points at least a minimum distance apart (Poisson-disk
+/// "dart throwing"), so they spread like real points of interest instead of
+/// clumping like uniform noise.
+///
+/// Accepted points are kept in a grid of cells as large as the distance, so
+/// only the neighbouring cells are searched. Distances in metres are chords
+/// between points on a sphere, which is exact for the small distances this
+/// is meant for and keeps the grid valid near the poles.
+pub struct PointSpacing {
+    distance: Distance,
+    // Minimum distance in grid units: metres or degrees
+    min: f64,
+    cells: HashMap<[i64; 3], Vec<[f64; 3]>>,
+}
+
+impl PointSpacing {
+    pub fn new(distance: Distance) -> Self {
+        let min = match distance {
+            // Chord subtending an arc of `meters`
+            Distance::Meters(meters) => {
+                2.0 * EARTH_RADIUS
+                    * (meters / (2.0 * EARTH_RADIUS))
+                        .min(std::f64::consts::FRAC_PI_2)
+                        .sin()
+            }
+            Distance::Degrees(degrees) => degrees,
+        };
+        PointSpacing {
+            distance,
+            min,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Records the geographic coordinate and returns true if it is at least
+    /// the distance away from every coordinate recorded so far; otherwise
+    /// leaves the spacing unchanged and returns false.
+    pub fn insert(&mut self, lon: f64, lat: f64) -> bool {
+        let p = match self.distance {
+            Distance::Meters(_) => {
+                let (lon, lat) = (lon.to_radians(), lat.to_radians());
+                [
+                    EARTH_RADIUS * lat.cos() * lon.cos(),
+                    EARTH_RADIUS * lat.cos() * lon.sin(),
+                    EARTH_RADIUS * lat.sin(),
+                ]
+            }
+            Distance::Degrees(_) => [lon, lat, 0.0],
+        };
+        if self.min <= 0.0 {
+            return true;
+        }
+        let cell = p.map(|v| (v / self.min).floor() as i64);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let key = [cell[0] + dx, cell[1] + dy, cell[2] + dz];
+                    let near = self.cells.get(&key).is_some_and(|points| {
+                        points.iter().any(|q| {
+                            let d2: f64 = (0..3).map(|i| (p[i] - q[i]).powi(2)).sum();
+                            d2 < self.min * self.min
+                        })
+                    });
+                    if near {
+                        return false;
+                    }
+                }
+            }
+        }
+        self.cells.entry(cell).or_default().push(p);
+        true
+    }
+
+    /// Generates a random Point far enough from the points generated before.
+    pub fn random_point(&mut self, crs: &Crs) -> RandomGeojsonResult<RandomGeometry> {
+        let (lon, lat) = self.random_coords(crs)?;
+        let (x, y) = crs.transform().transform(lon, lat);
+        Ok(RandomGeometry::Point(vec![x, y]))
+    }
+
+    /// Generates a random geographic coordinate far enough from the points
+    /// generated before, and records it.
+    pub fn random_coords(&mut self, crs: &Crs) -> RandomGeojsonResult<(f64, f64)> {
+        for _ in 0..MAX_ATTEMPTS {
+            let (lon, lat) = random_geographic_coords(crs);
+            if self.insert(lon, lat) {
+                return Ok((lon, lat));
+            }
+        }
+        Err(RandomGeojsonError::InvalidArgument(format!(
+            "No room left for another point at least {} apart after {} points, use a smaller --min-distance or fewer features",
+            match self.distance {
+                Distance::Meters(m) => format!("{} m", m),
+                Distance::Degrees(d) => format!("{} degrees", d),
+            },
+            self.cells.values().map(Vec::len).sum::<usize>()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::{Bounds, Unit};
+
+    use super::*;
+
+    #[test]
+    fn test_distance_from_str() {
+        assert_eq!("500".parse::<Distance>().unwrap(), Distance::Meters(500.0));
+        assert_eq!("2km".parse::<Distance>().unwrap(), Distance::Meters(2000.0));
+        assert_eq!(
+            "0.5 deg".parse::<Distance>().unwrap(),
+            Distance::Degrees(0.5)
+        );
+        assert!("-1m".parse::<Distance>().is_err());
+        assert!("5 miles".parse::<Distance>().is_err());
+    }
+
+    #[test]
+    fn test_spaced_points() {
+        let bounds = Bounds::new(0.0, 0.0, 1.0, 1.0, Unit::Degrees);
+        let crs = Crs::WGS84.clipped(bounds).unwrap();
+        let mut spacing = PointSpacing::new(Distance::Meters(5000.0));
+        let points: Vec<Vec<f64>> = (0..100)
+            .map(|_| match spacing.random_point(&crs).unwrap() {
+                RandomGeometry::Point(p) => p,
+                _ => unreachable!(),
+            })
+            .collect();
+        // 5 km is about 0.045 degrees near the equator
+        for (i, a) in points.iter().enumerate() {
+            for b in &points[i + 1..] {
+                assert!(((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt() > 0.0449);
+            }
+        }
+
+        // A square degree holds far fewer than 1000 points 10 km apart
+        let mut spacing = PointSpacing::new(Distance::Meters(10_000.0));
+        assert!((0..1000).any(|_| spacing.random_point(&crs).is_err()));
+
+        let mut spacing = PointSpacing::new(Distance::Degrees(0.5));
+        assert!(spacing.insert(0.0, 0.0));
+        assert!(!spacing.insert(0.3, 0.3));
+        assert!(spacing.insert(0.4, 0.4));
+    }
+}
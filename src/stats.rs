@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use geojson::Feature;
+use serde_json::{Value, json};
+
+use crate::geometry::for_each_position;
+
+/// Summary of a set of features, built up one feature at a time.
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub features: usize,
+    /// Number of positions over all geometries.
+    pub positions: usize,
+    /// `[min_x, min_y, max_x, max_y]` of all positions.
+    pub bbox: Option<[f64; 4]>,
+    /// Features per geometry type, with "null" for features without one.
+    pub geometry_types: BTreeMap<&'static str, usize>,
+    /// For each property key, how many features have it with each JSON type.
+    pub properties: BTreeMap<String, BTreeMap<&'static str, usize>>,
+    /// Size of the file in bytes, if known.
+    pub size: Option<u64>,
+}
+
+impl Stats {
+    pub fn add(&mut self, feature: &Feature) {
+        self.features += 1;
+        let geometry_type = match &feature.geometry {
+            Some(geometry) => {
+                for_each_position(&geometry.value, &mut |p| {
+                    self.positions += 1;
+                    let b = self.bbox.get_or_insert([p[0], p[1], p[0], p[1]]);
+                    b[0] = b[0].min(p[0]);
+                    b[1] = b[1].min(p[1]);
+                    b[2] = b[2].max(p[0]);
+                    b[3] = b[3].max(p[1]);
+                });
+                geometry.value.type_name()
+            }
+            None => "null",
+        };
+        *self.geometry_types.entry(geometry_type).or_default() += 1;
+        for (key, value) in feature.properties.iter().flatten() {
+            *self
+                .properties
+                .entry(key.clone())
+                .or_default()
+                .entry(value_type(value))
+                .or_default() += 1;
+        }
+    }
+
+    /// The summary as a JSON object.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "size": self.size,
+            "features": self.features,
+            "positions": self.positions,
+            "bbox": self.bbox,
+            "geometry_types": self.geometry_types,
+            "properties": self.properties,
+        })
+    }
+}
+
+// JSON type of a property value, named like the --property types.
+fn value_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(n) if n.is_f64() => "float",
+        Value::Number(_) => "int",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(size) = self.size {
+            writeln!(f, "{:<16} {} bytes", "size", size)?;
+        }
+        writeln!(f, "{:<16} {}", "features", self.features)?;
+        writeln!(f, "{:<16} {}", "positions", self.positions)?;
+        match self.bbox {
+            Some([min_x, min_y, max_x, max_y]) => writeln!(
+                f,
+                "{:<16} {}, {}, {}, {}",
+                "bbox", min_x, min_y, max_x, max_y
+            )?,
+            None => writeln!(f, "{:<16} none", "bbox")?,
+        }
+        writeln!(f, "geometry types")?;
+        for (name, count) in &self.geometry_types {
+            writeln!(f, "  {:<20} {:>10}", name, count)?;
+        }
+        writeln!(f, "properties")?;
+        for (key, types) in &self.properties {
+            let count: usize = types.values().sum();
+            let types = types
+                .iter()
+                .map(|(name, count)| format!("{}: {}", name, count))
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(f, "  {:<20} {:>10}  {}", key, count, types)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Geometry, JsonObject};
+
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut properties = JsonObject::new();
+        properties.insert("name".to_string(), "a".into());
+        properties.insert("height".to_string(), 2.5.into());
+        let point = Feature {
+            geometry: Some(Geometry::new(geojson::Value::Point(vec![1.0, 2.0]))),
+            properties: Some(properties),
+            ..Default::default()
+        };
+        let line = Feature {
+            geometry: Some(Geometry::new(geojson::Value::LineString(vec![
+                vec![-1.0, 5.0],
+                vec![3.0, 0.0],
+            ]))),
+            properties: Some(JsonObject::from_iter([("height".to_string(), Value::Null)])),
+            ..Default::default()
+        };
+
+        let mut stats = Stats::default();
+        [point, line, Feature::default()]
+            .iter()
+            .for_each(|f| stats.add(f));
+        assert_eq!(stats.features, 3);
+        assert_eq!(stats.positions, 3);
+        assert_eq!(stats.bbox, Some([-1.0, 0.0, 3.0, 5.0]));
+        assert_eq!(
+            stats.geometry_types,
+            BTreeMap::from([("LineString", 1), ("Point", 1), ("null", 1)])
+        );
+        assert_eq!(
+            stats.properties["height"],
+            BTreeMap::from([("float", 1), ("null", 1)])
+        );
+        assert!(stats.to_string().contains("height"));
+        assert_eq!(stats.to_json()["properties"]["name"]["string"], 1);
+    }
+}
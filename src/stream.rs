@@ -0,0 +1,348 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use geojson::feature::Id;
+use geojson::{Feature, JsonObject};
+use rand::Rng;
+
+use crate::error::{RandomGeojsonError, RandomGeojsonResult};
+use crate::format::unix_time_millis;
+use crate::geometry::{Bounds, Crs};
+use crate::random::{self, random_uuid};
+
+/// Mean Earth radius in metres, used to move features.
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// GUID appended to the client key of a WebSocket handshake.
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// How often something happens, parsed from "500/s", "30/m", "2/h" or a plain
+/// number of events per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    pub per_second: f64,
+}
+
+impl Rate {
+    /// Time between two events.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.per_second)
+    }
+}
+
+impl std::str::FromStr for Rate {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (count, unit) = s.split_once('/').unwrap_or((s, "s"));
+        let seconds = match unit.trim() {
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => f64::NAN,
+        };
+        match count.trim().parse::<f64>() {
+            Ok(count) if count > 0.0 && count.is_finite() && !seconds.is_nan() => Ok(Rate {
+                per_second: count / seconds,
+            }),
+            _ => Err(RandomGeojsonError::InvalidArgument(format!(
+                "Invalid rate: {}, expected e.g. \"500/s\", \"30/m\" or \"2/h\"",
+                s
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for Rate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/s", self.per_second)
+    }
+}
+
+/// Largest number of events handed to a single `emit` call by [`pace`], so a
+/// slow consumer does not make batches grow without bound.
+const MAX_BATCH: usize = 10_000;
+
+/// Calls `emit` with the number of events due at `rate`, sleeping in between,
+/// until `limit` events have been emitted or forever if there is no limit.
+///
+/// Events are counted from the start, so time lost in `emit` is made up with
+/// larger batches instead of lowering the rate.
+pub fn pace(
+    rate: Rate,
+    limit: Option<usize>,
+    mut emit: impl FnMut(usize) -> RandomGeojsonResult<()>,
+) -> RandomGeojsonResult<()> {
+    let start = Instant::now();
+    let mut emitted = 0;
+    while limit.is_none_or(|limit| emitted < limit) {
+        let due = (start.elapsed().as_secs_f64() * rate.per_second) as usize + 1;
+        let batch = due
+            .saturating_sub(emitted)
+            .min(MAX_BATCH)
+            .min(limit.map_or(usize::MAX, |limit| limit - emitted));
+        if batch > 0 {
+            emit(batch)?;
+            emitted += batch;
+        }
+        let next = start + Duration::from_secs_f64(emitted as f64 / rate.per_second);
+        std::thread::sleep(next.saturating_duration_since(Instant::now()));
+    }
+    Ok(())
+}
+
+/// A point moving along a random trajectory, such as a vehicle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mover {
+    pub id: String,
+    /// Longitude and latitude in degrees.
+    pub position: (f64, f64),
+    /// Direction of travel in degrees clockwise from north.
+    pub heading: f64,
+    /// Speed in metres per second.
+    pub speed: f64,
+}
+
+impl Mover {
+    /// Places a mover at a random position within `bounds` (geographic),
+    /// heading anywhere at 5 to 30 m/s.
+    pub fn random(bounds: &Bounds) -> Self {
+        let mut rng = random::rng();
+        Mover {
+            id: random_uuid().to_string(),
+            position: (
+                rng.random_range(bounds.min_x..=bounds.max_x),
+                rng.random_range(bounds.min_y..=bounds.max_y),
+            ),
+            heading: rng.random_range(0.0..360.0),
+            speed: rng.random_range(5.0..30.0),
+        }
+    }
+
+    /// Moves for `seconds`, turning and changing speed a little, and turning
+    /// back when leaving `bounds`.
+    pub fn advance(&mut self, seconds: f64, bounds: &Bounds) {
+        let mut rng = random::rng();
+        self.heading = (self.heading + rng.random_range(-15.0..15.0)).rem_euclid(360.0);
+        self.speed = (self.speed + rng.random_range(-1.0..1.0)).clamp(1.0, 40.0);
+
+        let distance = self.speed * seconds;
+        let (lon, lat) = self.position;
+        let heading = self.heading.to_radians();
+        let dlat = (distance * heading.cos() / EARTH_RADIUS).to_degrees();
+        let dlon = (distance * heading.sin() / (EARTH_RADIUS * lat.to_radians().cos().max(1e-6)))
+            .to_degrees();
+        let (mut lon, mut lat) = (lon + dlon, lat + dlat);
+
+        // Reflect off the edges, like a ball on a table
+        if !(bounds.min_x..=bounds.max_x).contains(&lon) {
+            self.heading = 360.0 - self.heading;
+            lon = lon.clamp(bounds.min_x, bounds.max_x);
+        }
+        if !(bounds.min_y..=bounds.max_y).contains(&lat) {
+            self.heading = (180.0 - self.heading).rem_euclid(360.0);
+            lat = lat.clamp(bounds.min_y, bounds.max_y);
+        }
+        self.position = (lon, lat);
+    }
+
+    /// The current position as a Point feature in the coordinates of `crs`,
+    /// with speed, heading and a Unix timestamp in milliseconds as properties.
+    pub fn to_feature(&self, crs: &Crs) -> Feature {
+        let (x, y) = crs.transform().transform(self.position.0, self.position.1);
+        let mut properties = JsonObject::new();
+        properties.insert("speed".to_string(), self.speed.into());
+        properties.insert("heading".to_string(), self.heading.into());
+        properties.insert("timestamp".to_string(), unix_time_millis().into());
+        Feature {
+            id: Some(Id::String(self.id.clone())),
+            geometry: Some(geojson::Geometry::new(geojson::Value::Point(vec![x, y]))),
+            properties: Some(properties),
+            ..Default::default()
+        }
+    }
+}
+
+/// Settings of the stream server.
+#[derive(Debug, Clone)]
+pub struct StreamOptions {
+    /// Address to listen on, e.g. "127.0.0.1:8081".
+    pub address: String,
+    /// Coordinate system of the emitted features.
+    pub coordinate_system: String,
+    /// Geographic bounds the features move within.
+    pub bbox: Option<Bounds>,
+    /// Number of moving features per connection.
+    pub count: usize,
+    /// How often each feature reports its position.
+    pub rate: Rate,
+}
+
+impl StreamOptions {
+    fn crs(&self) -> RandomGeojsonResult<Crs> {
+        let crs: Crs = self.coordinate_system.parse()?;
+        match self.bbox {
+            Some(bbox) => crs.clipped(bbox),
+            None => Ok(crs),
+        }
+    }
+}
+
+/// Starts a WebSocket server. Every connection gets its own set of moving
+/// features and receives one text message with a GeoJSON Feature per
+/// position update, until it disconnects.
+pub fn stream(options: &StreamOptions) -> RandomGeojsonResult<()> {
+    // Fail early on an invalid coordinate system or bbox
+    options.crs()?;
+    let server = tiny_http::Server::http(&options.address).map_err(|e| {
+        RandomGeojsonError::Network(format!("Failed to listen on {}: {}", options.address, e))
+    })?;
+    eprintln!("Streaming on ws://{}", options.address);
+
+    for request in server.incoming_requests() {
+        let key = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Sec-WebSocket-Key"))
+            .map(|h| h.value.to_string());
+        let Some(key) = key else {
+            let response = tiny_http::Response::from_string("Expected a WebSocket upgrade\n")
+                .with_status_code(426);
+            let _ = request.respond(response);
+            continue;
+        };
+
+        let mut response = tiny_http::Response::empty(101);
+        if let Ok(header) =
+            tiny_http::Header::from_bytes("Sec-WebSocket-Accept", websocket_accept(&key))
+        {
+            response.add_header(header);
+        }
+        let mut socket = request.upgrade("websocket", response);
+        let options = options.clone();
+        std::thread::spawn(move || {
+            let crs = options.crs().expect("coordinate system was checked");
+            let bounds = crs.bounds();
+            let mut movers: Vec<Mover> =
+                (0..options.count).map(|_| Mover::random(&bounds)).collect();
+            let interval = options.rate.interval();
+            let mut next = Instant::now();
+            loop {
+                for mover in &mut movers {
+                    mover.advance(interval.as_secs_f64(), &bounds);
+                    let message =
+                        serde_json::to_string(&mover.to_feature(&crs)).expect("features serialize");
+                    // Stop as soon as the client goes away
+                    if socket
+                        .write_all(&websocket_frame(message.as_bytes()))
+                        .and_then(|_| socket.flush())
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+                next += interval;
+                std::thread::sleep(next.saturating_duration_since(Instant::now()));
+            }
+        });
+    }
+    Ok(())
+}
+
+// Value of the Sec-WebSocket-Accept header answering a client key.
+fn websocket_accept(key: &str) -> String {
+    let digest = sha1_smol::Sha1::from(format!("{}{}", key.trim(), WEBSOCKET_GUID)).digest();
+    base64(&digest.bytes())
+}
+
+// Wraps a payload in an unmasked, final WebSocket text frame.
+fn websocket_frame(payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x81];
+    match payload.len() {
+        n if n < 126 => frame.push(n as u8),
+        n if n <= usize::from(u16::MAX) => {
+            frame.push(126);
+            frame.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n => {
+            frame.push(127);
+            frame.extend_from_slice(&(n as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Unit;
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!("500/s".parse::<Rate>().unwrap().per_second, 500.0);
+        assert_eq!("30/m".parse::<Rate>().unwrap().per_second, 0.5);
+        assert_eq!(
+            "2".parse::<Rate>().unwrap().interval(),
+            Duration::from_millis(500)
+        );
+        assert!("0/s".parse::<Rate>().is_err());
+        assert!("5/d".parse::<Rate>().is_err());
+    }
+
+    #[test]
+    fn test_pace() {
+        let mut batches = Vec::new();
+        let start = Instant::now();
+        pace("200/s".parse().unwrap(), Some(20), |n| {
+            batches.push(n);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(batches.iter().sum::<usize>(), 20);
+        // 20 events at 200/s take about 0.1 s
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[test]
+    fn test_mover_stays_in_bounds() {
+        let bounds = Bounds::new(0.0, 0.0, 0.01, 0.01, Unit::Degrees);
+        let mut mover = Mover::random(&bounds);
+        for _ in 0..1000 {
+            mover.advance(10.0, &bounds);
+            assert!(bounds.contains(mover.position.0, mover.position.1));
+        }
+    }
+
+    #[test]
+    fn test_websocket_handshake() {
+        // Example from RFC 6455
+        assert_eq!(
+            websocket_accept("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+        assert_eq!(&websocket_frame(b"hi")[..], &[0x81, 2, b'h', b'i']);
+        assert_eq!(websocket_frame(&[0; 200])[1..4], [126, 0, 200]);
+    }
+}
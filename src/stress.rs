@@ -0,0 +1,113 @@
+use std::f64::consts::TAU;
+
+use rand::Rng;
+
+use crate::anchor::project;
+use crate::geometry::{Crs, RandomGeometry};
+use crate::random;
+use crate::spacing::Distance;
+use crate::walk::advance;
+
+/// Largest change of direction between two steps of a stress LineString, in
+/// radians.
+const MAX_TURN: f64 = 0.5;
+
+/// Generates a LineString of `vertices` vertices for benchmarking parsers
+/// and simplification against pathological inputs: a random walk from the
+/// middle of the area, with steps so short that it meanders over a part of
+/// it rather than crossing it back and forth.
+pub fn stress_linestring(crs: &Crs, vertices: usize) -> RandomGeometry {
+    let mut rng = random::rng();
+    let bounds = crs.bounds();
+    let step = Distance::Degrees(bounds.width().min(bounds.height()) / (vertices as f64).sqrt());
+    let mut position = middle(crs);
+    let mut heading = rng.random_range(0.0..TAU);
+    let mut line = Vec::with_capacity(vertices);
+    line.push(position);
+    for _ in 1..vertices {
+        let turn = rng.random_range(-MAX_TURN..=MAX_TURN);
+        (position, heading) = advance(crs, position, heading + turn, step);
+        line.push(position);
+    }
+    RandomGeometry::LineString(project(crs, &line))
+}
+
+/// Generates a Polygon whose exterior ring has `vertices` distinct vertices,
+/// for benchmarking like [`stress_linestring`]: a wiggly outline around the
+/// middle of the area, with each vertex in its own direction from it so
+/// that the ring never crosses itself.
+pub fn stress_polygon(crs: &Crs, vertices: usize) -> RandomGeometry {
+    let mut rng = random::rng();
+    let bounds = crs.bounds();
+    let (lon, lat) = middle(crs);
+    let radius = 0.4 * bounds.width().min(bounds.height());
+    // A random walk of the radius, bent so both ends meet
+    let mut share = 1.0f64;
+    let mut shares: Vec<f64> = (0..=vertices)
+        .map(|_| {
+            share = (share + rng.random_range(-0.02..0.02)).clamp(0.3, 1.0);
+            share
+        })
+        .collect();
+    let drift = shares[vertices] - shares[0];
+    for (i, share) in shares.iter_mut().enumerate() {
+        *share -= drift * i as f64 / vertices as f64;
+    }
+    let mut ring: Vec<(f64, f64)> = (0..vertices)
+        .map(|i| {
+            let angle = TAU * i as f64 / vertices as f64;
+            let r = radius * shares[i].clamp(0.1, 1.0);
+            (lon + r * angle.cos(), lat + r * angle.sin())
+        })
+        .collect();
+    ring.push(ring[0]);
+    RandomGeometry::Polygon(vec![project(crs, &ring)])
+}
+
+// The middle of the bounds of `crs`; masks are not taken into account.
+fn middle(crs: &Crs) -> (f64, f64) {
+    let bounds = crs.bounds();
+    (
+        (bounds.min_x + bounds.max_x) / 2.0,
+        (bounds.min_y + bounds.max_y) / 2.0,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::{Bounds, Unit};
+    use crate::overlap::segments_intersect;
+
+    use super::*;
+
+    #[test]
+    fn test_stress_linestring() {
+        let bounds = Bounds::new(0.0, 0.0, 1.0, 1.0, Unit::Degrees);
+        let crs = Crs::WGS84.clipped(bounds).unwrap();
+        let RandomGeometry::LineString(line) = stress_linestring(&crs, 100_000) else {
+            unreachable!()
+        };
+        assert_eq!(line.len(), 100_000);
+        assert!(line.iter().all(|p| bounds.contains(p[0], p[1])));
+    }
+
+    #[test]
+    fn test_stress_polygon() {
+        let RandomGeometry::Polygon(rings) = stress_polygon(&Crs::WGS84, 500) else {
+            unreachable!()
+        };
+        let ring = &rings[0];
+        assert_eq!(ring.len(), 501);
+        assert_eq!(ring.first(), ring.last());
+        // Simple: edges only meet their neighbours
+        let edges: Vec<_> = ring.windows(2).collect();
+        for (i, a) in edges.iter().enumerate() {
+            for (j, b) in edges.iter().enumerate().skip(i + 2) {
+                if i == 0 && j == edges.len() - 1 {
+                    continue;
+                }
+                assert!(!segments_intersect(&a[0], &a[1], &b[0], &b[1]));
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use geojson::JsonObject;
+use rand::Rng;
+use serde_json::Value;
+
+use crate::error::RandomGeojsonError;
+use crate::property::random_word;
+use crate::random;
+
+/// A string with placeholders filled in for each feature, e.g.
+/// `{name} ({population})` or `{int(1,200)} {word} Street`.
+///
+/// Placeholders are `{index}` for the index of the feature, `{word}` for a
+/// random word, `{int(a,b)}` and `{float(a,b)}` for random numbers from `a`
+/// to `b`, and any other name for the value of that property of the
+/// feature, with missing and null properties left empty. `{{` and `}}` stand
+/// for literal braces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Property(String),
+    Index,
+    Word,
+    Int(i64, i64),
+    Float(f64, f64),
+}
+
+impl Template {
+    /// Fills in the template for the feature at `index` with `properties`.
+    pub fn render(&self, properties: &JsonObject, index: usize) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(s) => out.push_str(s),
+                Part::Property(name) => match properties.get(name) {
+                    None | Some(Value::Null) => {}
+                    Some(Value::String(s)) => out.push_str(s),
+                    Some(value) => out.push_str(&value.to_string()),
+                },
+                Part::Index => out.push_str(&index.to_string()),
+                Part::Word => out.push_str(&random_word()),
+                Part::Int(min, max) => {
+                    out.push_str(&random::rng().random_range(*min..=*max).to_string())
+                }
+                Part::Float(min, max) => {
+                    let value = random::rng().random_range(*min..=*max);
+                    out.push_str(&format!("{:.2}", value));
+                }
+            }
+        }
+        out
+    }
+
+    /// Names of the properties the template refers to.
+    pub fn properties(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Property(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// Parses a template, optionally in double quotes.
+impl std::str::FromStr for Template {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| {
+            RandomGeojsonError::InvalidArgument(format!("Invalid template '{}': {}", s, reason))
+        };
+        let text = s
+            .strip_prefix('"')
+            .and_then(|rest| rest.strip_suffix('"'))
+            .unwrap_or(s);
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let (placeholder, rest) = chars
+                        .as_str()
+                        .split_once('}')
+                        .ok_or_else(|| invalid("missing '}'".to_string()))?;
+                    chars = rest.chars();
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(placeholder_part(placeholder.trim()).map_err(invalid)?);
+                }
+                '}' => return Err(invalid("unmatched '}'".to_string())),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+}
+
+fn placeholder_part(placeholder: &str) -> Result<Part, String> {
+    let Some((function, args)) = placeholder.split_once('(') else {
+        return match placeholder {
+            "" => Err("empty placeholder {}".to_string()),
+            "index" => Ok(Part::Index),
+            "word" => Ok(Part::Word),
+            name => Ok(Part::Property(name.to_string())),
+        };
+    };
+    let invalid = || format!("invalid placeholder {{{}}}", placeholder);
+    let (min, max) = args
+        .strip_suffix(')')
+        .and_then(|args| args.split_once(','))
+        .ok_or_else(invalid)?;
+    let (min, max) = (min.trim(), max.trim());
+    match function.trim() {
+        "int" => match (min.parse::<i64>(), max.parse::<i64>()) {
+            (Ok(min), Ok(max)) if min <= max => Ok(Part::Int(min, max)),
+            _ => Err(invalid()),
+        },
+        "float" => match (min.parse::<f64>(), max.parse::<f64>()) {
+            (Ok(min), Ok(max)) if min <= max => Ok(Part::Float(min, max)),
+            _ => Err(invalid()),
+        },
+        function => Err(format!("unknown function {}", function)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let properties = json!({"name": "Leeds", "population": 812000, "ref": null});
+        let properties = properties.as_object().unwrap();
+        let template: Template = "\"{name} ({population}){ref}{missing} #{index} {{x}}\""
+            .parse()
+            .unwrap();
+        assert_eq!(template.render(properties, 4), "Leeds (812000) #4 {x}");
+
+        let address: Template = "{int(1,200)} {word} Street".parse().unwrap();
+        for _ in 0..50 {
+            let address = address.render(properties, 0);
+            let (number, rest) = address.split_once(' ').unwrap();
+            assert!((1..=200).contains(&number.parse::<i64>().unwrap()));
+            assert!(rest.ends_with(" Street") && rest.len() > " Street".len());
+        }
+        let score: f64 = "{float(0.5, 1)}"
+            .parse::<Template>()
+            .unwrap()
+            .render(properties, 0)
+            .parse()
+            .unwrap();
+        assert!((0.5..=1.0).contains(&score));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for template in [
+            "{name",
+            "name}",
+            "{}",
+            "{int(5,1)}",
+            "{int(1)}",
+            "{max(1,2)}",
+        ] {
+            assert!(template.parse::<Template>().is_err(), "{}", template);
+        }
+    }
+}
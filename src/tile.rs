@@ -0,0 +1,150 @@
+use std::f64::consts::PI;
+
+use crate::error::RandomGeojsonError;
+use crate::geometry::{Bounds, CoordTransform, Unit, WEB_MERCATOR_EXTENT, WebMercator};
+
+/// Deepest zoom level accepted for tiles.
+pub const MAX_ZOOM: u8 = 24;
+
+/// A tile of the XYZ (slippy map) scheme, with y growing southwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileId {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileId {
+    pub fn new(z: u8, x: u32, y: u32) -> Result<Self, RandomGeojsonError> {
+        if z > MAX_ZOOM {
+            return Err(RandomGeojsonError::InvalidArgument(format!(
+                "Zoom level must be at most {}",
+                MAX_ZOOM
+            )));
+        }
+        let n = 1u32 << z;
+        if x >= n || y >= n {
+            return Err(RandomGeojsonError::InvalidArgument(format!(
+                "Tile {}/{}/{} is outside the zoom level",
+                z, x, y
+            )));
+        }
+        Ok(TileId { z, x, y })
+    }
+
+    /// Geographic extent of the tile in degrees.
+    pub fn bounds(&self) -> Bounds {
+        let n = f64::from(1u32 << self.z);
+        let lon = |x: u32| f64::from(x) / n * 360.0 - 180.0;
+        let lat = |y: u32| {
+            (PI * (1.0 - 2.0 * f64::from(y) / n))
+                .sinh()
+                .atan()
+                .to_degrees()
+        };
+        Bounds::new(
+            lon(self.x),
+            lat(self.y + 1),
+            lon(self.x + 1),
+            lat(self.y),
+            Unit::Degrees,
+        )
+    }
+
+    /// Extent of the tile in Web Mercator metres.
+    pub fn mercator_bounds(&self) -> Bounds {
+        let size = WEB_MERCATOR_EXTENT.width() / f64::from(1u32 << self.z);
+        Bounds::new(
+            WEB_MERCATOR_EXTENT.min_x + f64::from(self.x) * size,
+            WEB_MERCATOR_EXTENT.max_y - f64::from(self.y + 1) * size,
+            WEB_MERCATOR_EXTENT.min_x + f64::from(self.x + 1) * size,
+            WEB_MERCATOR_EXTENT.max_y - f64::from(self.y) * size,
+            Unit::Meters,
+        )
+    }
+
+    /// Returns the tile of zoom level `z` containing a Web Mercator position.
+    pub fn containing(z: u8, x: f64, y: f64) -> TileId {
+        let n = 1u32 << z;
+        let size = WEB_MERCATOR_EXTENT.width() / f64::from(n);
+        let index = |offset: f64| ((offset / size).floor().max(0.0) as u32).min(n - 1);
+        TileId {
+            z,
+            x: index(x - WEB_MERCATOR_EXTENT.min_x),
+            y: index(WEB_MERCATOR_EXTENT.max_y - y),
+        }
+    }
+}
+
+/// Converts a position in the given EPSG code (4326 or 3857) to Web Mercator.
+pub fn to_mercator(epsg: Option<u32>, position: &[f64]) -> Option<(f64, f64)> {
+    match epsg {
+        Some(4326) => Some(WebMercator.transform(position[0], position[1])),
+        Some(3857) => Some((position[0], position[1])),
+        _ => None,
+    }
+}
+
+/// Converts Web Mercator metres back to longitude and latitude in degrees.
+pub fn from_mercator(x: f64, y: f64) -> (f64, f64) {
+    let radius = WEB_MERCATOR_EXTENT.max_x / PI;
+    (
+        (x / radius).to_degrees(),
+        (2.0 * (y / radius).exp().atan() - PI / 2.0).to_degrees(),
+    )
+}
+
+impl std::fmt::Display for TileId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}/{}", self.z, self.x, self.y)
+    }
+}
+
+impl std::str::FromStr for TileId {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RandomGeojsonError::InvalidArgument(format!("Invalid tile: {}", s));
+        let parts: Vec<&str> = s.split('/').collect();
+        let [z, x, y] = parts.as_slice() else {
+            return Err(invalid());
+        };
+        TileId::new(
+            z.parse().map_err(|_| invalid())?,
+            x.parse().map_err(|_| invalid())?,
+            y.parse().map_err(|_| invalid())?,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_from_str() {
+        assert_eq!(
+            "3/4/2".parse::<TileId>().unwrap(),
+            TileId { z: 3, x: 4, y: 2 }
+        );
+        assert!("3/8/2".parse::<TileId>().is_err());
+        assert!("3/4".parse::<TileId>().is_err());
+        assert!("a/b/c".parse::<TileId>().is_err());
+    }
+
+    #[test]
+    fn test_tile_bounds() {
+        let world = TileId::new(0, 0, 0).unwrap();
+        assert_eq!(world.bounds().min_x, -180.0);
+        assert!((world.bounds().max_y - 85.0511287798).abs() < 1e-9);
+        assert_eq!(world.mercator_bounds(), WEB_MERCATOR_EXTENT);
+
+        let tile = TileId::new(1, 1, 0).unwrap();
+        let bounds = tile.bounds();
+        assert_eq!((bounds.min_x, bounds.min_y), (0.0, 0.0));
+        let (x, y) = WebMercator.transform(10.0, 10.0);
+        assert_eq!(TileId::containing(1, x, y), tile);
+        let (lon, lat) = from_mercator(x, y);
+        assert!((lon - 10.0).abs() < 1e-9 && (lat - 10.0).abs() < 1e-9);
+    }
+}
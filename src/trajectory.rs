@@ -0,0 +1,233 @@
+use std::f64::consts::TAU;
+
+use geojson::JsonObject;
+use rand::Rng;
+use serde_json::Value;
+
+use crate::anchor::{project, project_one};
+use crate::error::RandomGeojsonError;
+use crate::geometry::{Crs, RandomGeometry, random_geographic_coords};
+use crate::random;
+use crate::spacing::Distance;
+use crate::walk::advance;
+
+/// Largest change of direction between two fixes, in radians.
+const MAX_TURN: f64 = std::f64::consts::PI / 9.0;
+
+/// Start of the year trajectories start in, 2024-01-01T00:00:00Z, in seconds
+/// since the Unix epoch.
+pub(crate) const START: i64 = 1_704_067_200;
+
+/// Seconds in a leap year.
+pub(crate) const YEAR: i64 = 366 * 86_400;
+
+/// A range of speeds in km/h.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeedRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Parses a speed such as "50" or a range such as "30..80", in km/h. Ranges
+/// written "30-80" are accepted too.
+impl std::str::FromStr for SpeedRange {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            RandomGeojsonError::InvalidArgument(format!(
+                "Invalid speed: {}, expected km/h such as \"50\" or \"30..80\"",
+                s
+            ))
+        };
+        let (min, max) = s
+            .split_once("..")
+            .or_else(|| s.split_once('-'))
+            .unwrap_or((s, s));
+        let min: f64 = min.trim().parse().map_err(|_| invalid())?;
+        let max: f64 = max.trim().parse().map_err(|_| invalid())?;
+        if !(min.is_finite() && max.is_finite() && 0.0 <= min && min <= max) {
+            return Err(invalid());
+        }
+        Ok(SpeedRange { min, max })
+    }
+}
+
+/// A position of a trajectory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fix {
+    pub lon: f64,
+    pub lat: f64,
+    /// Seconds since the Unix epoch.
+    pub time: i64,
+    /// Speed since the previous fix, in km/h.
+    pub speed: f64,
+}
+
+/// Simulates a GPS trace of 10 to 100 fixes `interval` seconds apart,
+/// starting at a random time in 2024.
+///
+/// The speed drifts within `speed` and the heading by up to 20 degrees
+/// between fixes; the trace turns away from the edges of the area.
+pub fn random_trajectory(crs: &Crs, speed: SpeedRange, interval: f64) -> Vec<Fix> {
+    let mut rng = random::rng();
+    let mut position = random_geographic_coords(crs);
+    let mut heading = rng.random_range(0.0..TAU);
+    let mut current = rng.random_range(speed.min..=speed.max);
+    let start = START + rng.random_range(0..YEAR);
+    let mut fixes = vec![Fix {
+        lon: position.0,
+        lat: position.1,
+        time: start,
+        speed: current,
+    }];
+    for i in 1..rng.random_range(10..=100) {
+        let drift = (speed.max - speed.min) * 0.1;
+        current = (current + rng.random_range(-drift..=drift)).clamp(speed.min, speed.max);
+        let meters = current / 3.6 * interval;
+        let turn = rng.random_range(-MAX_TURN..=MAX_TURN);
+        (position, heading) = advance(crs, position, heading + turn, Distance::Meters(meters));
+        fixes.push(Fix {
+            lon: position.0,
+            lat: position.1,
+            time: start + (i as f64 * interval).round() as i64,
+            speed: current,
+        });
+    }
+    fixes
+}
+
+/// A trajectory as a LineString, with the times and speeds of its vertices
+/// as `coordinateProperties`, the convention of GPX converters.
+pub fn trajectory_linestring(crs: &Crs, fixes: &[Fix]) -> (RandomGeometry, JsonObject) {
+    let coords: Vec<(f64, f64)> = fixes.iter().map(|f| (f.lon, f.lat)).collect();
+    let times: Vec<Value> = fixes.iter().map(|f| timestamp(f.time).into()).collect();
+    let speeds: Vec<Value> = fixes.iter().map(|f| round(f.speed).into()).collect();
+    let mut coordinate_properties = JsonObject::new();
+    coordinate_properties.insert("times".to_string(), times.into());
+    coordinate_properties.insert("speeds".to_string(), speeds.into());
+
+    let mut properties = JsonObject::new();
+    if let (Some(first), Some(last)) = (fixes.first(), fixes.last()) {
+        properties.insert("start_time".to_string(), timestamp(first.time).into());
+        properties.insert("end_time".to_string(), timestamp(last.time).into());
+    }
+    properties.insert(
+        "coordinateProperties".to_string(),
+        coordinate_properties.into(),
+    );
+    (
+        RandomGeometry::LineString(project(crs, &coords)),
+        properties,
+    )
+}
+
+/// A fix as a Point with `track_id`, `sequence`, `timestamp` and `speed`
+/// properties.
+pub fn fix_point(
+    crs: &Crs,
+    track_id: usize,
+    sequence: usize,
+    fix: &Fix,
+) -> (RandomGeometry, JsonObject) {
+    let mut properties = JsonObject::new();
+    properties.insert("track_id".to_string(), track_id.into());
+    properties.insert("sequence".to_string(), sequence.into());
+    properties.insert("timestamp".to_string(), timestamp(fix.time).into());
+    properties.insert("speed".to_string(), round(fix.speed).into());
+    let point = project_one(crs, (fix.lon, fix.lat));
+    (RandomGeometry::Point(point), properties)
+}
+
+/// Formats seconds since the Unix epoch as an ISO 8601 UTC timestamp such
+/// as "2024-03-01T12:00:00Z".
+pub fn timestamp(seconds: i64) -> String {
+    let (days, time) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+fn round(speed: f64) -> f64 {
+    (speed * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speed_range_from_str() {
+        let range: SpeedRange = "30..80".parse().unwrap();
+        assert_eq!(
+            range,
+            SpeedRange {
+                min: 30.0,
+                max: 80.0
+            }
+        );
+        assert_eq!("30-80".parse::<SpeedRange>().unwrap(), range);
+        assert_eq!("2.5..80".parse::<SpeedRange>().unwrap().min, 2.5);
+        let single: SpeedRange = "50".parse().unwrap();
+        assert_eq!(
+            single,
+            SpeedRange {
+                min: 50.0,
+                max: 50.0
+            }
+        );
+        assert!("80..30".parse::<SpeedRange>().is_err());
+        assert!("fast".parse::<SpeedRange>().is_err());
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(timestamp(START), "2024-01-01T00:00:00Z");
+        // Leap day and the last second of 2024
+        assert_eq!(timestamp(1_709_210_096), "2024-02-29T12:34:56Z");
+        assert_eq!(timestamp(START + YEAR - 1), "2024-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn test_random_trajectory() {
+        let speed = SpeedRange {
+            min: 36.0,
+            max: 36.0,
+        };
+        let fixes = random_trajectory(&Crs::WGS84, speed, 10.0);
+        assert!((10..=100).contains(&fixes.len()));
+        for pair in fixes.windows(2) {
+            assert_eq!(pair[1].time - pair[0].time, 10);
+            // 10 m/s for 10 s is about 0.0009 degrees of latitude
+            let step = ((pair[1].lat - pair[0].lat) * 111_320.0).abs();
+            assert!(step <= 100.0 + 1e-6, "{}", step);
+        }
+
+        let (geometry, properties) = trajectory_linestring(&Crs::WGS84, &fixes);
+        let RandomGeometry::LineString(coords) = geometry else {
+            unreachable!()
+        };
+        let times = properties["coordinateProperties"]["times"]
+            .as_array()
+            .unwrap();
+        assert_eq!(times.len(), coords.len());
+        assert_eq!(properties["start_time"], times[0]);
+    }
+}
@@ -0,0 +1,238 @@
+use geojson::FeatureCollection;
+
+use crate::error::RandomGeojsonError;
+use crate::geometry::{Bounds, for_each_position};
+
+/// Comparison operator used by numeric assertions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Comparison {
+    fn holds(&self, left: usize, right: usize) -> bool {
+        match self {
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Gt => left > right,
+            Comparison::Ge => left >= right,
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+        }
+    }
+}
+
+/// A property of a FeatureCollection checked by `random-geojson validate`.
+///
+/// Supported forms:
+/// - `count>=1000` (also `<`, `<=`, `>`, `==`, `!=`)
+/// - `bbox within -10,40,10,55`
+/// - `types in Point,Polygon`
+/// - `property name exists`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Assertion {
+    Count(Comparison, usize),
+    BboxWithin(Bounds),
+    TypesIn(Vec<String>),
+    PropertyExists(String),
+}
+
+impl Assertion {
+    /// Checks the assertion, describing the failure if it does not hold.
+    pub fn check(&self, fc: &FeatureCollection) -> Result<(), String> {
+        match self {
+            Assertion::Count(op, expected) => {
+                let count = fc.features.len();
+                if op.holds(count, *expected) {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "feature count is {}, expected {} {}",
+                        count,
+                        op.symbol(),
+                        expected
+                    ))
+                }
+            }
+            Assertion::BboxWithin(bounds) => {
+                let mut outside = 0;
+                let mut actual: Option<Bounds> = None;
+                for geometry in fc.features.iter().filter_map(|f| f.geometry.as_ref()) {
+                    for_each_position(&geometry.value, &mut |p| {
+                        let (x, y) = (p[0], p.get(1).copied().unwrap_or(f64::NAN));
+                        if !bounds.contains(x, y) {
+                            outside += 1;
+                        }
+                        let b = actual.get_or_insert(Bounds::new(x, y, x, y, bounds.unit));
+                        b.min_x = b.min_x.min(x);
+                        b.min_y = b.min_y.min(y);
+                        b.max_x = b.max_x.max(x);
+                        b.max_y = b.max_y.max(y);
+                    });
+                }
+                match (outside, actual) {
+                    (0, _) => Ok(()),
+                    (n, Some(b)) => Err(format!(
+                        "{} positions outside bbox, actual bbox is {},{},{},{}",
+                        n, b.min_x, b.min_y, b.max_x, b.max_y
+                    )),
+                    (n, None) => Err(format!("{} positions outside bbox", n)),
+                }
+            }
+            Assertion::TypesIn(allowed) => {
+                for (i, feature) in fc.features.iter().enumerate() {
+                    let kind = feature
+                        .geometry
+                        .as_ref()
+                        .map(|g| g.value.type_name())
+                        .unwrap_or("null");
+                    if !allowed.iter().any(|a| a.eq_ignore_ascii_case(kind)) {
+                        return Err(format!("feature {} has geometry type {}", i, kind));
+                    }
+                }
+                Ok(())
+            }
+            Assertion::PropertyExists(name) => {
+                let missing = fc
+                    .features
+                    .iter()
+                    .filter(|f| !f.contains_property(name))
+                    .count();
+                if missing == 0 {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{} features are missing property '{}'",
+                        missing, name
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Assertion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Assertion::Count(op, value) => write!(f, "count{}{}", op.symbol(), value),
+            Assertion::BboxWithin(b) => write!(
+                f,
+                "bbox within {},{},{},{}",
+                b.min_x, b.min_y, b.max_x, b.max_y
+            ),
+            Assertion::TypesIn(types) => write!(f, "types in {}", types.join(",")),
+            Assertion::PropertyExists(name) => write!(f, "property {} exists", name),
+        }
+    }
+}
+
+impl std::str::FromStr for Assertion {
+    type Err = RandomGeojsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RandomGeojsonError::InvalidArgument(format!("Invalid assertion: {}", s));
+        let trimmed = s.trim();
+
+        if let Some(rest) = trimmed.strip_prefix("count") {
+            let rest = rest.trim_start();
+            // Two-character operators first so that ">=" is not read as ">"
+            let operators = [
+                ("<=", Comparison::Le),
+                (">=", Comparison::Ge),
+                ("==", Comparison::Eq),
+                ("!=", Comparison::Ne),
+                ("<", Comparison::Lt),
+                (">", Comparison::Gt),
+                ("=", Comparison::Eq),
+            ];
+            let (op, value) = operators
+                .iter()
+                .find_map(|(symbol, op)| rest.strip_prefix(symbol).map(|v| (*op, v)))
+                .ok_or_else(invalid)?;
+            let value = value.trim().parse().map_err(|_| invalid())?;
+            return Ok(Assertion::Count(op, value));
+        }
+
+        let words: Vec<&str> = trimmed.splitn(3, char::is_whitespace).collect();
+        match words.as_slice() {
+            ["bbox", "within", values] => Ok(Assertion::BboxWithin(
+                values.parse::<Bounds>().map_err(|_| invalid())?,
+            )),
+            ["types", "in", types] => Ok(Assertion::TypesIn(
+                types.split(',').map(|t| t.trim().to_string()).collect(),
+            )),
+            ["property", name, "exists"] => Ok(Assertion::PropertyExists(name.to_string())),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geojson::{Feature, Geometry, Value};
+
+    use super::*;
+    use crate::geometry::Unit;
+
+    fn point_collection(points: &[(f64, f64)]) -> FeatureCollection {
+        FeatureCollection {
+            features: points
+                .iter()
+                .map(|&(x, y)| Feature {
+                    geometry: Some(Geometry::new(Value::Point(vec![x, y]))),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_assertions() {
+        assert_eq!(
+            "count>=1000".parse::<Assertion>().unwrap(),
+            Assertion::Count(Comparison::Ge, 1000)
+        );
+        assert_eq!(
+            "count < 5".parse::<Assertion>().unwrap(),
+            Assertion::Count(Comparison::Lt, 5)
+        );
+        assert_eq!(
+            "bbox within -10,40,10,55".parse::<Assertion>().unwrap(),
+            Assertion::BboxWithin(Bounds::new(-10.0, 40.0, 10.0, 55.0, Unit::Degrees))
+        );
+        assert!("count>>1".parse::<Assertion>().is_err());
+        assert!("bbox within 10,40,-10,55".parse::<Assertion>().is_err());
+        assert!("something else".parse::<Assertion>().is_err());
+    }
+
+    #[test]
+    fn test_check_assertions() {
+        let fc = point_collection(&[(0.0, 45.0), (5.0, 50.0)]);
+        let check = |s: &str| s.parse::<Assertion>().unwrap().check(&fc);
+
+        assert!(check("count==2").is_ok());
+        assert!(check("count>2").is_err());
+        assert!(check("bbox within -10,40,10,55").is_ok());
+        assert!(check("bbox within -10,40,1,55").is_err());
+        assert!(check("types in point").is_ok());
+        assert!(check("types in Polygon").is_err());
+        assert!(check("property name exists").is_err());
+    }
+}
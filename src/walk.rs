@@ -0,0 +1,150 @@
+use std::f64::consts::{FRAC_PI_4, TAU};
+
+use rand::Rng;
+
+use crate::anchor::{offset, project, project_one};
+use crate::geometry::{Crs, RandomGeometry, random_geographic_coords};
+use crate::random;
+use crate::spacing::Distance;
+
+/// Largest change of direction between two steps of a walk, in radians.
+const MAX_TURN: f64 = FRAC_PI_4;
+
+/// Directions tried, at ever shorter distances, for a step that would leave
+/// the area before staying in place.
+const MAX_RETRIES: usize = 30;
+
+/// Generates a LineString of 10 to 50 vertices where each one is a step of
+/// half to all of `step` from the previous one, turning by up to 45 degrees
+/// each time, like a GPS track.
+pub fn random_walk(crs: &Crs, step: Distance) -> RandomGeometry {
+    RandomGeometry::LineString(project(crs, &walk_vertices(crs, step)))
+}
+
+/// The geographic vertices of a [`random_walk`], before projection.
+pub(crate) fn walk_vertices(crs: &Crs, step: Distance) -> Vec<(f64, f64)> {
+    let mut rng = random::rng();
+    let mut position = random_geographic_coords(crs);
+    let mut heading = rng.random_range(0.0..TAU);
+    let mut vertices = vec![position];
+    for _ in 1..rng.random_range(10..=50) {
+        let distance = step.scale(rng.random_range(0.5..=1.0));
+        let turn = rng.random_range(-MAX_TURN..=MAX_TURN);
+        (position, heading) = advance(crs, position, heading + turn, distance);
+        vertices.push(position);
+    }
+    vertices
+}
+
+/// Generates `objects` random points and moves each of them by a step of
+/// half to all of `step` between `snapshots` moments, turning by up to 45
+/// degrees at a time. Returns the Points of every moment in turn, with the
+/// objects in the same order in each.
+pub fn moving_points(
+    crs: &Crs,
+    objects: usize,
+    snapshots: usize,
+    step: Distance,
+) -> Vec<Vec<RandomGeometry>> {
+    let mut rng = random::rng();
+    let mut states: Vec<((f64, f64), f64)> = (0..objects)
+        .map(|_| (random_geographic_coords(crs), rng.random_range(0.0..TAU)))
+        .collect();
+    let mut moments = Vec::with_capacity(snapshots);
+    for t in 0..snapshots {
+        if t > 0 {
+            for (position, heading) in &mut states {
+                let distance = step.scale(rng.random_range(0.5..=1.0));
+                let turn = rng.random_range(-MAX_TURN..=MAX_TURN);
+                (*position, *heading) = advance(crs, *position, *heading + turn, distance);
+            }
+        }
+        moments.push(
+            states
+                .iter()
+                .map(|&(position, _)| RandomGeometry::Point(project_one(crs, position)))
+                .collect(),
+        );
+    }
+    moments
+}
+
+/// Moves a geographic position `distance` along `heading`, in radians
+/// counter-clockwise from east, and returns where it ends up and the heading
+/// taken.
+///
+/// A step that would leave the area of `crs` is retried in random directions
+/// at halving distances, so walks bounce off its edges and masks.
+pub(crate) fn advance(
+    crs: &Crs,
+    from: (f64, f64),
+    heading: f64,
+    distance: Distance,
+) -> ((f64, f64), f64) {
+    let mut rng = random::rng();
+    let bounds = crs.bounds();
+    let (mut heading, mut distance) = (heading, distance);
+    for _ in 0..MAX_RETRIES {
+        let (sin, cos) = heading.sin_cos();
+        let to = match distance {
+            Distance::Meters(meters) => offset(from.0, from.1, meters * cos, meters * sin),
+            Distance::Degrees(degrees) => (from.0 + degrees * cos, from.1 + degrees * sin),
+        };
+        if bounds.contains(to.0, to.1) && crs.accepts(to.0, to.1) {
+            return (to, heading);
+        }
+        heading = rng.random_range(0.0..TAU);
+        distance = distance.scale(0.5);
+    }
+    (from, heading)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::{Bounds, Unit};
+
+    use super::*;
+
+    #[test]
+    fn test_random_walk() {
+        let bounds = Bounds::new(0.0, 0.0, 1.0, 1.0, Unit::Degrees);
+        let crs = Crs::WGS84.clipped(bounds).unwrap();
+        for _ in 0..20 {
+            let RandomGeometry::LineString(vertices) = random_walk(&crs, Distance::Degrees(0.2))
+            else {
+                unreachable!("random_walk returns linestrings")
+            };
+            assert!((10..=50).contains(&vertices.len()));
+            assert!(vertices.iter().all(|v| bounds.contains(v[0], v[1])));
+            for pair in vertices.windows(2) {
+                let step = (pair[1][0] - pair[0][0]).hypot(pair[1][1] - pair[0][1]);
+                assert!(step <= 0.2 + 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_moving_points() {
+        let moments = moving_points(&Crs::WGS84, 5, 4, Distance::Degrees(0.1));
+        assert_eq!(moments.len(), 4);
+        for pair in moments.windows(2) {
+            assert_eq!(pair[1].len(), 5);
+            for (before, after) in pair[0].iter().zip(&pair[1]) {
+                let (RandomGeometry::Point(a), RandomGeometry::Point(b)) = (before, after) else {
+                    unreachable!("moving_points returns points")
+                };
+                let step = (b[0] - a[0]).hypot(b[1] - a[1]);
+                assert!(step <= 0.1 + 1e-9);
+            }
+        }
+        assert!(moving_points(&Crs::WGS84, 3, 0, Distance::Degrees(0.1)).is_empty());
+    }
+
+    #[test]
+    fn test_advance_in_meters() {
+        let ((lon, lat), heading) = advance(&Crs::WGS84, (0.0, 0.0), 0.0, Distance::Meters(1000.0));
+        assert_eq!(heading, 0.0);
+        assert!((lon - 1000.0 / 111_320.0).abs() < 1e-9);
+        assert_eq!(lat, 0.0);
+    }
+}
@@ -0,0 +1,19 @@
+use wasm_bindgen::prelude::*;
+
+use crate::options::generate_json;
+
+/// Generates a random GeoJSON FeatureCollection in the browser, e.g. for a
+/// MapLibre or Leaflet source: `generateRandomGeojson({length: 50,
+/// geometryType: "Point"})`.
+#[wasm_bindgen(js_name = generateRandomGeojson)]
+pub fn generate_random_geojson(options: JsValue) -> Result<JsValue, JsError> {
+    let options = if options.is_undefined() || options.is_null() {
+        "{}".to_string()
+    } else {
+        js_sys::JSON::stringify(&options)
+            .map_err(|_| JsError::new("Options must be a plain object"))?
+            .into()
+    };
+    let json = generate_json(&options).map_err(|e| JsError::new(&e.to_string()))?;
+    js_sys::JSON::parse(&json).map_err(|_| JsError::new("Generated invalid JSON"))
+}